@@ -6,6 +6,9 @@
 
 mod raw;
 
+#[cfg(test)]
+mod tests;
+
 use raw::SyntaxNodePtr;
 use rowan::{Language, SyntaxNode};
 use std::convert::TryInto;
@@ -105,3 +108,125 @@ where
     self.raw.hash(state)
   }
 }
+
+/// Types which are some syntax nodes of a Rowan language, and can tell which
+/// by their kind alone.
+pub trait CanCast: HasLanguage {
+  /// Returns whether a node of the `kind` is one of these.
+  fn can_cast(kind: <Self::Language as Language>::Kind) -> bool;
+}
+
+/// Kinds of syntax nodes with IDs which do not change when the grammar is
+/// edited, unlike their raw kinds, so that they may be persisted.
+///
+/// `syntax-gen` implements this with its `stable_ids` option.
+pub trait StableKind: Sized {
+  /// Returns the stable ID of this kind.
+  fn to_stable_u16(&self) -> u16;
+  /// Returns the kind with this stable ID, if any.
+  fn from_stable_u16(id: u16) -> Option<Self>;
+}
+
+impl<N> AstPtr<N>
+where
+  N: HasLanguage,
+  <N::Language as Language>::Kind: Copy + StableKind,
+{
+  /// Returns a hash of the stable ID of the kind and the range of the node,
+  /// for persisting. Unlike the hash from [`Hash`], it depends on nothing else.
+  pub fn stable_hash(&self) -> u64 {
+    let range = self.raw.range();
+    let kind = self.raw.kind().to_stable_u16().to_le_bytes();
+    let start = u32::from(range.start()).to_le_bytes();
+    let end = u32::from(range.end()).to_le_bytes();
+    // FNV-1a.
+    let mut ret: u64 = 0xcbf2_9ce4_8422_2325;
+    for &b in kind.iter().chain(start.iter()).chain(end.iter()) {
+      ret ^= u64::from(b);
+      ret = ret.wrapping_mul(0x0100_0000_01b3);
+    }
+    ret
+  }
+}
+
+impl<N> AstPtr<N>
+where
+  N: HasLanguage,
+  <N::Language as Language>::Kind: Copy,
+{
+  /// Returns this as a pointer to an `M`, or `None` if the node can't be an
+  /// `M`.
+  ///
+  /// This goes either way between e.g. an enum and the types of its variants:
+  /// from a variant to the enum always succeeds, and from the enum to a
+  /// variant succeeds only if the node is that variant.
+  pub fn cast<M>(self) -> Option<AstPtr<M>>
+  where
+    M: CanCast<Language = N::Language>,
+  {
+    M::can_cast(self.raw.kind()).then(|| AstPtr { raw: self.raw })
+  }
+}
+
+/// Serialized as a tuple of the stable ID of the kind, the start, and the end
+/// of the node.
+///
+/// It is an error to deserialize an unknown stable ID, or one for a kind which
+/// `N` can't be a node of.
+#[cfg(feature = "serde")]
+impl<N> serde::Serialize for AstPtr<N>
+where
+  N: HasLanguage,
+  <N::Language as Language>::Kind: Copy + StableKind,
+{
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: serde::Serializer,
+  {
+    let range = self.raw.range();
+    let tuple = (
+      self.raw.kind().to_stable_u16(),
+      u32::from(range.start()),
+      u32::from(range.end()),
+    );
+    serde::Serialize::serialize(&tuple, serializer)
+  }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, N> serde::Deserialize<'de> for AstPtr<N>
+where
+  N: CanCast,
+  <N::Language as Language>::Kind: Copy + StableKind,
+{
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: serde::Deserializer<'de>,
+  {
+    let (kind, start, end): (u16, u32, u32) =
+      serde::Deserialize::deserialize(deserializer)?;
+    if start > end {
+      return Err(serde::de::Error::custom("start is after end"));
+    }
+    let kind = match StableKind::from_stable_u16(kind) {
+      Some(x) => x,
+      None => {
+        return Err(serde::de::Error::custom(format!(
+          "no kind has the stable ID {}",
+          kind
+        )))
+      }
+    };
+    if !N::can_cast(kind) {
+      return Err(serde::de::Error::custom(format!(
+        "a node of kind {:?} is not a {}",
+        kind,
+        std::any::type_name::<N>()
+      )));
+    }
+    let range = rowan::TextRange::new(start.into(), end.into());
+    Ok(Self {
+      raw: SyntaxNodePtr::from_parts(kind, range),
+    })
+  }
+}
@@ -20,6 +20,22 @@ where
       range: node.text_range(),
     }
   }
+
+  #[cfg(feature = "serde")]
+  pub(crate) fn from_parts(kind: L::Kind, range: TextRange) -> Self {
+    Self { kind, range }
+  }
+
+  pub(crate) fn kind(&self) -> L::Kind
+  where
+    L::Kind: Copy,
+  {
+    self.kind
+  }
+
+  pub(crate) fn range(&self) -> TextRange {
+    self.range
+  }
 }
 
 impl<L> SyntaxNodePtr<L>
@@ -0,0 +1,180 @@
+use crate::{AstPtr, CanCast, HasLanguage, StableKind};
+use rowan::{GreenNodeBuilder, Language};
+use std::convert::TryFrom;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Kind {
+  Root,
+  Num,
+  Add,
+}
+
+impl StableKind for Kind {
+  fn to_stable_u16(&self) -> u16 {
+    // not the same as the raw kinds.
+    match *self {
+      Kind::Root => 7,
+      Kind::Num => 3,
+      Kind::Add => 5,
+    }
+  }
+
+  fn from_stable_u16(id: u16) -> Option<Self> {
+    let ret = match id {
+      7 => Kind::Root,
+      3 => Kind::Num,
+      5 => Kind::Add,
+      _ => return None,
+    };
+    Some(ret)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Lang {}
+
+impl Language for Lang {
+  type Kind = Kind;
+
+  fn kind_from_raw(raw: rowan::SyntaxKind) -> Kind {
+    match raw.0 {
+      0 => Kind::Root,
+      1 => Kind::Num,
+      2 => Kind::Add,
+      _ => panic!("bad raw kind: {}", raw.0),
+    }
+  }
+
+  fn kind_to_raw(kind: Kind) -> rowan::SyntaxKind {
+    rowan::SyntaxKind(kind as u16)
+  }
+}
+
+type SyntaxNode = rowan::SyntaxNode<Lang>;
+
+macro_rules! node {
+  ($name:ident, $($kind:ident)|+) => {
+    #[derive(Debug, PartialEq, Eq)]
+    struct $name(SyntaxNode);
+
+    impl HasLanguage for $name {
+      type Language = Lang;
+    }
+
+    impl CanCast for $name {
+      fn can_cast(kind: Kind) -> bool {
+        matches!(kind, $(Kind::$kind)|+)
+      }
+    }
+
+    impl AsRef<SyntaxNode> for $name {
+      fn as_ref(&self) -> &SyntaxNode {
+        &self.0
+      }
+    }
+
+    impl TryFrom<SyntaxNode> for $name {
+      type Error = SyntaxNode;
+
+      fn try_from(node: SyntaxNode) -> Result<Self, SyntaxNode> {
+        if Self::can_cast(node.kind()) {
+          Ok(Self(node))
+        } else {
+          Err(node)
+        }
+      }
+    }
+  };
+}
+
+node!(Num, Num);
+node!(Add, Add);
+// like an enum of `Num` and `Add`.
+node!(Expr, Num | Add);
+
+/// The tree for `1+2`.
+fn root() -> SyntaxNode {
+  let mut b = GreenNodeBuilder::new();
+  b.start_node(Lang::kind_to_raw(Kind::Root));
+  b.start_node(Lang::kind_to_raw(Kind::Add));
+  for (idx, s) in ["1", "+", "2"].iter().enumerate() {
+    if idx % 2 == 0 {
+      b.start_node(Lang::kind_to_raw(Kind::Num));
+      b.token(rowan::SyntaxKind(9), s);
+      b.finish_node();
+    } else {
+      b.token(rowan::SyntaxKind(9), s);
+    }
+  }
+  b.finish_node();
+  b.finish_node();
+  SyntaxNode::new_root(b.finish())
+}
+
+fn add() -> Add {
+  Add::try_from(root().first_child().unwrap()).unwrap()
+}
+
+#[test]
+fn to_node() {
+  let add = add();
+  let num = Num::try_from(add.0.last_child().unwrap()).unwrap();
+  let ptr = AstPtr::new(&num);
+  // nodes from different trees are never equal.
+  let got = ptr.to_node(root());
+  assert_eq!(got.0.text_range(), num.0.text_range());
+  assert_eq!(got.0.text().to_string(), "2");
+}
+
+#[test]
+fn cast() {
+  let add = add();
+  let num = Num::try_from(add.0.first_child().unwrap()).unwrap();
+  let num_ptr = AstPtr::new(&num);
+  let add_ptr = AstPtr::new(&add);
+  let expr_ptr = num_ptr.cast::<Expr>().unwrap();
+  assert_eq!(expr_ptr, AstPtr::new(&Expr(num.0.clone())));
+  assert_eq!(expr_ptr.cast::<Num>(), Some(num_ptr));
+  assert!(expr_ptr.cast::<Add>().is_none());
+  assert!(num_ptr.cast::<Add>().is_none());
+  assert!(add_ptr.cast::<Expr>().is_some());
+}
+
+#[test]
+fn stable_hash() {
+  let add = add();
+  let num = Num::try_from(add.0.first_child().unwrap()).unwrap();
+  let num_ptr = AstPtr::new(&num);
+  let expr_ptr = num_ptr.cast::<Expr>().unwrap();
+  assert_eq!(num_ptr.stable_hash(), expr_ptr.stable_hash());
+  assert_ne!(num_ptr.stable_hash(), AstPtr::new(&add).stable_hash());
+  // this must not change, since it may be persisted. it uses the stable ID,
+  // not the raw kind.
+  assert_eq!(num_ptr.stable_hash(), 0x2272_d4d7_fa7c_ce67);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde() {
+  use serde_json::json;
+  let add_ptr = AstPtr::new(&add());
+  let json = serde_json::to_value(add_ptr).unwrap();
+  assert_eq!(json, json!([5, 0, 3]));
+  let back: AstPtr<Add> = serde_json::from_value(json).unwrap();
+  assert_eq!(back, add_ptr);
+  let expr: AstPtr<Expr> = serde_json::from_value(json!([5, 0, 3])).unwrap();
+  assert_eq!(expr, add_ptr.cast::<Expr>().unwrap());
+  let bad = [
+    // the raw kind of `Add`, which is not a stable ID.
+    json!([2, 0, 3]),
+    // the stable ID of `Num`.
+    json!([3, 0, 3]),
+    // the root is not an `Add`.
+    json!([7, 0, 3]),
+    // backwards.
+    json!([5, 3, 0]),
+  ];
+  for bad in bad.iter() {
+    assert!(serde_json::from_value::<AstPtr<Add>>(bad.clone()).is_err());
+  }
+}
@@ -0,0 +1,128 @@
+//! Hierarchical document symbols (an 'outline'), extracted from a syntax tree.
+//!
+//! Each supported version of `lsp-types` is behind a feature of the same name,
+//! like `lsp-types-0-94`, which converts [`DocumentSymbol`]s into its
+//! `DocumentSymbol`s.
+
+#![deny(missing_debug_implementations)]
+#![deny(missing_docs)]
+#![deny(rust_2018_idioms)]
+
+#[cfg(test)]
+mod tests;
+
+use rowan::{Language, SyntaxNode, TextRange, WalkEvent};
+use text_pos::{PositionDb, Range};
+
+/// Information about a node which is a symbol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Info<K> {
+  /// The name of the symbol.
+  pub name: String,
+  /// The kind of the symbol.
+  pub kind: K,
+  /// The range to select when navigating to the symbol, e.g. the range of its
+  /// name. Must be contained in the range of the node.
+  pub selection_range: TextRange,
+}
+
+/// A symbol in a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentSymbol<K> {
+  /// The name of the symbol.
+  pub name: String,
+  /// The kind of the symbol.
+  pub kind: K,
+  /// The range of the whole node for the symbol.
+  pub range: Range,
+  /// The range to select when navigating to the symbol.
+  pub selection_range: Range,
+  /// The symbols contained in this symbol.
+  pub children: Vec<DocumentSymbol<K>>,
+}
+
+/// Returns the symbols in the tree rooted at `root`, in order.
+///
+/// `info` is called on every node in the tree, and should return `Some` iff
+/// the node is a symbol. The symbols for the nodes inside a symbol's node are
+/// that symbol's children.
+pub fn get<L, K, F>(
+  root: &SyntaxNode<L>,
+  db: &PositionDb,
+  info: F,
+) -> Vec<DocumentSymbol<K>>
+where
+  L: Language,
+  F: Fn(&SyntaxNode<L>) -> Option<Info<K>>,
+{
+  let mut ret = Vec::new();
+  let mut stack: Vec<(SyntaxNode<L>, DocumentSymbol<K>)> = Vec::new();
+  for ev in root.preorder() {
+    match ev {
+      WalkEvent::Enter(node) => {
+        if let Some(info) = info(&node) {
+          let sym = DocumentSymbol {
+            name: info.name,
+            kind: info.kind,
+            range: db.range(node.text_range()),
+            selection_range: db.range(info.selection_range),
+            children: Vec::new(),
+          };
+          stack.push((node, sym));
+        }
+      }
+      WalkEvent::Leave(node) => {
+        if stack.last().map(|x| &x.0) != Some(&node) {
+          continue;
+        }
+        let (_, sym) = stack.pop().unwrap();
+        match stack.last_mut() {
+          None => ret.push(sym),
+          Some((_, parent)) => parent.children.push(sym),
+        }
+      }
+    }
+  }
+  ret
+}
+
+#[allow(unused_macros)]
+macro_rules! impl_lsp {
+  ($lsp:ident) => {
+    /// The kind is converted with `Into`, and there is no detail.
+    impl<K> From<DocumentSymbol<K>> for $lsp::DocumentSymbol
+    where
+      K: Into<$lsp::SymbolKind>,
+    {
+      fn from(sym: DocumentSymbol<K>) -> Self {
+        let range = |r: Range| $lsp::Range {
+          start: $lsp::Position {
+            line: r.start.line,
+            character: r.start.character,
+          },
+          end: $lsp::Position {
+            line: r.end.line,
+            character: r.end.character,
+          },
+        };
+        #[allow(deprecated)]
+        $lsp::DocumentSymbol {
+          name: sym.name,
+          detail: None,
+          kind: sym.kind.into(),
+          tags: None,
+          deprecated: None,
+          range: range(sym.range),
+          selection_range: range(sym.selection_range),
+          children: Some(sym.children.into_iter().map(Into::into).collect()),
+        }
+      }
+    }
+  };
+}
+
+#[cfg(feature = "lsp-types-0-93")]
+impl_lsp!(lsp_types_0_93);
+
+#[cfg(feature = "lsp-types-0-94")]
+impl_lsp!(lsp_types_0_94);
@@ -0,0 +1,147 @@
+use crate::{get, DocumentSymbol, Info};
+use rowan::{GreenNodeBuilder, Language, NodeOrToken, SyntaxNode};
+use text_pos::{Position, PositionDb, Range};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum SK {
+  Whitespace,
+  Word,
+  Block,
+  Root,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Lang {}
+
+impl Language for Lang {
+  type Kind = SK;
+
+  fn kind_from_raw(raw: rowan::SyntaxKind) -> Self::Kind {
+    [SK::Whitespace, SK::Word, SK::Block, SK::Root][raw.0 as usize]
+  }
+
+  fn kind_to_raw(kind: Self::Kind) -> rowan::SyntaxKind {
+    rowan::SyntaxKind(kind as u16)
+  }
+}
+
+/// Each line is a token: `{` starts a block, `}` ends it, else it's a word.
+fn tree(s: &str) -> SyntaxNode<Lang> {
+  let mut b = GreenNodeBuilder::new();
+  b.start_node(Lang::kind_to_raw(SK::Root));
+  for (idx, line) in s.lines().enumerate() {
+    if idx != 0 {
+      b.token(Lang::kind_to_raw(SK::Whitespace), "\n");
+    }
+    if line == "{" {
+      b.start_node(Lang::kind_to_raw(SK::Block));
+    }
+    b.token(Lang::kind_to_raw(SK::Word), line);
+    if line == "}" {
+      b.finish_node();
+    }
+  }
+  b.finish_node();
+  SyntaxNode::new_root(b.finish())
+}
+
+/// A block is a symbol named after the word after the `{`, if any.
+fn info(node: &SyntaxNode<Lang>) -> Option<Info<()>> {
+  if node.kind() != SK::Block {
+    return None;
+  }
+  let name = node
+    .children_with_tokens()
+    .filter_map(NodeOrToken::into_token)
+    .filter(|tok| tok.kind() == SK::Word && tok.text() != "}")
+    .nth(1)?;
+  Some(Info {
+    name: name.text().to_owned(),
+    kind: (),
+    selection_range: name.text_range(),
+  })
+}
+
+fn range(a: (u32, u32), b: (u32, u32)) -> Range {
+  Range {
+    start: Position {
+      line: a.0,
+      character: a.1,
+    },
+    end: Position {
+      line: b.0,
+      character: b.1,
+    },
+  }
+}
+
+#[test]
+fn nested() {
+  let s = "{\nfoo\n{\nbar\n}\n{\nquz\n}\n}\n{\n}";
+  let db = PositionDb::new(s);
+  let got = get(&tree(s), &db, info);
+  let sym = |name: &str, a, b, sel, children| DocumentSymbol {
+    name: name.to_owned(),
+    kind: (),
+    range: range(a, b),
+    selection_range: sel,
+    children,
+  };
+  let want = vec![sym(
+    "foo",
+    (0, 0),
+    (8, 1),
+    range((1, 0), (1, 3)),
+    vec![
+      sym("bar", (2, 0), (4, 1), range((3, 0), (3, 3)), vec![]),
+      sym("quz", (5, 0), (7, 1), range((6, 0), (6, 3)), vec![]),
+    ],
+  )];
+  assert_eq!(got, want);
+}
+
+// the same tests for each version of `lsp-types`.
+#[allow(unused_macros)]
+macro_rules! lsp_tests {
+  ($name:ident, $lsp:ident) => {
+    #[test]
+    fn $name() {
+      let s = "{\nfoo\n{\nbar\n}\n}";
+      let db = PositionDb::new(s);
+      let info = |node: &SyntaxNode<Lang>| {
+        let info = info(node)?;
+        Some(Info {
+          name: info.name,
+          kind: $lsp::SymbolKind::NAMESPACE,
+          selection_range: info.selection_range,
+        })
+      };
+      let mut got = get(&tree(s), &db, info);
+      assert_eq!(got.len(), 1);
+      let got = $lsp::DocumentSymbol::from(got.pop().unwrap());
+      assert_eq!(got.name, "foo");
+      assert_eq!(got.kind, $lsp::SymbolKind::NAMESPACE);
+      let pos = |line, character| $lsp::Position { line, character };
+      let want = $lsp::Range {
+        start: pos(0, 0),
+        end: pos(5, 1),
+      };
+      assert_eq!(got.range, want);
+      let want = $lsp::Range {
+        start: pos(1, 0),
+        end: pos(1, 3),
+      };
+      assert_eq!(got.selection_range, want);
+      let children = got.children.unwrap();
+      assert_eq!(children.len(), 1);
+      assert_eq!(children[0].name, "bar");
+      assert_eq!(children[0].children, Some(Vec::new()));
+    }
+  };
+}
+
+#[cfg(feature = "lsp-types-0-93")]
+lsp_tests!(lsp_types_0_93, lsp_types_0_93);
+
+#[cfg(feature = "lsp-types-0-94")]
+lsp_tests!(lsp_types_0_94, lsp_types_0_94);
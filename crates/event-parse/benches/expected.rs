@@ -0,0 +1,171 @@
+//! Parsing with a grammar which, like many, tries alternatives by saving and
+//! restoring, and checks for a few kinds at each token. This stresses the
+//! storage of the expected kinds.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use event_parse::{Parser, Sink};
+use token::{Token, Triviable};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SK {
+  Space,
+  Let,
+  Name,
+  Num,
+  Eq,
+  Plus,
+  LRound,
+  RRound,
+  Semi,
+  Root,
+  LetStmt,
+  ExprStmt,
+  Expr,
+}
+
+impl Triviable for SK {
+  fn is_trivia(&self) -> bool {
+    matches!(*self, SK::Space)
+  }
+}
+
+fn input() -> Vec<Token<'static, SK>> {
+  let stmts: [&[(SK, &str)]; 2] = [
+    &[
+      (SK::Let, "let"),
+      (SK::Name, "x"),
+      (SK::Eq, "="),
+      (SK::LRound, "("),
+      (SK::Num, "1"),
+      (SK::Plus, "+"),
+      (SK::Name, "y"),
+      (SK::RRound, ")"),
+      (SK::Plus, "+"),
+      (SK::Num, "2"),
+      (SK::Semi, ";"),
+    ],
+    &[
+      (SK::Name, "x"),
+      (SK::Plus, "+"),
+      (SK::Num, "3"),
+      (SK::Semi, ";"),
+    ],
+  ];
+  let mut ret = Vec::new();
+  for i in 0..5000 {
+    for &(kind, text) in stmts[i % 2] {
+      ret.push(Token::new(kind, text));
+      ret.push(Token::new(SK::Space, " "));
+    }
+  }
+  ret
+}
+
+fn root(p: &mut Parser<'_, SK>) {
+  let en = p.enter();
+  while p.peek().is_some() {
+    stmt(p);
+  }
+  p.exit(en, SK::Root);
+}
+
+fn stmt(p: &mut Parser<'_, SK>) {
+  // try a let statement first, and restore if it was not one.
+  let save = p.save();
+  let en = p.enter();
+  if p.at(SK::Let) {
+    p.bump();
+    p.eat(SK::Name);
+    p.eat(SK::Eq);
+    expr(p);
+    p.eat(SK::Semi);
+    p.exit(en, SK::LetStmt);
+    p.forget(save);
+    return;
+  }
+  p.abandon(en);
+  p.restore_keeping_expected(save);
+  let en = p.enter();
+  if !expr(p) {
+    p.error();
+    p.bump();
+  }
+  p.eat(SK::Semi);
+  p.exit(en, SK::ExprStmt);
+}
+
+fn expr(p: &mut Parser<'_, SK>) -> bool {
+  let en = p.enter();
+  if !atom(p) {
+    p.abandon(en);
+    return false;
+  }
+  while p.at(SK::Plus) {
+    p.bump();
+    if !atom(p) {
+      p.error();
+    }
+  }
+  p.exit(en, SK::Expr);
+  true
+}
+
+fn atom(p: &mut Parser<'_, SK>) -> bool {
+  if p.at(SK::Num) || p.at(SK::Name) {
+    p.bump();
+    true
+  } else if p.at(SK::LRound) {
+    p.bump();
+    expr(p);
+    p.eat(SK::RRound);
+    true
+  } else {
+    false
+  }
+}
+
+#[derive(Default)]
+struct CountSink(usize);
+
+impl Sink<SK> for CountSink {
+  fn enter(&mut self, _: SK) {
+    self.0 += 1;
+  }
+
+  fn token(&mut self, _: Token<'_, SK>) {
+    self.0 += 1;
+  }
+
+  fn exit(&mut self) {}
+
+  fn error(&mut self, _: Vec<SK>, _: std::ops::Range<usize>) {
+    self.0 += 1;
+  }
+}
+
+fn parse(tokens: &[Token<'_, SK>]) -> usize {
+  let mut p = Parser::new(tokens);
+  root(&mut p);
+  p.memory_usage()
+}
+
+fn parse_and_finish(tokens: &[Token<'_, SK>]) -> usize {
+  let mut p = Parser::new(tokens);
+  root(&mut p);
+  let mut sink = CountSink::default();
+  p.finish(&mut sink);
+  sink.0
+}
+
+fn expected(c: &mut Criterion) {
+  let tokens = input();
+  let mut group = c.benchmark_group("expected");
+  group.bench_function("parse", |b| b.iter(|| parse(black_box(&tokens))));
+  group.bench_function("parse_and_finish", |b| {
+    b.iter(|| parse_and_finish(black_box(&tokens)))
+  });
+  group.finish();
+}
+
+criterion_group!(benches, expected);
+criterion_main!(benches);
@@ -0,0 +1,121 @@
+//! Attaching trivia to non-trivia tokens.
+
+use token::{Token, Triviable};
+
+/// What a token is attached to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attach {
+  /// Not attached to anything. All non-trivia tokens are this.
+  None,
+  /// Attached to the next non-trivia token.
+  Leading,
+  /// Attached to the previous non-trivia token.
+  Trailing,
+}
+
+/// Where to put the trivia at the start of a file, before the first non-trivia
+/// token, or at the end of a file, after the last non-trivia token.
+///
+/// This is trivia like a byte order mark, a shebang line, or a licence header.
+/// The 'root' construct is the outermost one, and 'items' are the constructs
+/// directly inside it, as with [`crate::Parser::parse_file`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileTrivia {
+  /// Put the trivia where it would be put anywhere else in the file, per
+  /// [`crate::Parser::set_attach`], or in the root if it is attached to
+  /// nothing. The default.
+  Attach,
+  /// Put the trivia in the root, regardless of what it is attached to.
+  Root,
+  /// Put the trivia in the first item for the start of the file, or the last
+  /// item for the end, regardless of what it is attached to. If there are no
+  /// items, put it in the root.
+  Item,
+}
+
+impl Default for FileTrivia {
+  fn default() -> Self {
+    Self::Attach
+  }
+}
+
+/// Returns what each token in `tokens` is attached to.
+///
+/// Trivia tokens which contain only whitespace are 'blank'. Other trivia
+/// tokens, like comments, are 'comments'. Then, between two non-trivia tokens:
+///
+/// - Comments on the same line as the previous token, and any blank trivia in
+///   between, are trailing trivia for the previous token.
+/// - Comments before the next token, not separated from it by a blank line,
+///   and any blank trivia in between, are leading trivia for the next token.
+/// - All other trivia is not attached.
+///
+/// So, in
+///
+/// ```text
+/// foo; // about foo
+///
+/// // about bar
+/// bar;
+/// ```
+///
+/// the first comment is attached to the first `;`, and the second comment is
+/// attached to `bar`.
+pub fn attach_trivia<K>(tokens: &[Token<'_, K>]) -> Vec<Attach>
+where
+  K: Triviable,
+{
+  let mut ret = vec![Attach::None; tokens.len()];
+  let mut idx = 0;
+  while idx < tokens.len() {
+    if !tokens[idx].kind.is_trivia() {
+      idx += 1;
+      continue;
+    }
+    let start = idx;
+    while idx < tokens.len() && tokens[idx].kind.is_trivia() {
+      idx += 1;
+    }
+    let run = &tokens[start..idx];
+    let mut trailing_end = 0;
+    if start != 0 {
+      for (i, tok) in run.iter().enumerate() {
+        let has_newline = tok.text.contains('\n');
+        if is_blank(tok) {
+          if has_newline {
+            break;
+          }
+        } else {
+          trailing_end = i + 1;
+          if has_newline {
+            break;
+          }
+        }
+      }
+    }
+    let mut leading_start = run.len();
+    if idx != tokens.len() {
+      for (i, tok) in run.iter().enumerate().skip(trailing_end).rev() {
+        if is_blank(tok) {
+          if tok.text.matches('\n').count() >= 2 {
+            break;
+          }
+        } else {
+          leading_start = i;
+        }
+      }
+    }
+    for (i, attach) in ret[start..idx].iter_mut().enumerate() {
+      if i < trailing_end {
+        *attach = Attach::Trailing;
+      } else if i >= leading_start {
+        *attach = Attach::Leading;
+      }
+    }
+  }
+  ret
+}
+
+fn is_blank<K>(tok: &Token<'_, K>) -> bool {
+  tok.text.trim().is_empty()
+}
@@ -0,0 +1,412 @@
+//! Checking that a sink is called correctly.
+
+use crate::{RawSink, Sink};
+use std::fmt;
+use std::ops::Range;
+use token::Token;
+
+/// A sink which checks the calls made to it, and forwards everything
+/// unchanged, to another sink. Meant for tests.
+///
+/// It panics, with the number of calls so far and the kinds of the syntax
+/// constructs entered but not yet exited, if:
+///
+/// - there is more than one root syntax construct.
+/// - a syntax construct is exited without being entered.
+/// - tokens are reported outside the root.
+/// - with a [`RawSink`], the ranges of tokens are empty, or they and the
+///   remapped tokens do not follow one another, starting from the `base` given
+///   to [`CheckSink::new_at`].
+/// - with a [`Sink`], a run of trivia is empty.
+/// - a missing token is not at the next token with a [`RawSink`], or has a
+///   non-empty range with a [`Sink`].
+/// - errors are reported outside the root, unless allowed with
+///   [`CheckSink::set_errors_outside_root`].
+/// - a context is empty, or is not directly followed by an error or a payload.
+/// - a payload is not directly followed by an error, other than a missing
+///   token or an unclosed delimiter.
+/// - with a [`RawSink`], an unclosed delimiter is not at a token already
+///   added.
+/// - an annotation does not directly follow an enter or another annotation.
+/// - `start` is not the first call, or `finish_ok` is not the last call, is
+///   called before every syntax construct was exited, or is called with a
+///   different number of errors than were reported.
+///
+/// [`CheckSink::finish`] also checks that every syntax construct was exited
+/// and that there was a root.
+#[derive(Debug)]
+pub struct CheckSink<S, K> {
+  /// The sink.
+  pub sink: S,
+  stack: Vec<K>,
+  roots: usize,
+  next: usize,
+  calls: usize,
+  errors_outside_root: bool,
+  /// whether the last call was to set the context.
+  context: bool,
+  /// whether the last call was to set the payload.
+  payload: bool,
+  /// the number of errors, missing tokens, and unclosed delimiters reported.
+  errors: usize,
+  /// whether `finish_ok` was called.
+  finished: bool,
+  /// whether the last call was an enter or an annotation.
+  annotatable: bool,
+}
+
+impl<S, K> CheckSink<S, K>
+where
+  K: fmt::Debug,
+{
+  /// Returns a new `CheckSink` for a parser made with [`crate::Parser::new`].
+  pub fn new(sink: S) -> Self {
+    Self::new_at(sink, 0)
+  }
+
+  /// Returns a new `CheckSink` for a parser made with
+  /// [`crate::Parser::new_at`] with the `base`.
+  pub fn new_at(sink: S, base: usize) -> Self {
+    Self {
+      sink,
+      stack: Vec::new(),
+      roots: 0,
+      next: base,
+      calls: 0,
+      errors_outside_root: false,
+      context: false,
+      payload: false,
+      errors: 0,
+      finished: false,
+      annotatable: false,
+    }
+  }
+
+  /// Sets whether errors may be reported outside the root, where a sink
+  /// building a tree has no syntax construct to put them in. Defaults to
+  /// `false`.
+  pub fn set_errors_outside_root(&mut self, allow: bool) {
+    self.errors_outside_root = allow;
+  }
+
+  /// Returns the index of the next token to be reported. This is one past the
+  /// last token reported so far, or the `base` if none were.
+  pub fn next_token(&self) -> usize {
+    self.next
+  }
+
+  /// Checks that every syntax construct was exited and that there was a
+  /// root, then returns the sink.
+  pub fn finish(self) -> S {
+    if self.context {
+      self.fail(format_args!("context not followed by an error"));
+    }
+    if self.payload {
+      self.fail(format_args!("payload not followed by an error"));
+    }
+    if !self.stack.is_empty() {
+      self.fail(format_args!("not all syntax constructs were exited"));
+    }
+    if self.roots == 0 {
+      self.fail(format_args!("not exactly one root"));
+    }
+    self.sink
+  }
+
+  fn fail(&self, msg: fmt::Arguments<'_>) -> ! {
+    panic!(
+      "{} (at call {}, inside {:?}, at token {})",
+      msg, self.calls, self.stack, self.next
+    )
+  }
+
+  /// Counts a call, and checks that it may follow the last one.
+  fn call(&mut self, error: bool) {
+    if self.finished {
+      self.fail(format_args!("call after finish_ok"));
+    }
+    self.calls += 1;
+    if self.context && !error {
+      self.fail(format_args!("context not followed by an error"));
+    }
+    if self.payload && !error {
+      self.fail(format_args!("payload not followed by an error"));
+    }
+    self.context = false;
+    self.payload = false;
+    self.annotatable = false;
+  }
+
+  fn context(&mut self, labels: &[&'static str]) {
+    self.call(false);
+    if labels.is_empty() {
+      self.fail(format_args!("empty context"));
+    }
+    self.context = true;
+  }
+
+  fn start(&mut self) {
+    if self.calls != 0 {
+      self.fail(format_args!("start not the first call"));
+    }
+    self.call(false);
+  }
+
+  fn finish_ok(&mut self, errors: usize) {
+    self.call(false);
+    if !self.stack.is_empty() {
+      self.fail(format_args!("finish_ok before exiting everything"));
+    }
+    if errors != self.errors {
+      self.fail(format_args!(
+        "finish_ok with {} errors, but {} were reported",
+        errors, self.errors
+      ));
+    }
+    self.finished = true;
+  }
+
+  fn payload(&mut self) {
+    if self.payload {
+      self.fail(format_args!("payload not followed by an error"));
+    }
+    // like an error, a payload may follow a context.
+    self.call(true);
+    self.payload = true;
+  }
+
+  fn missing(&mut self, kind: &K) {
+    if self.payload {
+      self.fail(format_args!("payload followed by a missing token"));
+    }
+    self.error(std::slice::from_ref(kind));
+  }
+
+  fn unclosed(&mut self, close: &K) {
+    if self.payload {
+      self.fail(format_args!("payload followed by an unclosed delimiter"));
+    }
+    self.error(std::slice::from_ref(close));
+  }
+
+  fn enter(&mut self, kind: &K)
+  where
+    K: Clone,
+  {
+    self.call(false);
+    if self.stack.is_empty() {
+      self.roots += 1;
+      if self.roots > 1 {
+        self.fail(format_args!("more than one root: {:?}", kind));
+      }
+    }
+    self.stack.push(kind.clone());
+    self.annotatable = true;
+  }
+
+  fn annotation(&mut self) {
+    if !self.annotatable {
+      self.fail(format_args!("annotation not after an enter"));
+    }
+    self.call(false);
+    self.annotatable = true;
+  }
+
+  fn exit(&mut self) {
+    self.call(false);
+    if self.stack.pop().is_none() {
+      self.fail(format_args!("exit without enter"));
+    }
+  }
+
+  fn tokens(&mut self, n: usize) {
+    self.call(false);
+    if self.stack.is_empty() {
+      self.fail(format_args!("tokens outside the root"));
+    }
+    self.next += n;
+  }
+
+  fn error(&mut self, expected: &[K]) {
+    self.call(true);
+    self.errors += 1;
+    if self.stack.is_empty() && !self.errors_outside_root {
+      self.fail(format_args!("error outside the root: {:?}", expected));
+    }
+  }
+}
+
+impl<S, K, E> Sink<K, E> for CheckSink<S, K>
+where
+  S: Sink<K, E>,
+  K: Clone + fmt::Debug,
+{
+  fn enter(&mut self, kind: K) {
+    CheckSink::enter(self, &kind);
+    self.sink.enter(kind);
+  }
+
+  fn token(&mut self, token: Token<'_, K>) {
+    CheckSink::tokens(self, 1);
+    self.sink.token(token);
+  }
+
+  fn exit(&mut self) {
+    CheckSink::exit(self);
+    self.sink.exit();
+  }
+
+  fn error(&mut self, expected: Vec<K>, range: Range<usize>) {
+    CheckSink::error(self, &expected);
+    self.sink.error(expected, range);
+  }
+
+  fn error_capped(
+    &mut self,
+    expected: Vec<K>,
+    more: usize,
+    range: Range<usize>,
+  ) {
+    CheckSink::error(self, &expected);
+    self.sink.error_capped(expected, more, range);
+  }
+
+  fn missing(&mut self, kind: K, range: Range<usize>) {
+    CheckSink::missing(self, &kind);
+    if !range.is_empty() {
+      self.fail(format_args!("missing with non-empty range: {:?}", range));
+    }
+    self.sink.missing(kind, range);
+  }
+
+  fn unclosed(&mut self, close: K, range: Range<usize>) {
+    CheckSink::unclosed(self, &close);
+    self.sink.unclosed(close, range);
+  }
+
+  fn context(&mut self, labels: &[&'static str]) {
+    CheckSink::context(self, labels);
+    self.sink.context(labels);
+  }
+
+  fn payload(&mut self, payload: E) {
+    CheckSink::payload(self);
+    self.sink.payload(payload);
+  }
+
+  fn annotation(&mut self, data: u32) {
+    CheckSink::annotation(self);
+    self.sink.annotation(data);
+  }
+
+  fn start(&mut self, total_tokens: usize) {
+    CheckSink::start(self);
+    self.sink.start(total_tokens);
+  }
+
+  fn finish_ok(&mut self, errors: usize) {
+    CheckSink::finish_ok(self, errors);
+    self.sink.finish_ok(errors);
+  }
+
+  fn group_trivia(&self) -> bool {
+    self.sink.group_trivia()
+  }
+
+  fn trivia(&mut self, tokens: &[Token<'_, K>])
+  where
+    K: Copy,
+  {
+    if tokens.is_empty() {
+      self.fail(format_args!("empty trivia"));
+    }
+    CheckSink::tokens(self, tokens.len());
+    self.sink.trivia(tokens);
+  }
+}
+
+impl<S, K, E> RawSink<K, E> for CheckSink<S, K>
+where
+  S: RawSink<K, E>,
+  K: Clone + fmt::Debug,
+{
+  fn enter(&mut self, kind: K) {
+    CheckSink::enter(self, &kind);
+    self.sink.enter(kind);
+  }
+
+  fn tokens(&mut self, range: Range<usize>) {
+    if range.start != self.next {
+      self.fail(format_args!("tokens out of order: {:?}", range));
+    }
+    if range.start >= range.end {
+      self.fail(format_args!("empty tokens: {:?}", range));
+    }
+    CheckSink::tokens(self, range.len());
+    self.sink.tokens(range);
+  }
+
+  fn remapped(&mut self, kind: K, idx: usize) {
+    if idx != self.next {
+      self.fail(format_args!("remapped token out of order: {}", idx));
+    }
+    CheckSink::tokens(self, 1);
+    self.sink.remapped(kind, idx);
+  }
+
+  fn exit(&mut self) {
+    CheckSink::exit(self);
+    self.sink.exit();
+  }
+
+  fn error(&mut self, expected: Vec<K>, idx: usize) {
+    CheckSink::error(self, &expected);
+    self.sink.error(expected, idx);
+  }
+
+  fn error_capped(&mut self, expected: Vec<K>, more: usize, idx: usize) {
+    CheckSink::error(self, &expected);
+    self.sink.error_capped(expected, more, idx);
+  }
+
+  fn missing(&mut self, kind: K, idx: usize) {
+    CheckSink::missing(self, &kind);
+    if idx != self.next {
+      self.fail(format_args!("missing not at the next token: {}", idx));
+    }
+    self.sink.missing(kind, idx);
+  }
+
+  fn unclosed(&mut self, close: K, idx: usize) {
+    CheckSink::unclosed(self, &close);
+    if idx >= self.next {
+      self.fail(format_args!("unclosed not at an added token: {}", idx));
+    }
+    self.sink.unclosed(close, idx);
+  }
+
+  fn context(&mut self, labels: &[&'static str]) {
+    CheckSink::context(self, labels);
+    self.sink.context(labels);
+  }
+
+  fn payload(&mut self, payload: E) {
+    CheckSink::payload(self);
+    self.sink.payload(payload);
+  }
+
+  fn annotation(&mut self, data: u32) {
+    CheckSink::annotation(self);
+    self.sink.annotation(data);
+  }
+
+  fn start(&mut self, total_tokens: usize) {
+    CheckSink::start(self);
+    self.sink.start(total_tokens);
+  }
+
+  fn finish_ok(&mut self, errors: usize) {
+    CheckSink::finish_ok(self, errors);
+    self.sink.finish_ok(errors);
+  }
+}
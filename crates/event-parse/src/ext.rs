@@ -0,0 +1,73 @@
+//! Grammar functions as methods on the parser.
+
+use crate::Parser;
+
+/// The supertrait of traits made with [`grammar_ext`], implemented for
+/// [`Parser`].
+///
+/// It is public only so that those traits may name it. It does not stop other
+/// types from implementing it, but nothing else needs to.
+///
+/// [`grammar_ext`]: crate::grammar_ext
+pub trait ParserLike {}
+
+impl<K, E> ParserLike for Parser<'_, K, E> {}
+
+/// Defines a trait with a method on [`Parser`] for each of some grammar
+/// functions, so the grammar may call e.g. `p.expr()` instead of `expr(p)`.
+///
+/// Each method calls the function of the same name, or the function after
+/// `=`, with the parser and the arguments. The kind may be followed by the
+/// payload type of the parser's errors, if it is not `String`, as with
+/// [`Parser::new_with_payload`]. Argument and return types may not
+/// mention the lifetime of the parser. Methods do not shadow the inherent
+/// methods of the parser, so they should not share names.
+///
+/// ```ignore
+/// event_parse::grammar_ext! {
+///   /// Methods for parsing the language.
+///   pub(crate) trait Grammar for SyntaxKind {
+///     /// Parses an expression.
+///     fn expr() -> Option<Exited>;
+///     fn delimited(close: SyntaxKind) = delimited::get;
+///   }
+/// }
+/// ```
+///
+/// The trait is implemented for the `Parser` with the kind and payload type,
+/// and is not meant to be implemented for anything else.
+#[macro_export]
+macro_rules! grammar_ext {
+  (
+    $(#[$attr:meta])*
+    $vis:vis trait $name:ident for $kind:ty $(, $payload:ty)? {
+      $(
+        $(#[$method_attr:meta])*
+        fn $method:ident($($arg:ident: $arg_ty:ty),* $(,)?)
+          $(-> $ret:ty)? $(= $func:path)?;
+      )*
+    }
+  ) => {
+    $(#[$attr])*
+    $vis trait $name: $crate::ParserLike {
+      $(
+        $(#[$method_attr])*
+        fn $method(&mut self, $($arg: $arg_ty),*) $(-> $ret)?;
+      )*
+    }
+
+    impl $name for $crate::Parser<'_, $kind $(, $payload)?> {
+      $(
+        fn $method(&mut self, $($arg: $arg_ty),*) $(-> $ret)? {
+          $crate::grammar_ext!(@func $method $($func)?)(self, $($arg),*)
+        }
+      )*
+    }
+  };
+  (@func $method:ident) => {
+    $method
+  };
+  (@func $method:ident $func:path) => {
+    $func
+  };
+}
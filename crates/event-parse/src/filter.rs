@@ -0,0 +1,335 @@
+//! Filtering errors before they reach a sink.
+
+use crate::{RawSink, Sink};
+use std::ops::Range;
+use token::Token;
+
+/// An error reported to a sink.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorEvent<K> {
+  /// An error expecting the kinds, with `more` kinds omitted. Reported with
+  /// [`Sink::error`] if `more` is 0, else with [`Sink::error_capped`].
+  Error {
+    /// The expected kinds.
+    expected: Vec<K>,
+    /// The number of omitted expected kinds.
+    more: usize,
+  },
+  /// A missing token of the kind. Reported with [`Sink::missing`].
+  Missing(K),
+  /// An unclosed delimiter, expecting a closing delimiter of the kind.
+  /// Reported with [`Sink::unclosed`].
+  Unclosed(K),
+}
+
+/// Where an error is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorAt {
+  /// The byte range of the token it is at, reported to a [`Sink`].
+  Range(Range<usize>),
+  /// The index of the token it is at, reported to a [`RawSink`].
+  Idx(usize),
+}
+
+/// What is known about an error besides its kind.
+#[derive(Debug)]
+pub struct ErrorInfo<'a, E = String> {
+  /// Where the error is.
+  pub at: ErrorAt,
+  /// The labels on the context stack, outermost first. See
+  /// [`crate::Parser::context`].
+  pub context: &'a [&'static str],
+  /// The payload given to [`crate::Parser::error_with`], if any.
+  pub payload: Option<&'a E>,
+}
+
+/// Types which can drop or transform errors before they reach a sink.
+///
+/// This is implemented for closures.
+pub trait ErrorFilter<K, E = String> {
+  /// Returns the error to report instead of `error`, or `None` to drop it.
+  fn filter(
+    &mut self,
+    error: ErrorEvent<K>,
+    info: &ErrorInfo<'_, E>,
+  ) -> Option<ErrorEvent<K>>;
+}
+
+impl<K, E, F> ErrorFilter<K, E> for F
+where
+  F: FnMut(ErrorEvent<K>, &ErrorInfo<'_, E>) -> Option<ErrorEvent<K>>,
+{
+  fn filter(
+    &mut self,
+    error: ErrorEvent<K>,
+    info: &ErrorInfo<'_, E>,
+  ) -> Option<ErrorEvent<K>> {
+    self(error, info)
+  }
+}
+
+/// A sink which passes every error through a filter, and forwards everything
+/// else unchanged, to another sink.
+///
+/// The context and payload of an error are forwarded only if the error is
+/// kept, and only the kept errors are counted for [`Sink::finish_ok`].
+///
+/// Since this is itself a sink, filters may be layered.
+#[derive(Debug)]
+pub struct FilterSink<S, F, E = String> {
+  /// The sink.
+  pub sink: S,
+  /// The filter.
+  pub filter: F,
+  context: Vec<&'static str>,
+  payload: Option<E>,
+  /// the number of errors kept.
+  errors: usize,
+}
+
+impl<S, F, E> FilterSink<S, F, E> {
+  /// Returns a new `FilterSink`.
+  pub fn new(sink: S, filter: F) -> Self {
+    Self {
+      sink,
+      filter,
+      context: Vec::new(),
+      payload: None,
+      errors: 0,
+    }
+  }
+
+  fn report<K, R>(&mut self, error: ErrorEvent<K>, at: R::At)
+  where
+    F: ErrorFilter<K, E>,
+    R: ReportTo<S, K, E>,
+  {
+    let info = ErrorInfo {
+      at: R::error_at(&at),
+      context: &self.context,
+      payload: self.payload.as_ref(),
+    };
+    let error = self.filter.filter(error, &info);
+    let payload = self.payload.take();
+    if let Some(error) = error {
+      if !self.context.is_empty() {
+        R::context(&mut self.sink, &self.context);
+      }
+      if let Some(payload) = payload {
+        R::payload(&mut self.sink, payload);
+      }
+      self.errors += 1;
+      R::error(&mut self.sink, error, at);
+    }
+    self.context.clear();
+  }
+}
+
+/// How to report errors to a [`Sink`] or a [`RawSink`] `S`, so that a
+/// [`FilterSink`] filters errors the same way for both.
+trait ReportTo<S, K, E> {
+  /// where an error is.
+  type At;
+  fn error_at(at: &Self::At) -> ErrorAt;
+  fn context(sink: &mut S, labels: &[&'static str]);
+  fn payload(sink: &mut S, payload: E);
+  fn error(sink: &mut S, error: ErrorEvent<K>, at: Self::At);
+}
+
+/// Reporting to a [`Sink`].
+enum ToSink {}
+
+impl<S, K, E> ReportTo<S, K, E> for ToSink
+where
+  S: Sink<K, E>,
+{
+  type At = Range<usize>;
+
+  fn error_at(at: &Self::At) -> ErrorAt {
+    ErrorAt::Range(at.clone())
+  }
+
+  fn context(sink: &mut S, labels: &[&'static str]) {
+    sink.context(labels);
+  }
+
+  fn payload(sink: &mut S, payload: E) {
+    sink.payload(payload);
+  }
+
+  fn error(sink: &mut S, error: ErrorEvent<K>, range: Self::At) {
+    match error {
+      ErrorEvent::Error { expected, more: 0 } => sink.error(expected, range),
+      ErrorEvent::Error { expected, more } => {
+        sink.error_capped(expected, more, range)
+      }
+      ErrorEvent::Missing(kind) => sink.missing(kind, range),
+      ErrorEvent::Unclosed(close) => sink.unclosed(close, range),
+    }
+  }
+}
+
+/// Reporting to a [`RawSink`].
+enum ToRawSink {}
+
+impl<S, K, E> ReportTo<S, K, E> for ToRawSink
+where
+  S: RawSink<K, E>,
+{
+  type At = usize;
+
+  fn error_at(&at: &Self::At) -> ErrorAt {
+    ErrorAt::Idx(at)
+  }
+
+  fn context(sink: &mut S, labels: &[&'static str]) {
+    sink.context(labels);
+  }
+
+  fn payload(sink: &mut S, payload: E) {
+    sink.payload(payload);
+  }
+
+  fn error(sink: &mut S, error: ErrorEvent<K>, idx: Self::At) {
+    match error {
+      ErrorEvent::Error { expected, more: 0 } => sink.error(expected, idx),
+      ErrorEvent::Error { expected, more } => {
+        sink.error_capped(expected, more, idx)
+      }
+      ErrorEvent::Missing(kind) => sink.missing(kind, idx),
+      ErrorEvent::Unclosed(close) => sink.unclosed(close, idx),
+    }
+  }
+}
+
+impl<K, E, S, F> Sink<K, E> for FilterSink<S, F, E>
+where
+  S: Sink<K, E>,
+  F: ErrorFilter<K, E>,
+{
+  fn enter(&mut self, kind: K) {
+    self.sink.enter(kind);
+  }
+
+  fn token(&mut self, token: Token<'_, K>) {
+    self.sink.token(token);
+  }
+
+  fn exit(&mut self) {
+    self.sink.exit();
+  }
+
+  fn error(&mut self, expected: Vec<K>, range: Range<usize>) {
+    self.report::<_, ToSink>(ErrorEvent::Error { expected, more: 0 }, range);
+  }
+
+  fn error_capped(
+    &mut self,
+    expected: Vec<K>,
+    more: usize,
+    range: Range<usize>,
+  ) {
+    self.report::<_, ToSink>(ErrorEvent::Error { expected, more }, range);
+  }
+
+  fn missing(&mut self, kind: K, range: Range<usize>) {
+    self.report::<_, ToSink>(ErrorEvent::Missing(kind), range);
+  }
+
+  fn unclosed(&mut self, close: K, range: Range<usize>) {
+    self.report::<_, ToSink>(ErrorEvent::Unclosed(close), range);
+  }
+
+  fn context(&mut self, labels: &[&'static str]) {
+    self.context.clear();
+    self.context.extend_from_slice(labels);
+  }
+
+  fn payload(&mut self, payload: E) {
+    self.payload = Some(payload);
+  }
+
+  fn annotation(&mut self, data: u32) {
+    self.sink.annotation(data);
+  }
+
+  fn start(&mut self, total_tokens: usize) {
+    self.errors = 0;
+    self.sink.start(total_tokens);
+  }
+
+  fn finish_ok(&mut self, _: usize) {
+    self.sink.finish_ok(self.errors);
+  }
+
+  fn group_trivia(&self) -> bool {
+    self.sink.group_trivia()
+  }
+
+  fn trivia(&mut self, tokens: &[Token<'_, K>])
+  where
+    K: Copy,
+  {
+    self.sink.trivia(tokens);
+  }
+}
+
+impl<K, E, S, F> RawSink<K, E> for FilterSink<S, F, E>
+where
+  S: RawSink<K, E>,
+  F: ErrorFilter<K, E>,
+{
+  fn enter(&mut self, kind: K) {
+    self.sink.enter(kind);
+  }
+
+  fn tokens(&mut self, range: Range<usize>) {
+    self.sink.tokens(range);
+  }
+
+  fn remapped(&mut self, kind: K, idx: usize) {
+    self.sink.remapped(kind, idx);
+  }
+
+  fn exit(&mut self) {
+    self.sink.exit();
+  }
+
+  fn error(&mut self, expected: Vec<K>, idx: usize) {
+    self.report::<_, ToRawSink>(ErrorEvent::Error { expected, more: 0 }, idx);
+  }
+
+  fn error_capped(&mut self, expected: Vec<K>, more: usize, idx: usize) {
+    self.report::<_, ToRawSink>(ErrorEvent::Error { expected, more }, idx);
+  }
+
+  fn missing(&mut self, kind: K, idx: usize) {
+    self.report::<_, ToRawSink>(ErrorEvent::Missing(kind), idx);
+  }
+
+  fn unclosed(&mut self, close: K, idx: usize) {
+    self.report::<_, ToRawSink>(ErrorEvent::Unclosed(close), idx);
+  }
+
+  fn context(&mut self, labels: &[&'static str]) {
+    self.context.clear();
+    self.context.extend_from_slice(labels);
+  }
+
+  fn payload(&mut self, payload: E) {
+    self.payload = Some(payload);
+  }
+
+  fn annotation(&mut self, data: u32) {
+    self.sink.annotation(data);
+  }
+
+  fn start(&mut self, total_tokens: usize) {
+    self.errors = 0;
+    self.sink.start(total_tokens);
+  }
+
+  fn finish_ok(&mut self, _: usize) {
+    self.sink.finish_ok(self.errors);
+  }
+}
@@ -0,0 +1,120 @@
+//! Fuzzing parsers, with the `arbitrary` feature.
+//!
+//! With `cargo fuzz`, after implementing [`FuzzKind`] for the kinds:
+//!
+//! ```ignore
+//! #![no_main]
+//! use event_parse::fuzz::{check, TokenStream};
+//! use libfuzzer_sys::fuzz_target;
+//!
+//! fuzz_target!(|stream: TokenStream<SyntaxKind>| {
+//!   check(&stream.tokens(), my_parser::root);
+//! });
+//! ```
+
+use crate::{CheckSink, Parser, RawSink};
+use arbitrary::{Arbitrary, Result, Unstructured};
+use std::fmt::Debug;
+use std::ops::Range;
+use token::{Token, Triviable};
+
+/// Kinds of tokens which may be generated for fuzzing.
+pub trait FuzzKind: 'static + Sized + Copy + Triviable {
+  /// Returns all the kinds of tokens to generate, both trivia and not.
+  fn kinds() -> &'static [Self];
+
+  /// Returns the text for a token of this kind.
+  fn text(&self) -> &'static str;
+
+  /// Returns the percentage, from 0 to 100, of generated tokens which should
+  /// be trivia. Defaults to 20.
+  fn trivia_percent() -> u8 {
+    20
+  }
+}
+
+/// A stream of tokens, generated with [`Arbitrary`].
+#[derive(Debug, Clone)]
+pub struct TokenStream<K> {
+  kinds: Vec<K>,
+}
+
+impl<K> TokenStream<K>
+where
+  K: FuzzKind,
+{
+  /// Returns the tokens.
+  pub fn tokens(&self) -> Vec<Token<'static, K>> {
+    self
+      .kinds
+      .iter()
+      .map(|&kind| Token::new(kind, kind.text()))
+      .collect()
+  }
+}
+
+impl<'a, K> Arbitrary<'a> for TokenStream<K>
+where
+  K: FuzzKind,
+{
+  fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+    let (trivia, other): (Vec<K>, Vec<K>) =
+      K::kinds().iter().partition(|k| k.is_trivia());
+    let percent = K::trivia_percent().min(100);
+    // choosing from a pool of one kind takes no bytes, so get the length
+    // first to be sure this ends.
+    let len = u.arbitrary_len::<u8>()?;
+    let mut kinds = Vec::with_capacity(len);
+    for _ in 0..len {
+      let pool = if other.is_empty()
+        || (!trivia.is_empty() && percent != 0 && u.ratio(percent, 100)?)
+      {
+        &trivia
+      } else {
+        &other
+      };
+      if pool.is_empty() {
+        break;
+      }
+      kinds.push(*u.choose(pool)?);
+    }
+    Ok(Self { kinds })
+  }
+}
+
+/// Parses the tokens with `parse`, then finishes the parser and panics if
+/// there was not exactly one root syntax construct, with every token in it
+/// exactly once, in order.
+///
+/// `parse` should parse everything in one root syntax construct, as with
+/// [`Parser::parse_file`]. Any panic in `parse` or in the parser itself also
+/// propagates, so with a fuzzer this checks that the parser never panics.
+pub fn check<K, F>(tokens: &[Token<'_, K>], parse: F)
+where
+  K: Copy + Triviable + Debug,
+  F: FnOnce(&mut Parser<'_, K>),
+{
+  let mut p = Parser::new(tokens);
+  parse(&mut p);
+  let mut sink = CheckSink::new(Discard);
+  p.finish_raw(&mut sink);
+  let next = sink.next_token();
+  sink.finish();
+  assert!(
+    next == tokens.len(),
+    "not all tokens were reported: {:?}",
+    &tokens[next..]
+  );
+}
+
+struct Discard;
+
+impl<K> RawSink<K> for Discard {
+  fn enter(&mut self, _: K) {}
+
+  fn tokens(&mut self, _: Range<usize>) {}
+
+  fn exit(&mut self) {}
+
+  fn error(&mut self, _: Vec<K>, _: usize) {}
+}
@@ -9,39 +9,255 @@
 //!    contiguous [`Token`]s using this `SyntaxKind`.
 //! 4. Define your language's grammar with functions operating on a [`Parser`].
 //! 5. Call [`Parser::finish`] when done, and feed it a suitable [`Sink`] for
-//!    the collected parsing events.
+//!    the collected parsing events. (Or call [`Parser::finish_raw`] with a
+//!    [`RawSink`].)
 //!
 //! A similar approach is used in [rust-analyzer][1].
 //!
+//! Nothing in this crate recurses on the nesting of the input, so deeply nested
+//! input cannot overflow the stack inside it. Grammar functions and sinks must
+//! make the same guarantee themselves if they need it.
+//!
+//! With [`grammar_ext`], grammar functions may be called as methods on the
+//! parser.
+//!
+//! Without a syntax tree library, the [`simple`] module has a sink which builds
+//! a simple tree.
+//!
+//! For expressions with operators, the [`pratt`] module has a driver for
+//! parsing them by precedence.
+//!
+//! For large inputs, [`lex_parallel`] lexes with a restartable lexer in
+//! parallel, producing one vector of tokens for one `Parser`.
+//!
+//! With the `arbitrary` feature, the [`fuzz`] module has helpers for fuzzing
+//! parsers.
+//!
+//! With the `tracing` feature, finishing happens in a `tracing` span with the
+//! number of tokens and errors, and [`Parser::rule_span`] is available to make
+//! spans for parsing individual rules.
+//!
 //! [1]: https://github.com/rust-analyzer/rust-analyzer
 
 #![deny(missing_debug_implementations)]
 #![deny(missing_docs)]
 #![deny(rust_2018_idioms)]
 
+mod attach;
+mod check;
+mod ext;
+mod filter;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+mod metrics;
+mod parallel;
+pub mod pratt;
+pub mod record;
+pub mod simple;
+mod tee;
+#[cfg(test)]
+mod tests;
+
+pub use attach::{attach_trivia, Attach, FileTrivia};
+pub use check::CheckSink;
+#[doc(hidden)]
+pub use ext::ParserLike;
+pub use filter::{ErrorAt, ErrorEvent, ErrorFilter, ErrorInfo, FilterSink};
+pub use metrics::{Metrics, MetricsSink};
+pub use parallel::lex_parallel;
+pub use tee::TeeSink;
+
 use drop_bomb::DropBomb;
-use token::{Token, Triviable};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use token::{Flags, Token, Triviable};
 
 /// A event-based parser.
+///
+/// Errors may carry payloads of type `E`, given to [`Self::error_with`].
+///
+/// Empty input, with no tokens or only trivia, needs no special handling.
+/// Entering and exiting a root there gives a valid root containing only the
+/// trivia, and nothing records an error unless the grammar requires a token.
 #[derive(Debug)]
-pub struct Parser<'input, K> {
+pub struct Parser<'input, K, E = String> {
   tokens: &'input [Token<'input, K>],
+  /// the index of the first token in some larger sequence of tokens.
+  base: usize,
   idx: usize,
+  /// a small vector with inline storage was measured to be slower than this,
+  /// even though most sets of expected kinds are small. see
+  /// `benches/expected.rs`.
   expected: Vec<K>,
   events: Vec<Option<Event<K>>>,
+  /// the `Entered`s, in order. those which were exited or abandoned are removed
+  /// once every later one was too.
+  open: Vec<Open<K>>,
+  /// the number of `open` which are not closed.
+  depth: usize,
+  /// the expected kinds for all the errors, which refer to ranges of this.
+  expected_table: Vec<K>,
+  /// shared with the `Context`s, which pop themselves when dropped.
+  context: Arc<Mutex<Vec<&'static str>>>,
+  /// the contexts for all the errors, which refer to ranges of this.
+  context_table: Vec<&'static str>,
+  /// the data for all the errors, which their events refer to by index, so
+  /// that the events stay small.
+  errors: Vec<ErrorData>,
+  /// the payloads of errors, with the indices of their events, in order.
+  payloads: Vec<(usize, E)>,
+  /// the annotations of syntax constructs, with the indices of their `Enter`
+  /// events, in the order they were made.
+  annotations: Vec<(usize, u32)>,
+  attach: Vec<Attach>,
+  file_leading: FileTrivia,
+  file_trailing: FileTrivia,
+  expected_order: Option<fn(&K, &K) -> Ordering>,
+  expected_cap: usize,
+  missing_follow: Vec<K>,
+  /// the number of calls to abandon.
+  abandons: usize,
+  strict_saves: bool,
 }
 
 impl<'input, K> Parser<'input, K> {
   /// Returns a new parser for the given tokens.
   pub fn new(tokens: &'input [Token<'input, K>]) -> Self {
+    Self::new_at(tokens, 0)
+  }
+
+  /// Returns a new parser for the given tokens, which are a sub-slice of some
+  /// larger sequence of tokens, starting at index `base` in that sequence.
+  ///
+  /// This is useful for e.g. re-parsing only part of a file, or parsing a
+  /// language embedded in another. The token ranges given to a [`RawSink`] are
+  /// indices in the larger sequence, i.e. they are offset by `base`.
+  pub fn new_at(tokens: &'input [Token<'input, K>], base: usize) -> Self {
+    Self::new_with_payload(tokens, base)
+  }
+}
+
+impl<'input, K, E> Parser<'input, K, E> {
+  /// Returns a new parser like [`Parser::new_at`], whose errors may carry
+  /// payloads of type `E` instead of `String`.
+  pub fn new_with_payload(
+    tokens: &'input [Token<'input, K>],
+    base: usize,
+  ) -> Self {
     Self {
       tokens,
+      base,
       idx: 0,
       expected: Vec::new(),
       events: Vec::new(),
+      open: Vec::new(),
+      depth: 0,
+      expected_table: Vec::new(),
+      context: Arc::default(),
+      context_table: Vec::new(),
+      errors: Vec::new(),
+      payloads: Vec::new(),
+      annotations: Vec::new(),
+      attach: Vec::new(),
+      file_leading: FileTrivia::Attach,
+      file_trailing: FileTrivia::Attach,
+      expected_order: None,
+      expected_cap: usize::MAX,
+      missing_follow: Vec::new(),
+      abandons: 0,
+      strict_saves: false,
     }
   }
 
+  /// Sets the order of the expected kinds reported with errors.
+  ///
+  /// By default, the expected kinds are reported in the order they were passed
+  /// to [`Self::at`], possibly with duplicates. This order depends on the
+  /// order in which the grammar tries things, so it can change when the grammar
+  /// is refactored.
+  ///
+  /// If an order is set, the expected kinds are instead guaranteed to be
+  /// sorted by `order`, and to contain no two kinds which `order` considers
+  /// equal. So the expected kinds for an error depend only on the set of kinds
+  /// that were tried, not the order in which they were tried. For instance,
+  /// `order` could compare the discriminants or descriptions of the kinds.
+  pub fn set_expected_order(&mut self, order: fn(&K, &K) -> Ordering) {
+    self.expected_order = Some(order);
+  }
+
+  /// Sets the maximum number of expected kinds stored for each error.
+  ///
+  /// If more kinds than this were expected, only the first `cap` of them (in
+  /// the order described in [`Self::set_expected_order`]) are stored, and the
+  /// error is reported with [`Sink::error_capped`] along with how many were
+  /// omitted. This bounds the memory used for errors on adversarial inputs.
+  ///
+  /// By default, there is no maximum.
+  pub fn set_expected_cap(&mut self, cap: usize) {
+    self.expected_cap = cap;
+  }
+
+  /// Sets whether dropping a [`Save`] without passing it to [`Self::restore`],
+  /// [`Self::restore_keeping_expected`], or [`Self::forget`] panics.
+  ///
+  /// If this was set and debug assertions are enabled, dropping such a `Save`
+  /// panics. Otherwise, it is silently allowed. Dropping a `Save` loses the
+  /// kinds expected when it was created, so later errors may report fewer
+  /// expected kinds than they should.
+  pub fn set_strict_saves(&mut self, strict: bool) {
+    self.strict_saves = strict;
+  }
+
+  /// Sets the kinds of tokens before which [`Self::eat`] reports a missing
+  /// token instead of consuming the current token.
+  ///
+  /// When `eat` fails and the current token's kind is in `follow`, it records
+  /// that the token it tried to eat was missing, reported with
+  /// [`Sink::missing`], and consumes nothing. This is appropriate when the
+  /// current token is likely to begin what comes after the missing token, as
+  /// with e.g. a missing `;` before a keyword starting the next statement.
+  ///
+  /// By default, `follow` is empty, so `eat` always consumes the current token
+  /// on failure.
+  pub fn set_missing_follow(&mut self, follow: Vec<K>) {
+    self.missing_follow = follow;
+  }
+
+  /// Sets what each token is attached to, e.g. with the result of
+  /// [`attach_trivia`]. There must be exactly one [`Attach`] for each token.
+  ///
+  /// When finishing, trivia attached to a token is kept in the same syntax
+  /// construct as that token. Leading trivia is put in the outermost construct
+  /// that starts at the token, and trailing trivia is put in the outermost
+  /// construct that ends at the token, except that trivia is never attached to
+  /// the root construct if it could be attached to a top-level item instead.
+  ///
+  /// By default, no trivia is attached to anything.
+  pub fn set_attach(&mut self, attach: Vec<Attach>) {
+    assert_eq!(attach.len(), self.tokens.len());
+    self.attach = attach;
+  }
+
+  /// Sets where to put the trivia before the first non-trivia token.
+  ///
+  /// By default, this is [`FileTrivia::Attach`], so e.g. a licence header
+  /// directly above the first item is put in that item if it is attached to
+  /// the item's first token.
+  pub fn set_file_leading_trivia(&mut self, leading: FileTrivia) {
+    self.file_leading = leading;
+  }
+
+  /// Sets where to put the trivia after the last non-trivia token.
+  ///
+  /// By default, this is [`FileTrivia::Attach`]. With it, trivia which is
+  /// attached to nothing, like all the trivia in a file with no items, is put
+  /// in the root.
+  pub fn set_file_trailing_trivia(&mut self, trailing: FileTrivia) {
+    self.file_trailing = trailing;
+  }
+
   /// Starts parsing a syntax construct.
   ///
   /// The returned [`Entered`] must eventually be passed to [`Self::exit`] or
@@ -62,25 +278,77 @@ impl<'input, K> Parser<'input, K> {
   ///
   /// then Weird Things might happen.
   pub fn enter(&mut self) -> Entered {
+    self.enter_open(None)
+  }
+
+  /// Starts parsing a syntax construct, like [`Self::enter`], which is expected
+  /// to be exited with the given `kind`.
+  ///
+  /// The kind is only used for [`Self::open_kinds`]. The construct still gets
+  /// the kind it is exited with.
+  pub fn enter_kind(&mut self, kind: K) -> Entered {
+    self.enter_open(Some(kind))
+  }
+
+  fn enter_open(&mut self, kind: Option<K>) -> Entered {
     let idx = self.events.len();
     self.events.push(None);
+    self.open.push(Open {
+      idx,
+      kind,
+      closed: false,
+    });
+    self.depth += 1;
     Entered {
       bomb: DropBomb::new("Entered markers must be exited"),
       idx,
     }
   }
 
+  fn close(&mut self, entered: &Entered) {
+    let pos = self.open.binary_search_by_key(&entered.idx, |x| x.idx);
+    self.open[pos.expect("Entered marker was not open")].closed = true;
+    self.depth -= 1;
+    while matches!(self.open.last(), Some(x) if x.closed) {
+      self.open.pop();
+    }
+  }
+
+  /// Returns the number of syntax constructs which were entered but not yet
+  /// exited or abandoned.
+  pub fn depth(&self) -> usize {
+    self.depth
+  }
+
+  /// Returns the kinds given to [`Self::enter_kind`] of the syntax constructs
+  /// which were entered but not yet exited or abandoned, outermost first, with
+  /// `None` for those entered otherwise.
+  ///
+  /// This is useful for e.g. recovering differently inside a loop body.
+  pub fn open_kinds(&self) -> impl DoubleEndedIterator<Item = Option<&K>> {
+    self
+      .open
+      .iter()
+      .filter(|x| !x.closed)
+      .map(|x| x.kind.as_ref())
+  }
+
   /// Abandons parsing a syntax construct.
   ///
   /// The events recorded since this syntax construct began, if any, will belong
-  /// to the parent.
+  /// to the parent. This includes any completed syntax constructs that were
+  /// made children of this one with [`Self::precede`], so it is fine to e.g.
+  /// `precede` a node, try to parse more, and then `abandon` if there was
+  /// nothing more.
   pub fn abandon(&mut self, mut entered: Entered) {
+    self.close(&entered);
     entered.bomb.defuse();
-    assert!(self.events[entered.idx].is_none());
+    self.abandons += 1;
   }
 
   /// Finishes parsing a syntax construct.
   pub fn exit(&mut self, mut entered: Entered, kind: K) -> Exited {
+    self.close(&entered);
     entered.bomb.defuse();
     let ev = &mut self.events[entered.idx];
     assert!(ev.is_none());
@@ -107,9 +375,156 @@ impl<'input, K> Parser<'input, K> {
     }
     ret
   }
+
+  /// Annotates the completed syntax construct with `data`, which is reported
+  /// with [`Sink::annotation`] just after the construct is entered.
+  ///
+  /// This is for facts the grammar learned while parsing the construct, like
+  /// that it had a trailing comma, so they need not be found again from the
+  /// tree. A construct may have many annotations, which are reported in the
+  /// order they were made.
+  pub fn annotate(&mut self, exited: &Exited, data: u32) {
+    self.annotations.push((exited.idx, data));
+  }
+
+  /// Returns the index of the current token in the slice of tokens given to
+  /// [`Self::new`]. This may be the index of trivia before the current token.
+  pub fn token_idx(&self) -> usize {
+    self.idx
+  }
+
+  /// Returns an estimate of the number of bytes of heap memory used by the
+  /// parser, not including the tokens.
+  ///
+  /// This grows as parsing progresses, and is at its peak just before
+  /// finishing.
+  pub fn memory_usage(&self) -> usize {
+    use std::mem::size_of;
+    self.events.capacity() * size_of::<Option<Event<K>>>()
+      + (self.expected.capacity()
+        + self.expected_table.capacity()
+        + self.missing_follow.capacity())
+        * size_of::<K>()
+      + self.open.capacity() * size_of::<Open<K>>()
+      + self.attach.capacity() * size_of::<Attach>()
+      + self.context_table.capacity() * size_of::<&str>()
+      + self.errors.capacity() * size_of::<ErrorData>()
+      + self.payloads.capacity() * size_of::<(usize, E)>()
+      + self.annotations.capacity() * size_of::<(usize, u32)>()
+  }
+
+  /// Returns a span for parsing a rule with the given `name`, which should be
+  /// kept entered while the rule is parsed. The span records the index of the
+  /// token at which the rule started.
+  ///
+  /// Requires the `tracing` feature.
+  #[cfg(feature = "tracing")]
+  pub fn rule_span(&self, name: &'static str) -> tracing::span::EnteredSpan {
+    tracing::trace_span!("rule", name, token = self.idx).entered()
+  }
+
+  #[cfg(feature = "tracing")]
+  fn error_count(&self) -> usize {
+    self
+      .events
+      .iter()
+      .filter(|ev| {
+        matches!(
+          ev,
+          Some(Event::Error(..))
+            | Some(Event::Missing(..))
+            | Some(Event::Unclosed(..))
+        )
+      })
+      .count()
+  }
+
+  /// Pushes `label` onto the context stack, until the returned [`Context`] is
+  /// dropped.
+  ///
+  /// Errors recorded while labels are on the stack are reported along with
+  /// them, with [`Sink::context`], so that e.g. an error may say it expected
+  /// `,` while parsing function parameters.
+  pub fn context(&mut self, label: &'static str) -> Context {
+    let mut context = self.context.lock().unwrap();
+    let depth = context.len();
+    context.push(label);
+    Context {
+      stack: Arc::clone(&self.context),
+      depth,
+    }
+  }
+
+  /// Saves the state of the parser, so that it may later be restored with
+  /// [`Self::restore`].
+  ///
+  /// This also takes the set of expected kinds, so that it may be restored.
+  /// The returned [`Save`] should eventually be passed to `restore`,
+  /// [`Self::restore_keeping_expected`], or [`Self::forget`]. See
+  /// [`Self::set_strict_saves`].
+  pub fn save(&mut self) -> Save<K> {
+    Save {
+      idx: self.idx,
+      events_len: self.events.len(),
+      expected_table_len: self.expected_table.len(),
+      context_table_len: self.context_table.len(),
+      errors_len: self.errors.len(),
+      payloads_len: self.payloads.len(),
+      annotations_len: self.annotations.len(),
+      expected: std::mem::take(&mut self.expected),
+      done: false,
+      strict: self.strict_saves,
+    }
+  }
+
+  /// Restores the state of the parser to what it was when `save` was created.
+  ///
+  /// All [`Entered`]s created since `save` was created should be consumed
+  /// before calling this, and no [`Exited`] created before `save` was should
+  /// have been passed to [`Self::precede`] since.
+  pub fn restore(&mut self, mut save: Save<K>) {
+    save.done = true;
+    self.idx = save.idx;
+    self.events.truncate(save.events_len);
+    self.expected_table.truncate(save.expected_table_len);
+    self.context_table.truncate(save.context_table_len);
+    self.errors.truncate(save.errors_len);
+    self.payloads.truncate(save.payloads_len);
+    self.annotations.truncate(save.annotations_len);
+    self.expected = std::mem::take(&mut save.expected);
+  }
+
+  /// Like [`Self::restore`], but keeps the kinds expected since `save` was
+  /// created, adding them to the kinds expected when it was created.
+  ///
+  /// This is for trying alternatives in turn, restoring after each one fails,
+  /// so that an error after the last one reports the kinds that could have
+  /// started any of them. The kept kinds are only accurate if the failed
+  /// alternative did not consume any tokens before failing.
+  pub fn restore_keeping_expected(&mut self, save: Save<K>) {
+    let expected = std::mem::take(&mut self.expected);
+    self.restore(save);
+    self.expected.extend(expected);
+  }
+
+  /// Declares that `save` will not be restored, e.g. because the alternative
+  /// tried after creating it succeeded.
+  ///
+  /// If no tokens were consumed since `save` was created, this puts back the
+  /// kinds expected when it was created, before the kinds expected since.
+  /// Otherwise, those kinds are out of date, as with [`Self::bump`], and are
+  /// discarded.
+  pub fn forget(&mut self, mut save: Save<K>) {
+    save.done = true;
+    if self.idx == save.idx {
+      let mut expected = std::mem::take(&mut save.expected);
+      expected.append(&mut self.expected);
+      self.expected = expected;
+    }
+  }
 }
 
-impl<'input, K> Parser<'input, K>
+impl<'input, K, E> Parser<'input, K, E>
 where
   K: Copy + Triviable,
 {
@@ -128,6 +543,21 @@ where
     None
   }
 
+  /// Returns the index of the current token, or the number of tokens if the
+  /// parser is out of tokens.
+  fn peek_idx(&mut self) -> usize {
+    self.peek();
+    self.idx
+  }
+
+  /// Returns whether the current token has all of the `flags`, as set by the
+  /// lexer. Returns `false` if there is no current token.
+  ///
+  /// Unlike [`Self::at`], this does not record anything as expected.
+  pub fn at_flag(&mut self, flags: Flags) -> bool {
+    matches!(self.peek(), Some(tok) if tok.flags.contains(flags))
+  }
+
   /// Returns the token `n` tokens in front of the current token, or `None` if
   /// there is no such token.
   ///
@@ -154,42 +584,298 @@ where
   /// tokens.
   ///
   /// Panics if there are no more tokens, i.e. if [`Self::peek`] would return
-  /// `None` just prior to calling this.
+  /// `None` just prior to calling this. See [`Self::try_bump`] for a version
+  /// which does not panic.
   ///
   /// This is often used after calling [`Self::at`] to verify some expected
   /// token was present.
   pub fn bump(&mut self) -> Token<'input, K> {
-    let ret = self.peek().expect("bump with no tokens");
+    self.try_bump().expect("bump with no tokens")
+  }
+
+  /// Like [`Self::bump`], but returns `None` and does nothing else instead of
+  /// panicking if there are no more tokens.
+  ///
+  /// This is useful where consuming one more token is optional, like when
+  /// skipping a token after an error, which may be at the end of a truncated
+  /// input.
+  pub fn try_bump(&mut self) -> Option<Token<'input, K>> {
+    let ret = self.peek()?;
     self.events.push(Some(Event::Token));
     self.idx += 1;
     self.expected.clear();
-    ret
+    Some(ret)
+  }
+
+  /// Like [`Self::bump`], but the token is reported with the given `kind`
+  /// instead of its own, as with [`RawSink::remapped`]. Returns the token with
+  /// that kind.
+  ///
+  /// This is useful for contextual keywords, which are lexed like identifiers.
+  /// The `kind` should not be trivia.
+  pub fn bump_remap(&mut self, kind: K) -> Token<'input, K> {
+    let ret = self.bump();
+    let ev = self.events.last_mut().expect("no event for the token");
+    *ev = Some(Event::Remap(kind));
+    Token { kind, ..ret }
+  }
+
+  /// Starts tracking the progress of a grammar rule (i.e. a function which
+  /// parses some syntax construct) with the given `name`.
+  ///
+  /// The returned [`RuleStart`] must eventually be passed to
+  /// [`Self::end_rule`]. If it is not, it will panic when dropped.
+  pub fn start_rule(&mut self, name: &'static str) -> RuleStart {
+    self.peek();
+    RuleStart {
+      bomb: DropBomb::new("RuleStart markers must be ended"),
+      name,
+      idx: self.idx,
+      events_len: self.events.len(),
+      abandons: self.abandons,
+    }
+  }
+
+  /// Finishes tracking the progress of a grammar rule.
+  ///
+  /// With debug assertions enabled, this panics if, since `start` was
+  /// created, no tokens were consumed, no syntax constructs were abandoned,
+  /// and no errors were recorded. A rule which does none of these made no
+  /// progress while claiming success, which can lead to an infinite loop in a
+  /// rule which uses it repeatedly.
+  pub fn end_rule(&mut self, mut start: RuleStart) {
+    start.bomb.defuse();
+    if !cfg!(debug_assertions) {
+      return;
+    }
+    let progress = self.idx > start.idx
+      || self.abandons > start.abandons
+      || self.events.get(start.events_len..).map_or(false, |evs| {
+        evs.iter().any(|ev| {
+          matches!(
+            ev,
+            Some(Event::Error(..))
+              | Some(Event::Missing(..))
+              | Some(Event::Unclosed(..))
+          )
+        })
+      });
+    assert!(progress, "rule {} made no progress", start.name);
   }
 
   /// Records an error at the current token.
   pub fn error(&mut self) {
-    let expected = std::mem::take(&mut self.expected);
-    if self.peek().is_some() {
+    let (expected, more) = self.take_expected();
+    let at = self.peek_idx();
+    if at < self.tokens.len() {
       self.bump();
     }
-    self.events.push(Some(Event::Error(expected)));
+    let context = self.take_context();
+    self.push_error(ErrorData {
+      expected,
+      more,
+      context,
+      at,
+    });
   }
 
-  fn eat_trivia(&mut self, sink: &mut dyn Sink<K>) {
-    while let Some(&tok) = self.tokens.get(self.idx) {
-      if !tok.kind.is_trivia() {
+  /// Records an error at the current token, like [`Self::error`], with the
+  /// `payload`, which is reported with [`Sink::payload`].
+  ///
+  /// This is for data the grammar knows about the error, like a diagnostic
+  /// code or a suggested fix.
+  pub fn error_with(&mut self, payload: E) {
+    self.error();
+    self.payloads.push((self.events.len() - 1, payload));
+  }
+
+  /// Requires that there are no more tokens, besides trivia.
+  ///
+  /// If there are more tokens, this records an error at the first of them with
+  /// no expected kinds, which means the end of the tokens was expected, and
+  /// consumes the rest. Otherwise, this does nothing.
+  ///
+  /// Either way, [`Self::peek`] returns `None` afterwards.
+  pub fn expect_eof(&mut self) {
+    if self.peek().is_none() {
+      return;
+    }
+    self.expected.clear();
+    self.error();
+    while self.peek().is_some() {
+      self.bump();
+    }
+  }
+
+  /// Records an error at the current token, like [`Self::error`], but wraps
+  /// the token skipped because of the error in a syntax construct with the
+  /// given `kind`.
+  ///
+  /// `trivia` controls whether the trivia before the skipped token is also
+  /// put in that construct, so that the construct covers the whole region of
+  /// skipped text.
+  ///
+  /// If there are no more tokens, this is the same as `error`.
+  pub fn error_node(&mut self, kind: K, trivia: ErrorTrivia) {
+    let (expected, more) = self.take_expected();
+    let at = self.peek_idx();
+    if at < self.tokens.len() {
+      if let ErrorTrivia::Include = trivia {
+        if self.attach.is_empty() {
+          self.attach = vec![Attach::None; self.tokens.len()];
+        }
+        let mut idx = self.idx;
+        while idx > 0 && self.tokens[idx - 1].kind.is_trivia() {
+          idx -= 1;
+          self.attach[idx] = Attach::Leading;
+        }
+      }
+      let en = self.enter();
+      self.bump();
+      self.exit(en, kind);
+    }
+    let context = self.take_context();
+    self.push_error(ErrorData {
+      expected,
+      more,
+      context,
+      at,
+    });
+  }
+
+  /// Records an error with the `data`.
+  fn push_error(&mut self, data: ErrorData) {
+    let idx = u32::try_from(self.errors.len()).expect("too many errors");
+    self.errors.push(data);
+    self.events.push(Some(Event::Error(idx)));
+  }
+
+  /// Adds the data for a missing token or unclosed delimiter of the `kind` at
+  /// the token index `at`, with the current context, and returns its index.
+  fn push_error_data(&mut self, kind: K, at: usize) -> u32 {
+    let idx = u32::try_from(self.errors.len()).expect("too many errors");
+    let start = self.expected_table.len();
+    self.expected_table.push(kind);
+    let context = self.take_context();
+    self.errors.push(ErrorData {
+      expected: start..self.expected_table.len(),
+      more: 0,
+      context,
+      at,
+    });
+    idx
+  }
+
+  /// Moves the expected kinds into the table, and returns their range in it
+  /// and the number of kinds omitted because of the cap.
+  fn take_expected(&mut self) -> (Range<usize>, usize) {
+    if let Some(order) = self.expected_order {
+      self.expected.sort_by(order);
+      self
+        .expected
+        .dedup_by(|a, b| order(a, b) == Ordering::Equal);
+    }
+    let more = self.expected.len().saturating_sub(self.expected_cap);
+    self.expected.truncate(self.expected_cap);
+    let start = self.expected_table.len();
+    self.expected_table.append(&mut self.expected);
+    (start..self.expected_table.len(), more)
+  }
+
+  /// Copies the context stack into the table, and returns its range in it.
+  /// Consecutive errors in the same context share a range.
+  fn take_context(&mut self) -> Range<usize> {
+    let context = self.context.lock().unwrap();
+    let end = self.context_table.len();
+    let start = end.saturating_sub(context.len());
+    if !context.is_empty() && self.context_table[start..] == context[..] {
+      return start..end;
+    }
+    self.context_table.extend_from_slice(&context);
+    end..self.context_table.len()
+  }
+
+  fn eat_trivia(&mut self) {
+    self.eat_trivia_while(|_| true);
+  }
+
+  fn eat_trivia_while<F>(&mut self, f: F)
+  where
+    F: Fn(Attach) -> bool,
+  {
+    while let Some(tok) = self.tokens.get(self.idx) {
+      let attach = self.attach.get(self.idx).copied().unwrap_or(Attach::None);
+      if !tok.kind.is_trivia() || !f(attach) {
         break;
       }
-      sink.token(tok);
       self.idx += 1;
     }
   }
 
+  /// Returns whether the next event after `idx` is an exit.
+  fn next_is_exit(&self, idx: usize) -> bool {
+    let next = self.events[idx + 1..].iter().find_map(Option::as_ref);
+    matches!(next, Some(Event::Exit))
+  }
+
   /// Finishes parsing, and writes the parsed tree into the `sink`.
-  pub fn finish(mut self, sink: &mut dyn Sink<K>) {
+  pub fn finish(self, sink: &mut dyn Sink<K, E>) {
+    let tokens = self.tokens;
+    let base = self.base;
+    self.finish_raw(&mut TokenSink {
+      tokens,
+      base,
+      sink,
+      next: 0,
+      offset: 0,
+      buf: Vec::new(),
+    });
+  }
+
+  /// Finishes parsing, and writes the parsed tree into the `sink`, which
+  /// receives ranges of token indices instead of tokens.
+  ///
+  /// The sink receives the same tree as it would from [`Self::finish`], but
+  /// consecutive tokens are reported all at once.
+  ///
+  /// This is iterative, and calls the sink with the stack no deeper than it
+  /// was when this was called, no matter how deeply nested the tree is.
+  ///
+  /// With debug assertions, this panics if the events would report a token
+  /// more than once, out of order, or past the end of the tokens, which can
+  /// only happen if the parser was misused, as with [`Self::restore`]. It also
+  /// panics if some tokens would not be reported at all, because the grammar
+  /// did not consume them. See [`Self::expect_eof`].
+  pub fn finish_raw(mut self, sink: &mut dyn RawSink<K, E>) {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!(
+      "finish",
+      tokens = self.tokens.len(),
+      errors = self.error_count(),
+    )
+    .entered();
+    sink.start(self.tokens.len());
     self.idx = 0;
+    let mut errors: usize = 0;
+    // the start of the tokens not yet reported to the sink. the end is idx.
+    let mut start: usize = 0;
     let mut kinds = Vec::new();
     let mut levels: usize = 0;
+    let mut payloads =
+      std::mem::take(&mut self.payloads).into_iter().peekable();
+    let mut annotations = std::mem::take(&mut self.annotations);
+    annotations.sort_by_key(|&(ev, _)| ev);
+    // the trivia before `first` and after `last` is at the file start and end.
+    let first = self
+      .tokens
+      .iter()
+      .position(|tok| !tok.kind.is_trivia())
+      .unwrap_or(self.tokens.len());
+    let last = self
+      .tokens
+      .iter()
+      .rposition(|tok| !tok.kind.is_trivia())
+      .map_or(0, |idx| idx + 1);
     for idx in 0..self.events.len() {
       let ev = match self.events[idx].take() {
         Some(ev) => ev,
@@ -198,46 +884,147 @@ where
       match ev {
         Event::Enter(kind, mut parent) => {
           assert!(kinds.is_empty());
-          kinds.push(kind);
+          kinds.push((kind, idx));
           while let Some(p) = parent {
             match self.events[p].take() {
               Some(Event::Enter(kind, new_parent)) => {
-                kinds.push(kind);
+                kinds.push((kind, p));
                 parent = new_parent;
               }
+              // the parent was abandoned.
+              None => break,
               _ => unreachable!("{:?} was not an Enter", parent),
             }
           }
-          for kind in kinds.drain(..).rev() {
+          for (kind, ev) in kinds.drain(..).rev() {
+            let file_start = self.idx < first;
             // keep as much trivia as possible outside of what we're entering.
             if levels != 0 {
-              self.eat_trivia(sink);
+              match (file_start, self.file_leading) {
+                (true, FileTrivia::Root) => self.eat_trivia(),
+                (true, FileTrivia::Item) => {}
+                _ => self.eat_trivia_while(|a| a != Attach::Leading),
+              }
             }
+            flush(sink, self.base, &mut start, self.idx);
             sink.enter(kind);
+            let first = annotations
+              .binary_search_by(|&(i, _)| i.cmp(&ev).then(Ordering::Greater))
+              .unwrap_err();
+            for &(i, data) in &annotations[first..] {
+              if i != ev {
+                break;
+              }
+              sink.annotation(data);
+            }
+            // but keep leading trivia in the outermost construct that starts
+            // at the token it's attached to.
+            if levels != 0 {
+              match (file_start, self.file_leading) {
+                (true, FileTrivia::Item) => self.eat_trivia(),
+                _ => self.eat_trivia_while(|a| a == Attach::Leading),
+              }
+            }
             levels += 1;
           }
         }
         Event::Exit => {
+          let file_end = self.idx >= last;
+          match (file_end, self.file_trailing) {
+            (true, FileTrivia::Root) => {}
+            (true, FileTrivia::Item) => {
+              if levels <= 2 {
+                self.eat_trivia();
+              }
+            }
+            _ => {
+              let trailing =
+                self.attach.get(self.idx) == Some(&Attach::Trailing);
+              if trailing && (levels == 2 || !self.next_is_exit(idx)) {
+                self.eat_trivia_while(|a| a == Attach::Trailing);
+              }
+            }
+          }
+          // the root gets the trivia which is left, like that at the end of
+          // a file with no items, so no trivia is lost.
+          if levels == 1 {
+            self.eat_trivia();
+          }
+          flush(sink, self.base, &mut start, self.idx);
           sink.exit();
           levels -= 1;
           // keep as much trivia as possible outside of top-level items.
           if levels == 1 {
-            self.eat_trivia(sink);
+            self.eat_trivia_while(|a| a != Attach::Leading);
           }
         }
         Event::Token => {
-          self.eat_trivia(sink);
-          sink.token(self.tokens[self.idx]);
+          self.eat_trivia();
+          debug_assert!(self.idx < self.tokens.len(), "no token to report");
+          self.idx += 1;
+        }
+        Event::Remap(kind) => {
+          self.eat_trivia();
+          debug_assert!(self.idx < self.tokens.len(), "no token to report");
+          flush(sink, self.base, &mut start, self.idx);
+          sink.remapped(kind, self.base + self.idx);
           self.idx += 1;
+          start = self.idx;
+        }
+        Event::Raw(end) => self.idx = end,
+        Event::Error(error) => {
+          let data = &self.errors[error as usize];
+          flush(sink, self.base, &mut start, self.idx);
+          if !data.context.is_empty() {
+            sink.context(&self.context_table[data.context.clone()]);
+          }
+          if matches!(payloads.peek(), Some(&(i, _)) if i == idx) {
+            let (_, payload) = payloads.next().unwrap();
+            sink.payload(payload);
+          }
+          let expected = self.expected_table[data.expected.clone()].to_vec();
+          errors += 1;
+          if data.more == 0 {
+            sink.error(expected, self.base + data.at);
+          } else {
+            sink.error_capped(expected, data.more, self.base + data.at);
+          }
+        }
+        Event::Missing(error) => {
+          let data = &self.errors[error as usize];
+          flush(sink, self.base, &mut start, self.idx);
+          if !data.context.is_empty() {
+            sink.context(&self.context_table[data.context.clone()]);
+          }
+          errors += 1;
+          let kind = self.expected_table[data.expected.start];
+          sink.missing(kind, self.base + self.idx);
+        }
+        Event::Unclosed(error) => {
+          let data = &self.errors[error as usize];
+          flush(sink, self.base, &mut start, self.idx);
+          if !data.context.is_empty() {
+            sink.context(&self.context_table[data.context.clone()]);
+          }
+          errors += 1;
+          let close = self.expected_table[data.expected.start];
+          sink.unclosed(close, self.base + data.at);
         }
-        Event::Error(expected) => sink.error(expected),
       }
     }
     assert_eq!(levels, 0);
+    debug_assert_eq!(
+      self.idx,
+      self.tokens.len(),
+      "not all tokens were reported: {}..{}",
+      self.base + self.idx,
+      self.base + self.tokens.len()
+    );
+    sink.finish_ok(errors);
   }
 }
 
-impl<'input, K> Parser<'input, K>
+impl<'input, K, E> Parser<'input, K, E>
 where
   K: Copy + Triviable + Eq,
 {
@@ -247,18 +1034,305 @@ where
   /// [`Self::error`] is called later.
   pub fn at(&mut self, kind: K) -> bool {
     self.expected.push(kind);
-    self.peek().map_or(false, |tok| tok.kind == kind)
+    matches!(self.peek(), Some(tok) if tok.kind == kind)
   }
 
   /// If the current token's kind is `kind`, then this consumes it, else this
   /// errors. Returns the token if it was eaten.
+  ///
+  /// See [`Self::set_missing_follow`] for how this errors.
   pub fn eat(&mut self, kind: K) -> Option<Token<'input, K>> {
     if self.at(kind) {
-      Some(self.bump())
-    } else {
+      return Some(self.bump());
+    }
+    self.eat_failed(kind);
+    None
+  }
+
+  /// Returns whether the current token's text is `text`, whatever its kind.
+  /// This is for contextual keywords, which are lexed like identifiers but are
+  /// keywords in some places.
+  ///
+  /// Also records `kind` as one of the expected kinds, like [`Self::at`], so
+  /// it should describe the text, e.g. a kind for the keyword which the lexer
+  /// never produces. To consume the token with that kind, use
+  /// [`Self::bump_remap`].
+  pub fn at_text(&mut self, kind: K, text: &str) -> bool {
+    self.expected.push(kind);
+    matches!(self.peek(), Some(tok) if tok.text == text)
+  }
+
+  /// Like [`Self::eat`], but checks the current token with [`Self::at_text`].
+  /// A missing token is reported as a missing `kind`.
+  pub fn eat_text(&mut self, kind: K, text: &str) -> Option<Token<'input, K>> {
+    if self.at_text(kind, text) {
+      return Some(self.bump());
+    }
+    self.eat_failed(kind);
+    None
+  }
+
+  /// Records that a token of the given `kind` was missing, or an error, for
+  /// when eating it failed.
+  fn eat_failed(&mut self, kind: K) {
+    match self.peek() {
+      Some(tok) if self.missing_follow.contains(&tok.kind) => {
+        self.expected.clear();
+        let idx = self.push_error_data(kind, self.idx);
+        self.events.push(Some(Event::Missing(idx)));
+      }
+      _ => self.error(),
+    }
+  }
+
+  /// Consumes the tokens from the current token up to but not including the
+  /// next token whose kind is `close`, or to the end if there is none, as a
+  /// syntax construct of the given `kind`, which may be empty.
+  ///
+  /// The tokens are not interpreted, so this is useful for constructs which the
+  /// grammar does not parse, like the body of a macro. Trivia between them is
+  /// kept in the construct, even at the end.
+  pub fn raw_until(&mut self, close: K, kind: K) -> Exited {
+    let en = self.enter();
+    self.peek();
+    let end = self.tokens[self.idx..]
+      .iter()
+      .position(|tok| tok.kind == close)
+      .map_or(self.tokens.len(), |n| self.idx + n);
+    self.events.push(Some(Event::Raw(end)));
+    self.idx = end;
+    self.expected.clear();
+    self.exit(en, kind)
+  }
+
+  /// Parses a syntax construct of the given `kind` delimited by `open` and
+  /// `close`, like a parenthesized expression, calling `body` to parse the
+  /// tokens between them. The current token's kind must be `open`, e.g. as
+  /// checked with [`Self::at`].
+  ///
+  /// `pairs` are the opening and closing delimiters of every delimited
+  /// construct in the grammar, usually including `(open, close)`. They must all
+  /// be distinct.
+  ///
+  /// If `body` does not stop at `close`, this looks ahead for it, skipping
+  /// over nested pairs of delimiters. If it is found, this records an error at
+  /// the current token, then consumes the tokens up to and including it.
+  /// Else, this consumes the tokens up to the closing delimiter of an
+  /// enclosing construct, or to the end, and records that `open` was
+  /// unclosed, reported with [`Sink::unclosed`].
+  pub fn delimited<F>(
+    &mut self,
+    kind: K,
+    open: K,
+    close: K,
+    pairs: &[(K, K)],
+    body: F,
+  ) -> Exited
+  where
+    F: FnOnce(&mut Self),
+  {
+    let en = self.enter();
+    let opener = self.peek_idx();
+    assert!(self.bump().kind == open, "delimited not at open");
+    body(self);
+    if self.at(close) {
+      self.bump();
+      return self.exit(en, kind);
+    }
+    let (end, closed) = self.find_close(close, pairs);
+    if closed {
       self.error();
-      None
     }
+    while self.peek_idx() < end {
+      self.bump();
+    }
+    if closed {
+      self.bump();
+    } else {
+      self.expected.clear();
+      let idx = self.push_error_data(close, opener);
+      self.events.push(Some(Event::Unclosed(idx)));
+    }
+    self.exit(en, kind)
+  }
+
+  /// Returns the index of the next token whose kind is `close` and which is
+  /// not nested in other `pairs`, and `true`. If a closing delimiter of an
+  /// enclosing construct or the end comes first, returns its index and
+  /// `false`.
+  fn find_close(&self, close: K, pairs: &[(K, K)]) -> (usize, bool) {
+    // the closing delimiters of the nested constructs.
+    let mut stack = Vec::new();
+    for (idx, tok) in self.tokens.iter().enumerate().skip(self.idx) {
+      if let Some(&(_, c)) = pairs.iter().find(|&&(o, _)| o == tok.kind) {
+        stack.push(c);
+      } else if let Some(n) = stack.iter().rposition(|&c| c == tok.kind) {
+        stack.truncate(n);
+      } else if tok.kind == close {
+        return (idx, true);
+      } else if stack.is_empty() && pairs.iter().any(|&(_, c)| c == tok.kind) {
+        return (idx, false);
+      }
+    }
+    (self.tokens.len(), false)
+  }
+
+  /// Records an error at the current token, like [`Self::error`], then skips
+  /// tokens until the current token's kind is in `recovery`, or there are no
+  /// more tokens. The skipped tokens are wrapped in a syntax construct with the
+  /// given `kind`, so the rest of the tree is unaffected by them.
+  ///
+  /// The current token is always skipped, even if its kind is in `recovery`,
+  /// so this always makes progress. To not skip it in that case, check with
+  /// [`Self::at`] first.
+  ///
+  /// If there are no more tokens, this is the same as `error`.
+  pub fn error_recover(&mut self, kind: K, recovery: &[K]) {
+    let (expected, more) = self.take_expected();
+    let at = self.peek_idx();
+    if at < self.tokens.len() {
+      let en = self.enter();
+      self.bump();
+      while let Some(tok) = self.peek() {
+        if recovery.contains(&tok.kind) {
+          break;
+        }
+        self.bump();
+      }
+      self.exit(en, kind);
+    }
+    let context = self.take_context();
+    self.push_error(ErrorData {
+      expected,
+      more,
+      context,
+      at,
+    });
+  }
+
+  /// Parses a list of elements separated by `sep`, with an optional trailing
+  /// separator.
+  ///
+  /// `element` is called to parse each element. It should return whether it
+  /// successfully parsed an element, and should not record an error if it
+  /// consumed no tokens.
+  ///
+  /// If `end` is not empty, the list continues until the current token's kind
+  /// is in `end`, or there are no more tokens. Then, if `element` consumed no
+  /// tokens, this records an error and skips the current token. If it failed
+  /// after consuming tokens, this skips tokens until the next `sep` or the
+  /// end. A separator missing between two elements is recorded as missing,
+  /// reported with [`Sink::missing`]. The tokens in `end` are not consumed.
+  ///
+  /// If `end` is empty, the list ends when `element` consumes no tokens or is
+  /// not followed by `sep`.
+  pub fn sep_list<F>(&mut self, sep: K, end: &[K], mut element: F)
+  where
+    F: FnMut(&mut Self) -> bool,
+  {
+    loop {
+      if self.at_end(end) {
+        break;
+      }
+      let idx = self.peek_idx();
+      let ok = element(self);
+      let progress = self.peek_idx() != idx;
+      if !progress {
+        if end.is_empty() {
+          break;
+        }
+        let at_sep = matches!(self.peek(), Some(tok) if tok.kind == sep);
+        self.error();
+        if !at_sep {
+          self.skip_until(sep, end);
+        }
+        continue;
+      }
+      if !ok && !end.is_empty() {
+        self.skip_until(sep, end);
+      }
+      if self.at(sep) {
+        self.bump();
+        continue;
+      }
+      if end.is_empty() || self.at_end(end) {
+        break;
+      }
+      self.expected.clear();
+      let idx = self.push_error_data(sep, self.idx);
+      self.events.push(Some(Event::Missing(idx)));
+    }
+  }
+
+  /// Returns whether there are no more tokens, or the current token's kind is
+  /// in `end`, recording every kind in `end` as expected.
+  fn at_end(&mut self, end: &[K]) -> bool {
+    let mut ret = false;
+    for &kind in end {
+      ret |= self.at(kind);
+    }
+    ret || self.peek().is_none()
+  }
+
+  /// Consumes tokens until the current token's kind is `sep` or in `end`, or
+  /// there are no more tokens.
+  fn skip_until(&mut self, sep: K, end: &[K]) {
+    while let Some(tok) = self.peek() {
+      if tok.kind == sep || end.contains(&tok.kind) {
+        break;
+      }
+      self.bump();
+    }
+  }
+
+  /// Parses all of the tokens as a syntax construct of the given `kind`
+  /// containing a sequence of items.
+  ///
+  /// `item` is called repeatedly to parse an item until there are no more
+  /// tokens. It should return whether it successfully parsed an item.
+  ///
+  /// If `item` returns `false` or did not consume any tokens, this recovers by
+  /// skipping tokens until the current token's kind is in `recovery`, or there
+  /// are no more tokens. In addition, if `item` did not consume any tokens,
+  /// this records an error at the first skipped token, so that the error
+  /// reports the kinds `item` expected. (If `item` did consume tokens, it
+  /// should have reported its own error.) At least one token is always skipped
+  /// when recovering, so this always terminates.
+  ///
+  /// If there are no tokens besides trivia, as for an empty file, this gives a
+  /// construct of the given `kind` containing only the trivia, and records no
+  /// errors.
+  pub fn parse_file<F>(&mut self, kind: K, mut item: F, recovery: &[K])
+  where
+    F: FnMut(&mut Self) -> bool,
+  {
+    let en = self.enter();
+    while self.peek().is_some() {
+      let idx = self.idx;
+      let ok = item(self);
+      self.peek();
+      let progress = self.idx != idx;
+      if ok && progress {
+        continue;
+      }
+      if progress {
+        match self.peek() {
+          None => continue,
+          Some(tok) if recovery.contains(&tok.kind) => continue,
+          Some(_) => {}
+        }
+        self.bump();
+      } else {
+        self.error();
+      }
+      while let Some(tok) = self.peek() {
+        if recovery.contains(&tok.kind) {
+          break;
+        }
+        self.bump();
+      }
+    }
+    self.exit(en, kind);
   }
 }
 
@@ -270,43 +1344,477 @@ pub struct Entered {
   idx: usize,
 }
 
+/// A marker for a grammar rule whose progress is being tracked. If this is not
+/// consumed by a [`Parser`], it will panic when dropped.
+#[derive(Debug)]
+pub struct RuleStart {
+  bomb: DropBomb,
+  name: &'static str,
+  idx: usize,
+  events_len: usize,
+  abandons: usize,
+}
+
+/// A label on the context stack of a [`Parser`], which is popped when this is
+/// dropped. See [`Parser::context`].
+#[derive(Debug)]
+pub struct Context {
+  stack: Arc<Mutex<Vec<&'static str>>>,
+  depth: usize,
+}
+
+impl Drop for Context {
+  fn drop(&mut self) {
+    // also pops any labels pushed after this one which are still around.
+    if let Ok(mut stack) = self.stack.lock() {
+      stack.truncate(self.depth);
+    }
+  }
+}
+
 /// A marker for a syntax construct that has been fully parsed.
 #[derive(Debug)]
 pub struct Exited {
   idx: usize,
 }
 
-/// The saved state of the parser.
+/// How to handle the trivia before a token skipped because of an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorTrivia {
+  /// Keep the trivia outside of the error.
+  Exclude,
+  /// Include the trivia in the error.
+  Include,
+}
+
+/// The saved state of the parser. If this is not consumed by a [`Parser`], it
+/// may panic when dropped. See [`Parser::set_strict_saves`].
 #[derive(Debug)]
 pub struct Save<K> {
   idx: usize,
   events_len: usize,
+  expected_table_len: usize,
+  context_table_len: usize,
+  errors_len: usize,
+  payloads_len: usize,
+  annotations_len: usize,
   expected: Vec<K>,
+  done: bool,
+  strict: bool,
+}
+
+impl<K> Drop for Save<K> {
+  fn drop(&mut self) {
+    if self.strict && !std::thread::panicking() {
+      debug_assert!(self.done, "Save markers must be restored or forgotten");
+    }
+  }
 }
 
 /// Types which can construct a syntax tree.
-pub trait Sink<K> {
+///
+/// Each enter is matched by a later exit, and every other call is made between
+/// the enter and the exit of the root syntax construct, unless the grammar did
+/// otherwise. Wrapping a sink in a [`CheckSink`] checks this.
+pub trait Sink<K, E = String> {
   /// Enters a syntax construct with the given kind.
   fn enter(&mut self, kind: K);
   /// Adds a token to the given syntax construct.
+  ///
+  /// The start of the token is always set. If the lexer did not set it, it is
+  /// the end of the token before, or 0 for the first token given to the
+  /// [`Parser`].
   fn token(&mut self, token: Token<'_, K>);
   /// Exits a syntax construct.
   fn exit(&mut self);
-  /// Reports an error.
-  fn error(&mut self, expected: Vec<K>);
+  /// Reports an error at the token with the byte `range`, which was already
+  /// added, or at the empty range at the end of the tokens.
+  fn error(&mut self, expected: Vec<K>, range: Range<usize>);
+  /// Reports an error, for which `more` expected kinds were omitted because of
+  /// [`Parser::set_expected_cap`].
+  ///
+  /// By default, ignores `more` and reports the error with [`Self::error`].
+  fn error_capped(
+    &mut self,
+    expected: Vec<K>,
+    more: usize,
+    range: Range<usize>,
+  ) {
+    let _ = more;
+    self.error(expected, range);
+  }
+  /// Reports that a token of the given kind is missing here, at the empty byte
+  /// `range`.
+  ///
+  /// By default, reports an error expecting only that kind.
+  fn missing(&mut self, kind: K, range: Range<usize>) {
+    self.error(vec![kind], range);
+  }
+  /// Reports that the opening delimiter with the byte `range`, which was
+  /// already added, was not closed by a token of the kind `close`. See
+  /// [`Parser::delimited`].
+  ///
+  /// By default, reports an error at the opening delimiter expecting only
+  /// `close`.
+  fn unclosed(&mut self, close: K, range: Range<usize>) {
+    self.error(vec![close], range);
+  }
+  /// Sets the labels on the context stack, outermost first, for the next
+  /// error, missing token, or unclosed delimiter. See [`Parser::context`].
+  /// Only called when there are any labels.
+  ///
+  /// By default, does nothing.
+  fn context(&mut self, labels: &[&'static str]) {
+    let _ = labels;
+  }
+  /// Sets the payload given to [`Parser::error_with`] for the next error.
+  /// Only called for errors with payloads, after [`Self::context`].
+  ///
+  /// By default, does nothing.
+  fn payload(&mut self, payload: E) {
+    let _ = payload;
+  }
+  /// Annotates the syntax construct just entered with the `data` given to
+  /// [`Parser::annotate`]. Called just after [`Self::enter`], once for each
+  /// annotation, in the order they were made.
+  ///
+  /// By default, does nothing.
+  fn annotation(&mut self, data: u32) {
+    let _ = data;
+  }
+  /// Returns whether trivia tokens should be reported with [`Self::trivia`]
+  /// instead of [`Self::token`].
+  ///
+  /// By default, returns `false`.
+  fn group_trivia(&self) -> bool {
+    false
+  }
+  /// Adds a run of consecutive trivia tokens to the given syntax construct.
+  /// The run is never empty, and is as long as possible without crossing any
+  /// other call to the sink. The starts of the tokens are set as for
+  /// [`Self::token`].
+  ///
+  /// Only called if [`Self::group_trivia`] returns `true`. By default, adds
+  /// each token with [`Self::token`].
+  fn trivia(&mut self, tokens: &[Token<'_, K>])
+  where
+    K: Copy,
+  {
+    for &tok in tokens {
+      self.token(tok);
+    }
+  }
+  /// Called before any other call when finishing, with the number of tokens
+  /// given to the [`Parser`], including trivia, which is at least the number
+  /// of tokens reported. This is useful for e.g. reserving capacity.
+  ///
+  /// By default, does nothing.
+  fn start(&mut self, total_tokens: usize) {
+    let _ = total_tokens;
+  }
+  /// Called after every other call when finishing, with the number of errors,
+  /// missing tokens, and unclosed delimiters reported.
+  ///
+  /// By default, does nothing.
+  fn finish_ok(&mut self, errors: usize) {
+    let _ = errors;
+  }
+}
+
+/// Types which can construct a syntax tree, given the indices of tokens rather
+/// than the tokens themselves.
+///
+/// This allows a sink to e.g. slice the original text itself.
+///
+/// The calls are made as for a [`Sink`], and the ranges of tokens follow one
+/// another.
+pub trait RawSink<K, E = String> {
+  /// Enters a syntax construct with the given kind.
+  fn enter(&mut self, kind: K);
+  /// Adds the tokens whose indices in the slice of tokens given to
+  /// [`Parser::new`] are in the `range` to the given syntax construct.
+  ///
+  /// If the parser was created with [`Parser::new_at`], the indices are offset
+  /// by its `base`.
+  ///
+  /// The range is never empty.
+  fn tokens(&mut self, range: Range<usize>);
+  /// Adds the token with index `idx`, offset like for [`Self::tokens`], to the
+  /// given syntax construct, with the given `kind` instead of its own. See
+  /// [`Parser::bump_remap`].
+  ///
+  /// By default, adds the token with its own kind, with [`Self::tokens`].
+  fn remapped(&mut self, kind: K, idx: usize) {
+    let _ = kind;
+    self.tokens(idx..idx + 1);
+  }
+  /// Exits a syntax construct.
+  fn exit(&mut self);
+  /// Reports an error at the token with index `idx`, offset like for
+  /// [`Self::tokens`], which was already added. If the error is at the end of
+  /// the tokens, `idx` is the index after the last token.
+  fn error(&mut self, expected: Vec<K>, idx: usize);
+  /// Reports an error, for which `more` expected kinds were omitted because of
+  /// [`Parser::set_expected_cap`].
+  ///
+  /// By default, ignores `more` and reports the error with [`Self::error`].
+  fn error_capped(&mut self, expected: Vec<K>, more: usize, idx: usize) {
+    let _ = more;
+    self.error(expected, idx);
+  }
+  /// Reports that a token of the given kind is missing before the token with
+  /// index `idx`, offset like for [`Self::tokens`], which was not yet added.
+  ///
+  /// By default, reports an error expecting only that kind.
+  fn missing(&mut self, kind: K, idx: usize) {
+    self.error(vec![kind], idx);
+  }
+  /// Reports that the opening delimiter at the token with index `idx`, offset
+  /// like for [`Self::tokens`], which was already added, was not closed by a
+  /// token of the kind `close`. See [`Parser::delimited`].
+  ///
+  /// By default, reports an error at the opening delimiter expecting only
+  /// `close`.
+  fn unclosed(&mut self, close: K, idx: usize) {
+    self.error(vec![close], idx);
+  }
+  /// Sets the labels on the context stack, outermost first, for the next
+  /// error, missing token, or unclosed delimiter. See [`Parser::context`].
+  /// Only called when there are any labels.
+  ///
+  /// By default, does nothing.
+  fn context(&mut self, labels: &[&'static str]) {
+    let _ = labels;
+  }
+  /// Sets the payload given to [`Parser::error_with`] for the next error.
+  /// Only called for errors with payloads, after [`Self::context`].
+  ///
+  /// By default, does nothing.
+  fn payload(&mut self, payload: E) {
+    let _ = payload;
+  }
+  /// Annotates the syntax construct just entered with the `data` given to
+  /// [`Parser::annotate`]. Called just after [`Self::enter`], once for each
+  /// annotation, in the order they were made.
+  ///
+  /// By default, does nothing.
+  fn annotation(&mut self, data: u32) {
+    let _ = data;
+  }
+  /// Called before any other call when finishing, with the number of tokens
+  /// given to the [`Parser`], including trivia, which is at least the number
+  /// of tokens reported. This is useful for e.g. reserving capacity.
+  ///
+  /// By default, does nothing.
+  fn start(&mut self, total_tokens: usize) {
+    let _ = total_tokens;
+  }
+  /// Called after every other call when finishing, with the number of errors,
+  /// missing tokens, and unclosed delimiters reported.
+  ///
+  /// By default, does nothing.
+  fn finish_ok(&mut self, errors: usize) {
+    let _ = errors;
+  }
+}
+
+/// Reports the tokens in `start..end`, if any, offset by `base`, to the sink.
+fn flush<K, E>(
+  sink: &mut dyn RawSink<K, E>,
+  base: usize,
+  start: &mut usize,
+  end: usize,
+) {
+  debug_assert!(
+    *start <= end,
+    "tokens {}..{} were already reported",
+    base + end,
+    base + *start
+  );
+  if *start < end {
+    sink.tokens(base + *start..base + end);
+    *start = end;
+  }
+}
+
+/// Adapts a [`Sink`] into a [`RawSink`].
+struct TokenSink<'a, 'input, K, E> {
+  tokens: &'input [Token<'input, K>],
+  base: usize,
+  sink: &'a mut dyn Sink<K, E>,
+  /// the index of the first token not yet reported, not offset by `base`.
+  next: usize,
+  /// the byte offset of the end of the tokens reported so far.
+  offset: usize,
+  /// the tokens being reported, with their starts set.
+  buf: Vec<Token<'input, K>>,
+}
+
+impl<K, E> TokenSink<'_, '_, K, E>
+where
+  K: Copy,
+{
+  /// Returns the byte range of the token with index `idx`, offset by `base`,
+  /// or the empty range at the end of the tokens if there is no such token.
+  fn range(&self, idx: usize) -> Range<usize> {
+    let idx = idx - self.base;
+    let len = |tokens: &[Token<'_, K>]| -> usize {
+      tokens.iter().map(|tok| tok.text.len()).sum()
+    };
+    let tok = self.tokens.get(idx);
+    let start = match tok.and_then(|tok| tok.start) {
+      Some(start) => start,
+      None if idx < self.next => {
+        self.offset - len(&self.tokens[idx..self.next])
+      }
+      None => self.offset + len(&self.tokens[self.next..idx]),
+    };
+    start..start + tok.map_or(0, |tok| tok.text.len())
+  }
+}
+
+impl<K, E> RawSink<K, E> for TokenSink<'_, '_, K, E>
+where
+  K: Copy + Triviable,
+{
+  fn enter(&mut self, kind: K) {
+    self.sink.enter(kind);
+  }
+
+  fn tokens(&mut self, range: Range<usize>) {
+    let range = range.start - self.base..range.end - self.base;
+    self.next = range.end;
+    self.buf.clear();
+    for &tok in &self.tokens[range] {
+      let start = tok.start.unwrap_or(self.offset);
+      self.offset = start + tok.text.len();
+      self.buf.push(Token {
+        start: Some(start),
+        ..tok
+      });
+    }
+    let mut tokens = &self.buf[..];
+    if !self.sink.group_trivia() {
+      for &tok in tokens {
+        self.sink.token(tok);
+      }
+      return;
+    }
+    while let Some(tok) = tokens.first() {
+      let is_trivia = tok.kind.is_trivia();
+      let len = tokens
+        .iter()
+        .position(|t| t.kind.is_trivia() != is_trivia)
+        .unwrap_or(tokens.len());
+      let (run, rest) = tokens.split_at(len);
+      if is_trivia {
+        self.sink.trivia(run);
+      } else {
+        for &tok in run {
+          self.sink.token(tok);
+        }
+      }
+      tokens = rest;
+    }
+  }
+
+  fn remapped(&mut self, kind: K, idx: usize) {
+    let idx = idx - self.base;
+    let tok = self.tokens[idx];
+    let start = tok.start.unwrap_or(self.offset);
+    self.next = idx + 1;
+    self.offset = start + tok.text.len();
+    self.sink.token(Token {
+      kind,
+      start: Some(start),
+      ..tok
+    });
+  }
+
+  fn exit(&mut self) {
+    self.sink.exit();
+  }
+
+  fn error(&mut self, expected: Vec<K>, idx: usize) {
+    let range = self.range(idx);
+    self.sink.error(expected, range);
+  }
+
+  fn error_capped(&mut self, expected: Vec<K>, more: usize, idx: usize) {
+    let range = self.range(idx);
+    self.sink.error_capped(expected, more, range);
+  }
+
+  fn missing(&mut self, kind: K, idx: usize) {
+    let start = self.range(idx).start;
+    self.sink.missing(kind, start..start);
+  }
+
+  fn unclosed(&mut self, close: K, idx: usize) {
+    let range = self.range(idx);
+    self.sink.unclosed(close, range);
+  }
+
+  fn context(&mut self, labels: &[&'static str]) {
+    self.sink.context(labels);
+  }
+
+  fn payload(&mut self, payload: E) {
+    self.sink.payload(payload);
+  }
+
+  fn annotation(&mut self, data: u32) {
+    self.sink.annotation(data);
+  }
+
+  fn start(&mut self, total_tokens: usize) {
+    self.sink.start(total_tokens);
+  }
+
+  fn finish_ok(&mut self, errors: usize) {
+    self.sink.finish_ok(errors);
+  }
+}
+
+/// An `Entered`.
+#[derive(Debug)]
+struct Open<K> {
+  /// the index of its event.
+  idx: usize,
+  /// the kind given to `enter_kind`, if any.
+  kind: Option<K>,
+  closed: bool,
 }
 
 #[derive(Debug)]
 enum Event<K> {
   Enter(K, Option<usize>),
   Token,
+  /// a token, reported with this kind instead of its own.
+  Remap(K),
+  /// all the tokens, trivia or not, up to the index.
+  Raw(usize),
   Exit,
-  Error(Vec<K>),
+  /// the index of its data.
+  Error(u32),
+  /// the index of its data.
+  Missing(u32),
+  /// the index of its data.
+  Unclosed(u32),
 }
 
-#[test]
-fn event_size() {
-  let ev = std::mem::size_of::<Event<()>>();
-  let op_ev = std::mem::size_of::<Option<Event<()>>>();
-  assert_eq!(ev, op_ev)
+/// The data for an `Event::Error`, `Event::Missing`, or `Event::Unclosed`.
+#[derive(Debug)]
+struct ErrorData {
+  /// the range of the expected kinds in the table. for a missing token or an
+  /// unclosed delimiter, this is just the kind of the missing token or the
+  /// closing delimiter.
+  expected: Range<usize>,
+  /// the number of expected kinds omitted because of the cap.
+  more: usize,
+  /// the range of the context in the table.
+  context: Range<usize>,
+  /// the index of the token it is at. for an unclosed delimiter, this is the
+  /// opening delimiter. a missing token is reported where its event is.
+  at: usize,
 }
@@ -0,0 +1,233 @@
+//! Counting what is reported to a sink.
+
+use crate::{RawSink, Sink};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Range;
+use token::Token;
+
+/// Metrics about a parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metrics<K>
+where
+  K: Eq + Hash,
+{
+  /// The number of nodes of each kind.
+  pub nodes: HashMap<K, usize>,
+  /// The number of tokens, including trivia.
+  pub tokens: usize,
+  /// The number of errors, including missing tokens and unclosed delimiters.
+  pub errors: usize,
+  /// The greatest number of nodes entered but not yet exited at once.
+  pub max_depth: usize,
+}
+
+impl<K> Default for Metrics<K>
+where
+  K: Eq + Hash,
+{
+  fn default() -> Self {
+    Self {
+      nodes: HashMap::new(),
+      tokens: 0,
+      errors: 0,
+      max_depth: 0,
+    }
+  }
+}
+
+impl<K> Metrics<K>
+where
+  K: Eq + Hash,
+{
+  /// Returns the total number of nodes.
+  pub fn node_count(&self) -> usize {
+    self.nodes.values().sum()
+  }
+}
+
+/// A sink which collects [`Metrics`], and forwards everything unchanged, to
+/// another sink.
+#[derive(Debug)]
+pub struct MetricsSink<S, K>
+where
+  K: Eq + Hash,
+{
+  /// The sink.
+  pub sink: S,
+  /// The metrics so far.
+  pub metrics: Metrics<K>,
+  depth: usize,
+}
+
+impl<S, K> MetricsSink<S, K>
+where
+  K: Eq + Hash,
+{
+  /// Returns a new `MetricsSink` with no metrics yet.
+  pub fn new(sink: S) -> Self {
+    Self {
+      sink,
+      metrics: Metrics::default(),
+      depth: 0,
+    }
+  }
+
+  /// Returns the sink and the metrics.
+  pub fn into_parts(self) -> (S, Metrics<K>) {
+    (self.sink, self.metrics)
+  }
+
+  fn enter(&mut self, kind: &K)
+  where
+    K: Clone,
+  {
+    *self.metrics.nodes.entry(kind.clone()).or_default() += 1;
+    self.depth += 1;
+    self.metrics.max_depth = self.metrics.max_depth.max(self.depth);
+  }
+}
+
+impl<S, K, E> Sink<K, E> for MetricsSink<S, K>
+where
+  S: Sink<K, E>,
+  K: Clone + Eq + Hash,
+{
+  fn enter(&mut self, kind: K) {
+    MetricsSink::enter(self, &kind);
+    self.sink.enter(kind);
+  }
+
+  fn token(&mut self, token: Token<'_, K>) {
+    self.metrics.tokens += 1;
+    self.sink.token(token);
+  }
+
+  fn exit(&mut self) {
+    self.depth -= 1;
+    self.sink.exit();
+  }
+
+  fn error(&mut self, expected: Vec<K>, range: Range<usize>) {
+    self.metrics.errors += 1;
+    self.sink.error(expected, range);
+  }
+
+  fn error_capped(
+    &mut self,
+    expected: Vec<K>,
+    more: usize,
+    range: Range<usize>,
+  ) {
+    self.metrics.errors += 1;
+    self.sink.error_capped(expected, more, range);
+  }
+
+  fn missing(&mut self, kind: K, range: Range<usize>) {
+    self.metrics.errors += 1;
+    self.sink.missing(kind, range);
+  }
+
+  fn unclosed(&mut self, close: K, range: Range<usize>) {
+    self.metrics.errors += 1;
+    self.sink.unclosed(close, range);
+  }
+
+  fn context(&mut self, labels: &[&'static str]) {
+    self.sink.context(labels);
+  }
+
+  fn payload(&mut self, payload: E) {
+    self.sink.payload(payload);
+  }
+
+  fn annotation(&mut self, data: u32) {
+    self.sink.annotation(data);
+  }
+
+  fn start(&mut self, total_tokens: usize) {
+    self.sink.start(total_tokens);
+  }
+
+  fn finish_ok(&mut self, errors: usize) {
+    self.sink.finish_ok(errors);
+  }
+
+  fn group_trivia(&self) -> bool {
+    self.sink.group_trivia()
+  }
+
+  fn trivia(&mut self, tokens: &[Token<'_, K>])
+  where
+    K: Copy,
+  {
+    self.metrics.tokens += tokens.len();
+    self.sink.trivia(tokens);
+  }
+}
+
+impl<S, K, E> RawSink<K, E> for MetricsSink<S, K>
+where
+  S: RawSink<K, E>,
+  K: Clone + Eq + Hash,
+{
+  fn enter(&mut self, kind: K) {
+    MetricsSink::enter(self, &kind);
+    self.sink.enter(kind);
+  }
+
+  fn tokens(&mut self, range: Range<usize>) {
+    self.metrics.tokens += range.len();
+    self.sink.tokens(range);
+  }
+
+  fn remapped(&mut self, kind: K, idx: usize) {
+    self.metrics.tokens += 1;
+    self.sink.remapped(kind, idx);
+  }
+
+  fn exit(&mut self) {
+    self.depth -= 1;
+    self.sink.exit();
+  }
+
+  fn error(&mut self, expected: Vec<K>, idx: usize) {
+    self.metrics.errors += 1;
+    self.sink.error(expected, idx);
+  }
+
+  fn error_capped(&mut self, expected: Vec<K>, more: usize, idx: usize) {
+    self.metrics.errors += 1;
+    self.sink.error_capped(expected, more, idx);
+  }
+
+  fn missing(&mut self, kind: K, idx: usize) {
+    self.metrics.errors += 1;
+    self.sink.missing(kind, idx);
+  }
+
+  fn unclosed(&mut self, close: K, idx: usize) {
+    self.metrics.errors += 1;
+    self.sink.unclosed(close, idx);
+  }
+
+  fn context(&mut self, labels: &[&'static str]) {
+    self.sink.context(labels);
+  }
+
+  fn payload(&mut self, payload: E) {
+    self.sink.payload(payload);
+  }
+
+  fn annotation(&mut self, data: u32) {
+    self.sink.annotation(data);
+  }
+
+  fn start(&mut self, total_tokens: usize) {
+    self.sink.start(total_tokens);
+  }
+
+  fn finish_ok(&mut self, errors: usize) {
+    self.sink.finish_ok(errors);
+  }
+}
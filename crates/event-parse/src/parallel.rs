@@ -0,0 +1,71 @@
+//! Lexing in parallel.
+
+use std::sync::Arc;
+use std::thread;
+use token::Token;
+
+/// Lexes `s` with `lex` in up to `chunks` chunks in parallel, and returns all
+/// the tokens, as if `lex` had lexed `s` all at once, with their starts set.
+///
+/// The chunks are split at boundaries given by `boundary`. `boundary(s, idx)`
+/// should return the first index at or after `idx` (and at most `s.len()`) at
+/// which `lex` may be restarted from its initial state, e.g. the start of the
+/// next line not inside a string or comment. It must be a char boundary.
+///
+/// `lex` must return contiguous tokens covering all of its input. Each chunk is
+/// copied to the thread lexing it, and the tokens are re-sliced from `s`.
+pub fn lex_parallel<K, B, F>(
+  s: &str,
+  chunks: usize,
+  boundary: B,
+  lex: F,
+) -> Vec<Token<'_, K>>
+where
+  K: Send + 'static,
+  B: Fn(&str, usize) -> usize,
+  F: Fn(&str) -> Vec<Token<'_, K>> + Send + Sync + 'static,
+{
+  let chunk_len = s.len() / chunks.max(1) + 1;
+  let mut ranges = Vec::with_capacity(chunks);
+  let mut start = 0;
+  while start < s.len() {
+    let end = boundary(s, (start + chunk_len).min(s.len()));
+    assert!(start < end && end <= s.len(), "bad boundary: {}", end);
+    ranges.push(start..end);
+    start = end;
+  }
+  let lex = Arc::new(lex);
+  let handles: Vec<_> = ranges
+    .iter()
+    .map(|range| {
+      let chunk = s[range.clone()].to_owned();
+      let lex = Arc::clone(&lex);
+      thread::spawn(move || {
+        let tokens = lex(&chunk);
+        let lens: Vec<_> = tokens
+          .into_iter()
+          .map(|t| (t.kind, t.text.len(), t.flags))
+          .collect();
+        let len: usize = lens.iter().map(|&(_, len, _)| len).sum();
+        assert_eq!(len, chunk.len(), "tokens did not cover the chunk");
+        lens
+      })
+    })
+    .collect();
+  let mut ret = Vec::new();
+  let mut idx = 0;
+  for handle in handles {
+    let lens = handle.join().expect("lexing thread panicked");
+    ret.reserve(lens.len());
+    for (kind, len, flags) in lens {
+      ret.push(Token {
+        kind,
+        text: &s[idx..idx + len],
+        flags,
+        start: Some(idx),
+      });
+      idx += len;
+    }
+  }
+  ret
+}
@@ -0,0 +1,170 @@
+//! Parsing expressions with prefix, infix, and postfix operators of different
+//! precedences, by Pratt parsing.
+//!
+//! Describe the operators with a [`Table`], then parse with [`expr`]. It makes
+//! the same calls to the [`Parser`] as a hand-written parser using
+//! [`Parser::precede`] would.
+//!
+//! [`Parser`]: crate::Parser
+//! [`Parser::precede`]: crate::Parser::precede
+
+use crate::{Entered, Exited, Parser};
+use token::Triviable;
+
+/// A prefix or postfix operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Op<K> {
+  /// The kind of the operator token.
+  pub token: K,
+  /// The precedence. Higher binds tighter. Must be positive.
+  pub prec: u32,
+  /// The kind of the syntax construct containing the operator and its
+  /// operand.
+  pub kind: K,
+}
+
+/// An infix operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Infix<K> {
+  /// The kind of the operator token.
+  pub token: K,
+  /// The precedence. Higher binds tighter. Must be positive.
+  pub prec: u32,
+  /// How operators of the same precedence group.
+  pub assoc: Assoc,
+  /// The kind of the syntax construct containing the operator and its
+  /// operands.
+  pub kind: K,
+}
+
+/// How infix operators of the same precedence group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assoc {
+  /// `a - b - c` is `(a - b) - c`.
+  Left,
+  /// `a = b = c` is `a = (b = c)`.
+  Right,
+}
+
+/// The operators of an expression grammar.
+///
+/// A prefix operator's operand contains exactly the operators which bind
+/// tighter than it, so with `-` looser than `.`, `-a.b` is `-(a.b)`. A postfix
+/// operator applies to the operand before it if it binds tighter than the
+/// operator on the other side of that operand, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table<K> {
+  /// The prefix operators.
+  pub prefix: Vec<Op<K>>,
+  /// The infix operators.
+  pub infix: Vec<Infix<K>>,
+  /// The postfix operators.
+  pub postfix: Vec<Op<K>>,
+}
+
+impl<K> Default for Table<K> {
+  fn default() -> Self {
+    Self {
+      prefix: Vec::new(),
+      infix: Vec::new(),
+      postfix: Vec::new(),
+    }
+  }
+}
+
+/// An operator whose syntax construct is waiting for an operand.
+struct Pending<K> {
+  /// the precedence which operators must exceed to be in the operand.
+  min: u32,
+  /// the construct for the operator, or `None` for the whole expression.
+  entered: Option<(Entered, K)>,
+}
+
+/// Parses an expression with the operators in the `table`, calling `operand`
+/// to parse each operand, and returns the syntax construct for it.
+///
+/// `operand` should return `None` if it failed to parse an operand, having
+/// recorded an error. Then this stops, exiting the constructs for the
+/// operators before the operand, and returns the outermost one, or `None` if
+/// there were none.
+///
+/// Whether a token is an operator is checked with [`Parser::at`], so errors
+/// report the operators which could have come next. This does not recurse on
+/// the nesting of the expression, but `operand` may, e.g. for parentheses.
+pub fn expr<'input, K, E, F>(
+  p: &mut Parser<'input, K, E>,
+  table: &Table<K>,
+  mut operand: F,
+) -> Option<Exited>
+where
+  K: Copy + Triviable + Eq,
+  F: FnMut(&mut Parser<'input, K, E>) -> Option<Exited>,
+{
+  let mut stack = vec![Pending {
+    min: 0,
+    entered: None,
+  }];
+  'operand: loop {
+    let prefix = table.prefix.iter().find(|op| p.at(op.token));
+    if let Some(op) = prefix {
+      let en = p.enter();
+      p.bump();
+      stack.push(Pending {
+        min: op.prec,
+        entered: Some((en, op.kind)),
+      });
+      continue;
+    }
+    let mut lhs = match operand(p) {
+      Some(x) => x,
+      None => return exit_all(p, stack),
+    };
+    loop {
+      let min = stack.last().expect("no pending operator").min;
+      let postfix = table
+        .postfix
+        .iter()
+        .find(|op| op.prec > min && p.at(op.token));
+      if let Some(op) = postfix {
+        let en = p.precede(lhs);
+        p.bump();
+        lhs = p.exit(en, op.kind);
+        continue;
+      }
+      let infix = table
+        .infix
+        .iter()
+        .find(|op| op.prec > min && p.at(op.token));
+      if let Some(op) = infix {
+        let en = p.precede(lhs);
+        p.bump();
+        let min = match op.assoc {
+          Assoc::Left => op.prec,
+          Assoc::Right => op.prec - 1,
+        };
+        stack.push(Pending {
+          min,
+          entered: Some((en, op.kind)),
+        });
+        continue 'operand;
+      }
+      match stack.pop().and_then(|x| x.entered) {
+        Some((en, kind)) => lhs = p.exit(en, kind),
+        None => return Some(lhs),
+      }
+    }
+  }
+}
+
+/// Exits the constructs for all the operators in the `stack`, innermost first,
+/// and returns the outermost, if any.
+fn exit_all<K, E>(
+  p: &mut Parser<'_, K, E>,
+  stack: Vec<Pending<K>>,
+) -> Option<Exited> {
+  let mut ret = None;
+  for (en, kind) in stack.into_iter().rev().filter_map(|x| x.entered) {
+    ret = Some(p.exit(en, kind));
+  }
+  ret
+}
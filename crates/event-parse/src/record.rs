@@ -0,0 +1,703 @@
+//! Recording the calls made to a [`Parser`], and replaying them step by step.
+
+use crate::{
+  Context, Entered, ErrorTrivia, Exited, Parser, RawSink, Save, Sink,
+};
+use std::fmt;
+use token::{Flags, Token, Triviable};
+
+/// A call to a [`Parser`] method, recorded by a [`Recorder`].
+///
+/// [`Entered`]s and [`Exited`]s are identified by ids, assigned in order from
+/// 0 to each `Entered` created by `enter`, `enter_kind`, or `precede`. The `Exited` from
+/// exiting an `Entered` has the same id as that `Entered`. [`Save`]s are also
+/// identified by ids, assigned separately.
+///
+/// `E` is the type of the payloads of errors, as for [`Parser`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Call<K, E = String> {
+  /// `enter`, creating the `Entered` with this id.
+  Enter(usize),
+  /// `enter_kind` with the kind, creating the `Entered` with this id.
+  EnterKind(usize, K),
+  /// `abandon` with the `Entered` with this id.
+  Abandon(usize),
+  /// `exit` with the `Entered` with this id and the kind.
+  Exit(usize, K),
+  /// `precede` with the `Exited` with the first id, creating the `Entered`
+  /// with the second id.
+  Precede(usize, usize),
+  /// `save`, creating the `Save` with this id.
+  Save(usize),
+  /// `restore` with the `Save` with this id.
+  Restore(usize),
+  /// `restore_keeping_expected` with the `Save` with this id.
+  RestoreKeepingExpected(usize),
+  /// `forget` with the `Save` with this id.
+  Forget(usize),
+  /// `bump`.
+  Bump,
+  /// `bump_remap` with the kind.
+  BumpRemap(K),
+  /// `error`.
+  Error,
+  /// `error_with` with the payload.
+  ErrorWith(E),
+  /// `error_node` with the kind and trivia handling.
+  ErrorNode(K, ErrorTrivia),
+  /// `error_recover` with the kind and the recovery set.
+  ErrorRecover(K, Vec<K>),
+  /// `expect_eof`.
+  ExpectEof,
+  /// `at` with the kind.
+  At(K),
+  /// `eat` with the kind.
+  Eat(K),
+  /// `at_text` with the kind and the text, which is written to the rest of
+  /// the line, so it must not contain a newline.
+  AtText(K, String),
+  /// `eat_text` with the kind and the text, like [`Call::AtText`].
+  EatText(K, String),
+  /// `raw_until` with the close kind and the kind, creating the `Exited` with
+  /// the id.
+  RawUntil(usize, K, K),
+  /// `annotate` with the `Exited` with the id and the data.
+  Annotate(usize, u32),
+}
+
+/// A log of calls.
+///
+/// The `Display` impl writes one call per line, using the `Debug` impl of the
+/// kinds and the `Display` impl of the payloads, which are written to the rest
+/// of the line. [`Log::parse`] parses that format back.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Log<K, E = String> {
+  /// The calls, in order.
+  pub calls: Vec<Call<K, E>>,
+}
+
+impl<K, E> fmt::Display for Log<K, E>
+where
+  K: fmt::Debug,
+  E: fmt::Display,
+{
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for call in self.calls.iter() {
+      match call {
+        Call::Enter(id) => writeln!(f, "enter {}", id)?,
+        Call::EnterKind(id, kind) => {
+          writeln!(f, "enter_kind {} {:?}", id, kind)?
+        }
+        Call::Abandon(id) => writeln!(f, "abandon {}", id)?,
+        Call::Exit(id, kind) => writeln!(f, "exit {} {:?}", id, kind)?,
+        Call::Precede(id, new) => writeln!(f, "precede {} {}", id, new)?,
+        Call::Save(id) => writeln!(f, "save {}", id)?,
+        Call::Restore(id) => writeln!(f, "restore {}", id)?,
+        Call::RestoreKeepingExpected(id) => {
+          writeln!(f, "restore_keeping_expected {}", id)?
+        }
+        Call::Forget(id) => writeln!(f, "forget {}", id)?,
+        Call::Bump => writeln!(f, "bump")?,
+        Call::Error => writeln!(f, "error")?,
+        Call::ErrorWith(payload) => writeln!(f, "error_with {}", payload)?,
+        Call::ErrorNode(kind, ErrorTrivia::Exclude) => {
+          writeln!(f, "error_node {:?} exclude", kind)?
+        }
+        Call::ErrorNode(kind, ErrorTrivia::Include) => {
+          writeln!(f, "error_node {:?} include", kind)?
+        }
+        Call::ErrorRecover(kind, recovery) => {
+          write!(f, "error_recover {:?}", kind)?;
+          for k in recovery.iter() {
+            write!(f, " {:?}", k)?;
+          }
+          writeln!(f)?
+        }
+        Call::ExpectEof => writeln!(f, "expect_eof")?,
+        Call::At(kind) => writeln!(f, "at {:?}", kind)?,
+        Call::Eat(kind) => writeln!(f, "eat {:?}", kind)?,
+        Call::AtText(kind, text) => writeln!(f, "at_text {:?} {}", kind, text)?,
+        Call::EatText(kind, text) => {
+          writeln!(f, "eat_text {:?} {}", kind, text)?
+        }
+        Call::BumpRemap(kind) => writeln!(f, "bump_remap {:?}", kind)?,
+        Call::RawUntil(id, close, kind) => {
+          writeln!(f, "raw_until {} {:?} {:?}", id, close, kind)?
+        }
+        Call::Annotate(id, data) => writeln!(f, "annotate {} {}", id, data)?,
+      }
+    }
+    Ok(())
+  }
+}
+
+impl<K> Log<K> {
+  /// Parses a log written by the `Display` impl, using `kind` to parse each
+  /// kind. Returns the zero-based line number of the first invalid line on
+  /// failure.
+  pub fn parse<F>(s: &str, kind: F) -> Result<Self, usize>
+  where
+    F: Fn(&str) -> Option<K>,
+  {
+    Self::parse_with_payload(s, kind, |p| Some(p.to_owned()))
+  }
+}
+
+impl<K, E> Log<K, E> {
+  /// Parses a log like [`Log::parse`], using `payload` to parse each payload.
+  pub fn parse_with_payload<F, G>(
+    s: &str,
+    kind: F,
+    payload: G,
+  ) -> Result<Self, usize>
+  where
+    F: Fn(&str) -> Option<K>,
+    G: Fn(&str) -> Option<E>,
+  {
+    let calls = s
+      .lines()
+      .enumerate()
+      .map(|(idx, line)| parse_call(line, &kind, &payload).ok_or(idx))
+      .collect::<Result<Vec<_>, _>>()?;
+    Ok(Self { calls })
+  }
+}
+
+fn parse_call<K, E, F, G>(line: &str, kind: F, payload: G) -> Option<Call<K, E>>
+where
+  F: Fn(&str) -> Option<K>,
+  G: Fn(&str) -> Option<E>,
+{
+  let mut iter = line.split_whitespace();
+  let name = iter.next()?;
+  let mut id = || iter.next()?.parse::<usize>().ok();
+  let ret = match name {
+    "enter" => Call::Enter(id()?),
+    "abandon" => Call::Abandon(id()?),
+    "save" => Call::Save(id()?),
+    "restore" => Call::Restore(id()?),
+    "restore_keeping_expected" => Call::RestoreKeepingExpected(id()?),
+    "forget" => Call::Forget(id()?),
+    "precede" => {
+      let old = id()?;
+      Call::Precede(old, id()?)
+    }
+    "bump" => Call::Bump,
+    "error" => Call::Error,
+    "expect_eof" => Call::ExpectEof,
+    "error_with" => {
+      // the payload is the rest of the line, which may contain spaces.
+      let rest = line.splitn(2, ' ').nth(1)?;
+      Call::ErrorWith(payload(rest)?)
+    }
+    "at_text" | "eat_text" => {
+      // the text is the rest of the line, which may contain spaces.
+      let mut parts = line.splitn(3, ' ').skip(1);
+      let k = kind(parts.next()?)?;
+      let text = parts.next()?.to_owned();
+      if name == "at_text" {
+        Call::AtText(k, text)
+      } else {
+        Call::EatText(k, text)
+      }
+    }
+    _ => {
+      let rest: Vec<_> = line.split_whitespace().skip(1).collect();
+      match (name, rest.as_slice()) {
+        ("exit", &[id, k]) => Call::Exit(id.parse().ok()?, kind(k)?),
+        ("enter_kind", &[id, k]) => Call::EnterKind(id.parse().ok()?, kind(k)?),
+        ("error_node", &[k, "exclude"]) => {
+          Call::ErrorNode(kind(k)?, ErrorTrivia::Exclude)
+        }
+        ("error_node", &[k, "include"]) => {
+          Call::ErrorNode(kind(k)?, ErrorTrivia::Include)
+        }
+        ("error_recover", &[k, ref recovery @ ..]) => {
+          let recovery = recovery
+            .iter()
+            .map(|&k| kind(k))
+            .collect::<Option<Vec<_>>>()?;
+          Call::ErrorRecover(kind(k)?, recovery)
+        }
+        ("at", &[k]) => Call::At(kind(k)?),
+        ("eat", &[k]) => Call::Eat(kind(k)?),
+        ("bump_remap", &[k]) => Call::BumpRemap(kind(k)?),
+        ("raw_until", &[id, c, k]) => {
+          Call::RawUntil(id.parse().ok()?, kind(c)?, kind(k)?)
+        }
+        ("annotate", &[id, data]) => {
+          Call::Annotate(id.parse().ok()?, data.parse().ok()?)
+        }
+        _ => return None,
+      }
+    }
+  };
+  Some(ret)
+}
+
+/// A marker returned by a [`Recorder`], wrapping a marker returned by a
+/// [`Parser`] with its id.
+#[derive(Debug)]
+pub struct Marker<T> {
+  inner: T,
+  id: usize,
+}
+
+/// A wrapper around a [`Parser`] which records the calls made to it in a
+/// [`Log`].
+///
+/// The methods are the same as those of `Parser`. Methods which don't change
+/// the result of parsing, like `peek`, are not recorded.
+#[derive(Debug)]
+pub struct Recorder<'input, K, E = String> {
+  parser: Parser<'input, K, E>,
+  log: Log<K, E>,
+  markers: usize,
+  saves: usize,
+}
+
+impl<'input, K, E> Recorder<'input, K, E>
+where
+  K: Copy + Triviable + Eq,
+{
+  /// Returns a new `Recorder` for the parser.
+  pub fn new(parser: Parser<'input, K, E>) -> Self {
+    Self {
+      parser,
+      log: Log { calls: Vec::new() },
+      markers: 0,
+      saves: 0,
+    }
+  }
+
+  /// Returns the log of calls so far.
+  pub fn log(&self) -> &Log<K, E> {
+    &self.log
+  }
+
+  fn marker(&mut self) -> usize {
+    let ret = self.markers;
+    self.markers += 1;
+    ret
+  }
+
+  /// See [`Parser::enter`].
+  pub fn enter(&mut self) -> Marker<Entered> {
+    let id = self.marker();
+    self.log.calls.push(Call::Enter(id));
+    let inner = self.parser.enter();
+    Marker { inner, id }
+  }
+
+  /// See [`Parser::enter_kind`].
+  pub fn enter_kind(&mut self, kind: K) -> Marker<Entered> {
+    let id = self.marker();
+    self.log.calls.push(Call::EnterKind(id, kind));
+    let inner = self.parser.enter_kind(kind);
+    Marker { inner, id }
+  }
+
+  /// See [`Parser::depth`].
+  pub fn depth(&self) -> usize {
+    self.parser.depth()
+  }
+
+  /// See [`Parser::open_kinds`].
+  pub fn open_kinds(&self) -> impl DoubleEndedIterator<Item = Option<&K>> {
+    self.parser.open_kinds()
+  }
+
+  /// See [`Parser::abandon`].
+  pub fn abandon(&mut self, entered: Marker<Entered>) {
+    self.log.calls.push(Call::Abandon(entered.id));
+    self.parser.abandon(entered.inner);
+  }
+
+  /// See [`Parser::exit`].
+  pub fn exit(&mut self, entered: Marker<Entered>, kind: K) -> Marker<Exited> {
+    self.log.calls.push(Call::Exit(entered.id, kind));
+    let inner = self.parser.exit(entered.inner, kind);
+    Marker {
+      inner,
+      id: entered.id,
+    }
+  }
+
+  /// See [`Parser::precede`].
+  pub fn precede(&mut self, exited: Marker<Exited>) -> Marker<Entered> {
+    let id = self.marker();
+    self.log.calls.push(Call::Precede(exited.id, id));
+    let inner = self.parser.precede(exited.inner);
+    Marker { inner, id }
+  }
+
+  /// See [`Parser::annotate`].
+  pub fn annotate(&mut self, exited: &Marker<Exited>, data: u32) {
+    self.log.calls.push(Call::Annotate(exited.id, data));
+    self.parser.annotate(&exited.inner, data);
+  }
+
+  /// See [`Parser::save`].
+  pub fn save(&mut self) -> Marker<Save<K>> {
+    let id = self.saves;
+    self.saves += 1;
+    self.log.calls.push(Call::Save(id));
+    let inner = self.parser.save();
+    Marker { inner, id }
+  }
+
+  /// See [`Parser::restore`].
+  pub fn restore(&mut self, save: Marker<Save<K>>) {
+    self.log.calls.push(Call::Restore(save.id));
+    self.parser.restore(save.inner);
+  }
+
+  /// See [`Parser::restore_keeping_expected`].
+  pub fn restore_keeping_expected(&mut self, save: Marker<Save<K>>) {
+    self.log.calls.push(Call::RestoreKeepingExpected(save.id));
+    self.parser.restore_keeping_expected(save.inner);
+  }
+
+  /// See [`Parser::forget`].
+  pub fn forget(&mut self, save: Marker<Save<K>>) {
+    self.log.calls.push(Call::Forget(save.id));
+    self.parser.forget(save.inner);
+  }
+
+  /// See [`Parser::context`]. Not recorded, since it only changes what is
+  /// reported along with errors.
+  pub fn context(&mut self, label: &'static str) -> Context {
+    self.parser.context(label)
+  }
+
+  /// See [`Parser::peek`].
+  pub fn peek(&mut self) -> Option<Token<'input, K>> {
+    self.parser.peek()
+  }
+
+  /// See [`Parser::at_flag`]. Not recorded, since like `peek` it does not
+  /// record anything as expected.
+  pub fn at_flag(&mut self, flags: Flags) -> bool {
+    self.parser.at_flag(flags)
+  }
+
+  /// See [`Parser::peek_n`].
+  pub fn peek_n(&mut self, n: usize) -> Option<Token<'input, K>> {
+    self.parser.peek_n(n)
+  }
+
+  /// See [`Parser::bump`].
+  pub fn bump(&mut self) -> Token<'input, K> {
+    self.log.calls.push(Call::Bump);
+    self.parser.bump()
+  }
+
+  /// See [`Parser::bump_remap`].
+  pub fn bump_remap(&mut self, kind: K) -> Token<'input, K> {
+    self.log.calls.push(Call::BumpRemap(kind));
+    self.parser.bump_remap(kind)
+  }
+
+  /// See [`Parser::try_bump`]. Records a bump only if there was a token.
+  pub fn try_bump(&mut self) -> Option<Token<'input, K>> {
+    let ret = self.parser.try_bump();
+    if ret.is_some() {
+      self.log.calls.push(Call::Bump);
+    }
+    ret
+  }
+
+  /// See [`Parser::error`].
+  pub fn error(&mut self) {
+    self.log.calls.push(Call::Error);
+    self.parser.error();
+  }
+
+  /// See [`Parser::error_with`].
+  pub fn error_with(&mut self, payload: E)
+  where
+    E: Clone,
+  {
+    self.log.calls.push(Call::ErrorWith(payload.clone()));
+    self.parser.error_with(payload);
+  }
+
+  /// See [`Parser::error_node`].
+  pub fn error_node(&mut self, kind: K, trivia: ErrorTrivia) {
+    self.log.calls.push(Call::ErrorNode(kind, trivia));
+    self.parser.error_node(kind, trivia);
+  }
+
+  /// See [`Parser::error_recover`].
+  pub fn error_recover(&mut self, kind: K, recovery: &[K]) {
+    self
+      .log
+      .calls
+      .push(Call::ErrorRecover(kind, recovery.to_vec()));
+    self.parser.error_recover(kind, recovery);
+  }
+
+  /// See [`Parser::expect_eof`].
+  pub fn expect_eof(&mut self) {
+    self.log.calls.push(Call::ExpectEof);
+    self.parser.expect_eof();
+  }
+
+  /// See [`Parser::at`].
+  pub fn at(&mut self, kind: K) -> bool {
+    self.log.calls.push(Call::At(kind));
+    self.parser.at(kind)
+  }
+
+  /// See [`Parser::eat`].
+  pub fn eat(&mut self, kind: K) -> Option<Token<'input, K>> {
+    self.log.calls.push(Call::Eat(kind));
+    self.parser.eat(kind)
+  }
+
+  /// See [`Parser::at_text`].
+  pub fn at_text(&mut self, kind: K, text: &str) -> bool {
+    self.log.calls.push(Call::AtText(kind, text.to_owned()));
+    self.parser.at_text(kind, text)
+  }
+
+  /// See [`Parser::eat_text`].
+  pub fn eat_text(&mut self, kind: K, text: &str) -> Option<Token<'input, K>> {
+    self.log.calls.push(Call::EatText(kind, text.to_owned()));
+    self.parser.eat_text(kind, text)
+  }
+
+  /// See [`Parser::raw_until`].
+  pub fn raw_until(&mut self, close: K, kind: K) -> Marker<Exited> {
+    let id = self.marker();
+    self.log.calls.push(Call::RawUntil(id, close, kind));
+    let inner = self.parser.raw_until(close, kind);
+    Marker { inner, id }
+  }
+
+  /// See [`Parser::finish`]. Also returns the log.
+  pub fn finish(self, sink: &mut dyn Sink<K, E>) -> Log<K, E> {
+    self.parser.finish(sink);
+    self.log
+  }
+
+  /// See [`Parser::finish_raw`]. Also returns the log.
+  pub fn finish_raw(self, sink: &mut dyn RawSink<K, E>) -> Log<K, E> {
+    self.parser.finish_raw(sink);
+    self.log
+  }
+}
+
+#[derive(Debug)]
+enum Slot {
+  Entered(Entered),
+  Exited(Exited),
+  Empty,
+}
+
+/// Replays a [`Log`] on a [`Parser`], one call at a time.
+///
+/// If the replay is dropped before all the calls are replayed, any
+/// [`Entered`]s not yet consumed are abandoned and any [`Save`]s not yet
+/// consumed are forgotten, so it does not panic.
+#[derive(Debug)]
+pub struct Replay<'input, K, E = String> {
+  parser: Parser<'input, K, E>,
+  log: Log<K, E>,
+  next: usize,
+  slots: Vec<Slot>,
+  saves: Vec<Option<Save<K>>>,
+}
+
+impl<'input, K, E> Replay<'input, K, E>
+where
+  K: Copy + Triviable + Eq,
+  E: Clone,
+{
+  /// Returns a new `Replay` of the log on the parser.
+  ///
+  /// The parser should be in the same state as the one which was recorded
+  /// when it was passed to [`Recorder::new`].
+  pub fn new(parser: Parser<'input, K, E>, log: Log<K, E>) -> Self {
+    Self {
+      parser,
+      log,
+      next: 0,
+      slots: Vec::new(),
+      saves: Vec::new(),
+    }
+  }
+
+  /// Returns the parser, in the state after the calls replayed so far.
+  pub fn parser(&self) -> &Parser<'input, K, E> {
+    &self.parser
+  }
+
+  /// Returns the next call to be replayed, if any.
+  pub fn next_call(&self) -> Option<&Call<K, E>> {
+    self.log.calls.get(self.next)
+  }
+
+  /// Returns the index in the log of the next call to be replayed.
+  pub fn next_index(&self) -> usize {
+    self.next
+  }
+
+  /// Replays the next call, if any, and returns it.
+  ///
+  /// Panics if the call refers to a marker that does not exist or was already
+  /// consumed.
+  pub fn step(&mut self) -> Option<Call<K, E>> {
+    let call = self.log.calls.get(self.next)?.clone();
+    self.next += 1;
+    match &call {
+      &Call::Enter(id) => {
+        let en = self.parser.enter();
+        self.set_slot(id, Slot::Entered(en));
+      }
+      &Call::EnterKind(id, kind) => {
+        let en = self.parser.enter_kind(kind);
+        self.set_slot(id, Slot::Entered(en));
+      }
+      &Call::Abandon(id) => {
+        let en = self.take_entered(id);
+        self.parser.abandon(en);
+      }
+      &Call::Exit(id, kind) => {
+        let en = self.take_entered(id);
+        let ex = self.parser.exit(en, kind);
+        self.set_slot(id, Slot::Exited(ex));
+      }
+      &Call::Precede(id, new) => {
+        let ex = match self.take_slot(id) {
+          Slot::Exited(x) => x,
+          _ => panic!("no Exited with id {}", id),
+        };
+        let en = self.parser.precede(ex);
+        self.set_slot(new, Slot::Entered(en));
+      }
+      &Call::Save(id) => {
+        let save = self.parser.save();
+        if self.saves.len() <= id {
+          self.saves.resize_with(id + 1, || None);
+        }
+        self.saves[id] = Some(save);
+      }
+      &Call::Restore(id) => {
+        let save = self.take_save(id);
+        self.parser.restore(save);
+      }
+      &Call::RestoreKeepingExpected(id) => {
+        let save = self.take_save(id);
+        self.parser.restore_keeping_expected(save);
+      }
+      &Call::Forget(id) => {
+        let save = self.take_save(id);
+        self.parser.forget(save);
+      }
+      &Call::Bump => {
+        self.parser.bump();
+      }
+      &Call::BumpRemap(kind) => {
+        self.parser.bump_remap(kind);
+      }
+      &Call::Error => self.parser.error(),
+      Call::ErrorWith(payload) => self.parser.error_with(payload.clone()),
+      &Call::ErrorNode(kind, trivia) => self.parser.error_node(kind, trivia),
+      Call::ErrorRecover(kind, recovery) => {
+        self.parser.error_recover(*kind, recovery)
+      }
+      &Call::ExpectEof => self.parser.expect_eof(),
+      &Call::At(kind) => {
+        self.parser.at(kind);
+      }
+      &Call::Eat(kind) => {
+        self.parser.eat(kind);
+      }
+      Call::AtText(kind, text) => {
+        self.parser.at_text(*kind, text);
+      }
+      Call::EatText(kind, text) => {
+        self.parser.eat_text(*kind, text);
+      }
+      &Call::RawUntil(id, close, kind) => {
+        let ex = self.parser.raw_until(close, kind);
+        self.set_slot(id, Slot::Exited(ex));
+      }
+      &Call::Annotate(id, data) => {
+        let ex = match self.take_slot(id) {
+          Slot::Exited(x) => x,
+          _ => panic!("no Exited with id {}", id),
+        };
+        self.parser.annotate(&ex, data);
+        self.set_slot(id, Slot::Exited(ex));
+      }
+    }
+    Some(call)
+  }
+
+  /// Replays calls until `stop` returns `true` for the parser before the next
+  /// call, or there are no more calls. Returns whether `stop` returned `true`.
+  ///
+  /// `stop` is a breakpoint, e.g. `|p| p.token_idx() == 523`.
+  pub fn run_until<F>(&mut self, mut stop: F) -> bool
+  where
+    F: FnMut(&Parser<'input, K, E>) -> bool,
+  {
+    while self.next_call().is_some() {
+      if stop(&self.parser) {
+        return true;
+      }
+      self.step();
+    }
+    false
+  }
+
+  /// Replays all the remaining calls, then finishes the parser. See
+  /// [`Parser::finish`].
+  pub fn finish(mut self, sink: &mut dyn Sink<K, E>) {
+    while self.step().is_some() {}
+    let empty = Parser::new_with_payload(&[], 0);
+    let parser = std::mem::replace(&mut self.parser, empty);
+    parser.finish(sink);
+  }
+
+  fn set_slot(&mut self, id: usize, slot: Slot) {
+    if self.slots.len() <= id {
+      self.slots.resize_with(id + 1, || Slot::Empty);
+    }
+    self.slots[id] = slot;
+  }
+
+  fn take_slot(&mut self, id: usize) -> Slot {
+    match self.slots.get_mut(id) {
+      Some(x) => std::mem::replace(x, Slot::Empty),
+      None => Slot::Empty,
+    }
+  }
+
+  fn take_save(&mut self, id: usize) -> Save<K> {
+    let save = self.saves.get_mut(id).and_then(Option::take);
+    save.unwrap_or_else(|| panic!("no Save with id {}", id))
+  }
+
+  fn take_entered(&mut self, id: usize) -> Entered {
+    match self.take_slot(id) {
+      Slot::Entered(x) => x,
+      _ => panic!("no Entered with id {}", id),
+    }
+  }
+}
+
+impl<K, E> Drop for Replay<'_, K, E> {
+  fn drop(&mut self) {
+    for slot in self.slots.drain(..) {
+      if let Slot::Entered(en) = slot {
+        self.parser.abandon(en);
+      }
+    }
+    for save in self.saves.drain(..).flatten() {
+      self.parser.forget(save);
+    }
+  }
+}
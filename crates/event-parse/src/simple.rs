@@ -0,0 +1,252 @@
+//! A simple syntax tree, for when a full syntax tree library is not needed.
+//!
+//! Build one by finishing a [`Parser`] into a [`SimpleTreeSink`].
+//!
+//! [`Parser`]: crate::Parser
+
+use crate::{ErrorEvent, RawSink};
+use std::ops::Range;
+use token::Token;
+
+/// A node in a [`SimpleTree`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+/// A child of a node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Element<K> {
+  /// A child node.
+  Node(NodeId),
+  /// Consecutive tokens, as indices into the tokens that were parsed, offset by
+  /// the `base` if the parser was created with [`crate::Parser::new_at`]. Never
+  /// empty.
+  Tokens(Range<usize>),
+  /// A token reported with a different kind than its own, and its index, like
+  /// for [`Element::Tokens`]. See [`crate::Parser::bump_remap`].
+  Remapped(K, usize),
+  /// An error, and the index of the token it is at, as for
+  /// [`RawSink::error`], [`RawSink::missing`], and [`RawSink::unclosed`].
+  Error(ErrorEvent<K>, usize),
+}
+
+#[derive(Debug, Clone)]
+struct Node<K> {
+  kind: K,
+  children: Vec<Element<K>>,
+  tokens: Range<usize>,
+  annotations: Vec<u32>,
+}
+
+/// An event from [`SimpleTree::walk`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalkEvent {
+  /// Entering a node, before its descendants.
+  Enter(NodeId),
+  /// Exiting a node, after its descendants.
+  Exit(NodeId),
+}
+
+/// A syntax tree, with no dependencies on a syntax tree library.
+#[derive(Debug, Clone)]
+pub struct SimpleTree<K> {
+  nodes: Vec<Node<K>>,
+  roots: Vec<NodeId>,
+}
+
+impl<K> SimpleTree<K> {
+  /// Returns the root nodes, in order. Usually there is just one.
+  pub fn roots(&self) -> &[NodeId] {
+    &self.roots
+  }
+
+  /// Returns the kind of the node.
+  pub fn kind(&self, node: NodeId) -> &K {
+    &self.nodes[node.0].kind
+  }
+
+  /// Returns the children of the node, in order.
+  pub fn children(&self, node: NodeId) -> &[Element<K>] {
+    &self.nodes[node.0].children
+  }
+
+  /// Returns the annotations of the node, in the order they were made. See
+  /// [`crate::Parser::annotate`].
+  pub fn annotations(&self, node: NodeId) -> &[u32] {
+    &self.nodes[node.0].annotations
+  }
+
+  /// Returns the indices of the tokens in the node, like
+  /// [`Element::Tokens`]. May be empty.
+  pub fn token_range(&self, node: NodeId) -> Range<usize> {
+    self.nodes[node.0].tokens.clone()
+  }
+
+  /// Returns the range of the node in the text, given the `tokens` which the
+  /// token indices refer to.
+  ///
+  /// This adds up the lengths of all the tokens before the node, so it takes
+  /// time linear in their number.
+  pub fn text_range(
+    &self,
+    node: NodeId,
+    tokens: &[Token<'_, K>],
+  ) -> Range<usize> {
+    let range = self.token_range(node);
+    let start: usize = tokens[..range.start].iter().map(|t| t.text.len()).sum();
+    let len: usize = tokens[range].iter().map(|t| t.text.len()).sum();
+    start..start + len
+  }
+
+  /// Returns an iterator over all the nodes in the tree, entering each node
+  /// before its descendants and exiting it after.
+  pub fn walk(&self) -> Walk<'_, K> {
+    Walk {
+      tree: self,
+      roots: self.roots.iter(),
+      stack: Vec::new(),
+    }
+  }
+}
+
+/// An iterator from [`SimpleTree::walk`].
+#[derive(Debug)]
+pub struct Walk<'a, K> {
+  tree: &'a SimpleTree<K>,
+  roots: std::slice::Iter<'a, NodeId>,
+  /// the nodes being walked, with the index of the next child to look at.
+  stack: Vec<(NodeId, usize)>,
+}
+
+impl<K> Iterator for Walk<'_, K> {
+  type Item = WalkEvent;
+
+  fn next(&mut self) -> Option<WalkEvent> {
+    loop {
+      let (node, idx) = match self.stack.last_mut() {
+        Some(x) => x,
+        None => {
+          let &root = self.roots.next()?;
+          self.stack.push((root, 0));
+          return Some(WalkEvent::Enter(root));
+        }
+      };
+      let node = *node;
+      match self.tree.children(node).get(*idx) {
+        None => {
+          self.stack.pop();
+          return Some(WalkEvent::Exit(node));
+        }
+        Some(child) => {
+          *idx += 1;
+          if let Element::Node(child) = *child {
+            self.stack.push((child, 0));
+            return Some(WalkEvent::Enter(child));
+          }
+        }
+      }
+    }
+  }
+}
+
+/// A sink which builds a [`SimpleTree`].
+#[derive(Debug)]
+pub struct SimpleTreeSink<K> {
+  tree: SimpleTree<K>,
+  stack: Vec<NodeId>,
+  /// the index after the last token reported.
+  next: usize,
+}
+
+impl<K> Default for SimpleTreeSink<K> {
+  fn default() -> Self {
+    Self::new_at(0)
+  }
+}
+
+impl<K> SimpleTreeSink<K> {
+  /// Returns a new sink.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Returns a new sink for a parser created with [`crate::Parser::new_at`]
+  /// with this `base`.
+  pub fn new_at(base: usize) -> Self {
+    Self {
+      tree: SimpleTree {
+        nodes: Vec::new(),
+        roots: Vec::new(),
+      },
+      stack: Vec::new(),
+      next: base,
+    }
+  }
+
+  /// Returns the tree. Panics if a node was entered but not exited.
+  pub fn finish(self) -> SimpleTree<K> {
+    assert!(self.stack.is_empty(), "unfinished node");
+    self.tree
+  }
+
+  fn push(&mut self, elem: Element<K>) {
+    let &node = self.stack.last().expect("outside of any node");
+    self.tree.nodes[node.0].children.push(elem);
+  }
+}
+
+impl<K> RawSink<K> for SimpleTreeSink<K> {
+  fn enter(&mut self, kind: K) {
+    let id = NodeId(self.tree.nodes.len());
+    match self.stack.last() {
+      Some(&parent) => {
+        self.tree.nodes[parent.0].children.push(Element::Node(id))
+      }
+      None => self.tree.roots.push(id),
+    }
+    self.tree.nodes.push(Node {
+      kind,
+      children: Vec::new(),
+      tokens: self.next..self.next,
+      annotations: Vec::new(),
+    });
+    self.stack.push(id);
+  }
+
+  fn tokens(&mut self, range: Range<usize>) {
+    self.next = range.end;
+    self.push(Element::Tokens(range));
+  }
+
+  fn remapped(&mut self, kind: K, idx: usize) {
+    self.next = idx + 1;
+    self.push(Element::Remapped(kind, idx));
+  }
+
+  fn exit(&mut self) {
+    let node = self.stack.pop().expect("exit without enter");
+    self.tree.nodes[node.0].tokens.end = self.next;
+  }
+
+  fn annotation(&mut self, data: u32) {
+    let &node = self.stack.last().expect("outside of any node");
+    self.tree.nodes[node.0].annotations.push(data);
+  }
+
+  fn error(&mut self, expected: Vec<K>, idx: usize) {
+    let error = ErrorEvent::Error { expected, more: 0 };
+    self.push(Element::Error(error, idx));
+  }
+
+  fn error_capped(&mut self, expected: Vec<K>, more: usize, idx: usize) {
+    let error = ErrorEvent::Error { expected, more };
+    self.push(Element::Error(error, idx));
+  }
+
+  fn missing(&mut self, kind: K, idx: usize) {
+    self.push(Element::Error(ErrorEvent::Missing(kind), idx));
+  }
+
+  fn unclosed(&mut self, close: K, idx: usize) {
+    self.push(Element::Error(ErrorEvent::Unclosed(close), idx));
+  }
+}
@@ -0,0 +1,217 @@
+//! Forwarding to two sinks at once.
+
+use crate::{RawSink, Sink};
+use std::ops::Range;
+use token::Token;
+
+/// A sink which forwards everything to two sinks, first `a` then `b`.
+///
+/// Trivia runs are reported to each sink as it asks, with
+/// [`Sink::group_trivia`].
+///
+/// This allows e.g. building a syntax tree and collecting a debug dump in one
+/// [`crate::Parser::finish`].
+#[derive(Debug, Default)]
+pub struct TeeSink<A, B> {
+  /// The first sink.
+  pub a: A,
+  /// The second sink.
+  pub b: B,
+}
+
+impl<A, B> TeeSink<A, B> {
+  /// Returns a new `TeeSink`.
+  pub fn new(a: A, b: B) -> Self {
+    Self { a, b }
+  }
+}
+
+impl<K, E, A, B> Sink<K, E> for TeeSink<A, B>
+where
+  K: Clone,
+  E: Clone,
+  A: Sink<K, E>,
+  B: Sink<K, E>,
+{
+  fn enter(&mut self, kind: K) {
+    self.a.enter(kind.clone());
+    self.b.enter(kind);
+  }
+
+  fn token(&mut self, token: Token<'_, K>) {
+    let Token {
+      kind,
+      text,
+      flags,
+      start,
+    } = token;
+    self.a.token(Token {
+      kind: kind.clone(),
+      text,
+      flags,
+      start,
+    });
+    self.b.token(Token {
+      kind,
+      text,
+      flags,
+      start,
+    });
+  }
+
+  fn exit(&mut self) {
+    self.a.exit();
+    self.b.exit();
+  }
+
+  fn error(&mut self, expected: Vec<K>, range: Range<usize>) {
+    self.a.error(expected.clone(), range.clone());
+    self.b.error(expected, range);
+  }
+
+  fn error_capped(
+    &mut self,
+    expected: Vec<K>,
+    more: usize,
+    range: Range<usize>,
+  ) {
+    self.a.error_capped(expected.clone(), more, range.clone());
+    self.b.error_capped(expected, more, range);
+  }
+
+  fn missing(&mut self, kind: K, range: Range<usize>) {
+    self.a.missing(kind.clone(), range.clone());
+    self.b.missing(kind, range);
+  }
+
+  fn unclosed(&mut self, close: K, range: Range<usize>) {
+    self.a.unclosed(close.clone(), range.clone());
+    self.b.unclosed(close, range);
+  }
+
+  fn context(&mut self, labels: &[&'static str]) {
+    self.a.context(labels);
+    self.b.context(labels);
+  }
+
+  fn payload(&mut self, payload: E) {
+    self.a.payload(payload.clone());
+    self.b.payload(payload);
+  }
+
+  fn annotation(&mut self, data: u32) {
+    self.a.annotation(data);
+    self.b.annotation(data);
+  }
+
+  fn start(&mut self, total_tokens: usize) {
+    self.a.start(total_tokens);
+    self.b.start(total_tokens);
+  }
+
+  fn finish_ok(&mut self, errors: usize) {
+    self.a.finish_ok(errors);
+    self.b.finish_ok(errors);
+  }
+
+  fn group_trivia(&self) -> bool {
+    self.a.group_trivia() || self.b.group_trivia()
+  }
+
+  fn trivia(&mut self, tokens: &[Token<'_, K>])
+  where
+    K: Copy,
+  {
+    trivia(&mut self.a, tokens);
+    trivia(&mut self.b, tokens);
+  }
+}
+
+/// Reports the trivia `tokens` to `sink`, which may not group trivia even if
+/// the other sink does.
+fn trivia<K, E, S>(sink: &mut S, tokens: &[Token<'_, K>])
+where
+  K: Copy,
+  S: Sink<K, E>,
+{
+  if sink.group_trivia() {
+    sink.trivia(tokens);
+  } else {
+    for &token in tokens {
+      sink.token(token);
+    }
+  }
+}
+
+impl<K, E, A, B> RawSink<K, E> for TeeSink<A, B>
+where
+  K: Clone,
+  E: Clone,
+  A: RawSink<K, E>,
+  B: RawSink<K, E>,
+{
+  fn enter(&mut self, kind: K) {
+    self.a.enter(kind.clone());
+    self.b.enter(kind);
+  }
+
+  fn tokens(&mut self, range: Range<usize>) {
+    self.a.tokens(range.clone());
+    self.b.tokens(range);
+  }
+
+  fn remapped(&mut self, kind: K, idx: usize) {
+    self.a.remapped(kind.clone(), idx);
+    self.b.remapped(kind, idx);
+  }
+
+  fn exit(&mut self) {
+    self.a.exit();
+    self.b.exit();
+  }
+
+  fn error(&mut self, expected: Vec<K>, idx: usize) {
+    self.a.error(expected.clone(), idx);
+    self.b.error(expected, idx);
+  }
+
+  fn error_capped(&mut self, expected: Vec<K>, more: usize, idx: usize) {
+    self.a.error_capped(expected.clone(), more, idx);
+    self.b.error_capped(expected, more, idx);
+  }
+
+  fn missing(&mut self, kind: K, idx: usize) {
+    self.a.missing(kind.clone(), idx);
+    self.b.missing(kind, idx);
+  }
+
+  fn unclosed(&mut self, close: K, idx: usize) {
+    self.a.unclosed(close.clone(), idx);
+    self.b.unclosed(close, idx);
+  }
+
+  fn context(&mut self, labels: &[&'static str]) {
+    self.a.context(labels);
+    self.b.context(labels);
+  }
+
+  fn payload(&mut self, payload: E) {
+    self.a.payload(payload.clone());
+    self.b.payload(payload);
+  }
+
+  fn annotation(&mut self, data: u32) {
+    self.a.annotation(data);
+    self.b.annotation(data);
+  }
+
+  fn start(&mut self, total_tokens: usize) {
+    self.a.start(total_tokens);
+    self.b.start(total_tokens);
+  }
+
+  fn finish_ok(&mut self, errors: usize) {
+    self.a.finish_ok(errors);
+    self.b.finish_ok(errors);
+  }
+}
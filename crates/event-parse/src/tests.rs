@@ -0,0 +1,2230 @@
+use crate::pratt::{self, Assoc, Infix, Op, Table};
+use crate::record::{Call, Log, Recorder, Replay};
+use crate::simple::{Element, SimpleTreeSink, WalkEvent};
+use crate::{
+  attach_trivia, lex_parallel, Attach, CheckSink, ErrorAt, ErrorEvent,
+  ErrorInfo, ErrorTrivia, Event, FileTrivia, FilterSink, MetricsSink, Parser,
+  RawSink, Sink, TeeSink,
+};
+use std::ops::Range;
+use token::{Flags, Token, Triviable};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum SK {
+  Space,
+  Num,
+  Plus,
+  Minus,
+  Star,
+  Caret,
+  Bang,
+  LParen,
+  RParen,
+  LBrack,
+  RBrack,
+  Root,
+  Expr,
+  Add,
+  Sub,
+  Mul,
+  Pow,
+  Neg,
+  Fact,
+  Paren,
+  Brack,
+  /// a contextual keyword, matched by text, never lexed.
+  Kw,
+  Error,
+}
+
+impl Triviable for SK {
+  fn is_trivia(&self) -> bool {
+    matches!(*self, SK::Space)
+  }
+}
+
+/// Every char is a token.
+fn lex(s: &str) -> Vec<Token<'_, SK>> {
+  s.char_indices()
+    .map(|(i, c)| {
+      let kind = match c {
+        ' ' => SK::Space,
+        '+' => SK::Plus,
+        '-' => SK::Minus,
+        '*' => SK::Star,
+        '^' => SK::Caret,
+        '!' => SK::Bang,
+        '(' => SK::LParen,
+        ')' => SK::RParen,
+        '[' => SK::LBrack,
+        ']' => SK::RBrack,
+        _ => SK::Num,
+      };
+      let text = &s[i..i + c.len_utf8()];
+      Token::new(kind, text)
+    })
+    .collect()
+}
+
+/// A sink that writes an indented dump of the tree.
+#[derive(Default)]
+struct DumpSink {
+  level: usize,
+  out: String,
+  /// whether to report trivia in runs.
+  group: bool,
+  /// whether to show the ranges of tokens and errors.
+  ranges: bool,
+}
+
+impl DumpSink {
+  fn line(&mut self, s: &str) {
+    for _ in 0..self.level {
+      self.out.push_str("  ");
+    }
+    self.out.push_str(s);
+    self.out.push('\n');
+  }
+
+  /// Like `line`, but shows the `range` if showing ranges.
+  fn line_at(&mut self, mut s: String, range: Range<usize>) {
+    if self.ranges {
+      s.push_str(&format!(" @ {:?}", range));
+    }
+    self.line(&s);
+  }
+}
+
+impl Sink<SK> for DumpSink {
+  fn enter(&mut self, kind: SK) {
+    self.line(&format!("{:?}", kind));
+    self.level += 1;
+  }
+
+  fn token(&mut self, token: Token<'_, SK>) {
+    let range = token.range().unwrap();
+    let s = format!("{:?} {:?}", token.kind, token.text);
+    self.line_at(s, range);
+  }
+
+  fn exit(&mut self) {
+    self.level -= 1;
+  }
+
+  fn error(&mut self, expected: Vec<SK>, range: Range<usize>) {
+    self.line_at(format!("error {:?}", expected), range);
+  }
+
+  fn error_capped(
+    &mut self,
+    expected: Vec<SK>,
+    more: usize,
+    range: Range<usize>,
+  ) {
+    let s = format!("error {:?} and {} more", expected, more);
+    self.line_at(s, range);
+  }
+
+  fn missing(&mut self, kind: SK, range: Range<usize>) {
+    self.line_at(format!("missing {:?}", kind), range);
+  }
+
+  fn unclosed(&mut self, close: SK, range: Range<usize>) {
+    self.line_at(format!("unclosed {:?}", close), range);
+  }
+
+  fn context(&mut self, labels: &[&'static str]) {
+    self.line(&format!("context {:?}", labels));
+  }
+
+  fn payload(&mut self, payload: String) {
+    self.line(&format!("payload {:?}", payload));
+  }
+
+  fn annotation(&mut self, data: u32) {
+    self.line(&format!("annotation {}", data));
+  }
+
+  fn group_trivia(&self) -> bool {
+    self.group
+  }
+
+  fn trivia(&mut self, tokens: &[Token<'_, SK>]) {
+    let text: String = tokens.iter().map(|t| t.text).collect();
+    self.line(&format!("trivia {:?}", text));
+  }
+}
+
+fn check<F>(s: &str, f: F, want: &str)
+where
+  F: FnOnce(&mut Parser<'_, SK>),
+{
+  let tokens = lex(s);
+  let mut p = Parser::new(&tokens);
+  let root = p.enter();
+  f(&mut p);
+  p.exit(root, SK::Root);
+  let mut sink = DumpSink::default();
+  p.finish(&mut sink);
+  assert_eq!(sink.out, want);
+}
+
+/// `<expr> ::= <num> | <expr> + <num>`, parsed with `precede`, and abandoning
+/// the speculative `Add` node when there is no `+`.
+fn expr(p: &mut Parser<'_, SK>) {
+  let en = p.enter();
+  p.eat(SK::Num);
+  let mut ex = p.exit(en, SK::Expr);
+  loop {
+    let en = p.precede(ex);
+    if !p.at(SK::Plus) {
+      p.abandon(en);
+      break;
+    }
+    p.bump();
+    let num = p.enter();
+    p.eat(SK::Num);
+    p.exit(num, SK::Expr);
+    ex = p.exit(en, SK::Add);
+  }
+}
+
+#[test]
+fn event_size() {
+  let ev = std::mem::size_of::<Event<()>>();
+  let op_ev = std::mem::size_of::<Option<Event<()>>>();
+  assert_eq!(ev, op_ev);
+  // there are many events, so they should not grow without notice. errors
+  // keep their data in a side table.
+  assert_eq!(std::mem::size_of::<Option<Event<SK>>>(), 24);
+}
+
+#[test]
+fn abandon_after_precede() {
+  check(
+    "1",
+    expr,
+    r#"Root
+  Expr
+    Num "1"
+"#,
+  );
+}
+
+#[test]
+fn abandon_after_precede_nested() {
+  check(
+    "1 + 2 + 3",
+    expr,
+    r#"Root
+  Add
+    Add
+      Expr
+        Num "1"
+      Space " "
+      Plus "+"
+      Space " "
+      Expr
+        Num "2"
+    Space " "
+    Plus "+"
+    Space " "
+    Expr
+      Num "3"
+"#,
+  );
+}
+
+#[test]
+fn abandon_with_children() {
+  check(
+    "1 2",
+    |p| {
+      let en = p.enter();
+      let inner = p.enter();
+      p.bump();
+      p.exit(inner, SK::Expr);
+      p.bump();
+      p.abandon(en);
+    },
+    r#"Root
+  Expr
+    Num "1"
+  Space " "
+  Num "2"
+"#,
+  );
+}
+
+#[test]
+fn save_restore() {
+  check(
+    "1 +",
+    |p| {
+      let save = p.save();
+      let en = p.enter();
+      p.bump();
+      p.bump();
+      p.exit(en, SK::Add);
+      p.restore(save);
+      let en = p.enter();
+      p.eat(SK::Num);
+      p.exit(en, SK::Expr);
+      p.eat(SK::Plus);
+    },
+    r#"Root
+  Expr
+    Num "1"
+  Space " "
+  Plus "+"
+"#,
+  );
+}
+
+#[test]
+fn restore_keeping_expected() {
+  check(
+    "+",
+    |p| {
+      p.at(SK::Space);
+      for &kind in [SK::Num, SK::Expr].iter() {
+        let save = p.save();
+        p.at(kind);
+        p.restore_keeping_expected(save);
+      }
+      let save = p.save();
+      p.at(SK::Add);
+      p.restore(save);
+      p.error();
+    },
+    r#"Root
+  Plus "+"
+  error [Space, Num, Expr]
+"#,
+  );
+}
+
+#[test]
+fn forget() {
+  check(
+    "+ +",
+    |p| {
+      p.set_strict_saves(true);
+      p.at(SK::Space);
+      // nothing consumed, so the kinds expected before the save are kept.
+      let save = p.save();
+      p.at(SK::Num);
+      p.forget(save);
+      p.error();
+      // consumed a token, so the kinds expected before the save are stale.
+      p.at(SK::Expr);
+      let save = p.save();
+      p.bump();
+      p.at(SK::Add);
+      p.forget(save);
+      p.error();
+    },
+    r#"Root
+  Plus "+"
+  error [Space, Num]
+  Space " "
+  Plus "+"
+  error [Add]
+"#,
+  );
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "Save markers must be restored or forgotten")]
+fn strict_saves() {
+  let tokens = lex("1");
+  let mut p = Parser::new(&tokens);
+  p.set_strict_saves(true);
+  let _ = p.save();
+}
+
+#[test]
+fn lax_saves() {
+  let tokens = lex("1");
+  let mut p = Parser::new(&tokens);
+  let _ = p.save();
+}
+
+#[test]
+fn context() {
+  check(
+    "+ + +",
+    |p| {
+      p.set_missing_follow(vec![SK::Plus]);
+      p.error();
+      let _sum = p.context("sum");
+      p.error();
+      {
+        let _num = p.context("number");
+        p.eat(SK::Num);
+        // restoring drops errors, and their contexts, since the save.
+        let save = p.save();
+        let _rhs = p.context("rhs");
+        p.error();
+        p.restore(save);
+      }
+      p.error();
+    },
+    r#"Root
+  Plus "+"
+  error []
+  Space " "
+  Plus "+"
+  context ["sum"]
+  error []
+  context ["sum", "number"]
+  missing Num
+  Space " "
+  Plus "+"
+  context ["sum"]
+  error []
+"#,
+  );
+}
+
+#[test]
+fn filter_info() {
+  let tokens = lex("1 + +");
+  let mut p = Parser::new(&tokens);
+  let root = p.enter();
+  p.bump();
+  p.error();
+  let cx = p.context("sum");
+  p.error();
+  drop(cx);
+  p.exit(root, SK::Root);
+  let mut seen = Vec::new();
+  let filter = |ev: ErrorEvent<SK>, info: &ErrorInfo<'_>| {
+    seen.push((info.at.clone(), info.context.to_vec()));
+    Some(ev)
+  };
+  let mut sink = FilterSink::new(RangeSink::default(), filter);
+  p.finish_raw(&mut sink);
+  drop(sink);
+  let want = vec![(ErrorAt::Idx(2), vec![]), (ErrorAt::Idx(4), vec!["sum"])];
+  assert_eq!(seen, want);
+}
+
+#[test]
+fn context_filter() {
+  let tokens = lex("+ +");
+  let mut p = Parser::new(&tokens);
+  let root = p.enter();
+  let cx = p.context("sum");
+  p.at(SK::Num);
+  p.error();
+  p.at(SK::Expr);
+  p.error();
+  drop(cx);
+  p.exit(root, SK::Root);
+  let filter = |ev: ErrorEvent<SK>, _: &ErrorInfo<'_>| match ev {
+    ErrorEvent::Error { ref expected, .. } if expected == &[SK::Num] => None,
+    ev => Some(ev),
+  };
+  let mut sink = CheckSink::new(FilterSink::new(DumpSink::default(), filter));
+  p.finish(&mut sink);
+  assert_eq!(
+    sink.finish().sink.out,
+    r#"Root
+  Plus "+"
+  Space " "
+  Plus "+"
+  context ["sum"]
+  error [Expr]
+"#
+  );
+}
+
+#[test]
+fn error_with() {
+  check(
+    "+ + +",
+    |p| {
+      p.error_with("a".to_owned());
+      // restoring drops payloads since the save.
+      let save = p.save();
+      p.error_with("b".to_owned());
+      p.restore(save);
+      let _cx = p.context("sum");
+      p.error_with("c".to_owned());
+      p.error();
+    },
+    r#"Root
+  Plus "+"
+  payload "a"
+  error []
+  Space " "
+  Plus "+"
+  context ["sum"]
+  payload "c"
+  error []
+  Space " "
+  Plus "+"
+  context ["sum"]
+  error []
+"#,
+  );
+}
+
+#[derive(Default)]
+struct PayloadSink(Vec<u32>);
+
+impl RawSink<SK, u32> for PayloadSink {
+  fn enter(&mut self, _: SK) {}
+
+  fn tokens(&mut self, _: Range<usize>) {}
+
+  fn exit(&mut self) {}
+
+  fn error(&mut self, _: Vec<SK>, _: usize) {}
+
+  fn payload(&mut self, payload: u32) {
+    self.0.push(payload);
+  }
+}
+
+#[test]
+fn error_with_filter() {
+  let tokens = lex("+ + +");
+  let mut p = Parser::<'_, SK, u32>::new_with_payload(&tokens, 0);
+  let root = p.enter();
+  p.at(SK::Num);
+  p.error_with(1);
+  p.error_with(2);
+  p.error_with(3);
+  p.exit(root, SK::Root);
+  let filter = |ev: ErrorEvent<SK>, info: &ErrorInfo<'_, u32>| {
+    if info.payload == Some(&1) {
+      None
+    } else {
+      Some(ev)
+    }
+  };
+  let mut sink =
+    CheckSink::new(FilterSink::new(PayloadSink::default(), filter));
+  p.finish_raw(&mut sink);
+  assert_eq!(sink.finish().sink.0, [2, 3]);
+}
+
+#[derive(Default)]
+struct StartFinishSink {
+  total_tokens: Option<usize>,
+  errors: Option<usize>,
+}
+
+impl RawSink<SK> for StartFinishSink {
+  fn enter(&mut self, _: SK) {}
+
+  fn tokens(&mut self, _: Range<usize>) {}
+
+  fn exit(&mut self) {}
+
+  fn error(&mut self, _: Vec<SK>, _: usize) {}
+
+  fn start(&mut self, total_tokens: usize) {
+    self.total_tokens = Some(total_tokens);
+  }
+
+  fn finish_ok(&mut self, errors: usize) {
+    self.errors = Some(errors);
+  }
+}
+
+#[test]
+fn start_finish_ok() {
+  let tokens = lex("+ + +");
+  let mut p = Parser::new(&tokens);
+  p.set_missing_follow(vec![SK::Plus]);
+  let root = p.enter();
+  p.eat(SK::Num);
+  p.at(SK::Num);
+  p.error();
+  p.error();
+  p.error();
+  p.exit(root, SK::Root);
+  let filter = |ev: ErrorEvent<SK>, _: &ErrorInfo<'_>| match ev {
+    ErrorEvent::Error { ref expected, .. } if expected.is_empty() => None,
+    ev => Some(ev),
+  };
+  let inner = FilterSink::new(StartFinishSink::default(), filter);
+  let mut sink = CheckSink::new(inner);
+  p.finish_raw(&mut sink);
+  let sink = sink.finish().sink;
+  assert_eq!(sink.total_tokens, Some(5));
+  // the missing token and the error expecting a number are kept.
+  assert_eq!(sink.errors, Some(2));
+}
+
+#[derive(Default)]
+struct RangeSink(Vec<String>);
+
+impl RawSink<SK> for RangeSink {
+  fn enter(&mut self, kind: SK) {
+    self.0.push(format!("{:?}", kind));
+  }
+
+  fn tokens(&mut self, range: Range<usize>) {
+    self.0.push(format!("{:?}", range));
+  }
+
+  fn exit(&mut self) {
+    self.0.push("exit".to_owned());
+  }
+
+  fn error(&mut self, expected: Vec<SK>, idx: usize) {
+    self.0.push(format!("error {:?} {}", expected, idx));
+  }
+}
+
+#[test]
+fn raw_sink() {
+  let tokens = lex("1 + 2");
+  let mut p = Parser::new(&tokens);
+  let root = p.enter();
+  expr(&mut p);
+  p.exit(root, SK::Root);
+  let mut sink = RangeSink::default();
+  p.finish_raw(&mut sink);
+  let want = [
+    "Root", "Add", "Expr", "0..1", "exit", "1..4", "Expr", "4..5", "exit",
+    "exit", "exit",
+  ];
+  assert_eq!(sink.0, want);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "tokens 1..2 were already reported")]
+fn report_twice() {
+  let tokens = lex("1+2");
+  let mut p = Parser::new(&tokens);
+  let root = p.enter();
+  p.bump();
+  p.bump();
+  let en = p.enter();
+  // as if restoring had gone wrong.
+  p.events.push(Some(Event::Raw(1)));
+  p.exit(en, SK::Expr);
+  p.exit(root, SK::Root);
+  p.finish_raw(&mut RangeSink::default());
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "no token to report")]
+fn report_past_end() {
+  let tokens = lex("1");
+  let mut p = Parser::new(&tokens);
+  let root = p.enter();
+  p.bump();
+  p.events.push(Some(Event::Token));
+  p.exit(root, SK::Root);
+  p.finish_raw(&mut RangeSink::default());
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "not all tokens were reported: 2..3")]
+fn report_unconsumed() {
+  let tokens = lex("1 +");
+  let mut p = Parser::new(&tokens);
+  let root = p.enter();
+  p.bump();
+  p.exit(root, SK::Root);
+  p.finish_raw(&mut RangeSink::default());
+}
+
+#[test]
+fn attach() {
+  let tokens = [
+    Token::new(SK::Num, "1"),
+    Token::new(SK::Space, " "),
+    Token::new(SK::Space, "# one"),
+    Token::new(SK::Space, "\n\n"),
+    Token::new(SK::Space, "# two"),
+    Token::new(SK::Space, "\n"),
+    Token::new(SK::Num, "2"),
+  ];
+  let attach = attach_trivia(&tokens);
+  assert_eq!(
+    attach,
+    [
+      Attach::None,
+      Attach::Trailing,
+      Attach::Trailing,
+      Attach::None,
+      Attach::Leading,
+      Attach::Leading,
+      Attach::None,
+    ]
+  );
+  let mut p = Parser::new(&tokens);
+  p.set_attach(attach);
+  let root = p.enter();
+  for _ in 0..2 {
+    let en = p.enter();
+    let num = p.enter();
+    p.bump();
+    p.exit(num, SK::Num);
+    p.exit(en, SK::Expr);
+  }
+  p.exit(root, SK::Root);
+  let mut sink = DumpSink::default();
+  p.finish(&mut sink);
+  let want = r##"Root
+  Expr
+    Num
+      Num "1"
+    Space " "
+    Space "# one"
+  Space "\n\n"
+  Expr
+    Space "# two"
+    Space "\n"
+    Num
+      Num "2"
+"##;
+  assert_eq!(sink.out, want);
+}
+
+/// Parses each of the non-trivia `tokens` as an item, with the file trivia
+/// policy.
+fn file_trivia_dump(tokens: &[Token<'_, SK>], policy: FileTrivia) -> String {
+  let mut p = Parser::new(tokens);
+  p.set_attach(attach_trivia(tokens));
+  p.set_file_leading_trivia(policy);
+  p.set_file_trailing_trivia(policy);
+  let root = p.enter();
+  while p.peek().is_some() {
+    let en = p.enter();
+    let num = p.enter();
+    p.bump();
+    p.exit(num, SK::Num);
+    p.exit(en, SK::Expr);
+  }
+  p.exit(root, SK::Root);
+  let mut sink = DumpSink::default();
+  p.finish(&mut sink);
+  sink.out
+}
+
+#[test]
+fn file_trivia() {
+  let tokens = [
+    Token::new(SK::Space, "# licence"),
+    Token::new(SK::Space, "\n"),
+    Token::new(SK::Num, "1"),
+    Token::new(SK::Space, " "),
+    Token::new(SK::Space, "# end"),
+    Token::new(SK::Space, "\n"),
+  ];
+  let want = r##"Root
+  Expr
+    Space "# licence"
+    Space "\n"
+    Num
+      Num "1"
+    Space " "
+    Space "# end"
+  Space "\n"
+"##;
+  assert_eq!(file_trivia_dump(&tokens, FileTrivia::Attach), want);
+  let want = r##"Root
+  Space "# licence"
+  Space "\n"
+  Expr
+    Num
+      Num "1"
+  Space " "
+  Space "# end"
+  Space "\n"
+"##;
+  assert_eq!(file_trivia_dump(&tokens, FileTrivia::Root), want);
+  let want = r##"Root
+  Expr
+    Space "# licence"
+    Space "\n"
+    Num
+      Num "1"
+    Space " "
+    Space "# end"
+    Space "\n"
+"##;
+  assert_eq!(file_trivia_dump(&tokens, FileTrivia::Item), want);
+}
+
+#[test]
+fn file_trivia_no_items() {
+  let tokens = [Token::new(SK::Space, "# a"), Token::new(SK::Space, "\n")];
+  let want = r##"Root
+  Space "# a"
+  Space "\n"
+"##;
+  assert_eq!(file_trivia_dump(&tokens, FileTrivia::Attach), want);
+  assert_eq!(file_trivia_dump(&tokens, FileTrivia::Root), want);
+  assert_eq!(file_trivia_dump(&tokens, FileTrivia::Item), want);
+}
+
+#[test]
+fn expected_order() {
+  check(
+    "+",
+    |p| {
+      p.set_expected_order(Ord::cmp);
+      for &kind in [SK::Plus, SK::Num, SK::Plus].iter() {
+        p.at(kind);
+      }
+      p.error();
+    },
+    r#"Root
+  Plus "+"
+  error [Num, Plus]
+"#,
+  );
+}
+
+#[test]
+fn error_node() {
+  let f = |trivia| {
+    move |p: &mut Parser<'_, SK>| {
+      let en = p.enter();
+      p.eat(SK::Num);
+      p.error_node(SK::Error, trivia);
+      p.exit(en, SK::Expr);
+    }
+  };
+  check(
+    "1  +",
+    f(ErrorTrivia::Exclude),
+    r#"Root
+  Expr
+    Num "1"
+    Space " "
+    Space " "
+    Error
+      Plus "+"
+    error []
+"#,
+  );
+  check(
+    "1  +",
+    f(ErrorTrivia::Include),
+    r#"Root
+  Expr
+    Num "1"
+    Error
+      Space " "
+      Space " "
+      Plus "+"
+    error []
+"#,
+  );
+}
+
+#[test]
+fn error_recover() {
+  check(
+    "+ + 1 +",
+    |p| {
+      p.at(SK::Num);
+      p.error_recover(SK::Error, &[SK::Num]);
+      p.bump();
+      p.error_recover(SK::Error, &[SK::Num]);
+      p.at(SK::Num);
+      p.error_recover(SK::Error, &[SK::Num]);
+    },
+    r#"Root
+  Error
+    Plus "+"
+    Space " "
+    Plus "+"
+  Space " "
+  error [Num]
+  Num "1"
+  Space " "
+  Error
+    Plus "+"
+  error []
+  error [Num]
+"#,
+  );
+}
+
+#[test]
+fn parse_file() {
+  let tokens = lex("++1 2");
+  let mut p = Parser::new(&tokens);
+  p.parse_file(
+    SK::Root,
+    |p| {
+      if p.at(SK::Num) {
+        expr(p);
+        true
+      } else {
+        false
+      }
+    },
+    &[SK::Num],
+  );
+  let mut sink = DumpSink::default();
+  p.finish(&mut sink);
+  let want = r#"Root
+  Plus "+"
+  error [Num]
+  Plus "+"
+  Expr
+    Num "1"
+  Space " "
+  Expr
+    Num "2"
+"#;
+  assert_eq!(sink.out, want);
+}
+
+#[test]
+fn missing() {
+  let tokens = lex("1+ +2");
+  let mut p = Parser::new(&tokens);
+  p.set_missing_follow(vec![SK::Plus]);
+  let root = p.enter();
+  expr(&mut p);
+  p.exit(root, SK::Root);
+  let mut sink = DumpSink::default();
+  p.finish(&mut sink);
+  let want = r#"Root
+  Add
+    Add
+      Expr
+        Num "1"
+      Plus "+"
+      Space " "
+      Expr
+        missing Num
+    Plus "+"
+    Expr
+      Num "2"
+"#;
+  assert_eq!(sink.out, want);
+}
+
+#[test]
+fn memory_usage() {
+  let tokens = lex("1+");
+  let mut p = Parser::new(&tokens);
+  assert_eq!(p.memory_usage(), 0);
+  let root = p.enter();
+  expr(&mut p);
+  p.exit(root, SK::Root);
+  assert!(p.memory_usage() >= 6 * std::mem::size_of::<Option<Event<SK>>>());
+}
+
+#[test]
+fn expect_eof() {
+  check(
+    "1 2+",
+    |p| {
+      p.eat(SK::Num);
+      p.expect_eof();
+      assert!(p.peek().is_none());
+      p.expect_eof();
+    },
+    r#"Root
+  Num "1"
+  Space " "
+  Num "2"
+  error []
+  Plus "+"
+"#,
+  );
+}
+
+#[test]
+fn expected_cap() {
+  check(
+    "+1",
+    |p| {
+      p.set_expected_order(Ord::cmp);
+      p.set_expected_cap(1);
+      for &kind in [SK::Plus, SK::Space, SK::Num].iter() {
+        p.at(kind);
+      }
+      p.error();
+      assert!(p.at(SK::Num));
+      p.error();
+    },
+    r#"Root
+  Plus "+"
+  error [Space] and 2 more
+  Num "1"
+  error [Num]
+"#,
+  );
+}
+
+#[test]
+fn new_at() {
+  let tokens = lex("9 1 + 2");
+  let mut p = Parser::new_at(&tokens[2..], 2);
+  let root = p.enter();
+  expr(&mut p);
+  p.exit(root, SK::Root);
+  let mut sink = RangeSink::default();
+  p.finish_raw(&mut sink);
+  let want = [
+    "Root", "Add", "Expr", "2..3", "exit", "3..6", "Expr", "6..7", "exit",
+    "exit", "exit",
+  ];
+  assert_eq!(sink.0, want);
+  let mut p = Parser::new_at(&tokens[2..4], 2);
+  let root = p.enter();
+  p.eat(SK::Num);
+  p.exit(root, SK::Root);
+  let mut sink = DumpSink::default();
+  p.finish(&mut sink);
+  assert_eq!(sink.out, "Root\n  Num \"1\"\n  Space \" \"\n");
+}
+
+fn tracked(p: &mut Parser<'_, SK>) {
+  let rule = p.start_rule("tracked");
+  if p.at(SK::Num) {
+    p.bump();
+  }
+  p.end_rule(rule);
+}
+
+#[test]
+fn rule_progress() {
+  let tokens = lex(" 1 +");
+  let mut p = Parser::new(&tokens);
+  tracked(&mut p);
+  let rule = p.start_rule("error");
+  p.error();
+  p.end_rule(rule);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "rule tracked made no progress")]
+fn rule_no_progress() {
+  let tokens = lex(" +");
+  let mut p = Parser::new(&tokens);
+  tracked(&mut p);
+}
+
+fn rec_expr(p: &mut Recorder<'_, SK>) {
+  let en = p.enter();
+  p.eat(SK::Num);
+  let ex = p.exit(en, SK::Expr);
+  let en = p.precede(ex);
+  if p.at(SK::Plus) {
+    p.bump();
+    rec_expr(p);
+    p.exit(en, SK::Add);
+  } else {
+    p.abandon(en);
+  }
+}
+
+fn from_name(s: &str) -> Option<SK> {
+  [
+    SK::Num,
+    SK::Plus,
+    SK::Root,
+    SK::Expr,
+    SK::Add,
+    SK::Error,
+    SK::Kw,
+  ]
+  .iter()
+  .copied()
+  .find(|k| format!("{:?}", k) == s)
+}
+
+#[test]
+fn record_replay() {
+  let tokens = lex("1 + 2");
+  let mut p = Recorder::new(Parser::new(&tokens));
+  rec_expr(&mut p);
+  let mut sink = DumpSink::default();
+  let log = p.finish(&mut sink);
+  let s = log.to_string();
+  assert_eq!(
+    s,
+    "enter 0\neat Num\nexit 0 Expr\nprecede 0 1\nat Plus\nbump\nenter 2\n\
+     eat Num\nexit 2 Expr\nprecede 2 3\nat Plus\nabandon 3\nexit 1 Add\n"
+  );
+  let log = Log::parse(&s, from_name).unwrap();
+  assert_eq!(Log::parse("enter 0\nexit 0 Nope\n", from_name), Err(1));
+  let annotate = Log::parse("annotate 0 7\n", from_name).unwrap();
+  assert_eq!(annotate.calls, [Call::Annotate(0, 7)]);
+  assert_eq!(annotate.to_string(), "annotate 0 7\n");
+  let mut replay = Replay::new(Parser::new(&tokens), log.clone());
+  assert!(replay.run_until(|p| p.token_idx() == 3));
+  assert_eq!(replay.next_call(), Some(&Call::Enter(2)));
+  // dropping mid-replay doesn't panic.
+  drop(replay);
+  let mut replayed = DumpSink::default();
+  Replay::new(Parser::new(&tokens), log).finish(&mut replayed);
+  assert_eq!(replayed.out, sink.out);
+}
+
+#[test]
+fn record_error_recover() {
+  let tokens = lex("+ + 1");
+  let mut p = Recorder::new(Parser::new(&tokens));
+  let root = p.enter();
+  p.error_recover(SK::Error, &[SK::Num, SK::Plus]);
+  p.error_recover(SK::Error, &[]);
+  p.exit(root, SK::Root);
+  let mut sink = DumpSink::default();
+  let log = p.finish(&mut sink);
+  let s = log.to_string();
+  assert_eq!(
+    s,
+    "enter 0\nerror_recover Error Num Plus\nerror_recover Error\n\
+     exit 0 Root\n"
+  );
+  let log = Log::parse(&s, from_name).unwrap();
+  assert_eq!(
+    log.calls[1],
+    Call::ErrorRecover(SK::Error, vec![SK::Num, SK::Plus])
+  );
+  let mut replayed = DumpSink::default();
+  Replay::new(Parser::new(&tokens), log).finish(&mut replayed);
+  assert_eq!(replayed.out, sink.out);
+}
+
+#[test]
+fn record_text() {
+  let tokens = lex("a b");
+  let mut p = Recorder::new(Parser::new(&tokens));
+  let root = p.enter();
+  assert!(!p.at_text(SK::Kw, "b"));
+  p.eat_text(SK::Kw, "a");
+  p.eat_text(SK::Kw, "a c");
+  p.exit(root, SK::Root);
+  let mut sink = DumpSink::default();
+  let log = p.finish(&mut sink);
+  let s = log.to_string();
+  assert_eq!(
+    s,
+    "enter 0\nat_text Kw b\neat_text Kw a\neat_text Kw a c\nexit 0 Root\n"
+  );
+  let log = Log::parse(&s, from_name).unwrap();
+  assert_eq!(log.calls[3], Call::EatText(SK::Kw, "a c".to_owned()));
+  assert_eq!(Log::parse("at_text Kw\n", from_name), Err(0));
+  let mut replayed = DumpSink::default();
+  Replay::new(Parser::new(&tokens), log).finish(&mut replayed);
+  assert_eq!(replayed.out, sink.out);
+}
+
+#[test]
+fn record_payload_and_kind() {
+  let tokens = lex("+ 1");
+  let parser = Parser::<'_, SK, u32>::new_with_payload(&tokens, 0);
+  let mut p = Recorder::new(parser);
+  let root = p.enter_kind(SK::Root);
+  p.error_with(7);
+  p.eat(SK::Num);
+  p.exit(root, SK::Root);
+  let mut sink = CheckSink::new(PayloadSink::default());
+  let log = p.finish_raw(&mut sink);
+  assert_eq!(sink.finish().0, [7]);
+  let s = log.to_string();
+  assert_eq!(s, "enter_kind 0 Root\nerror_with 7\neat Num\nexit 0 Root\n");
+  let log = Log::parse_with_payload(&s, from_name, |p| p.parse().ok());
+  let parser = Parser::new_with_payload(&tokens, 0);
+  let mut replay = Replay::new(parser, log.unwrap());
+  replay.step();
+  // the kind is replayed too.
+  let kinds: Vec<_> = replay.parser().open_kinds().collect();
+  assert_eq!(kinds, [Some(&SK::Root)]);
+  assert_eq!(replay.step(), Some(Call::ErrorWith(7)));
+  drop(replay);
+  let log = Log::parse("error_with a b\n", from_name).unwrap();
+  assert_eq!(log.calls, [Call::ErrorWith("a b".to_owned())]);
+}
+
+#[test]
+fn tee_sink() {
+  let tokens = lex("1 + ");
+  let mut p = Parser::new(&tokens);
+  let root = p.enter();
+  expr(&mut p);
+  p.exit(root, SK::Root);
+  let mut sink = TeeSink::new(DumpSink::default(), DumpSink::default());
+  p.finish(&mut sink);
+  assert!(sink.a.out.contains("error"));
+  assert_eq!(sink.a.out, sink.b.out);
+}
+
+#[test]
+fn tee_sink_group_one() {
+  let tokens = lex("1  + ");
+  let finish = |sink: &mut dyn Sink<SK>| {
+    let mut p = Parser::new(&tokens);
+    let root = p.enter();
+    expr(&mut p);
+    p.exit(root, SK::Root);
+    p.finish(sink);
+  };
+  let group = || DumpSink {
+    group: true,
+    ..DumpSink::default()
+  };
+  let mut sink = TeeSink::new(group(), DumpSink::default());
+  finish(&mut sink);
+  let mut want_a = group();
+  finish(&mut want_a);
+  let mut want_b = DumpSink::default();
+  finish(&mut want_b);
+  assert!(sink.a.out.contains("trivia"));
+  assert_eq!(sink.a.out, want_a.out);
+  assert!(!sink.b.out.contains("trivia"));
+  assert_eq!(sink.b.out, want_b.out);
+}
+
+#[test]
+fn lex_parallel_same() {
+  let s = "1 + 22 + 333 + 4444 + 55555 + 666666";
+  // restart after a space.
+  let boundary = |s: &str, idx: usize| match s[idx..].find(' ') {
+    Some(n) => idx + n + 1,
+    None => s.len(),
+  };
+  for chunks in 1..10 {
+    let tokens = lex_parallel(s, chunks, boundary, lex);
+    let want = lex(s);
+    assert_eq!(tokens.len(), want.len());
+    for (a, b) in tokens.iter().zip(want.iter()) {
+      assert_eq!((a.kind, a.text), (b.kind, b.text));
+    }
+  }
+  assert!(lex_parallel("", 4, boundary, lex).is_empty());
+}
+
+#[test]
+fn filter_sink() {
+  let tokens = lex("+");
+  let mut p = Parser::new(&tokens);
+  p.set_missing_follow(vec![SK::Plus]);
+  let root = p.enter();
+  p.eat(SK::Num);
+  p.eat(SK::Num);
+  p.at(SK::Plus);
+  p.error();
+  p.exit(root, SK::Root);
+  // drop consecutive duplicates, and report a missing token as an error.
+  let mut last = None;
+  let filter = |ev: ErrorEvent<SK>, _: &ErrorInfo<'_>| {
+    let ev = match ev {
+      ErrorEvent::Missing(kind) => ErrorEvent::Error {
+        expected: vec![kind],
+        more: 0,
+      },
+      ev => ev,
+    };
+    if last.as_ref() == Some(&ev) {
+      return None;
+    }
+    last = Some(ev.clone());
+    Some(ev)
+  };
+  let mut sink = FilterSink::new(DumpSink::default(), filter);
+  p.finish(&mut sink);
+  assert_eq!(
+    sink.sink.out,
+    r#"Root
+  error [Num]
+  Plus "+"
+  error [Plus]
+"#
+  );
+}
+
+#[test]
+fn metrics_sink() {
+  let tokens = lex("1 + 2 +");
+  let parse = || {
+    let mut p = Parser::new(&tokens);
+    let root = p.enter();
+    expr(&mut p);
+    p.exit(root, SK::Root);
+    p
+  };
+  let mut raw = MetricsSink::new(RangeSink::default());
+  parse().finish_raw(&mut raw);
+  let mut sink = MetricsSink::new(DumpSink::default());
+  parse().finish(&mut sink);
+  let (dump, metrics) = sink.into_parts();
+  assert!(dump.out.contains("error"));
+  assert_eq!(metrics, raw.metrics);
+  assert_eq!(metrics.nodes[&SK::Root], 1);
+  assert_eq!(metrics.nodes[&SK::Add], 2);
+  assert_eq!(metrics.nodes[&SK::Expr], 3);
+  assert_eq!(metrics.node_count(), 6);
+  assert_eq!(metrics.tokens, tokens.len());
+  assert_eq!(metrics.errors, 1);
+  assert_eq!(metrics.max_depth, 4);
+}
+
+crate::grammar_ext! {
+  /// Methods for the grammar.
+  trait Grammar for SK {
+    fn expr();
+    /// Eats the `kind` while at it, and returns how many were eaten.
+    fn eat_many(kind: SK,) -> usize = rules::eat_many;
+  }
+}
+
+crate::grammar_ext! {
+  trait PayloadGrammar for SK, u32 {
+    fn error_code(code: u32) = rules::error_code;
+  }
+}
+
+mod rules {
+  use super::SK;
+  use crate::Parser;
+
+  pub(super) fn eat_many(p: &mut Parser<'_, SK>, kind: SK) -> usize {
+    let mut ret = 0;
+    while p.eat(kind).is_some() {
+      ret += 1;
+    }
+    ret
+  }
+
+  pub(super) fn error_code(p: &mut Parser<'_, SK, u32>, code: u32) {
+    p.error_with(code);
+  }
+}
+
+#[test]
+fn grammar_ext() {
+  let tokens = lex("1 + 2");
+  let mut p = Parser::new(&tokens);
+  let root = p.enter();
+  p.expr();
+  p.exit(root, SK::Root);
+  let mut sink = DumpSink::default();
+  p.finish(&mut sink);
+  let mut want = DumpSink::default();
+  let mut p = Parser::new(&tokens);
+  let root = p.enter();
+  expr(&mut p);
+  p.exit(root, SK::Root);
+  p.finish(&mut want);
+  assert_eq!(sink.out, want.out);
+  let tokens = lex("+++1");
+  let mut p = Parser::new(&tokens);
+  let root = p.enter();
+  assert_eq!(p.eat_many(SK::Plus), 3);
+  assert_eq!(p.eat_many(SK::Plus), 0);
+  p.eat(SK::Num);
+  p.exit(root, SK::Root);
+  p.finish(&mut DumpSink::default());
+  let tokens = lex("+");
+  let mut p = Parser::<'_, SK, u32>::new_with_payload(&tokens, 0);
+  let root = p.enter();
+  p.error_code(7);
+  p.exit(root, SK::Root);
+  let mut sink = PayloadSink::default();
+  p.finish_raw(&mut sink);
+  assert_eq!(sink.0, [7]);
+}
+
+#[test]
+fn check_sink() {
+  let tokens = lex("1 + ");
+  let parse = || {
+    let mut p = Parser::new(&tokens);
+    let root = p.enter();
+    expr(&mut p);
+    p.exit(root, SK::Root);
+    p
+  };
+  let mut sink = CheckSink::new(DumpSink::default());
+  parse().finish(&mut sink);
+  assert!(sink.finish().out.contains("error"));
+  let mut sink = CheckSink::new(RangeSink::default());
+  parse().finish_raw(&mut sink);
+  assert_eq!(sink.next_token(), tokens.len());
+  sink.finish();
+}
+
+#[test]
+fn check_sink_errors_outside_root() {
+  let mut sink = CheckSink::new(RangeSink::default());
+  sink.set_errors_outside_root(true);
+  RawSink::error(&mut sink, vec![SK::Num], 0);
+  RawSink::enter(&mut sink, SK::Root);
+  RawSink::exit(&mut sink);
+  sink.finish();
+}
+
+#[test]
+#[should_panic(
+  expected = "exit without enter (at call 4, inside [], at token 1)"
+)]
+fn check_sink_exit() {
+  let mut sink = CheckSink::new(RangeSink::default());
+  RawSink::enter(&mut sink, SK::Root);
+  RawSink::tokens(&mut sink, 0..1);
+  RawSink::exit(&mut sink);
+  RawSink::exit(&mut sink);
+}
+
+#[test]
+#[should_panic(expected = "tokens out of order: 2..3")]
+fn check_sink_order() {
+  let mut sink = CheckSink::new(RangeSink::default());
+  RawSink::enter(&mut sink, SK::Root);
+  RawSink::tokens(&mut sink, 0..1);
+  RawSink::tokens(&mut sink, 2..3);
+}
+
+#[test]
+#[should_panic(expected = "context not followed by an error (at call 3")]
+fn check_sink_context() {
+  let mut sink = CheckSink::new(DumpSink::default());
+  Sink::enter(&mut sink, SK::Root);
+  Sink::context(&mut sink, &["sum"]);
+  Sink::exit(&mut sink);
+}
+
+#[test]
+#[should_panic(expected = "not all syntax constructs were exited")]
+fn check_sink_unbalanced() {
+  let mut sink = CheckSink::new(DumpSink::default());
+  Sink::enter(&mut sink, SK::Root);
+  Sink::enter(&mut sink, SK::Expr);
+  Sink::exit(&mut sink);
+  sink.finish();
+}
+
+#[cfg(feature = "arbitrary")]
+impl crate::fuzz::FuzzKind for SK {
+  fn kinds() -> &'static [Self] {
+    &[SK::Space, SK::Num, SK::Plus]
+  }
+
+  fn text(&self) -> &'static str {
+    match *self {
+      SK::Space => " ",
+      SK::Num => "1",
+      SK::Plus => "+",
+      _ => unreachable!("not a token: {:?}", self),
+    }
+  }
+}
+
+#[cfg(feature = "arbitrary")]
+fn fuzz_file(p: &mut Parser<'_, SK>) {
+  p.parse_file(
+    SK::Root,
+    |p| {
+      if p.at(SK::Num) {
+        expr(p);
+        true
+      } else {
+        false
+      }
+    },
+    &[SK::Num],
+  );
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+fn fuzz_check() {
+  use crate::fuzz::{check, TokenStream};
+  use arbitrary::{Arbitrary as _, Unstructured};
+  for seed in 0u8..=255 {
+    let data: Vec<u8> = (0u8..64)
+      .map(|i| i.wrapping_mul(seed).wrapping_add(seed))
+      .collect();
+    let mut u = Unstructured::new(&data);
+    let stream = TokenStream::<SK>::arbitrary(&mut u).unwrap();
+    check(&stream.tokens(), fuzz_file);
+  }
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+fn fuzz_check_trivia() {
+  for s in ["", "  ", " 1  ", "+ "].iter() {
+    crate::fuzz::check(&lex(s), fuzz_file);
+  }
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+#[should_panic(expected = "not all tokens were reported")]
+fn fuzz_check_unreported() {
+  crate::fuzz::check(&lex("1 +"), |p| {
+    let root = p.enter();
+    p.bump();
+    p.exit(root, SK::Root);
+  });
+}
+
+#[cfg(feature = "arbitrary")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NoTrivia {
+  Space,
+  Num,
+}
+
+#[cfg(feature = "arbitrary")]
+impl Triviable for NoTrivia {
+  fn is_trivia(&self) -> bool {
+    matches!(*self, NoTrivia::Space)
+  }
+}
+
+#[cfg(feature = "arbitrary")]
+impl crate::fuzz::FuzzKind for NoTrivia {
+  fn kinds() -> &'static [Self] {
+    &[NoTrivia::Space, NoTrivia::Num]
+  }
+
+  fn text(&self) -> &'static str {
+    match *self {
+      NoTrivia::Space => " ",
+      NoTrivia::Num => "1",
+    }
+  }
+
+  fn trivia_percent() -> u8 {
+    0
+  }
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+fn fuzz_no_trivia_one_kind() {
+  use crate::fuzz::TokenStream;
+  use arbitrary::{Arbitrary as _, Unstructured};
+  let data: Vec<u8> = (0u8..64).collect();
+  let mut u = Unstructured::new(&data);
+  let stream = TokenStream::<NoTrivia>::arbitrary(&mut u).unwrap();
+  assert!(stream.tokens().iter().all(|t| t.kind == NoTrivia::Num));
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+#[should_panic(expected = "not exactly one root")]
+fn fuzz_check_no_root() {
+  let tokens = lex("1");
+  crate::fuzz::check(&tokens, |p| {
+    p.bump();
+  });
+}
+
+#[test]
+fn group_trivia() {
+  let tokens = lex("  1  +   2 ");
+  let mut p = Parser::new(&tokens);
+  let root = p.enter();
+  expr(&mut p);
+  p.exit(root, SK::Root);
+  let mut sink = DumpSink {
+    group: true,
+    ..DumpSink::default()
+  };
+  p.finish(&mut sink);
+  let want = r#"Root
+  trivia "  "
+  Add
+    Expr
+      Num "1"
+    trivia "  "
+    Plus "+"
+    trivia "   "
+    Expr
+      Num "2"
+  trivia " "
+"#;
+  assert_eq!(sink.out, want);
+}
+
+/// Counts the tokens and the deepest nesting.
+#[derive(Default)]
+struct CountSink {
+  level: usize,
+  max_level: usize,
+  tokens: usize,
+}
+
+impl Sink<SK> for CountSink {
+  fn enter(&mut self, _: SK) {
+    self.level += 1;
+    self.max_level = self.max_level.max(self.level);
+  }
+
+  fn token(&mut self, _: Token<'_, SK>) {
+    self.tokens += 1;
+  }
+
+  fn exit(&mut self) {
+    self.level -= 1;
+  }
+
+  fn error(&mut self, _: Vec<SK>, _: Range<usize>) {}
+}
+
+#[test]
+fn deep_nesting() {
+  const DEPTH: usize = 1_000_000;
+  let tokens = lex("1+");
+  let mut p = Parser::new(&tokens);
+  let root = p.enter();
+  // deep with enter.
+  let entered: Vec<_> = (0..DEPTH).map(|_| p.enter()).collect();
+  p.bump();
+  for en in entered {
+    p.exit(en, SK::Expr);
+  }
+  // deep with precede.
+  let en = p.enter();
+  p.bump();
+  let mut ex = p.exit(en, SK::Expr);
+  for _ in 0..DEPTH {
+    let en = p.precede(ex);
+    ex = p.exit(en, SK::Add);
+  }
+  p.exit(root, SK::Root);
+  let mut sink = CountSink::default();
+  p.finish(&mut sink);
+  assert_eq!(sink.level, 0);
+  assert_eq!(sink.max_level, DEPTH + 2);
+  assert_eq!(sink.tokens, 2);
+}
+
+#[test]
+fn depth() {
+  let tokens = lex("1+1");
+  let mut p = Parser::new(&tokens);
+  assert_eq!(p.depth(), 0);
+  let root = p.enter_kind(SK::Root);
+  let en = p.enter();
+  p.bump();
+  let ex = p.exit(en, SK::Expr);
+  assert_eq!(p.depth(), 1);
+  let add = p.precede(ex);
+  let num = p.enter_kind(SK::Expr);
+  let kinds: Vec<_> = p.open_kinds().collect();
+  assert_eq!(kinds, [Some(&SK::Root), None, Some(&SK::Expr)]);
+  assert_eq!(p.open_kinds().next_back(), Some(Some(&SK::Expr)));
+  let extra = p.enter();
+  p.abandon(extra);
+  // exited out of order.
+  p.exit(add, SK::Add);
+  assert_eq!(p.depth(), 2);
+  let kinds: Vec<_> = p.open_kinds().collect();
+  assert_eq!(kinds, [Some(&SK::Root), Some(&SK::Expr)]);
+  p.exit(num, SK::Expr);
+  p.exit(root, SK::Root);
+  assert_eq!(p.depth(), 0);
+  assert_eq!(p.open_kinds().count(), 0);
+}
+
+#[test]
+fn at_flag() {
+  // like `1 + 1\n1`, with the newline lexed away into a flag.
+  let mut tokens = lex("1 +1 1");
+  tokens[5].flags = Flags::NEWLINE_BEFORE;
+  assert!(tokens[5]
+    .flags
+    .contains(Flags::NEWLINE_BEFORE | Flags::NONE));
+  let mut p = Parser::new(&tokens);
+  let root = p.enter();
+  while p.peek().is_some() {
+    expr(&mut p);
+    assert!(p.peek().is_none() || p.at_flag(Flags::NEWLINE_BEFORE));
+  }
+  p.exit(root, SK::Root);
+  let mut sink = DumpSink::default();
+  p.finish(&mut sink);
+  let want = r#"Root
+  Add
+    Expr
+      Num "1"
+    Space " "
+    Plus "+"
+    Expr
+      Num "1"
+  Space " "
+  Expr
+    Num "1"
+"#;
+  assert_eq!(sink.out, want);
+}
+
+#[test]
+fn raw_until() {
+  let tokens = lex(" 1 2 + +1");
+  let mut p = Recorder::new(Parser::new(&tokens));
+  let root = p.enter();
+  p.raw_until(SK::Plus, SK::Expr);
+  p.bump();
+  p.raw_until(SK::Plus, SK::Expr);
+  p.bump();
+  p.eat(SK::Num);
+  p.exit(root, SK::Root);
+  let mut sink = DumpSink::default();
+  let log = p.finish(&mut sink);
+  let want = r#"Root
+  Space " "
+  Expr
+    Num "1"
+    Space " "
+    Num "2"
+    Space " "
+  Plus "+"
+  Space " "
+  Expr
+  Plus "+"
+  Num "1"
+"#;
+  assert_eq!(sink.out, want);
+  let s = log.to_string();
+  assert!(s.contains("raw_until 1 Plus Expr\n"));
+  assert_eq!(Log::parse(&s, from_name).unwrap().to_string(), s);
+}
+
+#[test]
+fn try_bump() {
+  let tokens = lex("1 ");
+  let mut p = Recorder::new(Parser::new(&tokens));
+  let root = p.enter();
+  assert!(!p.at(SK::Plus));
+  assert!(!p.at_flag(Flags::NEWLINE_BEFORE));
+  assert_eq!(p.try_bump().unwrap().text, "1");
+  assert!(p.try_bump().is_none());
+  assert!(p.try_bump().is_none());
+  p.exit(root, SK::Root);
+  let mut sink = DumpSink::default();
+  let log = p.finish(&mut sink);
+  let want = r#"Root
+  Num "1"
+  Space " "
+"#;
+  assert_eq!(sink.out, want);
+  assert_eq!(log.to_string(), "enter 0\nat Plus\nbump\nexit 0 Root\n");
+}
+
+#[test]
+fn ranges() {
+  let parse = |tokens: &[Token<'_, SK>]| {
+    let mut p = Parser::new(tokens);
+    p.set_missing_follow(vec![SK::Plus]);
+    let root = p.enter();
+    p.eat(SK::Num);
+    p.eat(SK::Num);
+    p.bump();
+    p.error();
+    p.error();
+    p.exit(root, SK::Root);
+    let mut sink = DumpSink {
+      ranges: true,
+      ..DumpSink::default()
+    };
+    p.finish(&mut sink);
+    sink.out
+  };
+  let mut tokens = lex("1 +  +");
+  let want = r#"Root
+  Num "1" @ 0..1
+  missing Num @ 1..1
+  Space " " @ 1..2
+  Plus "+" @ 2..3
+  Space " " @ 3..4
+  Space " " @ 4..5
+  Plus "+" @ 5..6
+  error [] @ 5..6
+  error [] @ 6..6
+"#;
+  assert_eq!(parse(&tokens), want);
+  // starts set by the lexer are kept.
+  for (idx, tok) in tokens.iter_mut().enumerate() {
+    tok.start = Some(10 + idx);
+  }
+  let want = r#"Root
+  Num "1" @ 10..11
+  missing Num @ 11..11
+  Space " " @ 11..12
+  Plus "+" @ 12..13
+  Space " " @ 13..14
+  Space " " @ 14..15
+  Plus "+" @ 15..16
+  error [] @ 15..16
+  error [] @ 16..16
+"#;
+  assert_eq!(parse(&tokens), want);
+}
+
+#[test]
+fn simple_tree() {
+  let tokens = lex("1 + +");
+  let mut p = Parser::new(&tokens);
+  let root = p.enter();
+  expr(&mut p);
+  p.exit(root, SK::Root);
+  let mut sink = SimpleTreeSink::new();
+  p.finish_raw(&mut sink);
+  let tree = sink.finish();
+  let mut out = String::new();
+  let mut level = 0;
+  for ev in tree.walk() {
+    match ev {
+      WalkEvent::Enter(node) => {
+        let range = tree.text_range(node, &tokens);
+        let line = format!("{:?} {:?}\n", tree.kind(node), range);
+        out.push_str(&"  ".repeat(level));
+        out.push_str(&line);
+        level += 1;
+      }
+      WalkEvent::Exit(_) => level -= 1,
+    }
+  }
+  let want = r#"Root 0..5
+  Add 0..5
+    Expr 0..1
+    Expr 4..5
+"#;
+  assert_eq!(out, want);
+  let &root = tree.roots().first().unwrap();
+  assert_eq!(tree.roots().len(), 1);
+  assert_eq!(tree.token_range(root), 0..5);
+  let add = match tree.children(root) {
+    [Element::Node(x)] => *x,
+    _ => panic!("bad root children"),
+  };
+  let num = match tree.children(add).last().unwrap() {
+    Element::Node(x) => *x,
+    _ => panic!("bad add children"),
+  };
+  let want = vec![
+    Element::Tokens(4..5),
+    Element::Error(
+      ErrorEvent::Error {
+        expected: vec![SK::Num],
+        more: 0,
+      },
+      4,
+    ),
+  ];
+  assert_eq!(tree.children(num), want.as_slice());
+}
+
+fn pratt_table() -> Table<SK> {
+  let infix = |token, prec, assoc, kind| Infix {
+    token,
+    prec,
+    assoc,
+    kind,
+  };
+  Table {
+    prefix: vec![Op {
+      token: SK::Minus,
+      prec: 3,
+      kind: SK::Neg,
+    }],
+    infix: vec![
+      infix(SK::Plus, 1, Assoc::Left, SK::Add),
+      infix(SK::Minus, 1, Assoc::Left, SK::Sub),
+      infix(SK::Star, 2, Assoc::Left, SK::Mul),
+      infix(SK::Caret, 4, Assoc::Right, SK::Pow),
+    ],
+    postfix: vec![Op {
+      token: SK::Bang,
+      prec: 5,
+      kind: SK::Fact,
+    }],
+  }
+}
+
+fn pratt_expr(p: &mut Parser<'_, SK>) {
+  pratt::expr(p, &pratt_table(), |p| {
+    if p.at(SK::Num) {
+      let en = p.enter();
+      p.bump();
+      Some(p.exit(en, SK::Expr))
+    } else {
+      p.error();
+      None
+    }
+  });
+}
+
+#[test]
+fn pratt() {
+  check(
+    "-1-2*3!^4^5+6",
+    pratt_expr,
+    r#"Root
+  Add
+    Sub
+      Neg
+        Minus "-"
+        Expr
+          Num "1"
+      Minus "-"
+      Mul
+        Expr
+          Num "2"
+        Star "*"
+        Pow
+          Fact
+            Expr
+              Num "3"
+            Bang "!"
+          Caret "^"
+          Pow
+            Expr
+              Num "4"
+            Caret "^"
+            Expr
+              Num "5"
+    Plus "+"
+    Expr
+      Num "6"
+"#,
+  );
+}
+
+#[test]
+fn pratt_error() {
+  // the constructs for the operators before the failed operand are exited.
+  check(
+    "1*-+",
+    pratt_expr,
+    r#"Root
+  Mul
+    Expr
+      Num "1"
+    Star "*"
+    Neg
+      Minus "-"
+      Plus "+"
+      error [Minus, Num]
+"#,
+  );
+}
+
+fn sep_list(end: &'static [SK]) -> impl FnOnce(&mut Parser<'_, SK>) {
+  move |p| {
+    p.sep_list(SK::Minus, end, |p| {
+      if !p.at(SK::Num) {
+        return false;
+      }
+      let en = p.enter();
+      p.bump();
+      p.exit(en, SK::Expr);
+      // fail after consuming a token before a `*`.
+      !p.at(SK::Star)
+    });
+    p.expect_eof();
+  }
+}
+
+#[test]
+fn sep_list_trailing() {
+  check(
+    "1-2-!",
+    sep_list(&[SK::Bang]),
+    r#"Root
+  Expr
+    Num "1"
+  Minus "-"
+  Expr
+    Num "2"
+  Minus "-"
+  Bang "!"
+  error []
+"#,
+  );
+}
+
+#[test]
+fn sep_list_errors() {
+  check(
+    "1--2 3*^^-4",
+    sep_list(&[SK::Bang]),
+    r#"Root
+  Expr
+    Num "1"
+  Minus "-"
+  Minus "-"
+  error [Bang, Num]
+  Expr
+    Num "2"
+  Space " "
+  missing Minus
+  Expr
+    Num "3"
+  Star "*"
+  Caret "^"
+  Caret "^"
+  Minus "-"
+  Expr
+    Num "4"
+"#,
+  );
+}
+
+#[test]
+fn sep_list_no_end() {
+  check(
+    "1-2^",
+    sep_list(&[]),
+    r#"Root
+  Expr
+    Num "1"
+  Minus "-"
+  Expr
+    Num "2"
+  Caret "^"
+  error []
+"#,
+  );
+}
+
+const PAIRS: [(SK, SK); 2] =
+  [(SK::LParen, SK::RParen), (SK::LBrack, SK::RBrack)];
+
+/// `<group> ::= (<num> | ( <group> ) | [ <group> ])*`
+fn group(p: &mut Parser<'_, SK>) {
+  loop {
+    if p.at(SK::Num) {
+      p.bump();
+    } else if p.at(SK::LParen) {
+      p.delimited(SK::Paren, SK::LParen, SK::RParen, &PAIRS, group);
+    } else if p.at(SK::LBrack) {
+      p.delimited(SK::Brack, SK::LBrack, SK::RBrack, &PAIRS, group);
+    } else {
+      break;
+    }
+  }
+}
+
+#[test]
+fn delimited() {
+  check(
+    "(1[2])",
+    group,
+    r#"Root
+  Paren
+    LParen "("
+    Num "1"
+    Brack
+      LBrack "["
+      Num "2"
+      RBrack "]"
+    RParen ")"
+"#,
+  );
+}
+
+#[test]
+fn delimited_junk() {
+  check(
+    "(1+[2]3)4",
+    group,
+    r#"Root
+  Paren
+    LParen "("
+    Num "1"
+    Plus "+"
+    error [Num, LParen, LBrack, RParen]
+    LBrack "["
+    Num "2"
+    RBrack "]"
+    Num "3"
+    RParen ")"
+  Num "4"
+"#,
+  );
+}
+
+#[test]
+fn delimited_unclosed() {
+  let tokens = lex("[1(2]3 (4");
+  let mut p = Parser::new(&tokens);
+  let root = p.enter();
+  group(&mut p);
+  p.exit(root, SK::Root);
+  let mut sink = CheckSink::new(DumpSink {
+    ranges: true,
+    ..DumpSink::default()
+  });
+  p.finish(&mut sink);
+  assert_eq!(
+    sink.finish().out,
+    r#"Root
+  Brack
+    LBrack "[" @ 0..1
+    Num "1" @ 1..2
+    Paren
+      LParen "(" @ 2..3
+      Num "2" @ 3..4
+      unclosed RParen @ 2..3
+    RBrack "]" @ 4..5
+  Num "3" @ 5..6
+  Space " " @ 6..7
+  Paren
+    LParen "(" @ 7..8
+    Num "4" @ 8..9
+    unclosed RParen @ 7..8
+"#
+  );
+}
+
+/// `1+2`, annotating every node. The annotation made after saving is dropped
+/// when restoring.
+fn annotated(p: &mut Parser<'_, SK>) {
+  let root = p.enter();
+  let en = p.enter();
+  p.bump();
+  let lhs = p.exit(en, SK::Expr);
+  p.annotate(&lhs, 1);
+  let en = p.precede(lhs);
+  p.bump();
+  let save = p.save();
+  let rhs = p.enter();
+  p.bump();
+  let rhs = p.exit(rhs, SK::Expr);
+  p.annotate(&rhs, 9);
+  p.restore(save);
+  let rhs = p.enter();
+  p.bump();
+  let rhs = p.exit(rhs, SK::Expr);
+  p.annotate(&rhs, 3);
+  let add = p.exit(en, SK::Add);
+  p.annotate(&add, 2);
+  p.annotate(&add, 4);
+  let root = p.exit(root, SK::Root);
+  p.annotate(&root, 0);
+}
+
+#[test]
+fn annotate() {
+  let tokens = lex("1+2");
+  let mut p = Parser::new(&tokens);
+  annotated(&mut p);
+  let mut sink = CheckSink::new(DumpSink::default());
+  p.finish(&mut sink);
+  assert_eq!(
+    sink.finish().out,
+    r#"Root
+  annotation 0
+  Add
+    annotation 2
+    annotation 4
+    Expr
+      annotation 1
+      Num "1"
+    Plus "+"
+    Expr
+      annotation 3
+      Num "2"
+"#
+  );
+  let mut p = Parser::new(&tokens);
+  annotated(&mut p);
+  let mut sink = SimpleTreeSink::new();
+  p.finish_raw(&mut sink);
+  let tree = sink.finish();
+  let annotations: Vec<_> = tree
+    .walk()
+    .filter_map(|ev| match ev {
+      WalkEvent::Enter(node) => Some(tree.annotations(node).to_vec()),
+      WalkEvent::Exit(_) => None,
+    })
+    .collect();
+  assert_eq!(annotations, [vec![0], vec![2, 4], vec![1], vec![3]]);
+}
+
+#[test]
+fn eat_text() {
+  check(
+    "ab+",
+    |p| {
+      p.set_missing_follow(vec![SK::Plus]);
+      assert!(!p.at_text(SK::Kw, "b"));
+      p.eat_text(SK::Kw, "a");
+      p.eat_text(SK::Kw, "a");
+      p.eat_text(SK::Kw, "a");
+      p.bump();
+      p.eat_text(SK::Kw, "a");
+    },
+    r#"Root
+  Num "a"
+  Num "b"
+  error [Kw]
+  missing Kw
+  Plus "+"
+  error [Kw]
+"#,
+  );
+}
+
+#[test]
+fn bump_remap() {
+  check(
+    "a 1",
+    |p| {
+      assert!(p.at_text(SK::Kw, "a"));
+      assert_eq!(p.bump_remap(SK::Kw).kind, SK::Kw);
+      p.bump();
+    },
+    r#"Root
+  Kw "a"
+  Space " "
+  Num "1"
+"#,
+  );
+  let tokens = lex("1 a 2");
+  let mut p = Parser::new(&tokens);
+  let root = p.enter();
+  p.bump();
+  p.bump_remap(SK::Kw);
+  p.bump();
+  p.exit(root, SK::Root);
+  let mut sink = CheckSink::new(SimpleTreeSink::new());
+  p.finish_raw(&mut sink);
+  let tree = sink.finish().finish();
+  assert_eq!(
+    tree.children(tree.roots()[0]),
+    [
+      Element::Tokens(0..2),
+      Element::Remapped(SK::Kw, 2),
+      Element::Tokens(3..5)
+    ]
+  );
+}
+
+#[test]
+fn empty_input() {
+  let trivia = "Root\n  Space \" \"\n  Space \" \"\n";
+  for &(s, want) in [("", "Root\n"), ("  ", trivia)].iter() {
+    let tokens = lex(s);
+    let mut p = Parser::new(&tokens);
+    p.parse_file(SK::Root, |p| p.eat(SK::Num).is_some(), &[]);
+    p.expect_eof();
+    let mut sink = CheckSink::new(MetricsSink::new(DumpSink::default()));
+    p.finish(&mut sink);
+    let (sink, metrics) = sink.finish().into_parts();
+    assert_eq!(metrics.errors, 0);
+    assert_eq!(metrics.node_count(), 1);
+    assert_eq!(sink.out, want);
+  }
+}
@@ -0,0 +1,248 @@
+//! Messages for syntax errors, like "expected `)`, found `;`".
+//!
+//! A [`Message`] holds the descriptions of what was expected, what was found,
+//! and optionally the syntax construct being parsed. Rendering it lists the
+//! expected things in words, or just counts them if there are too many, and
+//! fills in a [`Template`].
+
+#![deny(missing_debug_implementations)]
+#![deny(missing_docs)]
+#![deny(rust_2018_idioms)]
+
+#[cfg(test)]
+mod tests;
+
+use std::fmt;
+
+/// What was found where something else was expected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Found<'a> {
+  /// A token with the description, like "`;`" or "an integer".
+  Token(&'a str),
+  /// The end of the file.
+  EndOfFile,
+}
+
+/// A noun, in singular and plural.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Noun<'a> {
+  /// The singular, like "token".
+  pub one: &'a str,
+  /// The plural, like "tokens".
+  pub many: &'a str,
+}
+
+impl<'a> Noun<'a> {
+  /// Returns the noun for `n` things.
+  pub fn get(&self, n: usize) -> &'a str {
+    if n == 1 {
+      self.one
+    } else {
+      self.many
+    }
+  }
+}
+
+/// Templates for the parts of a message.
+///
+/// In each template, `{expected}`, `{found}`, and `{context}` are replaced by
+/// the description of the expected things, the found thing, and the context.
+/// Other text is kept as-is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Template<'a> {
+  /// The message when something was expected. Defaults to
+  /// `"expected {expected}, found {found}"`.
+  pub expected: &'a str,
+  /// The message when nothing in particular was expected. Defaults to
+  /// `"unexpected {found}"`.
+  pub unexpected: &'a str,
+  /// Appended to the message if there is a context. Defaults to
+  /// `" in {context}"`.
+  pub context: &'a str,
+  /// Used for `{found}` for [`Found::EndOfFile`]. Defaults to `"end of file"`.
+  pub end_of_file: &'a str,
+}
+
+impl Default for Template<'_> {
+  fn default() -> Self {
+    Self {
+      expected: "expected {expected}, found {found}",
+      unexpected: "unexpected {found}",
+      context: " in {context}",
+      end_of_file: "end of file",
+    }
+  }
+}
+
+/// Options for rendering a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options<'a> {
+  /// The most expected things to list. If more were expected, they are only
+  /// counted, as in "one of 17 tokens". Defaults to 5.
+  pub max_listed: usize,
+  /// The noun for the expected things, when counting them. Defaults to
+  /// "token".
+  pub noun: Noun<'a>,
+  /// The templates.
+  pub template: Template<'a>,
+}
+
+impl Default for Options<'_> {
+  fn default() -> Self {
+    Self {
+      max_listed: 5,
+      noun: Noun {
+        one: "token",
+        many: "tokens",
+      },
+      template: Template::default(),
+    }
+  }
+}
+
+/// A message for a syntax error.
+///
+/// Displaying it renders it with the default [`Options`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message<'a> {
+  /// The descriptions of the expected things, like "`)`" or "an expression".
+  /// Duplicates are ignored.
+  pub expected: Vec<&'a str>,
+  /// The number of other expected things, without descriptions, like when the
+  /// parser capped how many it reported.
+  pub more: usize,
+  /// What was found.
+  pub found: Found<'a>,
+  /// The description of the syntax construct being parsed, if any, like "a
+  /// let statement".
+  pub context: Option<&'a str>,
+}
+
+impl<'a> Message<'a> {
+  /// Returns a new message about finding `found` where nothing in particular
+  /// was expected.
+  pub fn new(found: Found<'a>) -> Self {
+    Self {
+      expected: Vec::new(),
+      more: 0,
+      found,
+      context: None,
+    }
+  }
+
+  /// Returns this, expecting also the things with the `descriptions`.
+  pub fn expected<I>(mut self, descriptions: I) -> Self
+  where
+    I: IntoIterator<Item = &'a str>,
+  {
+    self.expected.extend(descriptions);
+    self
+  }
+
+  /// Returns this, expecting also `more` other things.
+  pub fn more(mut self, more: usize) -> Self {
+    self.more += more;
+    self
+  }
+
+  /// Returns this, in the `context`.
+  pub fn context(mut self, context: &'a str) -> Self {
+    self.context = Some(context);
+    self
+  }
+
+  /// Renders the message with the `options`.
+  pub fn render(&self, options: &Options<'_>) -> String {
+    let template = &options.template;
+    let found = match self.found {
+      Found::Token(s) => s,
+      Found::EndOfFile => template.end_of_file,
+    };
+    let expected = self.expected_text(options);
+    let context = self.context.unwrap_or_default();
+    let args = [
+      ("expected", expected.as_str()),
+      ("found", found),
+      ("context", context),
+    ];
+    let mut ret = String::new();
+    let main = if expected.is_empty() {
+      template.unexpected
+    } else {
+      template.expected
+    };
+    fill(&mut ret, main, &args);
+    if self.context.is_some() {
+      fill(&mut ret, template.context, &args);
+    }
+    ret
+  }
+
+  /// Returns the description of all the expected things, or the empty string
+  /// if nothing was.
+  fn expected_text(&self, options: &Options<'_>) -> String {
+    let mut listed: Vec<&str> = Vec::with_capacity(self.expected.len());
+    for &x in self.expected.iter() {
+      if !listed.contains(&x) {
+        listed.push(x);
+      }
+    }
+    let total = listed.len() + self.more;
+    if total > options.max_listed {
+      return if total == 1 {
+        format!("1 {}", options.noun.one)
+      } else {
+        format!("one of {} {}", total, options.noun.many)
+      };
+    }
+    let mut items: Vec<String> =
+      listed.into_iter().map(str::to_owned).collect();
+    if self.more != 0 {
+      let other = if items.is_empty() { "" } else { "other " };
+      items.push(format!(
+        "{} {}{}",
+        self.more,
+        other,
+        options.noun.get(self.more)
+      ));
+    }
+    match items.as_slice() {
+      [] => String::new(),
+      [a] => a.clone(),
+      [a, b] => format!("{} or {}", a, b),
+      [init @ .., last] => format!("one of {}, or {}", init.join(", "), last),
+    }
+  }
+}
+
+impl fmt::Display for Message<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.render(&Options::default()))
+  }
+}
+
+/// Pushes the `template` to `buf`, replacing each `{name}` with the value for
+/// the name in `args`. Unknown names are kept as-is.
+fn fill(buf: &mut String, template: &str, args: &[(&str, &str)]) {
+  let mut rest = template;
+  while let Some(start) = rest.find('{') {
+    buf.push_str(&rest[..start]);
+    rest = &rest[start..];
+    let arg = rest.find('}').and_then(|end| {
+      let name = &rest[1..end];
+      let &(_, value) = args.iter().find(|&&(n, _)| n == name)?;
+      Some((end, value))
+    });
+    match arg {
+      Some((end, value)) => {
+        buf.push_str(value);
+        rest = &rest[end + 1..];
+      }
+      None => {
+        buf.push('{');
+        rest = &rest[1..];
+      }
+    }
+  }
+  buf.push_str(rest);
+}
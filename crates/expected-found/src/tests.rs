@@ -0,0 +1,90 @@
+use crate::{Found, Message, Noun, Options, Template};
+
+fn check(m: Message<'_>, want: &str) {
+  assert_eq!(m.to_string(), want);
+}
+
+#[test]
+fn list() {
+  let semi = Found::Token("`;`");
+  check(
+    Message::new(semi).expected(vec!["`)`"]),
+    "expected `)`, found `;`",
+  );
+  check(
+    Message::new(semi).expected(vec!["`)`", "`,`", "`)`"]),
+    "expected `)` or `,`, found `;`",
+  );
+  check(
+    Message::new(semi).expected(vec!["`(`", "`[`", "a name"]),
+    "expected one of `(`, `[`, or a name, found `;`",
+  );
+  check(Message::new(semi), "unexpected `;`");
+}
+
+#[test]
+fn more() {
+  let eof = Found::EndOfFile;
+  check(
+    Message::new(eof).expected(vec!["`)`"]).more(1),
+    "expected `)` or 1 other token, found end of file",
+  );
+  check(
+    Message::new(eof).expected(vec!["`)`", "`]`"]).more(2),
+    "expected one of `)`, `]`, or 2 other tokens, found end of file",
+  );
+  check(
+    Message::new(eof).more(3),
+    "expected 3 tokens, found end of file",
+  );
+}
+
+#[test]
+fn truncate() {
+  let names: Vec<String> = (0..17).map(|i| format!("`{}`", i)).collect();
+  let m = Message::new(Found::Token("`+`"))
+    .expected(names.iter().map(String::as_str));
+  check(m.clone(), "expected one of 17 tokens, found `+`");
+  let options = Options {
+    max_listed: 0,
+    ..Options::default()
+  };
+  let m = Message::new(Found::Token("`+`")).expected(vec!["`(`"]);
+  assert_eq!(m.render(&options), "expected 1 token, found `+`");
+}
+
+#[test]
+fn template() {
+  let m = Message::new(Found::EndOfFile)
+    .expected(vec!["an expression", "a statement"])
+    .more(3)
+    .context("a block");
+  check(
+    m.clone(),
+    "expected one of an expression, a statement, or 3 other tokens, found end \
+     of file in a block",
+  );
+  let options = Options {
+    max_listed: 10,
+    noun: Noun {
+      one: "item",
+      many: "items",
+    },
+    template: Template {
+      expected: "{found}: wanted {expected} {unknown}",
+      unexpected: "{found}: wanted nothing",
+      context: " (while parsing {context})",
+      end_of_file: "EOF",
+    },
+  };
+  assert_eq!(
+    m.render(&options),
+    "EOF: wanted one of an expression, a statement, or 3 other items \
+     {unknown} (while parsing a block)"
+  );
+  let m = Message::new(Found::Token("{expected}")).context("{found}");
+  assert_eq!(
+    m.render(&options),
+    "{expected}: wanted nothing (while parsing {found})"
+  );
+}
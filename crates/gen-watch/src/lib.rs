@@ -0,0 +1,222 @@
+//! Re-running code generation when its inputs change. Intended for use in an
+//! `xtask` which calls `syntax-gen`.
+
+#![deny(missing_debug_implementations)]
+#![deny(missing_docs)]
+#![deny(rust_2018_idioms)]
+
+#[cfg(test)]
+mod tests;
+
+use rustc_hash::FxHashMap;
+use std::any::Any;
+use std::fmt;
+use std::io::{ErrorKind, Result};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+/// Configuration for a [`Watcher`].
+#[derive(Debug)]
+pub struct Config {
+  /// The files to watch, like the grammar and config files.
+  pub inputs: Vec<PathBuf>,
+  /// The files written by generation, to report diffs for.
+  pub outputs: Vec<PathBuf>,
+  /// If set, run `cargo check` on this package after generating.
+  pub check_package: Option<String>,
+  /// How long to wait between checking the inputs for changes. Defaults to
+  /// half a second.
+  pub interval: Duration,
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Self {
+      inputs: Vec::new(),
+      outputs: Vec::new(),
+      check_package: None,
+      interval: Duration::from_millis(500),
+    }
+  }
+}
+
+/// The change to an output file from generation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diff {
+  /// The path of the file.
+  pub path: PathBuf,
+  /// The number of lines added.
+  pub added: usize,
+  /// The number of lines removed.
+  pub removed: usize,
+}
+
+impl fmt::Display for Diff {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{}: +{} -{}",
+      self.path.display(),
+      self.added,
+      self.removed
+    )
+  }
+}
+
+/// The state of a file, used to check whether it changed.
+type Stamp = Option<(SystemTime, u64)>;
+
+/// Watches inputs for changes and re-runs generation.
+#[derive(Debug)]
+pub struct Watcher {
+  config: Config,
+  stamps: Vec<Stamp>,
+  polled: bool,
+}
+
+impl Watcher {
+  /// Returns a new `Watcher`. The first [`Self::poll`] will report a change.
+  pub fn new(config: Config) -> Self {
+    let stamps = vec![None; config.inputs.len()];
+    Self {
+      config,
+      stamps,
+      polled: false,
+    }
+  }
+
+  /// Returns whether any input was created, modified, or deleted since the
+  /// last call.
+  pub fn poll(&mut self) -> Result<bool> {
+    let mut ret = !self.polled;
+    self.polled = true;
+    for (path, old) in self.config.inputs.iter().zip(self.stamps.iter_mut()) {
+      let new = match std::fs::metadata(path) {
+        Ok(m) => Some((m.modified()?, m.len())),
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => return Err(e),
+      };
+      if *old != new {
+        *old = new;
+        ret = true;
+      }
+    }
+    Ok(ret)
+  }
+
+  /// Runs `gen`, and returns the diffs for the outputs which changed.
+  pub fn regenerate<F>(&self, gen: F) -> Result<Vec<Diff>>
+  where
+    F: FnOnce() -> Result<()>,
+  {
+    let old = self
+      .config
+      .outputs
+      .iter()
+      .map(|path| read(path))
+      .collect::<Result<Vec<_>>>()?;
+    gen()?;
+    let mut ret = Vec::new();
+    for (path, old) in self.config.outputs.iter().zip(old) {
+      let new = read(path)?;
+      if old != new {
+        let (added, removed) = line_diff(&old, &new);
+        ret.push(Diff {
+          path: path.clone(),
+          added,
+          removed,
+        });
+      }
+    }
+    Ok(ret)
+  }
+
+  /// Runs `cargo check` on the configured package, if any. Returns whether the
+  /// check succeeded (or was not configured).
+  pub fn check(&self) -> Result<bool> {
+    let pkg = match &self.config.check_package {
+      Some(x) => x,
+      None => return Ok(true),
+    };
+    let status = Command::new("cargo")
+      .args(&["check", "--package", pkg])
+      .status()?;
+    Ok(status.success())
+  }
+
+  /// Forever: waits for the inputs to change, then runs `gen`, prints the
+  /// diffs, and runs the check.
+  ///
+  /// Errors and panics from `gen` are printed, not returned, so that the
+  /// inputs may be fixed while this keeps running.
+  pub fn run<F>(mut self, mut gen: F) -> Result<()>
+  where
+    F: FnMut() -> Result<()>,
+  {
+    loop {
+      if self.poll()? {
+        let res = catch_unwind(AssertUnwindSafe(|| self.regenerate(&mut gen)));
+        match res {
+          Ok(Ok(diffs)) => {
+            if diffs.is_empty() {
+              println!("no changes");
+            }
+            for diff in diffs {
+              println!("{}", diff);
+            }
+            if !self.check()? {
+              println!("check failed");
+            }
+          }
+          Ok(Err(e)) => println!("error: {}", e),
+          Err(e) => println!("panic: {}", panic_message(e.as_ref())),
+        }
+      }
+      std::thread::sleep(self.config.interval);
+    }
+  }
+}
+
+/// Returns the message of a panic, if it has one.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+  if let Some(s) = payload.downcast_ref::<&str>() {
+    s
+  } else if let Some(s) = payload.downcast_ref::<String>() {
+    s
+  } else {
+    "<no message>"
+  }
+}
+
+/// Returns the contents of the file, or the empty string if it doesn't exist.
+fn read(path: &Path) -> Result<String> {
+  match std::fs::read_to_string(path) {
+    Ok(x) => Ok(x),
+    Err(e) if e.kind() == ErrorKind::NotFound => Ok(String::new()),
+    Err(e) => Err(e),
+  }
+}
+
+/// Returns the number of lines added and removed to get from `old` to `new`,
+/// ignoring the order of the lines.
+fn line_diff(old: &str, new: &str) -> (usize, usize) {
+  let mut counts = FxHashMap::<&str, isize>::default();
+  for line in old.lines() {
+    *counts.entry(line).or_default() -= 1;
+  }
+  for line in new.lines() {
+    *counts.entry(line).or_default() += 1;
+  }
+  let mut added = 0;
+  let mut removed = 0;
+  for &n in counts.values() {
+    if n > 0 {
+      added += n as usize;
+    } else {
+      removed += (-n) as usize;
+    }
+  }
+  (added, removed)
+}
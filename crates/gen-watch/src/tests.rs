@@ -0,0 +1,61 @@
+use crate::{line_diff, panic_message, Config, Diff, Watcher};
+use std::path::PathBuf;
+
+#[test]
+fn diff() {
+  assert_eq!(line_diff("a\nb\nc\n", "a\nc\nd\ne\n"), (2, 1));
+  assert_eq!(line_diff("a\n", "a\n"), (0, 0));
+}
+
+#[test]
+fn watch() {
+  let dir =
+    std::env::temp_dir().join(format!("gen-watch-{}", std::process::id()));
+  std::fs::create_dir_all(&dir).unwrap();
+  let input = dir.join("in.txt");
+  let output = dir.join("out.txt");
+  std::fs::write(&input, "a").unwrap();
+  let mut w = Watcher::new(Config {
+    inputs: vec![input.clone()],
+    outputs: vec![output.clone()],
+    ..Config::default()
+  });
+  assert!(w.poll().unwrap());
+  assert!(!w.poll().unwrap());
+  std::fs::write(&input, "ab").unwrap();
+  assert!(w.poll().unwrap());
+  let diffs = w.regenerate(|| std::fs::write(&output, "x\ny\n")).unwrap();
+  let want = Diff {
+    path: PathBuf::from(&output),
+    added: 2,
+    removed: 0,
+  };
+  assert_eq!(diffs, [want]);
+  let diffs = w.regenerate(|| std::fs::write(&output, "x\ny\n")).unwrap();
+  assert!(diffs.is_empty());
+  assert!(w.check().unwrap());
+  std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn poll_missing() {
+  let dir = std::env::temp_dir()
+    .join(format!("gen-watch-missing-{}", std::process::id()));
+  let mut w = Watcher::new(Config {
+    inputs: vec![dir.join("in.txt")],
+    ..Config::default()
+  });
+  assert!(w.poll().unwrap());
+  assert!(!w.poll().unwrap());
+  let mut w = Watcher::new(Config::default());
+  assert!(w.poll().unwrap());
+  assert!(!w.poll().unwrap());
+}
+
+#[test]
+fn panic_payload() {
+  let e = std::panic::catch_unwind(|| panic!("bad {}", "grammar")).unwrap_err();
+  assert_eq!(panic_message(e.as_ref()), "bad grammar");
+  let e = std::panic::catch_unwind(|| panic!("bad")).unwrap_err();
+  assert_eq!(panic_message(e.as_ref()), "bad");
+}
@@ -0,0 +1,267 @@
+//! A persistent on-disk cache for rowan green trees, so a language server need
+//! not re-parse every unchanged file on startup.
+//!
+//! Trees are stored in a compact binary format, keyed by a hash of the text
+//! that was parsed and a hash of the grammar that parsed it. The text of a
+//! cached tree is compared with the text when it is read, so a collision of
+//! the hashes never gives the wrong tree.
+//!
+//! Nothing is evicted automatically. Use [`Cache::prune`] to remove trees which
+//! are no longer needed, e.g. for an old grammar.
+
+#![deny(missing_debug_implementations)]
+#![deny(missing_docs)]
+#![deny(rust_2018_idioms)]
+
+#[cfg(test)]
+mod tests;
+
+use rowan::{
+  GreenNode, GreenNodeBuilder, GreenNodeData, NodeOrToken, SyntaxKind,
+};
+use std::convert::TryFrom as _;
+use std::path::PathBuf;
+
+const MAGIC: &[u8; 4] = b"GRN1";
+const NODE: u8 = 0;
+const TOKEN: u8 = 1;
+const EXT: &str = "green";
+
+/// The key for a cached tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+  /// A hash of the grammar, which should change whenever the parser or the
+  /// kinds change, like the `SyntaxKind::GRAMMAR_HASH` generated by
+  /// `syntax-gen`. If the parser changes without the grammar changing, the
+  /// hash should be combined with a version of the parser.
+  pub grammar: u64,
+  /// A hash of the text which was parsed, e.g. from [`hash`].
+  pub text: u64,
+}
+
+/// Returns a hash of the bytes which is stable across runs, platforms, and
+/// compiler versions, unlike the hashers in `std`.
+pub fn hash(bytes: &[u8]) -> u64 {
+  // FNV-1a
+  let mut ret: u64 = 0xcbf2_9ce4_8422_2325;
+  for &b in bytes {
+    ret ^= u64::from(b);
+    ret = ret.wrapping_mul(0x0100_0000_01b3);
+  }
+  ret
+}
+
+/// Encodes the tree rooted at `root` with the `key`.
+pub fn encode(key: Key, root: &GreenNodeData) -> Vec<u8> {
+  let mut ret = Vec::new();
+  ret.extend_from_slice(MAGIC);
+  ret.extend_from_slice(&key.grammar.to_le_bytes());
+  ret.extend_from_slice(&key.text.to_le_bytes());
+  push_node(&mut ret, root);
+  let mut stack = vec![root.children()];
+  while let Some(children) = stack.last_mut() {
+    match children.next() {
+      None => {
+        stack.pop();
+      }
+      Some(NodeOrToken::Node(node)) => {
+        push_node(&mut ret, node);
+        stack.push(node.children());
+      }
+      Some(NodeOrToken::Token(token)) => {
+        ret.push(TOKEN);
+        ret.extend_from_slice(&token.kind().0.to_le_bytes());
+        push_len(&mut ret, token.text().len());
+        ret.extend_from_slice(token.text().as_bytes());
+      }
+    }
+  }
+  ret
+}
+
+fn push_node(buf: &mut Vec<u8>, node: &GreenNodeData) {
+  buf.push(NODE);
+  buf.extend_from_slice(&node.kind().0.to_le_bytes());
+  push_len(buf, node.children().len());
+}
+
+fn push_len(buf: &mut Vec<u8>, len: usize) {
+  let len = u32::try_from(len).expect("too long to encode");
+  buf.extend_from_slice(&len.to_le_bytes());
+}
+
+/// Decodes a tree from [`encode`]. Returns `None` if it was encoded with a
+/// different `key`, if the text of the tree is not `text`, or if the bytes are
+/// not a valid encoding.
+pub fn decode(key: Key, text: &str, bytes: &[u8]) -> Option<GreenNode> {
+  let mut r = Reader { bytes };
+  if r.take(MAGIC.len())? != MAGIC
+    || r.u64()? != key.grammar
+    || r.u64()? != key.text
+  {
+    return None;
+  }
+  let mut b = GreenNodeBuilder::new();
+  // the text not yet matched by a token.
+  let mut rest = text.as_bytes();
+  // the number of children left to decode for each node being built.
+  let mut remaining = Vec::new();
+  if r.u8()? != NODE {
+    return None;
+  }
+  b.start_node(SyntaxKind(r.u16()?));
+  remaining.push(r.u32()?);
+  loop {
+    while let Some(&0) = remaining.last() {
+      remaining.pop();
+      b.finish_node();
+    }
+    let last = match remaining.last_mut() {
+      Some(x) => x,
+      None => break,
+    };
+    *last -= 1;
+    let tag = r.u8()?;
+    let kind = SyntaxKind(r.u16()?);
+    let len = r.u32()?;
+    match tag {
+      NODE => {
+        b.start_node(kind);
+        remaining.push(len);
+      }
+      TOKEN => {
+        let token = r.take(usize::try_from(len).ok()?)?;
+        if !rest.starts_with(token) {
+          return None;
+        }
+        rest = &rest[token.len()..];
+        b.token(kind, std::str::from_utf8(token).ok()?);
+      }
+      _ => return None,
+    }
+  }
+  if !r.bytes.is_empty() || !rest.is_empty() {
+    return None;
+  }
+  Some(b.finish())
+}
+
+struct Reader<'a> {
+  bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+  fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+    if self.bytes.len() < n {
+      return None;
+    }
+    let (ret, rest) = self.bytes.split_at(n);
+    self.bytes = rest;
+    Some(ret)
+  }
+
+  fn u8(&mut self) -> Option<u8> {
+    Some(self.take(1)?[0])
+  }
+
+  fn u16(&mut self) -> Option<u16> {
+    let mut buf = [0u8; 2];
+    buf.copy_from_slice(self.take(2)?);
+    Some(u16::from_le_bytes(buf))
+  }
+
+  fn u32(&mut self) -> Option<u32> {
+    let mut buf = [0u8; 4];
+    buf.copy_from_slice(self.take(4)?);
+    Some(u32::from_le_bytes(buf))
+  }
+
+  fn u64(&mut self) -> Option<u64> {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(self.take(8)?);
+    Some(u64::from_le_bytes(buf))
+  }
+}
+
+/// A cache of trees in a directory, one file per key.
+#[derive(Debug, Clone)]
+pub struct Cache {
+  dir: PathBuf,
+}
+
+impl Cache {
+  /// Returns a new cache in the directory `dir`, which need not exist yet.
+  pub fn new(dir: PathBuf) -> Self {
+    Self { dir }
+  }
+
+  fn path(&self, key: Key) -> PathBuf {
+    self
+      .dir
+      .join(format!("{:016x}-{:016x}.{}", key.grammar, key.text, EXT))
+  }
+
+  /// Returns the cached tree for the `key` and the `text`, if there is one and
+  /// it could be read and decoded.
+  pub fn get(&self, key: Key, text: &str) -> Option<GreenNode> {
+    let bytes = std::fs::read(self.path(key)).ok()?;
+    decode(key, text, &bytes)
+  }
+
+  /// Caches the tree rooted at `root` for the `key`.
+  ///
+  /// The file is written atomically, so a concurrent [`Self::get`] sees either
+  /// no tree or the whole tree.
+  pub fn insert(&self, key: Key, root: &GreenNodeData) -> std::io::Result<()> {
+    std::fs::create_dir_all(&self.dir)?;
+    let path = self.path(key);
+    let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+    std::fs::write(&tmp, encode(key, root))?;
+    std::fs::rename(&tmp, &path)
+  }
+
+  /// Removes every cached tree whose key `keep` returns `false` for, and
+  /// returns how many were removed.
+  ///
+  /// For instance, `|key| key.grammar == GRAMMAR_HASH` removes the trees for
+  /// every other grammar. Other files in the directory are left alone.
+  pub fn prune<F>(&self, mut keep: F) -> std::io::Result<usize>
+  where
+    F: FnMut(Key) -> bool,
+  {
+    let entries = match std::fs::read_dir(&self.dir) {
+      Ok(x) => x,
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+      Err(e) => return Err(e),
+    };
+    let mut ret = 0;
+    for entry in entries {
+      let path = entry?.path();
+      let key = match path.file_name().and_then(|x| x.to_str()).and_then(key_of)
+      {
+        Some(x) => x,
+        None => continue,
+      };
+      if !keep(key) {
+        std::fs::remove_file(&path)?;
+        ret += 1;
+      }
+    }
+    Ok(ret)
+  }
+}
+
+/// Returns the key of the file name from [`Cache::path`], if it is one.
+fn key_of(name: &str) -> Option<Key> {
+  let name = name.strip_suffix(EXT)?.strip_suffix('.')?;
+  let mut iter = name.split('-');
+  let grammar = iter.next()?;
+  let text = iter.next()?;
+  if iter.next().is_some() || grammar.len() != 16 || text.len() != 16 {
+    return None;
+  }
+  Some(Key {
+    grammar: u64::from_str_radix(grammar, 16).ok()?,
+    text: u64::from_str_radix(text, 16).ok()?,
+  })
+}
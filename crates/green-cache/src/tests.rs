@@ -0,0 +1,119 @@
+use crate::{decode, encode, hash, Cache, Key};
+use rowan::{GreenNode, GreenNodeBuilder, SyntaxKind};
+
+/// Each line is a node containing words separated by spaces, and an empty
+/// node at the end.
+fn tree(s: &str) -> GreenNode {
+  let mut b = GreenNodeBuilder::new();
+  b.start_node(SyntaxKind(3));
+  for (idx, line) in s.split('\n').enumerate() {
+    if idx != 0 {
+      b.token(SyntaxKind(0), "\n");
+    }
+    b.start_node(SyntaxKind(2));
+    for (idx, word) in line.split(' ').enumerate() {
+      if idx != 0 {
+        b.token(SyntaxKind(0), " ");
+      }
+      b.token(SyntaxKind(1), word);
+    }
+    b.finish_node();
+  }
+  b.start_node(SyntaxKind(4));
+  b.finish_node();
+  b.finish_node();
+  b.finish()
+}
+
+fn key(s: &str) -> Key {
+  Key {
+    grammar: 123,
+    text: hash(s.as_bytes()),
+  }
+}
+
+#[test]
+fn round_trip() {
+  let s = "a bc\n\ndé f";
+  let green = tree(s);
+  let bytes = encode(key(s), &green);
+  assert_eq!(decode(key(s), s, &bytes), Some(green));
+}
+
+#[test]
+fn wrong_key() {
+  let s = "a b";
+  let bytes = encode(key(s), &tree(s));
+  assert_eq!(decode(key("a c"), s, &bytes), None);
+  let other_grammar = Key {
+    grammar: 456,
+    ..key(s)
+  };
+  assert_eq!(decode(other_grammar, s, &bytes), None);
+}
+
+#[test]
+fn wrong_text() {
+  let s = "a b";
+  let bytes = encode(key(s), &tree(s));
+  // as if the hashes of the texts collided.
+  for &other in ["a c", "a b ", "a ", ""].iter() {
+    assert_eq!(decode(key(s), other, &bytes), None);
+  }
+}
+
+#[test]
+fn malformed() {
+  let s = "a b\nc";
+  let mut bytes = encode(key(s), &tree(s));
+  for len in 0..bytes.len() {
+    assert_eq!(decode(key(s), s, &bytes[..len]), None);
+  }
+  bytes.push(0);
+  assert_eq!(decode(key(s), s, &bytes), None);
+}
+
+#[test]
+fn hash_stable() {
+  assert_eq!(hash(b""), 0xcbf2_9ce4_8422_2325);
+  assert_eq!(hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+}
+
+#[test]
+fn cache() {
+  let dir = std::env::temp_dir()
+    .join(format!("green-cache-test-{}", std::process::id()));
+  let cache = Cache::new(dir.clone());
+  let s = "a b\nc";
+  assert_eq!(cache.get(key(s), s), None);
+  cache.insert(key(s), &tree(s)).unwrap();
+  assert_eq!(cache.get(key(s), s), Some(tree(s)));
+  assert_eq!(cache.get(key(s), "a b\nd"), None);
+  assert_eq!(cache.get(key("a"), "a"), None);
+  std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn prune() {
+  let dir = std::env::temp_dir()
+    .join(format!("green-cache-prune-test-{}", std::process::id()));
+  let cache = Cache::new(dir.clone());
+  assert_eq!(cache.prune(|_| false).unwrap(), 0);
+  let new_grammar = |s: &str| Key {
+    grammar: 456,
+    ..key(s)
+  };
+  for &s in ["a", "b", "c"].iter() {
+    cache.insert(key(s), &tree(s)).unwrap();
+  }
+  cache.insert(new_grammar("a"), &tree("a")).unwrap();
+  std::fs::write(dir.join("other.txt"), "hi").unwrap();
+  let removed = cache.prune(|k| k.grammar == 456 || k == key("b")).unwrap();
+  assert_eq!(removed, 2);
+  assert_eq!(cache.get(key("a"), "a"), None);
+  assert_eq!(cache.get(key("b"), "b"), Some(tree("b")));
+  assert_eq!(cache.get(key("c"), "c"), None);
+  assert_eq!(cache.get(new_grammar("a"), "a"), Some(tree("a")));
+  assert!(dir.join("other.txt").exists());
+  std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,207 @@
+//! Building the markup content of hovers: fenced code blocks for signatures,
+//! doc comments passed through as Markdown, and plain text, rendered as either
+//! Markdown or a plain text fallback.
+
+#![deny(missing_debug_implementations)]
+#![deny(missing_docs)]
+#![deny(rust_2018_idioms)]
+
+mod scan;
+
+#[cfg(test)]
+mod tests;
+
+use std::ops::Range;
+
+/// A kind of markup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkupKind {
+  /// Plain text, for clients which do not support Markdown.
+  PlainText,
+  /// Markdown.
+  Markdown,
+}
+
+impl MarkupKind {
+  /// Returns the name of this kind in the language server protocol.
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      MarkupKind::PlainText => "plaintext",
+      MarkupKind::Markdown => "markdown",
+    }
+  }
+}
+
+/// Rendered markup, ready to send to a client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkupContent {
+  /// The kind of the markup.
+  pub kind: MarkupKind,
+  /// The text of the markup.
+  pub value: String,
+  /// The links in the doc comments, in order.
+  pub links: Vec<Link>,
+}
+
+/// A Markdown link, like `[text](dest)`, in a doc comment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+  /// The range of the whole link in the rendered value.
+  pub range: Range<usize>,
+  /// The destination of the link.
+  pub dest: String,
+}
+
+/// A builder for markup.
+///
+/// Sections are pushed in order and separated by blank lines when rendered.
+#[derive(Debug, Clone)]
+pub struct Markup {
+  lang: String,
+  sections: Vec<Section>,
+}
+
+#[derive(Debug, Clone)]
+enum Section {
+  Code(String),
+  Doc(String),
+  Text(String),
+  Rule,
+}
+
+impl Markup {
+  /// Returns a new, empty builder for markup about code in the language with
+  /// the `lang` id, like `rust`.
+  pub fn new(lang: &str) -> Self {
+    Self {
+      lang: lang.to_owned(),
+      sections: Vec::new(),
+    }
+  }
+
+  /// Returns whether nothing was pushed.
+  pub fn is_empty(&self) -> bool {
+    self.sections.is_empty()
+  }
+
+  /// Pushes code, like a signature, in a fenced code block tagged with the
+  /// language. The fence is long enough that no backticks in `code` end it.
+  pub fn push_code(&mut self, code: &str) {
+    self
+      .sections
+      .push(Section::Code(code.trim_end().to_owned()));
+  }
+
+  /// Pushes the Markdown of a doc comment as-is. Links in it are reported in
+  /// the rendered [`MarkupContent::links`]. A code block left open at the end
+  /// is closed, so it does not swallow later sections.
+  pub fn push_doc(&mut self, doc: &str) {
+    self.sections.push(Section::Doc(doc.trim_end().to_owned()));
+  }
+
+  /// Pushes plain text, which is escaped so it is shown literally, with its
+  /// line breaks kept.
+  pub fn push_text(&mut self, text: &str) {
+    self
+      .sections
+      .push(Section::Text(text.trim_end().to_owned()));
+  }
+
+  /// Pushes a horizontal rule.
+  pub fn push_rule(&mut self) {
+    self.sections.push(Section::Rule);
+  }
+
+  /// Renders the markup as the `kind`.
+  ///
+  /// With [`MarkupKind::PlainText`], code is not fenced, doc comments are
+  /// shown as their Markdown source, and text is not escaped.
+  pub fn finish(self, kind: MarkupKind) -> MarkupContent {
+    let mut value = String::new();
+    let mut links = Vec::new();
+    for (idx, section) in self.sections.iter().enumerate() {
+      if idx != 0 {
+        value.push_str("\n\n");
+      }
+      match section {
+        Section::Code(code) => match kind {
+          MarkupKind::PlainText => value.push_str(code),
+          MarkupKind::Markdown => {
+            let fence = "`".repeat(longest_run(code, '`').max(2) + 1);
+            value.push_str(&fence);
+            value.push_str(&self.lang);
+            value.push('\n');
+            value.push_str(code);
+            value.push('\n');
+            value.push_str(&fence);
+          }
+        },
+        Section::Doc(doc) => {
+          let scan = scan::get(doc);
+          let base = value.len();
+          links.extend(scan.links.into_iter().map(|link| Link {
+            range: base + link.range.start..base + link.range.end,
+            dest: link.dest,
+          }));
+          value.push_str(doc);
+          if let (MarkupKind::Markdown, Some(fence)) = (kind, scan.open_fence) {
+            value.push('\n');
+            value.push_str(&fence);
+          }
+        }
+        Section::Text(text) => match kind {
+          MarkupKind::PlainText => value.push_str(text),
+          MarkupKind::Markdown => {
+            for (idx, line) in text.lines().enumerate() {
+              if idx != 0 {
+                value.push_str("  \n");
+              }
+              escape(&mut value, line);
+            }
+          }
+        },
+        Section::Rule => value.push_str("---"),
+      }
+    }
+    MarkupContent { kind, value, links }
+  }
+}
+
+/// Returns the length of the longest run of `c` in `s`.
+fn longest_run(s: &str, c: char) -> usize {
+  let mut ret = 0;
+  let mut cur = 0;
+  for x in s.chars() {
+    if x == c {
+      cur += 1;
+      ret = ret.max(cur);
+    } else {
+      cur = 0;
+    }
+  }
+  ret
+}
+
+/// Pushes the `line` to `buf`, escaped so no part of it is Markdown syntax.
+fn escape(buf: &mut String, line: &str) {
+  let rest = line.trim_start_matches(' ');
+  // leading spaces would start an indented code block.
+  for _ in 0..line.len() - rest.len() {
+    buf.push_str("&#32;");
+  }
+  let digits =
+    rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+  for (idx, c) in rest.char_indices() {
+    let block = idx == 0 && matches!(c, '-' | '+' | '=')
+      || idx == digits && digits != 0 && matches!(c, '.' | ')');
+    if block
+      || matches!(
+        c,
+        '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~' | '&'
+      )
+    {
+      buf.push('\\');
+    }
+    buf.push(c);
+  }
+}
@@ -0,0 +1,157 @@
+//! Scanning Markdown for links and code blocks.
+
+use crate::Link;
+
+/// The result of scanning Markdown.
+pub(crate) struct Scan {
+  /// The inline links outside of code, with ranges relative to the Markdown.
+  pub(crate) links: Vec<Link>,
+  /// The fence which would close the code block left open at the end, if any.
+  pub(crate) open_fence: Option<String>,
+}
+
+/// Scans the Markdown `s`.
+///
+/// This understands only as much Markdown as needed to find the links: fenced
+/// code blocks, code spans, backslash escapes, and inline links.
+pub(crate) fn get(s: &str) -> Scan {
+  let mut links = Vec::new();
+  let mut open_fence: Option<String> = None;
+  let mut start = 0;
+  while start < s.len() {
+    let end = s[start..].find('\n').map_or(s.len(), |idx| start + idx);
+    let line = &s[start..end];
+    match (fence(line), &open_fence) {
+      (Some((f, _)), None) => open_fence = Some(f.to_owned()),
+      (Some((f, rest)), Some(open)) => {
+        if f.starts_with(open.as_str()) && rest.trim().is_empty() {
+          open_fence = None;
+        }
+      }
+      (None, Some(_)) => {}
+      (None, None) => inline(line, start, &mut links),
+    }
+    start = end + 1;
+  }
+  Scan { links, open_fence }
+}
+
+/// If the `line` is a code fence, returns the fence and the rest of the line.
+fn fence(line: &str) -> Option<(&str, &str)> {
+  let trimmed = line.trim_start_matches(' ');
+  if line.len() - trimmed.len() > 3 {
+    return None;
+  }
+  let c = trimmed.chars().next()?;
+  if !matches!(c, '`' | '~') {
+    return None;
+  }
+  let rest = trimmed.trim_start_matches(c);
+  let len = trimmed.len() - rest.len();
+  (len >= 3).then(|| (&trimmed[..len], rest))
+}
+
+/// Pushes the inline links in the `line`, which starts at `base`, to `links`.
+fn inline(line: &str, base: usize, links: &mut Vec<Link>) {
+  let bs = line.as_bytes();
+  let mut idx = 0;
+  while idx < bs.len() {
+    match bs[idx] {
+      b'\\' => idx += 2,
+      b'`' => {
+        let len = run(&bs[idx..], b'`');
+        let after = idx + len;
+        idx = match close_code_span(&bs[after..], len) {
+          Some(end) => after + end,
+          None => after,
+        };
+      }
+      b'[' => match link(&line[idx..]) {
+        Some((len, dest)) => {
+          let is_image = idx != 0 && bs[idx - 1] == b'!';
+          if !is_image {
+            let start = base + idx;
+            links.push(Link {
+              range: start..start + len,
+              dest: dest.to_owned(),
+            });
+          }
+          idx += len;
+        }
+        None => idx += 1,
+      },
+      _ => idx += 1,
+    }
+  }
+}
+
+/// Returns the length of the run of `b` at the start of `bs`.
+fn run(bs: &[u8], b: u8) -> usize {
+  bs.iter().take_while(|&&x| x == b).count()
+}
+
+/// Returns the index just after the run of exactly `len` backticks in `bs`
+/// which closes a code span, if any.
+fn close_code_span(bs: &[u8], len: usize) -> Option<usize> {
+  let mut idx = 0;
+  while idx < bs.len() {
+    if bs[idx] == b'`' {
+      let n = run(&bs[idx..], b'`');
+      if n == len {
+        return Some(idx + n);
+      }
+      idx += n;
+    } else {
+      idx += 1;
+    }
+  }
+  None
+}
+
+/// If `s` starts with an inline link like `[text](dest "title")`, returns its
+/// length and destination.
+fn link(s: &str) -> Option<(usize, &str)> {
+  let bs = s.as_bytes();
+  let mut depth = 0usize;
+  let mut idx = 0;
+  let text_end = loop {
+    match bs.get(idx)? {
+      b'\\' => idx += 1,
+      b'[' => depth += 1,
+      b']' => {
+        depth -= 1;
+        if depth == 0 {
+          break idx;
+        }
+      }
+      _ => {}
+    }
+    idx += 1;
+  };
+  if bs.get(text_end + 1) != Some(&b'(') {
+    return None;
+  }
+  let dest_start = text_end + 2;
+  let mut idx = dest_start;
+  let mut depth = 0usize;
+  let end = loop {
+    match bs.get(idx)? {
+      b'\\' => idx += 1,
+      b'(' => depth += 1,
+      b')' => {
+        if depth == 0 {
+          break idx;
+        }
+        depth -= 1;
+      }
+      _ => {}
+    }
+    idx += 1;
+  };
+  let inner = s[dest_start..end].trim();
+  let dest = match inner.strip_prefix('<') {
+    Some(rest) => rest.split('>').next().unwrap_or(rest),
+    None => inner.split(char::is_whitespace).next().unwrap_or(inner),
+  };
+  Some((end + 1, dest))
+}
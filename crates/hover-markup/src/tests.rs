@@ -0,0 +1,78 @@
+use crate::{Link, Markup, MarkupKind};
+
+#[test]
+fn code() {
+  let mut m = Markup::new("rust");
+  m.push_code("fn f() -> u32\n");
+  let got = m.finish(MarkupKind::Markdown);
+  assert_eq!(got.value, "```rust\nfn f() -> u32\n```");
+  assert!(got.links.is_empty());
+}
+
+#[test]
+fn code_backticks() {
+  let mut m = Markup::new("md");
+  m.push_code("a ``` b `` c");
+  let got = m.finish(MarkupKind::Markdown);
+  assert_eq!(got.value, "````md\na ``` b `` c\n````");
+}
+
+#[test]
+fn doc_links() {
+  let mut m = Markup::new("rust");
+  m.push_code("fn f()");
+  m.push_doc("See [`g`](crate::g) and [h](<a b> \"t\").");
+  let got = m.finish(MarkupKind::Markdown);
+  let want = vec![
+    Link {
+      range: 24..39,
+      dest: "crate::g".to_owned(),
+    },
+    Link {
+      range: 44..58,
+      dest: "a b".to_owned(),
+    },
+  ];
+  assert_eq!(got.links, want);
+  assert_eq!(&got.value[24..39], "[`g`](crate::g)");
+  assert_eq!(&got.value[44..58], "[h](<a b> \"t\")");
+}
+
+#[test]
+fn doc_no_links_in_code() {
+  let mut m = Markup::new("rust");
+  m.push_doc(
+    "`[a](b)` \\[c](d) ![e](f) [[g]](h)\n```\n[i](j)\n```\n  ~~~~\n[k](l)",
+  );
+  let got = m.finish(MarkupKind::Markdown);
+  let dests: Vec<_> = got.links.iter().map(|x| x.dest.as_str()).collect();
+  assert_eq!(dests, ["h"]);
+  assert!(got.value.ends_with("[k](l)\n~~~~"));
+}
+
+#[test]
+fn doc_plain() {
+  let mut m = Markup::new("rust");
+  m.push_code("fn f()");
+  m.push_rule();
+  m.push_doc("```\nx [y](z)");
+  let got = m.finish(MarkupKind::PlainText);
+  assert_eq!(got.value, "fn f()\n\n---\n\n```\nx [y](z)");
+  assert!(got.links.is_empty());
+}
+
+#[test]
+fn text() {
+  let mut m = Markup::new("rust");
+  m.push_text("a_b *c* <d> [e]\n- f\n  1. g\n\nh");
+  let got = m.finish(MarkupKind::Markdown);
+  assert_eq!(
+    got.value,
+    "a\\_b \\*c\\* \\<d\\> \\[e\\]  \n\\- f  \n&#32;&#32;1\\. g  \n  \nh"
+  );
+  let mut m = Markup::new("rust");
+  m.push_text("a_b\n- f");
+  let got = m.finish(MarkupKind::PlainText);
+  assert_eq!(got.value, "a_b\n- f");
+  assert_eq!(got.kind.as_str(), "plaintext");
+}
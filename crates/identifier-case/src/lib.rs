@@ -29,6 +29,22 @@ pub fn snake_to_pascal(s: &str) -> String {
   ret
 }
 
+/// Works with either upper or lower snake case.
+///
+/// ```
+/// # use identifier_case::snake_to_camel;
+/// assert_eq!(snake_to_camel("fella"), "fella");
+/// assert_eq!(snake_to_camel("the_best"), "theBest");
+/// assert_eq!(snake_to_camel("HEY_THERE_DUDE"), "heyThereDude");
+/// ```
+pub fn snake_to_camel(s: &str) -> String {
+  let mut ret = snake_to_pascal(s);
+  if let Some(c) = ret.get_mut(..1) {
+    c.make_ascii_lowercase();
+  }
+  ret
+}
+
 /// Also works for camelCase.
 ///
 ///```
@@ -0,0 +1,202 @@
+//! Plumbing for inlay hints: collecting hints from the part of a syntax tree in
+//! a range, ordering and deduplicating them, and encoding data to resolve them
+//! later.
+//!
+//! Each supported version of `lsp-types` is behind a feature of the same name,
+//! like `lsp-types-0-94`, which converts [`InlayHint`]s and [`Kind`]s into its
+//! `InlayHint`s and `InlayHintKind`s.
+
+#![deny(missing_debug_implementations)]
+#![deny(missing_docs)]
+#![deny(rust_2018_idioms)]
+
+#[cfg(test)]
+mod tests;
+
+use rowan::{Language, SyntaxNode, TextRange, TextSize, WalkEvent};
+use text_pos::{Position, PositionDb};
+
+/// A hint, as reported by a language.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hint {
+  /// The offset at which to show the hint.
+  pub offset: TextSize,
+  /// The label of the hint.
+  pub label: String,
+  /// The kind of hint.
+  pub kind: Kind,
+  /// Data with which to resolve the hint later, if any. See [`Resolve`].
+  pub data: Option<u64>,
+}
+
+/// A kind of hint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+  /// A type, shown after e.g. a variable.
+  Type,
+  /// A parameter name, shown before an argument.
+  Parameter,
+}
+
+/// A hint, ready to send to a client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlayHint {
+  /// The position at which to show the hint.
+  pub position: Position,
+  /// The label of the hint.
+  pub label: String,
+  /// The kind of hint.
+  pub kind: Kind,
+  /// Whether to add padding before the hint. True for type hints.
+  pub padding_left: bool,
+  /// Whether to add padding after the hint. True for parameter hints.
+  pub padding_right: bool,
+  /// The encoded [`Resolve`] for the hint, if it had data.
+  pub data: Option<String>,
+}
+
+/// What is needed to resolve a hint later: the version of the document it came
+/// from, its offset, and its data.
+///
+/// This is encoded as a string to send to the client, which sends it back in a
+/// request to resolve the hint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resolve {
+  /// The version of the document.
+  pub version: i32,
+  /// The offset of the hint.
+  pub offset: TextSize,
+  /// The data of the hint.
+  pub data: u64,
+}
+
+impl Resolve {
+  /// Encodes this as a string.
+  pub fn encode(&self) -> String {
+    format!("{}:{}:{}", self.version, u32::from(self.offset), self.data)
+  }
+
+  /// Decodes a string from [`Self::encode`].
+  pub fn decode(s: &str) -> Option<Self> {
+    let mut iter = s.split(':');
+    let version = iter.next()?.parse().ok()?;
+    let offset = TextSize::from(iter.next()?.parse::<u32>().ok()?);
+    let data = iter.next()?.parse().ok()?;
+    if iter.next().is_some() {
+      return None;
+    }
+    Some(Self {
+      version,
+      offset,
+      data,
+    })
+  }
+}
+
+/// Returns the hints in `range` for the tree rooted at `root`, a tree for the
+/// document with the given `version`.
+///
+/// `hints` is called on every node whose range intersects `range`, and should
+/// push the hints for that node. Nodes outside `range` are not visited.
+///
+/// Hints whose offsets are outside `range` are dropped. The rest are ordered by
+/// offset, keeping the order in which they were pushed for equal offsets, and
+/// of any hints with the same offset, label, and kind, only the first is kept.
+pub fn get<L, F>(
+  root: &SyntaxNode<L>,
+  range: TextRange,
+  db: &PositionDb,
+  version: i32,
+  mut hints: F,
+) -> Vec<InlayHint>
+where
+  L: Language,
+  F: FnMut(&SyntaxNode<L>, &mut Vec<Hint>),
+{
+  let mut ac = Vec::new();
+  let mut preorder = root.preorder();
+  while let Some(ev) = preorder.next() {
+    let node = match ev {
+      WalkEvent::Enter(x) => x,
+      WalkEvent::Leave(_) => continue,
+    };
+    if node.text_range().intersect(range).is_none() {
+      preorder.skip_subtree();
+      continue;
+    }
+    hints(&node, &mut ac);
+  }
+  ac.retain(|h| range.contains_inclusive(h.offset));
+  ac.sort_by_key(|h| h.offset);
+  let mut uniq: Vec<Hint> = Vec::with_capacity(ac.len());
+  for h in ac {
+    let dup = uniq
+      .iter()
+      .rev()
+      .take_while(|x| x.offset == h.offset)
+      .any(|x| x.label == h.label && x.kind == h.kind);
+    if !dup {
+      uniq.push(h);
+    }
+  }
+  uniq
+    .into_iter()
+    .map(|h| {
+      let offset = h.offset;
+      InlayHint {
+        position: db.position(offset),
+        label: h.label,
+        kind: h.kind,
+        padding_left: h.kind == Kind::Type,
+        padding_right: h.kind == Kind::Parameter,
+        data: h.data.map(|data| {
+          let resolve = Resolve {
+            version,
+            offset,
+            data,
+          };
+          resolve.encode()
+        }),
+      }
+    })
+    .collect()
+}
+
+#[allow(unused_macros)]
+macro_rules! impl_lsp {
+  ($lsp:ident) => {
+    impl From<Kind> for $lsp::InlayHintKind {
+      fn from(kind: Kind) -> Self {
+        match kind {
+          Kind::Type => $lsp::InlayHintKind::TYPE,
+          Kind::Parameter => $lsp::InlayHintKind::PARAMETER,
+        }
+      }
+    }
+
+    /// The data is a JSON string.
+    impl From<InlayHint> for $lsp::InlayHint {
+      fn from(hint: InlayHint) -> Self {
+        $lsp::InlayHint {
+          position: $lsp::Position {
+            line: hint.position.line,
+            character: hint.position.character,
+          },
+          label: $lsp::InlayHintLabel::String(hint.label),
+          kind: Some(hint.kind.into()),
+          text_edits: None,
+          tooltip: None,
+          padding_left: Some(hint.padding_left),
+          padding_right: Some(hint.padding_right),
+          data: hint.data.map(Into::into),
+        }
+      }
+    }
+  };
+}
+
+#[cfg(feature = "lsp-types-0-93")]
+impl_lsp!(lsp_types_0_93);
+
+#[cfg(feature = "lsp-types-0-94")]
+impl_lsp!(lsp_types_0_94);
@@ -0,0 +1,165 @@
+use crate::{get, Hint, InlayHint, Kind, Resolve};
+use rowan::{GreenNodeBuilder, Language, SyntaxNode, TextRange, TextSize};
+use text_pos::{Position, PositionDb};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum SK {
+  Whitespace,
+  Word,
+  Line,
+  Root,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Lang {}
+
+impl Language for Lang {
+  type Kind = SK;
+
+  fn kind_from_raw(raw: rowan::SyntaxKind) -> Self::Kind {
+    [SK::Whitespace, SK::Word, SK::Line, SK::Root][raw.0 as usize]
+  }
+
+  fn kind_to_raw(kind: Self::Kind) -> rowan::SyntaxKind {
+    rowan::SyntaxKind(kind as u16)
+  }
+}
+
+/// Each line is a node containing a word.
+fn tree(s: &str) -> SyntaxNode<Lang> {
+  let mut b = GreenNodeBuilder::new();
+  b.start_node(Lang::kind_to_raw(SK::Root));
+  for (idx, line) in s.lines().enumerate() {
+    if idx != 0 {
+      b.token(Lang::kind_to_raw(SK::Whitespace), "\n");
+    }
+    b.start_node(Lang::kind_to_raw(SK::Line));
+    b.token(Lang::kind_to_raw(SK::Word), line);
+    b.finish_node();
+  }
+  b.finish_node();
+  SyntaxNode::new_root(b.finish())
+}
+
+fn range(start: u32, end: u32) -> TextRange {
+  TextRange::new(TextSize::from(start), TextSize::from(end))
+}
+
+/// Each line gets a type hint at its end with its length, pushed twice, and a
+/// parameter hint at its start, with data.
+fn hints(node: &SyntaxNode<Lang>, ac: &mut Vec<Hint>) {
+  if node.kind() != SK::Line {
+    return;
+  }
+  let r = node.text_range();
+  let ty = Hint {
+    offset: r.end(),
+    label: format!(": {}", u32::from(r.len())),
+    kind: Kind::Type,
+    data: None,
+  };
+  ac.push(ty.clone());
+  ac.push(Hint {
+    offset: r.start(),
+    label: "x:".to_owned(),
+    kind: Kind::Parameter,
+    data: Some(u32::from(r.start()).into()),
+  });
+  ac.push(ty);
+}
+
+#[test]
+fn smoke() {
+  let s = "ab\nc\ndef";
+  let root = tree(s);
+  let db = PositionDb::new(s);
+  let mut visited = 0;
+  let got = get(&root, range(3, 4), &db, 7, |node, ac| {
+    visited += 1;
+    hints(node, ac);
+  });
+  // root and the middle line.
+  assert_eq!(visited, 2);
+  let pos = |line, character| Position { line, character };
+  let want = vec![
+    InlayHint {
+      position: pos(1, 0),
+      label: "x:".to_owned(),
+      kind: Kind::Parameter,
+      padding_left: false,
+      padding_right: true,
+      data: Some("7:3:3".to_owned()),
+    },
+    InlayHint {
+      position: pos(1, 1),
+      label: ": 1".to_owned(),
+      kind: Kind::Type,
+      padding_left: true,
+      padding_right: false,
+      data: None,
+    },
+  ];
+  assert_eq!(got, want);
+}
+
+#[test]
+fn clip() {
+  let s = "ab\nc\ndef";
+  let root = tree(s);
+  let db = PositionDb::new(s);
+  // intersects the first two lines, but only contains the end of the first.
+  let got = get(&root, range(1, 2), &db, 0, hints);
+  let labels: Vec<_> = got.iter().map(|h| h.label.as_str()).collect();
+  assert_eq!(labels, [": 2"]);
+}
+
+#[test]
+fn resolve() {
+  let r = Resolve {
+    version: -1,
+    offset: TextSize::from(5),
+    data: 123,
+  };
+  assert_eq!(Resolve::decode(&r.encode()), Some(r));
+  assert_eq!(Resolve::decode("1:2"), None);
+  assert_eq!(Resolve::decode("1:2:3:4"), None);
+}
+
+// the same tests for each version of `lsp-types`.
+#[allow(unused_macros)]
+macro_rules! lsp_tests {
+  ($name:ident, $lsp:ident) => {
+    #[test]
+    fn $name() {
+      let s = "ab\nc";
+      let db = PositionDb::new(s);
+      let got = get(&tree(s), range(3, 4), &db, 7, hints);
+      let got: Vec<$lsp::InlayHint> = got.into_iter().map(Into::into).collect();
+      assert_eq!(got.len(), 2);
+      let param = &got[0];
+      let pos = $lsp::Position {
+        line: 1,
+        character: 0,
+      };
+      assert_eq!(param.position, pos);
+      match &param.label {
+        $lsp::InlayHintLabel::String(s) => assert_eq!(s, "x:"),
+        $lsp::InlayHintLabel::LabelParts(_) => panic!("not a string"),
+      }
+      assert_eq!(param.kind, Some($lsp::InlayHintKind::PARAMETER));
+      assert_eq!(param.padding_left, Some(false));
+      assert_eq!(param.padding_right, Some(true));
+      assert_eq!(param.data, Some("7:3:3".into()));
+      let ty = &got[1];
+      assert_eq!(ty.kind, Some($lsp::InlayHintKind::TYPE));
+      assert_eq!(ty.padding_left, Some(true));
+      assert_eq!(ty.data, None);
+    }
+  };
+}
+
+#[cfg(feature = "lsp-types-0-93")]
+lsp_tests!(lsp_types_0_93, lsp_types_0_93);
+
+#[cfg(feature = "lsp-types-0-94")]
+lsp_tests!(lsp_types_0_94, lsp_types_0_94);
@@ -0,0 +1,82 @@
+//! Conversions between text ranges and LSP ranges.
+//!
+//! Each supported version of `lsp-types` is behind a feature of the same name,
+//! like `lsp-types-0-94`. Positions from the client which are out of bounds are
+//! clamped, instead of causing a panic: for instance, a range ending on the
+//! line after the last line ends at the end of the file.
+
+#![deny(missing_debug_implementations)]
+#![deny(missing_docs)]
+#![deny(rust_2018_idioms)]
+
+#[cfg(test)]
+mod tests;
+
+pub use text_pos::PositionDb;
+
+#[allow(unused_imports)]
+use text_size::{TextRange, TextSize};
+
+/// Conversion into an LSP type `T`.
+pub trait ToLsp<T> {
+  /// Converts this into a `T`, first clamping it to the bounds of the input
+  /// for `db`.
+  fn to_lsp(self, db: &PositionDb) -> T;
+}
+
+/// Conversion from an LSP type `T`.
+pub trait FromLsp<T>: Sized {
+  /// Converts the `T` into this, first clamping it to the bounds of the input
+  /// for `db`.
+  fn from_lsp(x: T, db: &PositionDb) -> Self;
+}
+
+#[allow(unused_macros)]
+macro_rules! impl_lsp {
+  ($lsp:ident) => {
+    impl ToLsp<$lsp::Position> for TextSize {
+      fn to_lsp(self, db: &PositionDb) -> $lsp::Position {
+        let pos = db.position(self.min(db.end()));
+        $lsp::Position {
+          line: pos.line,
+          character: pos.character,
+        }
+      }
+    }
+
+    impl ToLsp<$lsp::Range> for TextRange {
+      fn to_lsp(self, db: &PositionDb) -> $lsp::Range {
+        $lsp::Range {
+          start: self.start().to_lsp(db),
+          end: self.end().to_lsp(db),
+        }
+      }
+    }
+
+    impl FromLsp<$lsp::Position> for TextSize {
+      fn from_lsp(x: $lsp::Position, db: &PositionDb) -> Self {
+        let pos = text_pos::Position {
+          line: x.line,
+          character: x.character,
+        };
+        db.text_size(db.clamp(pos))
+      }
+    }
+
+    impl FromLsp<$lsp::Range> for TextRange {
+      /// A range whose start is after its end is treated as if they were
+      /// swapped.
+      fn from_lsp(x: $lsp::Range, db: &PositionDb) -> Self {
+        let start = TextSize::from_lsp(x.start, db);
+        let end = TextSize::from_lsp(x.end, db);
+        TextRange::new(start.min(end), start.max(end))
+      }
+    }
+  };
+}
+
+#[cfg(feature = "lsp-types-0-93")]
+impl_lsp!(lsp_types_0_93);
+
+#[cfg(feature = "lsp-types-0-94")]
+impl_lsp!(lsp_types_0_94);
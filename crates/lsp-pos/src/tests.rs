@@ -0,0 +1,95 @@
+// the same tests for each version of `lsp-types`.
+#[allow(unused_macros)]
+macro_rules! tests {
+  ($name:ident, $lsp:ident) => {
+    mod $name {
+      use crate::{FromLsp as _, PositionDb, ToLsp as _};
+      use text_size::{TextRange, TextSize};
+      use $lsp::{Position, Range};
+
+      fn pos(line: u32, character: u32) -> Position {
+        Position { line, character }
+      }
+
+      fn range(start: Position, end: Position) -> Range {
+        Range { start, end }
+      }
+
+      fn text_range(start: u32, end: u32) -> TextRange {
+        TextRange::new(start.into(), end.into())
+      }
+
+      #[test]
+      fn round_trip() {
+        let db = PositionDb::new("a𐐀b\nhi\n");
+        let tests = [
+          (text_range(0, 0), range(pos(0, 0), pos(0, 0))),
+          (text_range(1, 5), range(pos(0, 1), pos(0, 3))),
+          (text_range(5, 9), range(pos(0, 3), pos(1, 2))),
+          (text_range(0, 10), range(pos(0, 0), pos(2, 0))),
+        ];
+        for &(tr, r) in tests.iter() {
+          let got: Range = tr.to_lsp(&db);
+          assert_eq!(got, r);
+          assert_eq!(TextRange::from_lsp(r, &db), tr);
+        }
+      }
+
+      #[test]
+      fn clamp_from_lsp() {
+        let db = PositionDb::new("a𐐀b\nhi\n");
+        let tests = [
+          // past the end of the line
+          (range(pos(1, 0), pos(1, 99)), text_range(7, 9)),
+          // the whole file, as some clients send it
+          (range(pos(0, 0), pos(3, 0)), text_range(0, 10)),
+          (range(pos(99, 99), pos(99, 99)), text_range(10, 10)),
+          // backwards
+          (range(pos(1, 2), pos(0, 1)), text_range(1, 9)),
+        ];
+        for &(r, tr) in tests.iter() {
+          assert_eq!(TextRange::from_lsp(r, &db), tr);
+        }
+      }
+
+      #[test]
+      fn clamp_to_lsp() {
+        let db = PositionDb::new("hi\n");
+        let p: Position = TextSize::from(99).to_lsp(&db);
+        assert_eq!(p, pos(1, 0));
+        let r: Range = text_range(1, 99).to_lsp(&db);
+        assert_eq!(r, range(pos(0, 1), pos(1, 0)));
+      }
+
+      #[test]
+      fn clamp_mid_char() {
+        let db = PositionDb::new("a𐐀b");
+        // the middle of the surrogate pair for 𐐀.
+        let r = range(pos(0, 2), pos(0, 2));
+        assert_eq!(TextRange::from_lsp(r, &db), text_range(1, 1));
+      }
+
+      #[test]
+      fn clamp_crlf() {
+        let db = PositionDb::new("hi\r\nthere");
+        let r = range(pos(0, 0), pos(0, 99));
+        assert_eq!(TextRange::from_lsp(r, &db), text_range(0, 2));
+      }
+
+      #[test]
+      fn empty() {
+        let db = PositionDb::new("");
+        let r = range(pos(0, 5), pos(2, 0));
+        assert_eq!(TextRange::from_lsp(r, &db), text_range(0, 0));
+        let r: Range = text_range(0, 0).to_lsp(&db);
+        assert_eq!(r, range(pos(0, 0), pos(0, 0)));
+      }
+    }
+  };
+}
+
+#[cfg(feature = "lsp-types-0-93")]
+tests!(lsp_types_0_93, lsp_types_0_93);
+
+#[cfg(feature = "lsp-types-0-94")]
+tests!(lsp_types_0_94, lsp_types_0_94);
@@ -0,0 +1,107 @@
+//! Finding the occurrences of a name in a syntax tree by text alone, before
+//! any name resolution. Useful for a 'dumb' rename.
+
+#![deny(missing_debug_implementations)]
+#![deny(missing_docs)]
+#![deny(rust_2018_idioms)]
+
+#[cfg(test)]
+mod tests;
+
+use rowan::{Language, SyntaxNode, SyntaxToken, TextRange, TextSize};
+
+/// How to search a kind of token for a name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Search {
+  /// The whole token text must equal the name. For names.
+  Exact,
+  /// The name may appear as a word anywhere in the token text. For comments,
+  /// strings, etc.
+  Words,
+}
+
+/// An occurrence of a name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Occurrence {
+  /// The range of the occurrence.
+  pub range: TextRange,
+  /// How the token containing the occurrence was searched.
+  pub search: Search,
+}
+
+/// Returns the name token at `offset`, if any. This is the token that would be
+/// renamed if a rename were requested at `offset`.
+///
+/// A token is a name token if `search` returns [`Search::Exact`] for its kind.
+pub fn name_at<L, F>(
+  root: &SyntaxNode<L>,
+  offset: TextSize,
+  search: F,
+) -> Option<SyntaxToken<L>>
+where
+  L: Language,
+  F: Fn(L::Kind) -> Option<Search>,
+{
+  root
+    .token_at_offset(offset)
+    .find(|tok| search(tok.kind()) == Some(Search::Exact))
+}
+
+/// Returns the occurrences of `name` in the tree rooted at `root`, in order.
+///
+/// `search` is called on the kind of each token, and returns how to search
+/// tokens of that kind, or `None` if they should not be searched.
+///
+/// An occurrence in a token searched with [`Search::Words`] must be a whole
+/// word, i.e. it must not be directly preceded or followed by an alphanumeric
+/// character or `_`.
+pub fn get<L, F>(root: &SyntaxNode<L>, name: &str, search: F) -> Vec<Occurrence>
+where
+  L: Language,
+  F: Fn(L::Kind) -> Option<Search>,
+{
+  let mut ret = Vec::new();
+  if name.is_empty() {
+    return ret;
+  }
+  for tok in root
+    .descendants_with_tokens()
+    .filter_map(|x| x.into_token())
+  {
+    let s = match search(tok.kind()) {
+      Some(x) => x,
+      None => continue,
+    };
+    let text = tok.text();
+    let start = tok.text_range().start();
+    match s {
+      Search::Exact => {
+        if text == name {
+          ret.push(Occurrence {
+            range: tok.text_range(),
+            search: s,
+          });
+        }
+      }
+      Search::Words => {
+        for (idx, _) in text.match_indices(name) {
+          let before = text[..idx].chars().next_back();
+          let after = text[idx + name.len()..].chars().next();
+          if before.map_or(false, is_word) || after.map_or(false, is_word) {
+            continue;
+          }
+          let idx = start + TextSize::from(idx as u32);
+          ret.push(Occurrence {
+            range: TextRange::at(idx, TextSize::of(name)),
+            search: s,
+          });
+        }
+      }
+    }
+  }
+  ret
+}
+
+fn is_word(c: char) -> bool {
+  c.is_alphanumeric() || c == '_'
+}
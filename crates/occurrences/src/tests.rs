@@ -0,0 +1,84 @@
+use crate::{get, name_at, Occurrence, Search};
+use rowan::{GreenNodeBuilder, Language, SyntaxNode, TextRange, TextSize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum SK {
+  Whitespace,
+  Comment,
+  Name,
+  Root,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Lang {}
+
+impl Language for Lang {
+  type Kind = SK;
+
+  fn kind_from_raw(raw: rowan::SyntaxKind) -> Self::Kind {
+    [SK::Whitespace, SK::Comment, SK::Name, SK::Root][raw.0 as usize]
+  }
+
+  fn kind_to_raw(kind: Self::Kind) -> rowan::SyntaxKind {
+    rowan::SyntaxKind(kind as u16)
+  }
+}
+
+/// Each line is a token: `#` is a comment, else it's a name.
+fn tree(s: &str) -> SyntaxNode<Lang> {
+  let mut b = GreenNodeBuilder::new();
+  b.start_node(Lang::kind_to_raw(SK::Root));
+  for (idx, line) in s.lines().enumerate() {
+    if idx != 0 {
+      b.token(Lang::kind_to_raw(SK::Whitespace), "\n");
+    }
+    let kind = if line.starts_with('#') {
+      SK::Comment
+    } else {
+      SK::Name
+    };
+    b.token(Lang::kind_to_raw(kind), line);
+  }
+  b.finish_node();
+  SyntaxNode::new_root(b.finish())
+}
+
+fn search(kind: SK) -> Option<Search> {
+  match kind {
+    SK::Name => Some(Search::Exact),
+    SK::Comment => Some(Search::Words),
+    SK::Whitespace | SK::Root => None,
+  }
+}
+
+fn range(start: u32, end: u32) -> TextRange {
+  TextRange::new(start.into(), end.into())
+}
+
+#[test]
+fn smoke() {
+  let root = tree("foo\n# foo, foobar, (foo)\nfoo_bar\nfoo");
+  let got = get(&root, "foo", search);
+  let want = [
+    Occurrence {
+      range: range(0, 3),
+      search: Search::Exact,
+    },
+    Occurrence {
+      range: range(6, 9),
+      search: Search::Words,
+    },
+    Occurrence {
+      range: range(20, 23),
+      search: Search::Words,
+    },
+    Occurrence {
+      range: range(33, 36),
+      search: Search::Exact,
+    },
+  ];
+  assert_eq!(got, want);
+  let tok = name_at(&root, TextSize::from(34), search).unwrap();
+  assert_eq!(tok.text_range(), range(33, 36));
+  assert!(name_at(&root, TextSize::from(7), search).is_none());
+}
@@ -0,0 +1,247 @@
+//! Re-parsing documents on a background thread as they change, with debouncing
+//! and cancellation of parses which were superseded by newer changes, and
+//! optionally reporting progress through the pending documents.
+
+#![deny(missing_debug_implementations)]
+#![deny(missing_docs)]
+#![deny(rust_2018_idioms)]
+
+#[cfg(test)]
+mod tests;
+
+/// A flag for a parse, set when the parse was superseded and its result will be
+/// thrown away.
+pub use progress::Cancel;
+
+use progress::{Backend, Noop, Progress};
+use rustc_hash::FxHashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// The result of a parse.
+#[derive(Debug)]
+pub struct Done<K, T> {
+  /// The document.
+  pub key: K,
+  /// The version of the document which was parsed.
+  pub version: i32,
+  /// The result.
+  pub value: T,
+}
+
+/// A scheduler for parsing documents identified by `K` into `T`.
+///
+/// When a document changes, it is parsed after it has not changed for the
+/// debounce duration. Parses happen one at a time on a background thread. If a
+/// document changes while it is being parsed, the parse is cancelled.
+///
+/// The results are delivered from the background thread. A result is never
+/// delivered after a newer version of its document was scheduled or the
+/// document was removed.
+pub struct Scheduler<K, T> {
+  shared: Arc<Shared<K>>,
+  thread: Option<JoinHandle<()>>,
+  result: std::marker::PhantomData<fn() -> T>,
+}
+
+impl<K, T> fmt::Debug for Scheduler<K, T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Scheduler").finish()
+  }
+}
+
+struct Shared<K> {
+  state: Mutex<State<K>>,
+  cond: Condvar,
+  debounce: Duration,
+}
+
+struct State<K> {
+  /// the latest version of each document, and the flag for the parse of it.
+  latest: FxHashMap<K, (i32, Cancel)>,
+  /// the documents waiting to be parsed.
+  pending: FxHashMap<K, Job>,
+  shutdown: bool,
+}
+
+struct Job {
+  version: i32,
+  text: String,
+  deadline: Instant,
+}
+
+impl<K, T> Scheduler<K, T>
+where
+  K: 'static + Eq + Hash + Clone + Send,
+  T: 'static,
+{
+  /// Returns a new scheduler and starts its background thread.
+  ///
+  /// `parse` parses the text of a version of a document. It may return `None`
+  /// if it noticed it was cancelled. `deliver` is called with each result which
+  /// was not superseded. It is called while the scheduler is locked, so it
+  /// should return quickly, e.g. by sending the result to the main loop.
+  pub fn new<P, D>(debounce: Duration, parse: P, deliver: D) -> Self
+  where
+    P: 'static + Send + Fn(&K, &str, &Cancel) -> Option<T>,
+    D: 'static + Send + Fn(Done<K, T>),
+  {
+    Self::with_progress(debounce, parse, deliver, || Noop)
+  }
+
+  /// Like [`Self::new`], but reports progress through the documents waiting to
+  /// be parsed.
+  ///
+  /// When parsing starts after there were no documents waiting, `progress` is
+  /// called for a backend for a new task, which ends when there are again no
+  /// documents waiting. Like `deliver`, the backend is called while the
+  /// scheduler is locked.
+  pub fn with_progress<P, D, G, B>(
+    debounce: Duration,
+    parse: P,
+    deliver: D,
+    progress: G,
+  ) -> Self
+  where
+    P: 'static + Send + Fn(&K, &str, &Cancel) -> Option<T>,
+    D: 'static + Send + Fn(Done<K, T>),
+    G: 'static + Send + FnMut() -> B,
+    B: Backend,
+  {
+    let shared = Arc::new(Shared {
+      state: Mutex::new(State {
+        latest: FxHashMap::default(),
+        pending: FxHashMap::default(),
+        shutdown: false,
+      }),
+      cond: Condvar::new(),
+      debounce,
+    });
+    let thread = {
+      let shared = Arc::clone(&shared);
+      std::thread::spawn(move || run(&shared, parse, deliver, progress))
+    };
+    Self {
+      shared,
+      thread: Some(thread),
+      result: std::marker::PhantomData,
+    }
+  }
+
+  /// Schedules a parse of the `text` of the `version` of the document `key`,
+  /// cancelling any earlier parse of the document.
+  pub fn schedule(&self, key: K, version: i32, text: String) {
+    let mut state = self.shared.state.lock().unwrap();
+    let cancel = Cancel::default();
+    if let Some((_, old)) = state.latest.insert(key.clone(), (version, cancel))
+    {
+      old.cancel();
+    }
+    let job = Job {
+      version,
+      text,
+      deadline: Instant::now() + self.shared.debounce,
+    };
+    state.pending.insert(key, job);
+    self.shared.cond.notify_one();
+  }
+
+  /// Forgets the document `key`, cancelling any parse of it.
+  pub fn remove(&self, key: &K) {
+    let mut state = self.shared.state.lock().unwrap();
+    if let Some((_, old)) = state.latest.remove(key) {
+      old.cancel();
+    }
+    state.pending.remove(key);
+  }
+}
+
+impl<K, T> Drop for Scheduler<K, T> {
+  fn drop(&mut self) {
+    if let Ok(mut state) = self.shared.state.lock() {
+      state.shutdown = true;
+      for (_, cancel) in state.latest.values() {
+        cancel.cancel();
+      }
+    }
+    self.shared.cond.notify_one();
+    if let Some(thread) = self.thread.take() {
+      // if the thread panicked, the parse or deliver function panicked, and
+      // there's nothing to do about it now.
+      let _ = thread.join();
+    }
+  }
+}
+
+fn run<K, T, P, D, G, B>(
+  shared: &Shared<K>,
+  parse: P,
+  deliver: D,
+  mut progress: G,
+) where
+  K: Eq + Hash + Clone,
+  P: Fn(&K, &str, &Cancel) -> Option<T>,
+  D: Fn(Done<K, T>),
+  G: FnMut() -> B,
+  B: Backend,
+{
+  let mut state = shared.state.lock().unwrap();
+  // the progress through the documents waiting to be parsed, and how many
+  // were parsed since it began.
+  let mut batch: Option<(Progress<B>, usize)> = None;
+  loop {
+    if state.shutdown {
+      return;
+    }
+    let next = state
+      .pending
+      .iter()
+      .min_by_key(|(_, job)| job.deadline)
+      .map(|(key, job)| (job.deadline, key.clone()));
+    let (deadline, key) = match next {
+      Some(x) => x,
+      None => {
+        state = shared.cond.wait(state).unwrap();
+        continue;
+      }
+    };
+    let now = Instant::now();
+    if now < deadline {
+      state = shared.cond.wait_timeout(state, deadline - now).unwrap().0;
+      continue;
+    }
+    let job = state.pending.remove(&key).expect("pending job");
+    let cancel = state.latest[&key].1.clone();
+    if batch.is_none() {
+      batch = Some((Progress::new(progress(), "Parsing", None), 0));
+    }
+    drop(state);
+    let value = parse(&key, &job.text, &cancel);
+    state = shared.state.lock().unwrap();
+    if let Some((p, done)) = batch.as_mut() {
+      *done += 1;
+      p.report(*done, *done + state.pending.len());
+    }
+    if state.pending.is_empty() {
+      batch = None;
+    }
+    let value = match value {
+      Some(x) => x,
+      None => continue,
+    };
+    let latest = matches!(
+      state.latest.get(&key),
+      Some((version, _)) if *version == job.version
+    );
+    if latest && !cancel.is_cancelled() {
+      deliver(Done {
+        key,
+        version: job.version,
+        value,
+      });
+    }
+  }
+}
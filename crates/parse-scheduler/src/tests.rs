@@ -0,0 +1,137 @@
+use crate::{Cancel, Done, Scheduler};
+use progress::Backend;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::time::Duration;
+
+const WAIT: Duration = Duration::from_secs(5);
+
+/// Returns a scheduler whose parse is the length of the text, counting the
+/// parses, and a receiver for the results.
+fn len_scheduler(
+  debounce: Duration,
+  count: Arc<AtomicUsize>,
+) -> (Scheduler<u32, usize>, Receiver<Done<u32, usize>>) {
+  let (send, recv) = channel();
+  let s = Scheduler::new(
+    debounce,
+    move |_: &u32, text: &str, _: &Cancel| {
+      count.fetch_add(1, Ordering::SeqCst);
+      Some(text.len())
+    },
+    move |done| send.send(done).unwrap(),
+  );
+  (s, recv)
+}
+
+#[test]
+fn debounce() {
+  let count = Arc::new(AtomicUsize::new(0));
+  let (s, recv) = len_scheduler(Duration::from_millis(100), Arc::clone(&count));
+  s.schedule(1, 1, "a".to_owned());
+  s.schedule(1, 2, "ab".to_owned());
+  s.schedule(2, 1, "xyz".to_owned());
+  s.schedule(1, 3, "abcd".to_owned());
+  let mut got: Vec<_> = (0..2)
+    .map(|_| {
+      let done = recv.recv_timeout(WAIT).unwrap();
+      (done.key, done.version, done.value)
+    })
+    .collect();
+  got.sort_unstable();
+  assert_eq!(got, vec![(1, 3, 4), (2, 1, 3)]);
+  assert_eq!(count.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn remove() {
+  let count = Arc::new(AtomicUsize::new(0));
+  let (s, recv) = len_scheduler(Duration::from_millis(100), Arc::clone(&count));
+  s.schedule(1, 1, "a".to_owned());
+  s.remove(&1);
+  s.schedule(2, 1, "xy".to_owned());
+  let done = recv.recv_timeout(WAIT).unwrap();
+  assert_eq!((done.key, done.version, done.value), (2, 1, 2));
+  drop(s);
+  assert!(recv.recv().is_err());
+  assert_eq!(count.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn cancel() {
+  let (started_send, started) = channel();
+  let (send, recv) = channel();
+  let s = Scheduler::new(
+    Duration::from_millis(0),
+    move |_: &u32, text: &str, cancel: &Cancel| {
+      started_send.send(text.to_owned()).unwrap();
+      if text == "slow" {
+        while !cancel.is_cancelled() {
+          std::thread::yield_now();
+        }
+        return None;
+      }
+      Some(text.to_owned())
+    },
+    move |done: Done<u32, String>| send.send(done).unwrap(),
+  );
+  s.schedule(1, 1, "slow".to_owned());
+  assert_eq!(started.recv_timeout(WAIT).unwrap(), "slow");
+  s.schedule(1, 2, "fast".to_owned());
+  assert_eq!(started.recv_timeout(WAIT).unwrap(), "fast");
+  let done = recv.recv_timeout(WAIT).unwrap();
+  assert_eq!(
+    (done.key, done.version, done.value),
+    (1, 2, "fast".to_owned())
+  );
+  drop(s);
+  assert!(recv.recv().is_err());
+}
+
+/// Sends each call as a string.
+struct SendBackend(Sender<String>);
+
+impl Backend for SendBackend {
+  fn begin(&mut self, title: &str, _: bool) {
+    self.0.send(format!("begin {}", title)).unwrap();
+  }
+
+  fn report(&mut self, _: Option<&str>, percentage: Option<u32>) {
+    self.0.send(format!("report {:?}", percentage)).unwrap();
+  }
+
+  fn end(&mut self, _: Option<&str>) {
+    self.0.send("end".to_owned()).unwrap();
+  }
+}
+
+#[test]
+fn progress() {
+  let (progress_send, progress) = channel();
+  let (send, recv) = channel();
+  let s = Scheduler::with_progress(
+    Duration::from_millis(100),
+    |_: &u32, text: &str, _: &Cancel| Some(text.len()),
+    move |done: Done<u32, usize>| send.send(done).unwrap(),
+    move || SendBackend(progress_send.clone()),
+  );
+  s.schedule(1, 1, "a".to_owned());
+  s.schedule(2, 1, "b".to_owned());
+  s.schedule(3, 1, "c".to_owned());
+  for _ in 0..3 {
+    recv.recv_timeout(WAIT).unwrap();
+  }
+  drop(s);
+  let got: Vec<_> = progress.iter().collect();
+  assert_eq!(
+    got,
+    [
+      "begin Parsing",
+      "report Some(33)",
+      "report Some(66)",
+      "report Some(100)",
+      "end",
+    ]
+  );
+}
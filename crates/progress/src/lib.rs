@@ -0,0 +1,207 @@
+//! Reporting the progress of long-running tasks, like indexing a workspace.
+//!
+//! A [`Progress`] begins a task on a [`Backend`] when created, reports how much
+//! of the task is done as it goes, and ends the task when finished or dropped.
+//! Reports are throttled so that clients are not sent a message for every
+//! small step. A task may also be cancellable, with a [`Cancel`] flag.
+//!
+//! With the `lsp` feature, which is on by default, [`Lsp`] is a backend which
+//! sends LSP `$/progress` notifications.
+
+#![deny(missing_debug_implementations)]
+#![deny(missing_docs)]
+#![deny(rust_2018_idioms)]
+
+#[cfg(feature = "lsp")]
+mod lsp;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "lsp")]
+pub use lsp::{Lsp, Token};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Something which shows the progress of a task, e.g. to a user.
+///
+/// Each task gets its own backend. `begin` is called first, then any number of
+/// calls to `report`, then `end`.
+pub trait Backend {
+  /// Begins the task with the title.
+  fn begin(&mut self, title: &str, cancellable: bool);
+  /// Reports the progress of the task, with an optional message and an
+  /// optional percentage from 0 to 100.
+  fn report(&mut self, message: Option<&str>, percentage: Option<u32>);
+  /// Ends the task, with an optional message.
+  fn end(&mut self, message: Option<&str>);
+}
+
+/// A backend which does nothing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Noop;
+
+impl Backend for Noop {
+  fn begin(&mut self, _: &str, _: bool) {}
+
+  fn report(&mut self, _: Option<&str>, _: Option<u32>) {}
+
+  fn end(&mut self, _: Option<&str>) {}
+}
+
+/// A flag for a task, set when the task was cancelled. Clones share the flag.
+#[derive(Debug, Clone, Default)]
+pub struct Cancel(Arc<AtomicBool>);
+
+impl Cancel {
+  /// Returns a new flag, not yet set.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Returns whether the task was cancelled. Tasks should check this
+  /// periodically and stop early if so.
+  pub fn is_cancelled(&self) -> bool {
+    self.0.load(Ordering::Relaxed)
+  }
+
+  /// Cancels the task.
+  pub fn cancel(&self) {
+    self.0.store(true, Ordering::Relaxed);
+  }
+}
+
+/// The progress of a task.
+///
+/// A percentage is only reported when it has grown by at least the step since
+/// the last reported one, and a message without a new percentage is only
+/// reported when at least the interval has passed since the last report.
+#[derive(Debug)]
+pub struct Progress<B>
+where
+  B: Backend,
+{
+  backend: B,
+  cancel: Option<Cancel>,
+  step: u32,
+  interval: Duration,
+  percentage: Option<u32>,
+  last: Option<Instant>,
+  ended: bool,
+}
+
+impl<B> Progress<B>
+where
+  B: Backend,
+{
+  /// Begins a task with the title on the backend. The task is cancellable if
+  /// there is a `cancel` flag.
+  pub fn new(mut backend: B, title: &str, cancel: Option<Cancel>) -> Self {
+    backend.begin(title, cancel.is_some());
+    Self {
+      backend,
+      cancel,
+      step: 1,
+      interval: Duration::from_millis(100),
+      percentage: None,
+      last: None,
+      ended: false,
+    }
+  }
+
+  /// Sets the least amount the percentage must grow by to be reported.
+  ///
+  /// By default, this is 1.
+  pub fn set_step(&mut self, step: u32) {
+    self.step = step.max(1);
+  }
+
+  /// Sets the least amount of time between reports of only a message.
+  ///
+  /// By default, this is 100 milliseconds.
+  pub fn set_interval(&mut self, interval: Duration) {
+    self.interval = interval;
+  }
+
+  /// Returns whether the task was cancelled.
+  pub fn is_cancelled(&self) -> bool {
+    self.cancel.as_ref().map_or(false, Cancel::is_cancelled)
+  }
+
+  /// Reports that `done` out of `total` units of work are done.
+  pub fn report(&mut self, done: usize, total: usize) {
+    self.report_with_message(done, total, None);
+  }
+
+  /// Reports a message about what is being done.
+  pub fn message(&mut self, message: &str) {
+    if self.due() {
+      self.send(Some(message), None);
+    }
+  }
+
+  /// Like [`Self::report`], but also with a message.
+  pub fn report_with_message(
+    &mut self,
+    done: usize,
+    total: usize,
+    message: Option<&str>,
+  ) {
+    let percentage = percentage(done, total);
+    let grown = match self.percentage {
+      None => true,
+      Some(old) => percentage >= old.saturating_add(self.step),
+    };
+    if grown {
+      self.percentage = Some(percentage);
+      self.send(message, Some(percentage));
+    } else if message.is_some() && self.due() {
+      self.send(message, None);
+    }
+  }
+
+  /// Ends the task, with an optional message. Dropping a `Progress` also ends
+  /// the task, without a message.
+  pub fn finish(mut self, message: Option<&str>) {
+    self.end(message);
+  }
+
+  fn due(&self) -> bool {
+    self
+      .last
+      .map_or(true, |last| last.elapsed() >= self.interval)
+  }
+
+  fn send(&mut self, message: Option<&str>, percentage: Option<u32>) {
+    self.last = Some(Instant::now());
+    self.backend.report(message, percentage);
+  }
+
+  fn end(&mut self, message: Option<&str>) {
+    if !self.ended {
+      self.ended = true;
+      self.backend.end(message);
+    }
+  }
+}
+
+impl<B> Drop for Progress<B>
+where
+  B: Backend,
+{
+  fn drop(&mut self) {
+    self.end(None);
+  }
+}
+
+/// Returns the percentage of `total` that `done` is, rounded down and at most
+/// 100. An empty task is all done.
+fn percentage(done: usize, total: usize) -> u32 {
+  if total == 0 {
+    return 100;
+  }
+  let ret = (done.min(total) as u128 * 100) / total as u128;
+  ret as u32
+}
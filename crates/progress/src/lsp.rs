@@ -0,0 +1,119 @@
+//! A backend sending LSP `$/progress` notifications.
+
+use crate::Backend;
+use serde_json::{json, Map, Value};
+
+/// A token identifying a task to the client.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Token {
+  /// A number.
+  Number(i32),
+  /// A string.
+  String(String),
+}
+
+impl Token {
+  /// Returns the token in the JSON, e.g. the `token` in the params of a
+  /// `window/workDoneProgress/cancel` notification.
+  pub fn from_json(value: &Value) -> Option<Self> {
+    match value {
+      Value::Number(n) => {
+        let n = n.as_i64()?;
+        if n < i64::from(i32::MIN) || n > i64::from(i32::MAX) {
+          return None;
+        }
+        Some(Self::Number(n as i32))
+      }
+      Value::String(s) => Some(Self::String(s.clone())),
+      _ => None,
+    }
+  }
+
+  /// Returns the token as JSON.
+  pub fn to_json(&self) -> Value {
+    match self {
+      Self::Number(n) => json!(n),
+      Self::String(s) => json!(s),
+    }
+  }
+}
+
+/// A backend which calls `send` with the params for each `$/progress`
+/// notification for the token.
+///
+/// The token should either have come from the client, as a `workDoneToken`,
+/// or have been created with a `window/workDoneProgress/create` request, whose
+/// params are [`Lsp::create_params`].
+pub struct Lsp<F> {
+  token: Token,
+  send: F,
+}
+
+impl<F> std::fmt::Debug for Lsp<F> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("Lsp").field("token", &self.token).finish()
+  }
+}
+
+impl<F> Lsp<F>
+where
+  F: FnMut(Value),
+{
+  /// Returns a new backend for the token.
+  pub fn new(token: Token, send: F) -> Self {
+    Self { token, send }
+  }
+
+  /// Returns the token.
+  pub fn token(&self) -> &Token {
+    &self.token
+  }
+
+  /// Returns the params for a `window/workDoneProgress/create` request for
+  /// the token.
+  pub fn create_params(&self) -> Value {
+    json!({ "token": self.token.to_json() })
+  }
+
+  fn send(&mut self, value: Map<String, Value>) {
+    let params = json!({
+      "token": self.token.to_json(),
+      "value": Value::Object(value),
+    });
+    (self.send)(params)
+  }
+}
+
+impl<F> Backend for Lsp<F>
+where
+  F: FnMut(Value),
+{
+  fn begin(&mut self, title: &str, cancellable: bool) {
+    let mut value = Map::new();
+    value.insert("kind".to_owned(), json!("begin"));
+    value.insert("title".to_owned(), json!(title));
+    value.insert("cancellable".to_owned(), json!(cancellable));
+    self.send(value);
+  }
+
+  fn report(&mut self, message: Option<&str>, percentage: Option<u32>) {
+    let mut value = Map::new();
+    value.insert("kind".to_owned(), json!("report"));
+    if let Some(message) = message {
+      value.insert("message".to_owned(), json!(message));
+    }
+    if let Some(percentage) = percentage {
+      value.insert("percentage".to_owned(), json!(percentage));
+    }
+    self.send(value);
+  }
+
+  fn end(&mut self, message: Option<&str>) {
+    let mut value = Map::new();
+    value.insert("kind".to_owned(), json!("end"));
+    if let Some(message) = message {
+      value.insert("message".to_owned(), json!(message));
+    }
+    self.send(value);
+  }
+}
@@ -0,0 +1,122 @@
+use crate::{Backend, Cancel, Progress};
+use std::time::Duration;
+
+/// Records the calls as strings.
+#[derive(Debug, Default)]
+struct Record(Vec<String>);
+
+impl Backend for &mut Record {
+  fn begin(&mut self, title: &str, cancellable: bool) {
+    self.0.push(format!("begin {} {}", title, cancellable));
+  }
+
+  fn report(&mut self, message: Option<&str>, percentage: Option<u32>) {
+    self
+      .0
+      .push(format!("report {:?} {:?}", message, percentage));
+  }
+
+  fn end(&mut self, message: Option<&str>) {
+    self.0.push(format!("end {:?}", message));
+  }
+}
+
+fn never() -> Duration {
+  Duration::from_secs(60 * 60)
+}
+
+#[test]
+fn throttle() {
+  let mut rec = Record::default();
+  let mut p = Progress::new(&mut rec, "Indexing", None);
+  p.set_step(10);
+  p.set_interval(never());
+  for done in 0..=1000 {
+    p.report(done, 1000);
+  }
+  p.finish(Some("done"));
+  let mut want = vec!["begin Indexing false".to_owned()];
+  want.extend((0..=10).map(|x| format!("report None Some({})", x * 10)));
+  want.push("end Some(\"done\")".to_owned());
+  assert_eq!(rec.0, want);
+}
+
+#[test]
+fn message() {
+  let mut rec = Record::default();
+  let mut p = Progress::new(&mut rec, "Parsing", None);
+  p.set_interval(never());
+  p.message("a.toy");
+  p.message("b.toy");
+  p.report_with_message(1, 3, Some("c.toy"));
+  p.report_with_message(1, 3, Some("d.toy"));
+  p.set_interval(Duration::from_secs(0));
+  p.message("e.toy");
+  drop(p);
+  assert_eq!(
+    rec.0,
+    [
+      "begin Parsing false",
+      "report Some(\"a.toy\") None",
+      "report Some(\"c.toy\") Some(33)",
+      "report Some(\"e.toy\") None",
+      "end None",
+    ]
+  );
+}
+
+#[test]
+fn cancel() {
+  let mut rec = Record::default();
+  let cancel = Cancel::new();
+  let p = Progress::new(&mut rec, "Indexing", Some(cancel.clone()));
+  assert!(!p.is_cancelled());
+  cancel.cancel();
+  assert!(p.is_cancelled());
+  drop(p);
+  assert_eq!(rec.0, ["begin Indexing true", "end None"]);
+  let p = Progress::new(crate::Noop, "Indexing", None);
+  assert!(!p.is_cancelled());
+}
+
+#[test]
+fn percentage() {
+  assert_eq!(crate::percentage(0, 0), 100);
+  assert_eq!(crate::percentage(1, 3), 33);
+  assert_eq!(crate::percentage(5, 3), 100);
+  assert_eq!(crate::percentage(usize::MAX - 1, usize::MAX), 99);
+}
+
+#[cfg(feature = "lsp")]
+#[test]
+fn lsp() {
+  use crate::{Lsp, Token};
+  use serde_json::json;
+  let mut sent = Vec::new();
+  let backend = Lsp::new(Token::Number(3), |x| sent.push(x));
+  assert_eq!(backend.create_params(), json!({ "token": 3 }));
+  let mut p = Progress::new(backend, "Indexing", Some(Cancel::new()));
+  p.report_with_message(1, 2, Some("a.toy"));
+  p.finish(None);
+  assert_eq!(
+    sent,
+    [
+      json!({
+        "token": 3,
+        "value": { "kind": "begin", "title": "Indexing", "cancellable": true },
+      }),
+      json!({
+        "token": 3,
+        "value": { "kind": "report", "message": "a.toy", "percentage": 50 },
+      }),
+      json!({ "token": 3, "value": { "kind": "end" } }),
+    ]
+  );
+  assert_eq!(
+    Token::from_json(&json!("x")),
+    Some(Token::String("x".into()))
+  );
+  assert_eq!(Token::from_json(&json!(3)), Some(Token::Number(3)));
+  assert_eq!(Token::from_json(&json!(1u64 << 40)), None);
+  assert_eq!(Token::from_json(&json!(null)), None);
+}
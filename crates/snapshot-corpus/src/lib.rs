@@ -0,0 +1,270 @@
+//! Corpora of sample source files, with snapshots of the trees and errors from
+//! parsing them.
+//!
+//! A corpus is a directory, searched recursively, of samples with some
+//! extension, like `let.toy`. Each sample has a tree snapshot next to it, like
+//! `let.tree`, and if parsing it gives any errors, an errors snapshot, like
+//! `let.errors`. Running a [`Corpus`] parses every sample and compares the
+//! results with the snapshots, or re-blesses the snapshots, per its [`Mode`].
+//!
+//! Usually, a test runs the corpus with the mode from [`Mode::from_env`] and
+//! calls [`Report::assert_ok`]. Re-bless with `CORPUS=bless cargo test`.
+
+#![deny(missing_debug_implementations)]
+#![deny(missing_docs)]
+#![deny(rust_2018_idioms)]
+
+#[cfg(test)]
+mod tests;
+
+use std::fmt;
+use std::io::{ErrorKind, Result};
+use std::path::PathBuf;
+
+const TREE: &str = "tree";
+const ERRORS: &str = "errors";
+
+/// What to do with the snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+  /// Fail if a snapshot does not match, and write missing snapshots. The
+  /// default.
+  Check,
+  /// Write every snapshot which is missing or does not match, and remove
+  /// snapshots with no sample.
+  Bless,
+  /// Fail if a snapshot does not match or is missing, or if there are
+  /// snapshots with no sample. For CI.
+  Strict,
+}
+
+impl Mode {
+  /// Returns the mode from the environment: from the `CORPUS` variable if it
+  /// is `check`, `bless`, or `strict`, else `Strict` if the `CI` variable is
+  /// set, else `Check`.
+  pub fn from_env() -> Self {
+    match std::env::var("CORPUS").as_deref() {
+      Ok("check") => Self::Check,
+      Ok("bless") => Self::Bless,
+      Ok("strict") => Self::Strict,
+      _ if std::env::var_os("CI").is_some() => Self::Strict,
+      _ => Self::Check,
+    }
+  }
+}
+
+impl Default for Mode {
+  fn default() -> Self {
+    Self::Check
+  }
+}
+
+/// The results of parsing a sample.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snapshot {
+  /// A dump of the syntax tree.
+  pub tree: String,
+  /// The errors, one per line. Empty if there were none.
+  pub errors: String,
+}
+
+/// A corpus of samples for a language.
+#[derive(Debug, Clone)]
+pub struct Corpus {
+  /// The directory.
+  pub dir: PathBuf,
+  /// The extension of the samples, like `toy`. Must not be `tree` or
+  /// `errors`.
+  pub extension: String,
+  /// What to do with the snapshots.
+  pub mode: Mode,
+}
+
+impl Corpus {
+  /// Returns a new corpus with the default mode.
+  pub fn new<P>(dir: P, extension: &str) -> Self
+  where
+    P: Into<PathBuf>,
+  {
+    Self {
+      dir: dir.into(),
+      extension: extension.to_owned(),
+      mode: Mode::default(),
+    }
+  }
+
+  /// Parses every sample with `parse`, and checks or writes the snapshots.
+  ///
+  /// Returns `Err` only if the files could not be read or written.
+  pub fn run<F>(&self, mut parse: F) -> Result<Report>
+  where
+    F: FnMut(&str) -> Snapshot,
+  {
+    assert!(
+      self.extension != TREE && self.extension != ERRORS,
+      "the extension of the samples is that of snapshots: {}",
+      self.extension
+    );
+    let mut report = Report::default();
+    let mut samples = Vec::new();
+    let mut snapshots = Vec::new();
+    let mut dirs = vec![self.dir.clone()];
+    while let Some(dir) = dirs.pop() {
+      for entry in std::fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+          dirs.push(path);
+          continue;
+        }
+        match path.extension().and_then(|x| x.to_str()) {
+          Some(x) if x == self.extension => samples.push(path),
+          Some(TREE) | Some(ERRORS) => snapshots.push(path),
+          _ => {}
+        }
+      }
+    }
+    samples.sort();
+    snapshots.sort();
+    for path in snapshots {
+      if path.with_extension(&self.extension).exists() {
+        continue;
+      }
+      match self.mode {
+        Mode::Check => {}
+        Mode::Bless => {
+          std::fs::remove_file(&path)?;
+          report.removed.push(path);
+        }
+        Mode::Strict => report.failures.push(Failure::Stale(path)),
+      }
+    }
+    for path in samples {
+      let text = std::fs::read_to_string(&path)?;
+      let got = parse(&text);
+      let tree = self.compare(&mut report, path.with_extension(TREE), got.tree);
+      let errors =
+        self.compare(&mut report, path.with_extension(ERRORS), got.errors);
+      if tree? && errors? {
+        report.passed.push(path);
+      }
+    }
+    Ok(report)
+  }
+
+  /// Compares the snapshot at `path` with `got`, and returns whether it
+  /// matched. An empty snapshot is the same as no file.
+  fn compare(
+    &self,
+    report: &mut Report,
+    path: PathBuf,
+    got: String,
+  ) -> Result<bool> {
+    let want = match std::fs::read_to_string(&path) {
+      Ok(x) => Some(x),
+      Err(e) if e.kind() == ErrorKind::NotFound => None,
+      Err(e) => return Err(e),
+    };
+    match (want, self.mode) {
+      (Some(want), _) if want == got => Ok(true),
+      (None, _) if got.is_empty() => Ok(true),
+      (None, Mode::Strict) => {
+        report.failures.push(Failure::Missing(path));
+        Ok(false)
+      }
+      (Some(want), Mode::Check) | (Some(want), Mode::Strict) => {
+        report.failures.push(Failure::Mismatch { path, want, got });
+        Ok(false)
+      }
+      (_, Mode::Check) | (_, Mode::Bless) => {
+        if got.is_empty() {
+          std::fs::remove_file(&path)?;
+        } else {
+          std::fs::write(&path, got)?;
+        }
+        report.written.push(path);
+        Ok(false)
+      }
+    }
+  }
+}
+
+/// A reason a corpus did not pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Failure {
+  /// The snapshot at the path was not what parsing gave.
+  Mismatch {
+    /// The path of the snapshot.
+    path: PathBuf,
+    /// The contents of the snapshot.
+    want: String,
+    /// What parsing gave.
+    got: String,
+  },
+  /// The snapshot at the path was missing.
+  Missing(PathBuf),
+  /// The snapshot at the path had no sample.
+  Stale(PathBuf),
+}
+
+impl fmt::Display for Failure {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Failure::Mismatch { path, want, got } => {
+        let (line, want, got) = first_difference(want, got);
+        writeln!(f, "{}: mismatch at line {}", path.display(), line)?;
+        writeln!(f, "  want: {}", want)?;
+        write!(f, "  got:  {}", got)
+      }
+      Failure::Missing(path) => write!(f, "{}: missing", path.display()),
+      Failure::Stale(path) => write!(f, "{}: no sample", path.display()),
+    }
+  }
+}
+
+/// Returns the one-based number of the first line which differs between `a`
+/// and `b`, and that line in each, or `<end>` if there is no such line.
+fn first_difference<'a>(a: &'a str, b: &'a str) -> (usize, &'a str, &'a str) {
+  let mut a = a.lines();
+  let mut b = b.lines();
+  let mut line = 1;
+  loop {
+    match (a.next(), b.next()) {
+      (Some(x), Some(y)) if x == y => line += 1,
+      (x, y) => return (line, x.unwrap_or("<end>"), y.unwrap_or("<end>")),
+    }
+  }
+}
+
+/// The results of running a corpus.
+#[derive(Debug, Default)]
+pub struct Report {
+  /// The samples whose snapshots all matched.
+  pub passed: Vec<PathBuf>,
+  /// The snapshots which were written, or removed because they became empty.
+  pub written: Vec<PathBuf>,
+  /// The snapshots which were removed because they had no sample.
+  pub removed: Vec<PathBuf>,
+  /// The failures.
+  pub failures: Vec<Failure>,
+}
+
+impl Report {
+  /// Returns whether there were no failures.
+  pub fn is_ok(&self) -> bool {
+    self.failures.is_empty()
+  }
+
+  /// Panics with the failures, if there were any.
+  pub fn assert_ok(&self) {
+    if self.is_ok() {
+      return;
+    }
+    let mut msg = String::new();
+    for failure in &self.failures {
+      msg.push_str(&failure.to_string());
+      msg.push('\n');
+    }
+    msg.push_str("to re-bless, run with CORPUS=bless");
+    panic!("{}", msg);
+  }
+}
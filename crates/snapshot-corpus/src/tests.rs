@@ -0,0 +1,66 @@
+use crate::{Corpus, Failure, Mode, Snapshot};
+use std::fs;
+
+/// The tree is the text upper-cased, and every `!` is an error.
+fn parse(s: &str) -> Snapshot {
+  let errors = s.matches('!').map(|_| "bang\n").collect();
+  Snapshot {
+    tree: s.to_uppercase(),
+    errors,
+  }
+}
+
+#[test]
+fn run() {
+  let dir = std::env::temp_dir()
+    .join(format!("snapshot-corpus-{}", std::process::id()));
+  fs::create_dir_all(dir.join("sub")).unwrap();
+  fs::write(dir.join("a.toy"), "a\n").unwrap();
+  fs::write(dir.join("sub/b.toy"), "b!\n").unwrap();
+  fs::write(dir.join("notes.txt"), "not a sample").unwrap();
+  let mut corpus = Corpus::new(&dir, "toy");
+  // missing snapshots are failures when strict.
+  corpus.mode = Mode::Strict;
+  let report = corpus.run(parse).unwrap();
+  assert_eq!(report.failures.len(), 3);
+  assert!(report.passed.is_empty());
+  // but are written when checking.
+  corpus.mode = Mode::Check;
+  let report = corpus.run(parse).unwrap();
+  assert_eq!(report.written.len(), 3);
+  assert_eq!(fs::read_to_string(dir.join("a.tree")).unwrap(), "A\n");
+  assert!(!dir.join("a.errors").exists());
+  assert_eq!(
+    fs::read_to_string(dir.join("sub/b.errors")).unwrap(),
+    "bang\n"
+  );
+  corpus.mode = Mode::Strict;
+  let report = corpus.run(parse).unwrap();
+  assert!(report.is_ok());
+  assert_eq!(report.passed, [dir.join("a.toy"), dir.join("sub/b.toy")]);
+  // changes and stale snapshots.
+  fs::write(dir.join("a.toy"), "a\nc\n").unwrap();
+  fs::remove_file(dir.join("sub/b.toy")).unwrap();
+  let report = corpus.run(parse).unwrap();
+  let mismatch = Failure::Mismatch {
+    path: dir.join("a.tree"),
+    want: "A\n".to_owned(),
+    got: "A\nC\n".to_owned(),
+  };
+  assert!(mismatch
+    .to_string()
+    .contains("line 2\n  want: <end>\n  got:  C"));
+  let stale = |name: &str| Failure::Stale(dir.join("sub").join(name));
+  assert_eq!(
+    report.failures,
+    [stale("b.errors"), stale("b.tree"), mismatch]
+  );
+  corpus.mode = Mode::Bless;
+  let report = corpus.run(parse).unwrap();
+  assert!(report.is_ok());
+  assert_eq!(report.removed.len(), 2);
+  assert_eq!(report.written, [dir.join("a.tree")]);
+  corpus.mode = Mode::Strict;
+  corpus.run(parse).unwrap().assert_ok();
+  fs::remove_dir_all(&dir).unwrap();
+}
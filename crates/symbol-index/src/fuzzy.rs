@@ -0,0 +1,79 @@
+//! Fuzzy matching and scoring.
+
+/// Folds the case of `c`, for comparing chars case-insensitively.
+pub(crate) fn fold(c: char) -> char {
+  c.to_lowercase().next().unwrap_or(c)
+}
+
+const MATCH: u32 = 1;
+const SAME_CASE: u32 = 1;
+const RUN: u32 = 2;
+const WORD_START: u32 = 3;
+const NAME_START: u32 = 5;
+
+/// Returns the score for matching the `query` against the `name`, or `None` if
+/// the chars of the query do not all appear in order in the name, ignoring
+/// case. An empty query matches everything with score 0.
+///
+/// This finds the best-scoring way to match, not just the first.
+pub(crate) fn score(query: &str, name: &str) -> Option<u32> {
+  let query: Vec<char> = query.chars().collect();
+  let name: Vec<char> = name.chars().collect();
+  if query.is_empty() {
+    return Some(0);
+  }
+  if query.len() > name.len() {
+    return None;
+  }
+  // the bonus for matching some query char at each index in the name, besides
+  // for runs and the same case.
+  let position: Vec<u32> = (0..name.len())
+    .map(|j| {
+      if j == 0 {
+        NAME_START
+      } else if is_word_start(name[j - 1], name[j]) {
+        WORD_START
+      } else {
+        0
+      }
+    })
+    .collect();
+  // prev[j] is the best score for matching the query so far with its last char
+  // at index j in the name.
+  let mut prev: Vec<Option<u32>> = vec![None; name.len()];
+  for (i, &q) in query.iter().enumerate() {
+    let mut cur: Vec<Option<u32>> = vec![None; name.len()];
+    // the best of prev[..j - 1], i.e. not counting a run.
+    let mut best_before: Option<u32> = None;
+    for j in 0..name.len() {
+      if j >= 2 {
+        best_before = best_before.max(prev[j - 2]);
+      }
+      if fold(q) != fold(name[j]) {
+        continue;
+      }
+      let here = MATCH + position[j] + if q == name[j] { SAME_CASE } else { 0 };
+      let from = if i == 0 {
+        Some(0)
+      } else {
+        let run = if j >= 1 {
+          prev[j - 1].map(|x| x + RUN)
+        } else {
+          None
+        };
+        let skip = if j >= 1 { best_before } else { None };
+        run.max(skip)
+      };
+      cur[j] = from.map(|x| x + here);
+    }
+    prev = cur;
+  }
+  prev.into_iter().flatten().max()
+}
+
+/// Returns whether `cur` starts a word, given it is right after `prev`.
+fn is_word_start(prev: char, cur: char) -> bool {
+  !prev.is_alphanumeric() && cur.is_alphanumeric()
+    || prev.is_lowercase() && cur.is_uppercase()
+    || !prev.is_numeric() && cur.is_numeric()
+}
@@ -0,0 +1,216 @@
+//! An index of the symbols in a workspace, for `workspace/symbol` and
+//! quick-open.
+//!
+//! Each symbol has a name, the file it is in, and a payload, like an `AstPtr`
+//! to its definition. Queries are matched fuzzily against the names: the
+//! characters of the query must appear in order in the name, but not
+//! necessarily next to each other, so e.g. `fbr` matches `foo_bar`. Matches are
+//! scored, preferring those at the start of the name, at the start of words,
+//! in runs, and with the same case as the query.
+//!
+//! The symbols for a file are replaced all at once when the file changes, so
+//! the index may be kept up to date incrementally. Indexing many files at once,
+//! as when starting up, may report progress and be cancelled.
+
+#![deny(missing_debug_implementations)]
+#![deny(missing_docs)]
+#![deny(rust_2018_idioms)]
+
+mod fuzzy;
+
+#[cfg(test)]
+mod tests;
+
+use progress::{Backend, Progress};
+use rustc_hash::FxHashMap;
+use std::cmp::Reverse;
+use std::hash::Hash;
+
+/// A symbol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol<F, P> {
+  /// The name.
+  pub name: String,
+  /// The file.
+  pub file: F,
+  /// The payload, like a pointer to the definition.
+  pub ptr: P,
+}
+
+/// A symbol which matched a query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match<'a, F, P> {
+  /// The symbol.
+  pub symbol: &'a Symbol<F, P>,
+  /// The score. Higher is better.
+  pub score: u32,
+}
+
+/// An index of symbols, which can hold up to `u32::MAX` many symbols at once.
+#[derive(Debug)]
+pub struct SymbolIndex<F, P> {
+  /// removed symbols are `None` until the next compaction.
+  symbols: Vec<Option<Symbol<F, P>>>,
+  files: FxHashMap<F, Vec<u32>>,
+  /// the symbols whose names contain each (case-folded) char.
+  chars: FxHashMap<char, Vec<u32>>,
+  dead: usize,
+}
+
+impl<F, P> Default for SymbolIndex<F, P> {
+  fn default() -> Self {
+    Self {
+      symbols: Vec::new(),
+      files: FxHashMap::default(),
+      chars: FxHashMap::default(),
+      dead: 0,
+    }
+  }
+}
+
+impl<F, P> SymbolIndex<F, P>
+where
+  F: Copy + Eq + Hash,
+{
+  /// Returns a new, empty index.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Returns the number of symbols.
+  pub fn len(&self) -> usize {
+    self.symbols.len() - self.dead
+  }
+
+  /// Returns whether there are no symbols.
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Inserts a symbol.
+  pub fn insert(&mut self, name: String, file: F, ptr: P) {
+    let id = self.symbols.len() as u32;
+    let mut cs: Vec<char> = name.chars().map(fuzzy::fold).collect();
+    cs.sort_unstable();
+    cs.dedup();
+    for c in cs {
+      self.chars.entry(c).or_default().push(id);
+    }
+    self.files.entry(file).or_default().push(id);
+    self.symbols.push(Some(Symbol { name, file, ptr }));
+  }
+
+  /// Replaces all the symbols in the `file` with the `symbols`, pairs of names
+  /// and payloads.
+  pub fn set_file<I>(&mut self, file: F, symbols: I)
+  where
+    I: IntoIterator<Item = (String, P)>,
+  {
+    self.remove_file(file);
+    for (name, ptr) in symbols {
+      self.insert(name, file, ptr);
+    }
+  }
+
+  /// Like [`Self::set_file`] for each of the `files`, pairs of files and their
+  /// symbols, reporting progress through them.
+  ///
+  /// Stops early if the progress was cancelled, leaving the remaining files
+  /// as they were. Returns whether every file was set.
+  pub fn set_files<I, S, B>(
+    &mut self,
+    files: I,
+    progress: &mut Progress<B>,
+  ) -> bool
+  where
+    I: IntoIterator<Item = (F, S)>,
+    I::IntoIter: ExactSizeIterator,
+    S: IntoIterator<Item = (String, P)>,
+    B: Backend,
+  {
+    let files = files.into_iter();
+    let total = files.len();
+    progress.report(0, total);
+    for (idx, (file, symbols)) in files.enumerate() {
+      if progress.is_cancelled() {
+        return false;
+      }
+      self.set_file(file, symbols);
+      progress.report(idx + 1, total);
+    }
+    true
+  }
+
+  /// Removes all the symbols in the `file`.
+  pub fn remove_file(&mut self, file: F) {
+    let ids = match self.files.remove(&file) {
+      Some(x) => x,
+      None => return,
+    };
+    for id in ids {
+      self.symbols[id as usize] = None;
+      self.dead += 1;
+    }
+    if self.dead > self.len() {
+      self.compact();
+    }
+  }
+
+  /// Returns an iterator over the symbols in the `file`.
+  pub fn file_symbols(
+    &self,
+    file: F,
+  ) -> impl Iterator<Item = &Symbol<F, P>> + '_ {
+    let ids = self.files.get(&file).map_or(&[][..], Vec::as_slice);
+    ids
+      .iter()
+      .filter_map(move |&id| self.symbols[id as usize].as_ref())
+  }
+
+  /// Returns at most `limit` symbols matching the `query`, best first.
+  ///
+  /// Ties are broken by preferring shorter names, then by name. An empty
+  /// query matches every symbol equally.
+  pub fn query(&self, query: &str, limit: usize) -> Vec<Match<'_, F, P>> {
+    let folded: Vec<char> = query.chars().map(fuzzy::fold).collect();
+    // every char of the query must be in the name, so only the symbols with
+    // the rarest one need to be checked.
+    let rarest = folded
+      .iter()
+      .map(|c| self.chars.get(c).map_or(&[][..], Vec::as_slice))
+      .min_by_key(|ids| ids.len());
+    let candidates: Box<dyn Iterator<Item = &Symbol<F, P>>> = match rarest {
+      None => Box::new(self.symbols.iter().filter_map(Option::as_ref)),
+      Some(ids) => Box::new(
+        ids
+          .iter()
+          .filter_map(move |&id| self.symbols[id as usize].as_ref()),
+      ),
+    };
+    let mut ret: Vec<_> = candidates
+      .filter_map(|symbol| {
+        let score = fuzzy::score(query, &symbol.name)?;
+        Some(Match { symbol, score })
+      })
+      .collect();
+    ret.sort_by(|a, b| {
+      let key = |m: &Match<'_, F, P>| (Reverse(m.score), m.symbol.name.len());
+      key(a)
+        .cmp(&key(b))
+        .then_with(|| a.symbol.name.cmp(&b.symbol.name))
+    });
+    ret.truncate(limit);
+    ret
+  }
+
+  /// Drops the removed symbols, re-numbering the rest.
+  fn compact(&mut self) {
+    let symbols = std::mem::take(&mut self.symbols);
+    self.files.clear();
+    self.chars.clear();
+    self.dead = 0;
+    for symbol in symbols.into_iter().flatten() {
+      self.insert(symbol.name, symbol.file, symbol.ptr);
+    }
+  }
+}
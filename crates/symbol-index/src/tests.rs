@@ -0,0 +1,108 @@
+use crate::fuzzy::score;
+use crate::SymbolIndex;
+use progress::{Backend, Cancel, Noop, Progress};
+
+fn names(idx: &SymbolIndex<u32, ()>, query: &str) -> Vec<String> {
+  idx
+    .query(query, usize::MAX)
+    .into_iter()
+    .map(|m| m.symbol.name.clone())
+    .collect()
+}
+
+#[test]
+fn fuzzy() {
+  assert!(score("fbr", "foo_bar").is_some());
+  assert!(score("FB", "fooBar").is_some());
+  assert!(score("bf", "foo_bar").is_none());
+  assert!(score("foobarr", "foo_bar").is_none());
+  assert_eq!(score("", "x"), Some(0));
+  // the start of a word beats the middle of one.
+  assert!(score("b", "foo_bar") > score("a", "foo_bar"));
+  // a run beats scattered chars.
+  assert!(score("ab", "xab") > score("ab", "xaxb"));
+  // the best match is found, not just the first.
+  assert!(score("ba", "xbxx_ba") > score("ba", "xbxx_xa"));
+  // the same case is slightly better.
+  assert!(score("B", "fooBar") > score("b", "fooBar"));
+}
+
+#[test]
+fn query() {
+  let mut idx = SymbolIndex::new();
+  for name in ["foo_bar", "FooBar", "format", "bar", "baz"].iter() {
+    idx.insert((*name).to_owned(), 1, ());
+  }
+  assert_eq!(names(&idx, "fb"), ["foo_bar", "FooBar"]);
+  assert_eq!(names(&idx, "ba"), ["bar", "baz", "foo_bar", "FooBar"]);
+  assert_eq!(names(&idx, "fmt"), ["format"]);
+  assert!(names(&idx, "qux").is_empty());
+  assert_eq!(names(&idx, "").len(), 5);
+  assert_eq!(idx.query("ba", 2).len(), 2);
+}
+
+#[test]
+fn files() {
+  let mut idx = SymbolIndex::new();
+  idx.set_file(1, vec![("a".to_owned(), ()), ("ab".to_owned(), ())]);
+  idx.set_file(2, vec![("abc".to_owned(), ())]);
+  assert_eq!(idx.len(), 3);
+  assert_eq!(names(&idx, "a"), ["a", "ab", "abc"]);
+  idx.set_file(1, vec![("b".to_owned(), ())]);
+  assert_eq!(idx.len(), 2);
+  assert_eq!(names(&idx, "a"), ["abc"]);
+  assert_eq!(names(&idx, "b"), ["b", "abc"]);
+  let in_1: Vec<_> = idx.file_symbols(1).map(|s| s.name.as_str()).collect();
+  assert_eq!(in_1, ["b"]);
+  idx.remove_file(2);
+  idx.remove_file(3);
+  assert_eq!(names(&idx, ""), ["b"]);
+  idx.remove_file(1);
+  assert!(idx.is_empty());
+  assert!(names(&idx, "").is_empty());
+}
+
+#[test]
+fn compact() {
+  let mut idx = SymbolIndex::new();
+  for i in 0..100u32 {
+    idx.set_file(i % 3, vec![(format!("sym{}", i), i)]);
+  }
+  assert_eq!(idx.len(), 3);
+  assert!(idx.symbols.len() < 10);
+  let mut got: Vec<_> =
+    idx.query("sym", 10).iter().map(|m| m.symbol.ptr).collect();
+  got.sort_unstable();
+  assert_eq!(got, [97, 98, 99]);
+}
+
+/// Cancels once half the work is done.
+struct CancelAtHalf(Cancel);
+
+impl Backend for CancelAtHalf {
+  fn begin(&mut self, _: &str, _: bool) {}
+
+  fn report(&mut self, _: Option<&str>, percentage: Option<u32>) {
+    if percentage == Some(50) {
+      self.0.cancel();
+    }
+  }
+
+  fn end(&mut self, _: Option<&str>) {}
+}
+
+#[test]
+fn set_files() {
+  let files =
+    || (0..4u32).map(|file| (file, vec![(format!("sym{}", file), ())]));
+  let mut idx = SymbolIndex::new();
+  let mut p = Progress::new(Noop, "Indexing", None);
+  assert!(idx.set_files(files(), &mut p));
+  assert_eq!(idx.len(), 4);
+  let mut idx = SymbolIndex::new();
+  let cancel = Cancel::new();
+  let backend = CancelAtHalf(cancel.clone());
+  let mut p = Progress::new(backend, "Indexing", Some(cancel));
+  assert!(!idx.set_files(files(), &mut p));
+  assert_eq!(names(&idx, "sym"), ["sym0", "sym1"]);
+}
@@ -1,4 +1,4 @@
-use crate::util::{ident, unwrap_node, unwrap_token, Cx};
+use crate::util::{self, ident, unwrap_node, unwrap_token, Cx};
 use proc_macro2::{Ident, TokenStream};
 use quote::{format_ident, quote};
 use rustc_hash::FxHashSet;
@@ -21,23 +21,41 @@ pub(crate) fn get(
 }
 
 fn get_nodes(cx: &Cx, name: Ident, rules: &[Rule]) -> TokenStream {
-  let lang = &cx.lang;
+  let display = util::display(cx, &name, quote! { &self.as_ref().text() });
+  let fingerprint = util::fingerprint(cx, &name);
   let mut defs = Vec::with_capacity(rules.len());
   let mut casts = Vec::with_capacity(rules.len());
+  let mut ref_casts = Vec::with_capacity(rules.len());
+  let mut kinds = Vec::with_capacity(rules.len());
   let mut syntaxes = Vec::with_capacity(rules.len());
   for rule in rules {
-    let name = ident(&cx.grammar[unwrap_node(rule)].name);
+    let name = cx.grammar[unwrap_node(rule)].name.as_str();
+    assert!(
+      !cx.is_transparent(name),
+      "cannot use transparent {} in alt",
+      name
+    );
+    assert!(
+      cx.has_type(name),
+      "cannot use {} without a type in alt with a type",
+      name
+    );
+    let name = ident(name);
     defs.push(quote! { #name(#name) });
     casts.push(quote! { SK::#name => Self::#name(#name(node)) });
+    ref_casts.push(quote! { SK::#name => Self::#name(#name(node.clone())) });
     syntaxes.push(quote! { Self::#name(x) => x.as_ref() });
+    kinds.push(name);
   }
+  let has_language = util::has_language(cx, &name, &kinds);
+  let non_exhaustive = util::non_exhaustive(&cx.options);
+  let vis = util::vis(&cx.options);
   quote! {
-    pub enum #name {
+    #non_exhaustive
+    #vis enum #name {
       #(#defs ,)*
     }
-    impl HasLanguage for #name {
-      type Language = #lang;
-    }
+    #has_language
     impl TryFrom<SyntaxNode> for #name {
       type Error = ();
       fn try_from(node: SyntaxNode) -> Result<Self, Self::Error> {
@@ -48,6 +66,22 @@ fn get_nodes(cx: &Cx, name: Ident, rules: &[Rule]) -> TokenStream {
         Ok(ret)
       }
     }
+    impl TryFrom<&SyntaxNode> for #name {
+      type Error = ();
+      fn try_from(node: &SyntaxNode) -> Result<Self, Self::Error> {
+        let ret = match node.kind() {
+          #(#ref_casts ,)*
+          _ => return Err(()),
+        };
+        Ok(ret)
+      }
+    }
+    impl TryFrom<&SyntaxElement> for #name {
+      type Error = ();
+      fn try_from(elem: &SyntaxElement) -> Result<Self, Self::Error> {
+        Self::try_from(elem.as_node().ok_or(())?)
+      }
+    }
     impl AsRef<SyntaxNode> for #name {
       fn as_ref(&self) -> &SyntaxNode {
         match self {
@@ -55,41 +89,53 @@ fn get_nodes(cx: &Cx, name: Ident, rules: &[Rule]) -> TokenStream {
         }
       }
     }
+    #display
+    #fingerprint
   }
 }
 
 fn get_tokens(cx: &Cx, name: Ident, rules: &[Rule]) -> TokenStream {
-  let lang = &cx.lang;
+  let display = util::display(cx, &name, quote! { self.token.text() });
   let name_kind = format_ident!("{}Kind", name);
   let mut defs = Vec::with_capacity(rules.len());
   let mut casts = Vec::with_capacity(rules.len());
   let mut to_strs = Vec::with_capacity(rules.len());
+  let mut kinds = Vec::with_capacity(rules.len());
   for rule in rules {
     let tok = unwrap_token(rule);
-    let name = ident(&cx.tokens.name(tok));
+    assert!(
+      !cx.tokens.aliases.contains_key(&tok),
+      "cannot use aliased token {} in alt {}",
+      cx.grammar[tok].name,
+      name
+    );
+    let name = ident(cx.tokens.name(tok));
     let text = cx.grammar[tok].name.as_str();
     defs.push(quote! { #name });
     casts.push(quote! { SK::#name => #name_kind::#name });
     to_strs.push(quote! { Self::#name => #text });
+    kinds.push(name);
   }
+  let has_language = util::has_language(cx, &name, &kinds);
+  let non_exhaustive = util::non_exhaustive(&cx.options);
+  let vis = util::vis(&cx.options);
   quote! {
-    pub enum #name_kind {
+    #non_exhaustive
+    #vis enum #name_kind {
       #(#defs ,)*
     }
     impl #name_kind {
-      pub fn to_str(&self) -> &'static str {
+      #vis fn to_str(&self) -> &'static str {
         match *self {
           #(#to_strs ,)*
         }
       }
     }
-    pub struct #name {
-      pub token: SyntaxToken,
-      pub kind: #name_kind,
-    }
-    impl HasLanguage for #name {
-      type Language = #lang;
+    #vis struct #name {
+      #vis token: SyntaxToken,
+      #vis kind: #name_kind,
     }
+    #has_language
     impl TryFrom<SyntaxToken> for #name {
       type Error = ();
       fn try_from(token: SyntaxToken) -> Result<Self, Self::Error> {
@@ -100,5 +146,22 @@ fn get_tokens(cx: &Cx, name: Ident, rules: &[Rule]) -> TokenStream {
         Ok(Self { token, kind })
       }
     }
+    impl TryFrom<&SyntaxToken> for #name {
+      type Error = ();
+      fn try_from(token: &SyntaxToken) -> Result<Self, Self::Error> {
+        let kind = match token.kind() {
+          #(#casts ,)*
+          _ => return Err(()),
+        };
+        Ok(Self { token: token.clone(), kind })
+      }
+    }
+    impl TryFrom<&SyntaxElement> for #name {
+      type Error = ();
+      fn try_from(elem: &SyntaxElement) -> Result<Self, Self::Error> {
+        Self::try_from(elem.as_token().ok_or(())?)
+      }
+    }
+    #display
   }
 }
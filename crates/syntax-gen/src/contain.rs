@@ -0,0 +1,35 @@
+//! The tokens which may appear directly inside nodes.
+
+use crate::util::{ident, Cx};
+use rustc_hash::FxHashSet;
+use ungrammar::Rule;
+
+/// Adds the names of the kinds of the tokens which may appear directly inside
+/// a node with the `rule` to `out`.
+///
+/// Tokens in transparent nodes and in alternatives of only tokens are directly
+/// inside the node, since neither has a `SyntaxKind` of its own. Tokens in other
+/// nodes are not.
+pub(crate) fn get(cx: &Cx, rule: &Rule, out: &mut FxHashSet<String>) {
+  match rule {
+    Rule::Node(node) => {
+      let data = &cx.grammar[*node];
+      if cx.is_transparent(&data.name)
+        || cx.token_alts.contains(&ident(&data.name))
+      {
+        get(cx, &data.rule, out);
+      }
+    }
+    Rule::Token(tok) => {
+      out.insert(cx.tokens.name(*tok).to_owned());
+    }
+    Rule::Labeled { rule, .. } | Rule::Opt(rule) | Rule::Rep(rule) => {
+      get(cx, rule, out)
+    }
+    Rule::Seq(rules) | Rule::Alt(rules) => {
+      for rule in rules {
+        get(cx, rule, out);
+      }
+    }
+  }
+}
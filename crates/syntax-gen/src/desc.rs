@@ -0,0 +1,83 @@
+//! Short human descriptions of nodes, for error messages.
+
+use crate::token::TokenDb;
+use identifier_case::pascal_to_snake;
+use ungrammar::{Grammar, Node, Rule};
+
+/// Returns a description of the node, like "an if expr: `if` cond block".
+///
+/// The name of the node is split into lowercase words, and the rule is
+/// summarized with tokens in backticks (or their description, for special
+/// tokens), nodes as their words, and labeled children as their labels.
+pub(crate) fn get(grammar: &Grammar, tokens: &TokenDb, node: Node) -> String {
+  let data = &grammar[node];
+  let name = words(&pascal_to_snake(&data.name));
+  let article = if name.starts_with(|c| "aeiou".contains(c)) {
+    "an"
+  } else {
+    "a"
+  };
+  let mut out = format!("{} {}: ", article, name);
+  rule(grammar, tokens, &data.rule, false, &mut out);
+  out
+}
+
+fn words(snake: &str) -> String {
+  snake.replace('_', " ")
+}
+
+fn rule(
+  grammar: &Grammar,
+  tokens: &TokenDb,
+  r: &Rule,
+  postfix: bool,
+  out: &mut String,
+) {
+  let needs_parens = postfix && matches!(r, Rule::Seq(_) | Rule::Alt(_));
+  if needs_parens {
+    out.push('(');
+  }
+  match r {
+    Rule::Node(node) => {
+      out.push_str(&words(&pascal_to_snake(&grammar[*node].name)))
+    }
+    Rule::Token(tok) => {
+      if let Some(sp) = tokens.special.get(tok) {
+        out.push_str(sp.desc);
+        return;
+      }
+      out.push('`');
+      out.push_str(&grammar[*tok].name);
+      out.push('`');
+    }
+    Rule::Labeled { label, .. } => out.push_str(&words(label)),
+    Rule::Opt(r) => {
+      rule(grammar, tokens, r, true, out);
+      out.push('?');
+    }
+    Rule::Rep(r) => {
+      rule(grammar, tokens, r, true, out);
+      out.push('*');
+    }
+    Rule::Seq(rules) => sep(grammar, tokens, rules, " ", out),
+    Rule::Alt(rules) => sep(grammar, tokens, rules, " or ", out),
+  }
+  if needs_parens {
+    out.push(')');
+  }
+}
+
+fn sep(
+  grammar: &Grammar,
+  tokens: &TokenDb,
+  rules: &[Rule],
+  s: &str,
+  out: &mut String,
+) {
+  for (idx, r) in rules.iter().enumerate() {
+    if idx != 0 {
+      out.push_str(s);
+    }
+    rule(grammar, tokens, r, false, out);
+  }
+}
@@ -0,0 +1,133 @@
+use crate::token::TokenDb;
+use crate::util::each_node;
+use rustc_hash::FxHashMap;
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use ungrammar::{Grammar, Node, Rule};
+
+/// Returns a Markdown reference for the grammar.
+pub(crate) fn get(lang: &str, grammar: &Grammar, tokens: &TokenDb) -> String {
+  let mut used_by = FxHashMap::<Node, BTreeSet<&str>>::default();
+  for node in grammar.iter() {
+    each_node(&grammar[node].rule, &mut |child| {
+      let users = used_by.entry(child).or_default();
+      users.insert(grammar[node].name.as_str());
+    });
+  }
+  let mut ret = String::new();
+  writeln!(ret, "# {} grammar", lang).unwrap();
+  writeln!(ret).unwrap();
+  writeln!(ret, "This file is generated. Do not edit it by hand.").unwrap();
+  writeln!(ret).unwrap();
+  writeln!(ret, "## Nodes").unwrap();
+  for node in grammar.iter() {
+    let data = &grammar[node];
+    writeln!(ret).unwrap();
+    writeln!(ret, "### {}", data.name).unwrap();
+    writeln!(ret).unwrap();
+    let mut s = String::new();
+    rule(grammar, &data.rule, Prec::Alt, &mut s);
+    writeln!(ret, "{} = {}", data.name, s).unwrap();
+    if let Some(users) = used_by.get(&node) {
+      writeln!(ret).unwrap();
+      let users: Vec<_> = users.iter().map(|&x| link(x)).collect();
+      writeln!(ret, "Used by: {}", users.join(", ")).unwrap();
+    }
+  }
+  let mut punctuation: Vec<_> = tokens.punctuation.iter().collect();
+  let mut keywords: Vec<_> = tokens.keywords.iter().collect();
+  for xs in [&mut punctuation, &mut keywords].iter_mut() {
+    xs.sort_unstable_by_key(|&(&tok, _)| {
+      let name = grammar[tok].name.as_str();
+      (Reverse(name.len()), name)
+    });
+  }
+  for &(title, ref xs) in
+    [("Punctuation", punctuation), ("Keywords", keywords)].iter()
+  {
+    writeln!(ret).unwrap();
+    writeln!(ret, "## {}", title).unwrap();
+    writeln!(ret).unwrap();
+    writeln!(ret, "| Text | Kind |").unwrap();
+    writeln!(ret, "| ---- | ---- |").unwrap();
+    for &(&tok, kind) in xs.iter() {
+      let text = grammar[tok].name.replace('|', "\\|");
+      writeln!(ret, "| `{}` | `{}` |", text, kind).unwrap();
+    }
+  }
+  let mut special: Vec<_> = tokens.special.values().collect();
+  special.sort_unstable();
+  writeln!(ret).unwrap();
+  writeln!(ret, "## Special tokens").unwrap();
+  writeln!(ret).unwrap();
+  writeln!(ret, "| Kind | Description | Example |").unwrap();
+  writeln!(ret, "| ---- | ----------- | ------- |").unwrap();
+  for sp in special {
+    let example = match sp.example {
+      Some(x) => format!("`{}`", x.replace('|', "\\|")),
+      None => String::new(),
+    };
+    writeln!(ret, "| `{}` | {} | {} |", sp.name, sp.desc, example).unwrap();
+  }
+  ret
+}
+
+/// How tightly the context of a rule binds, from loosest to tightest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Prec {
+  Alt,
+  Seq,
+  Postfix,
+}
+
+fn rule(grammar: &Grammar, r: &Rule, prec: Prec, out: &mut String) {
+  let needs_parens = match r {
+    Rule::Alt(_) => prec > Prec::Alt,
+    Rule::Seq(_) | Rule::Labeled { .. } => prec > Prec::Seq,
+    Rule::Node(_) | Rule::Token(_) | Rule::Opt(_) | Rule::Rep(_) => false,
+  };
+  if needs_parens {
+    out.push('(');
+  }
+  match r {
+    Rule::Node(node) => out.push_str(&link(&grammar[*node].name)),
+    Rule::Token(tok) => write!(out, "`'{}'`", grammar[*tok].name).unwrap(),
+    Rule::Labeled { label, rule: r } => {
+      write!(out, "{}:", label).unwrap();
+      rule(grammar, r, Prec::Postfix, out);
+    }
+    Rule::Opt(r) => {
+      rule(grammar, r, Prec::Postfix, out);
+      out.push('?');
+    }
+    Rule::Rep(r) => {
+      rule(grammar, r, Prec::Postfix, out);
+      out.push('*');
+    }
+    Rule::Seq(rules) => sep(grammar, rules, " ", Prec::Seq, out),
+    Rule::Alt(rules) => sep(grammar, rules, " | ", Prec::Seq, out),
+  }
+  if needs_parens {
+    out.push(')');
+  }
+}
+
+fn sep(
+  grammar: &Grammar,
+  rules: &[Rule],
+  s: &str,
+  prec: Prec,
+  out: &mut String,
+) {
+  for (idx, r) in rules.iter().enumerate() {
+    if idx != 0 {
+      out.push_str(s);
+    }
+    rule(grammar, r, prec, out);
+  }
+}
+
+fn link(name: &str) -> String {
+  format!("[{}](#{})", name, name.to_ascii_lowercase())
+}
@@ -0,0 +1,30 @@
+use crate::util::{self, ident, path, Cx};
+use crate::ParseEntry;
+use proc_macro2::TokenStream;
+use quote::quote;
+
+pub(crate) fn get(cx: &Cx, pe: &ParseEntry) -> Vec<TokenStream> {
+  let func = path(&pe.func);
+  let error = path(&pe.error);
+  let vis = util::vis(&cx.options);
+  cx.grammar
+    .iter()
+    .filter_map(|node| {
+      let name_str = cx.grammar[node].name.as_str();
+      let name = ident(name_str);
+      if !cx.has_type(name_str) || cx.token_alts.contains(&name) {
+        return None;
+      }
+      let ret = quote! {
+        impl #name {
+          #vis fn parse(text: &str) -> (Self, Vec<#error>) {
+            let (node, errors) = #func(text, #name_str);
+            let ret = node.try_into().expect("parse entry returned wrong node");
+            (ret, errors)
+          }
+        }
+      };
+      Some(ret)
+    })
+    .collect()
+}
@@ -0,0 +1,43 @@
+use identifier_case::pascal_to_snake;
+use proc_macro2::Ident;
+use std::fmt::Write as _;
+
+/// Returns a C header with an enum of the `kinds`, in discriminant order, and
+/// their names, prefixed with the name of the language `lang`.
+pub(crate) fn get(lang: &str, kinds: &[Ident]) -> String {
+  let lower = pascal_to_snake(lang);
+  let upper = lower.to_ascii_uppercase();
+  let mut ret = String::new();
+  writeln!(ret, "/* Generated by syntax-gen. Do not edit. */").unwrap();
+  writeln!(ret).unwrap();
+  writeln!(ret, "#ifndef {}_SYNTAX_KINDS_H", upper).unwrap();
+  writeln!(ret, "#define {}_SYNTAX_KINDS_H", upper).unwrap();
+  writeln!(ret).unwrap();
+  writeln!(ret, "enum {}_syntax_kind {{", lower).unwrap();
+  for (idx, kind) in kinds.iter().enumerate() {
+    let name = pascal_to_snake(&kind.to_string()).to_ascii_uppercase();
+    writeln!(ret, "  {}_{} = {},", upper, name, idx).unwrap();
+  }
+  writeln!(ret, "}};").unwrap();
+  writeln!(ret).unwrap();
+  writeln!(ret, "#define {}_SYNTAX_KIND_COUNT {}", upper, kinds.len()).unwrap();
+  writeln!(ret).unwrap();
+  writeln!(
+    ret,
+    "/* The names of the kinds, as in Rust, indexed by kind. */"
+  )
+  .unwrap();
+  writeln!(
+    ret,
+    "static const char *const {}_syntax_kind_names[{}_SYNTAX_KIND_COUNT] = {{",
+    lower, upper
+  )
+  .unwrap();
+  for kind in kinds {
+    writeln!(ret, "  \"{}\",", kind).unwrap();
+  }
+  writeln!(ret, "}};").unwrap();
+  writeln!(ret).unwrap();
+  writeln!(ret, "#endif").unwrap();
+  ret
+}
@@ -0,0 +1,144 @@
+use crate::token::Special;
+use proc_macro2::Ident;
+use std::fmt::Write as _;
+use ungrammar::{Grammar, Rule};
+
+/// The parts of the generated code described in the JSON.
+pub(crate) struct Api<'a> {
+  pub(crate) lang: &'a str,
+  pub(crate) grammar: &'a Grammar,
+  pub(crate) transparent: &'a [String],
+  pub(crate) kinds: &'a [Ident],
+  pub(crate) trivia: &'a [Ident],
+  pub(crate) punctuation: &'a [(&'a str, Ident)],
+  pub(crate) keywords: &'a [(&'a str, Ident)],
+  pub(crate) special: &'a [Special],
+}
+
+/// Returns a JSON description of the generated code.
+pub(crate) fn get(api: &Api<'_>) -> String {
+  let mut ret = String::new();
+  writeln!(ret, "{{").unwrap();
+  writeln!(ret, "  \"lang\": {},", string(api.lang)).unwrap();
+  let kinds: Vec<_> =
+    api.kinds.iter().map(|x| string(&x.to_string())).collect();
+  writeln!(ret, "  \"kinds\": [{}],", kinds.join(", ")).unwrap();
+  let trivia: Vec<_> =
+    api.trivia.iter().map(|x| string(&x.to_string())).collect();
+  writeln!(ret, "  \"trivia\": [{}],", trivia.join(", ")).unwrap();
+  let nodes: Vec<_> = api
+    .grammar
+    .iter()
+    .map(|node| {
+      let data = &api.grammar[node];
+      let transparent = api.transparent.contains(&data.name);
+      let mut r = String::new();
+      rule(api.grammar, &data.rule, &mut r);
+      format!(
+        "    {{\"name\": {}, \"transparent\": {}, \"rule\": {}}}",
+        string(&data.name),
+        transparent,
+        r
+      )
+    })
+    .collect();
+  writeln!(ret, "  \"nodes\": [\n{}\n  ],", nodes.join(",\n")).unwrap();
+  for &(title, xs) in
+    [("punctuation", api.punctuation), ("keywords", api.keywords)].iter()
+  {
+    let xs: Vec<_> = xs
+      .iter()
+      .map(|(text, kind)| {
+        format!(
+          "    {{\"text\": {}, \"kind\": {}}}",
+          string(text),
+          string(&kind.to_string())
+        )
+      })
+      .collect();
+    writeln!(ret, "  \"{}\": [\n{}\n  ],", title, xs.join(",\n")).unwrap();
+  }
+  let special: Vec<_> = api
+    .special
+    .iter()
+    .map(|sp| {
+      let example = match sp.example {
+        Some(x) => string(x),
+        None => "null".to_owned(),
+      };
+      format!(
+        "    {{\"text\": {}, \"kind\": {}, \"desc\": {}, \"example\": {}}}",
+        string(&sp.text),
+        string(&sp.name),
+        string(sp.desc),
+        example
+      )
+    })
+    .collect();
+  writeln!(ret, "  \"special\": [\n{}\n  ]", special.join(",\n")).unwrap();
+  writeln!(ret, "}}").unwrap();
+  ret
+}
+
+pub(crate) fn rule(grammar: &Grammar, r: &Rule, out: &mut String) {
+  match r {
+    Rule::Node(node) => {
+      write!(out, "{{\"node\": {}}}", string(&grammar[*node].name)).unwrap()
+    }
+    Rule::Token(tok) => {
+      write!(out, "{{\"token\": {}}}", string(&grammar[*tok].name)).unwrap()
+    }
+    Rule::Labeled { label, rule: r } => {
+      write!(out, "{{\"label\": {}, \"rule\": ", string(label)).unwrap();
+      rule(grammar, r, out);
+      out.push('}');
+    }
+    Rule::Opt(r) => {
+      out.push_str("{\"opt\": ");
+      rule(grammar, r, out);
+      out.push('}');
+    }
+    Rule::Rep(r) => {
+      out.push_str("{\"rep\": ");
+      rule(grammar, r, out);
+      out.push('}');
+    }
+    Rule::Seq(rules) => rules_array(grammar, "seq", rules, out),
+    Rule::Alt(rules) => rules_array(grammar, "alt", rules, out),
+  }
+}
+
+fn rules_array(
+  grammar: &Grammar,
+  name: &str,
+  rules: &[Rule],
+  out: &mut String,
+) {
+  write!(out, "{{\"{}\": [", name).unwrap();
+  for (idx, r) in rules.iter().enumerate() {
+    if idx != 0 {
+      out.push_str(", ");
+    }
+    rule(grammar, r, out);
+  }
+  out.push_str("]}");
+}
+
+/// Returns `s` as a JSON string literal.
+pub fn string(s: &str) -> String {
+  let mut ret = String::with_capacity(s.len() + 2);
+  ret.push('"');
+  for c in s.chars() {
+    match c {
+      '"' => ret.push_str("\\\""),
+      '\\' => ret.push_str("\\\\"),
+      '\n' => ret.push_str("\\n"),
+      '\r' => ret.push_str("\\r"),
+      '\t' => ret.push_str("\\t"),
+      c if (c as u32) < 0x20 => write!(ret, "\\u{:04x}", c as u32).unwrap(),
+      c => ret.push(c),
+    }
+  }
+  ret.push('"');
+  ret
+}
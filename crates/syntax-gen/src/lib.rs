@@ -7,69 +7,144 @@
 #![deny(rust_2018_idioms)]
 
 mod alt;
+mod contain;
+mod desc;
+mod doc;
+mod entry;
+mod header;
+mod json;
+mod merge;
+mod options;
+mod report;
 mod seq;
 mod token;
 mod util;
 
+#[cfg(test)]
+mod tests;
+
+// for `textmate`, which writes JSON too. not part of the API.
+#[doc(hidden)]
+pub use json::string as json_string;
+pub use merge::{merge_grammars, MergeError};
+pub use options::{
+  AccessorCase, AccessorOverride, AstNodes, Options, Output, ParseEntry,
+  RepeatedAccessor, TreeLib, Visibility,
+};
+pub use report::Report;
 pub use token::TokenKind;
 
 use crate::util::{ident, Cx};
-use proc_macro2::Literal;
+use proc_macro2::{Literal, TokenStream};
 use quote::quote;
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashMap, FxHashSet};
 use std::cmp::Reverse;
+use std::fmt::Write as _;
 use ungrammar::{Grammar, Rule};
 
+const AST_EXT: &str = "src/ast_ext.rs";
+const FACADE: &str = "src/syntax.rs";
+
 /// Generates Rust code from the `grammar` of the `lang` and writes it to
-/// `src/kind.rs` and `src/ast.rs`.
+/// `src/kind.rs` and `src/ast.rs`, or one of them per [`Options::output`].
 ///
 /// `lang` is the name of the language, `trivia` is a list of all the
-/// `SyntaxKind`s which should be made as trivia, and `grammar` is the grammar
-/// for the language.
+/// `SyntaxKind`s which should be made as trivia, `grammar` is the grammar for
+/// the language, and `options` controls details of the generated code. Each
+/// trivia kind is a new variant, so its name must differ from those of the
+/// other trivia, tokens, and nodes.
 ///
 /// `get_token` will be called once for each token in `grammar`, and should
 /// return a tuple `(kind, name)`, where `kind` is what kind of token this is (a
 /// [`TokenKind`]) and `name` is the name of the token, to be used as an enum
-/// variant in the generated `SyntaxKind`.
+/// variant in the generated `SyntaxKind`. Every token should have a different
+/// name, unless it shares a kind with others per [`Options::token_aliases`].
+/// If it does not recognize a token, it should return [`TokenKind::Unknown`],
+/// so that generation can panic with a report about all the unknown tokens,
+/// the nodes which use them, and similar known tokens.
 ///
 /// The generated Rust files will depend on:
 ///
-/// - `rowan` from crates.io
+/// - `rowan` from crates.io, or another tree library (see [`TreeLib`])
 /// - `token` from language-server-util
-/// - `ast-ptr` from language-server-util
+/// - `ast-ptr` from language-server-util, if using `rowan`
 ///
 /// The files will be formatted with rustfmt.
 ///
 /// `src/kind.rs` will contain definitions for the language's `SyntaxKind` and
 /// associated types, using all the different tokens extracted from `grammar`
-/// and processed with `get_token`.
+/// and processed with `get_token`. The variants of `SyntaxKind` are grouped:
+/// first trivia, then special tokens, keywords, punctuation, and finally nodes.
+/// `SyntaxKind::LAST_TOKEN_KIND` and `SyntaxKind::FIRST_NODE_KIND` mark the
+/// boundary between tokens and nodes. `SyntaxKind::grammar_desc` describes each
+/// node in words, based on its rule. `SyntaxKind::GRAMMAR_HASH` changes whenever
+/// the grammar or the kinds do. `SyntaxKind::child_tokens` lists the kinds
+/// of the tokens which may appear directly inside each node, and
+/// `SyntaxKind::can_contain` checks membership. The lookups of tokens by text,
+/// like `SyntaxKind::keyword` and `SyntaxKind::punctuation`, are `const fn`,
+/// and `SyntaxKind::KEYWORDS_BY_LEN` and `SyntaxKind::PUNCTUATION_BY_LEN` group
+/// those tokens by length.
 ///
 /// `src/ast.rs` will contain a strongly-typed API for traversing a syntax tree
-/// for `lang`, based on the `grammar`.
+/// for `lang`, based on the `grammar`. With [`TreeLib::Rowan`], every type
+/// implements `Display`, writing its text including trivia, and nodes whose
+/// required children are all tokens with fixed text, like punctuation and
+/// keywords, get an `empty` constructor, which makes a node with only those
+/// tokens. Every node also gets a `checked_new` constructor, which checks the
+/// kinds and number of its children against its rule. Every type for a node
+/// gets a `fingerprint` method, which hashes it ignoring trivia, for use as
+/// e.g. a cache key. Only the nodes in [`Options::ast_nodes`] get types.
 ///
-/// Returns `Err` if the files could not be written. Panics if certain
+/// Returns a [`Report`] about the grammar, or `Err` if the files could not be
+/// written or a transparent node refers to itself. Panics if certain other
 /// properties about `grammar` do not hold. (Read the source/panic messages to
 /// find out what they are.)
 pub fn gen<F>(
   lang: &str,
   trivia: &[&str],
   grammar: Grammar,
+  options: Options,
   get_token: F,
-) -> std::io::Result<()>
+) -> std::io::Result<Report>
 where
   F: Fn(&str) -> (TokenKind, String),
 {
   let lang = ident(lang);
-  let tokens = token::TokenDb::new(&grammar, get_token);
+  util::check_transparent(&grammar, &options.transparent)?;
+  let tokens = token::TokenDb::new(&grammar, get_token, &options.token_aliases);
+  assert!(
+    tokens.aliases.is_empty() || options.tree_lib == TreeLib::Rowan,
+    "token aliases require TreeLib::Rowan"
+  );
+  assert!(
+    options.output == Output::All || options.visibility == Visibility::Pub,
+    "splitting the output requires Visibility::Pub"
+  );
   let mut types = Vec::new();
   let trivia: Vec<_> = trivia.iter().map(|&x| ident(x)).collect();
-  let mut syntax_kinds = trivia.clone();
+  let mut node_kinds = Vec::new();
+  let mut grammar_desc_arms = Vec::new();
+  let mut child_tokens = Vec::new();
+  let mut overridden = FxHashSet::default();
   let mut cx = Cx {
     lang,
     grammar,
     tokens,
     token_alts: FxHashSet::default(),
+    options,
   };
+  for name in cx.options.ast_nodes.names() {
+    let node = cx
+      .grammar
+      .iter()
+      .find(|&node| cx.grammar[node].name == *name);
+    assert!(node.is_some(), "no node for ast_nodes: {}", name);
+    assert!(
+      !cx.is_transparent(name),
+      "cannot use transparent {} in ast_nodes",
+      name
+    );
+  }
   let mut token_alts = FxHashSet::default();
   // first process all the alts
   for node in cx.grammar.iter() {
@@ -78,7 +153,17 @@ where
       Rule::Alt(rules) => rules.as_slice(),
       _ => continue,
     };
-    types.push(alt::get(&cx, &mut token_alts, ident(&data.name), rules));
+    assert!(
+      !cx.is_transparent(&data.name),
+      "cannot make alt {} transparent",
+      data.name
+    );
+    if cx.has_type(&data.name) {
+      types.push(alt::get(&cx, &mut token_alts, ident(&data.name), rules));
+    } else if let Some(Rule::Token(_)) = rules.first() {
+      // still needed for which tokens are directly inside nodes.
+      token_alts.insert(ident(&data.name));
+    }
   }
   // it would be nicer if we could just mutate token_alts on the cx but we have
   // an active shared borrow to iterate over the grammar. so we use a kludge.
@@ -91,16 +176,44 @@ where
       Rule::Seq(rules) => rules.as_slice(),
       rule => std::slice::from_ref(rule),
     };
+    if cx.is_transparent(&data.name) {
+      continue;
+    }
     let name = ident(&data.name);
-    syntax_kinds.push(name.clone());
-    types.push(seq::get(&cx, name, rules));
+    let desc = desc::get(&cx.grammar, &cx.tokens, node);
+    grammar_desc_arms.push(quote! { Self::#name => #desc });
+    let mut contained = FxHashSet::default();
+    contain::get(&cx, &data.rule, &mut contained);
+    child_tokens.push((name.clone(), contained));
+    node_kinds.push(name.clone());
+    if cx.has_type(&data.name) {
+      types.push(seq::get(&cx, &mut overridden, name, rules));
+    }
+  }
+  for key in cx.options.accessor_overrides.keys() {
+    let node = key.split('.').next().unwrap_or_default();
+    assert!(
+      overridden.contains(key) || !cx.has_type(node),
+      "no accessor to override: {}",
+      key
+    );
+  }
+  if let Some(pe) = &cx.options.parse_entry {
+    types.extend(entry::get(&cx, pe));
+  }
+  let report = report::get(&cx.grammar, &cx.tokens, cx.options.root.as_deref());
+  if let Some(path) = &cx.options.grammar_doc {
+    let lang = cx.lang.to_string();
+    std::fs::write(path, doc::get(&lang, &cx.grammar, &cx.tokens))?;
   }
   let Cx {
     grammar,
     tokens,
     lang,
+    options,
     ..
   } = cx;
+  let vis = util::vis(&options);
   let keywords = {
     let mut xs: Vec<_> = tokens
       .keywords
@@ -128,11 +241,27 @@ where
     let bs = Literal::byte_string(name.as_bytes());
     quote! { (#bs, Self::#kind) }
   });
+  let punctuation_arms = punctuation.iter().map(|(name, kind)| {
+    let bs = Literal::byte_string(name.as_bytes());
+    quote! { #bs => Self::#kind }
+  });
+  let (max_punctuation_len, punctuation_by_len) = util::by_len(&punctuation);
+  let (max_keyword_len, keywords_by_len) = util::by_len(&keywords);
   let special = {
     let mut xs: Vec<_> = tokens.special.into_iter().map(|x| x.1).collect();
     xs.sort_unstable();
     xs
   };
+  // tokens which share a kind are adjacent, since they are sorted by name.
+  let special_kinds = {
+    let mut xs: Vec<&token::Special> = special.iter().collect();
+    xs.dedup_by(|a, b| a.name == b.name);
+    xs
+  };
+  let sharing_kind = |sp: &token::Special| {
+    let name = sp.name.clone();
+    special.iter().filter(move |x| x.name == name)
+  };
   let desc_arms = punctuation
     .iter()
     .chain(keywords.iter())
@@ -140,34 +269,287 @@ where
       let name = format!("`{}`", name);
       quote! { Self::#kind => #name }
     })
-    .chain(special.iter().map(|&(ref name, desc)| {
-      let kind = util::ident(name);
+    .chain(special_kinds.iter().map(|sp| {
+      let kind = util::ident(&sp.name);
+      let mut descs: Vec<_> = sharing_kind(sp).map(|x| x.desc).collect();
+      descs.dedup();
+      let desc = descs.join(" or ");
       quote! { Self::#kind => #desc }
     }));
+  let alias_preds: FxHashMap<&str, TokenStream> = tokens
+    .aliases
+    .iter()
+    .map(|(&tok, pred)| (grammar[tok].name.as_str(), util::path(pred)))
+    .collect();
+  let grammar_token_arms = punctuation
+    .iter()
+    .chain(keywords.iter())
+    .map(|&(name, ref kind)| quote! { Self::#kind => #name })
+    .chain(special.iter().map(|sp| {
+      let kind = util::ident(&sp.name);
+      let text = sp.text.as_str();
+      match alias_preds.get(text) {
+        None => quote! { Self::#kind => #text },
+        Some(pred) => quote! { Self::#kind if #pred(text) => #text },
+      }
+    }));
+  let canonical_text_arms = punctuation
+    .iter()
+    .chain(keywords.iter())
+    .map(|&(name, ref kind)| quote! { Self::#kind => #name });
+  let example_text_arms = special_kinds.iter().filter_map(|&sp| {
+    let kind = util::ident(&sp.name);
+    let example = sharing_kind(sp).find_map(|x| x.example)?;
+    Some(quote! { Self::#kind => #example })
+  });
   let self_trivia = trivia.iter().map(|id| {
     quote! { Self::#id }
   });
-  let new_syntax_kinds = keywords
-    .iter()
-    .cloned()
-    .chain(punctuation.iter().cloned())
-    .map(|x| x.1)
-    .chain(special.iter().map(|&(ref name, _)| util::ident(name)));
-  syntax_kinds.extend(new_syntax_kinds);
+  // the kinds are grouped: first trivia, special tokens, keywords, and
+  // punctuation (all tokens), then nodes.
+  let mut syntax_kinds = trivia.clone();
+  syntax_kinds.extend(special_kinds.iter().map(|sp| util::ident(&sp.name)));
+  syntax_kinds.extend(keywords.iter().map(|x| x.1.clone()));
+  syntax_kinds.extend(punctuation.iter().map(|x| x.1.clone()));
+  let num_tokens = syntax_kinds.len();
+  assert!(num_tokens != 0, "no tokens");
+  assert!(!node_kinds.is_empty(), "no nodes");
+  syntax_kinds.extend(node_kinds);
+  // each trivia kind is its own variant, so it must not share a name.
+  let (trivia_kinds, other_kinds) = syntax_kinds.split_at(trivia.len());
+  let mut bad_trivia = String::new();
+  for (idx, name) in trivia_kinds.iter().enumerate() {
+    let msg = if trivia_kinds[..idx].contains(name) {
+      "duplicate trivia"
+    } else if other_kinds.contains(name) {
+      "trivia is also a token or node"
+    } else {
+      continue;
+    };
+    writeln!(bad_trivia, "{}: {}", msg, name).unwrap();
+  }
+  assert!(bad_trivia.is_empty(), "{}", bad_trivia);
+  let write_kind = !matches!(options.output, Output::Ast(_));
+  let write_ast = options.output != Output::Kind;
+  let (stable_id_fns, stable_kind_impl) =
+    match options.stable_ids.as_ref().filter(|_| write_kind) {
+      None => (quote! {}, quote! {}),
+      Some(path) => {
+        let ids = util::stable_ids(path, &syntax_kinds)?;
+        let to_arms = syntax_kinds.iter().zip(ids.iter()).map(|(kind, id)| {
+          quote! { Self::#kind => #id }
+        });
+        let from_arms =
+          syntax_kinds.iter().zip(ids.iter()).map(|(kind, id)| {
+            quote! { #id => Self::#kind }
+          });
+        let fns = quote! {
+          /// Returns the stable ID of this kind, which does not change across
+          /// re-generations.
+          #vis fn to_stable_u16(&self) -> u16 {
+            match *self {
+              #(#to_arms ,)*
+            }
+          }
+
+          /// Returns the kind with this stable ID, if any.
+          #vis fn from_stable_u16(id: u16) -> Option<Self> {
+            let ret = match id {
+              #(#from_arms ,)*
+              _ => return None,
+            };
+            Some(ret)
+          }
+        };
+        let kind_impl = match options.tree_lib {
+          TreeLib::Rowan => quote! {
+            impl ast_ptr::StableKind for SyntaxKind {
+              fn to_stable_u16(&self) -> u16 {
+                SyntaxKind::to_stable_u16(self)
+              }
+
+              fn from_stable_u16(id: u16) -> Option<Self> {
+                SyntaxKind::from_stable_u16(id)
+              }
+            }
+          },
+          TreeLib::Cstree => quote! {},
+        };
+        (fns, kind_impl)
+      }
+    };
+  let tree_sitter_fns = if options.tree_sitter_ids.is_empty() || !write_kind {
+    quote! {}
+  } else {
+    for name in options.tree_sitter_ids.keys() {
+      assert!(
+        syntax_kinds.iter().any(|kind| kind == name),
+        "{} is not a kind",
+        name
+      );
+    }
+    let mut ids: Vec<_> = syntax_kinds
+      .iter()
+      .filter_map(|kind| {
+        let id = *options.tree_sitter_ids.get(&kind.to_string())?;
+        Some((kind, id))
+      })
+      .collect();
+    let mut to_arms: Vec<_> = ids
+      .iter()
+      .map(|(kind, id)| quote! { Self::#kind => #id })
+      .collect();
+    if ids.len() < syntax_kinds.len() {
+      to_arms.push(quote! { _ => return None });
+    }
+    ids.sort_unstable_by_key(|&(_, id)| id);
+    for w in ids.windows(2) {
+      assert!(
+        w[0].1 != w[1].1,
+        "{} and {} have the same tree-sitter ID",
+        w[0].0,
+        w[1].0
+      );
+    }
+    let from_arms = ids.iter().map(|(kind, id)| {
+      quote! { #id => Self::#kind }
+    });
+    quote! {
+      /// Returns the ID of the tree-sitter symbol for this kind, if any.
+      #vis fn to_tree_sitter(&self) -> Option<u16> {
+        let ret = match *self {
+          #(#to_arms ,)*
+        };
+        Some(ret)
+      }
+
+      /// Returns the kind for the tree-sitter symbol with this ID, if any.
+      #vis fn from_tree_sitter(id: u16) -> Option<Self> {
+        let ret = match id {
+          #(#from_arms ,)*
+          _ => return None,
+        };
+        Some(ret)
+      }
+    }
+  };
+  // sorted by kind, for binary search.
+  let child_tokens_arms =
+    child_tokens.iter().filter_map(|(name, contained)| {
+      if contained.is_empty() {
+        return None;
+      }
+      let toks = syntax_kinds[..num_tokens]
+        .iter()
+        .filter(|kind| contained.contains(&kind.to_string()));
+      Some(quote! { Self::#name => &[#(Self::#toks ,)*] })
+    });
+  let num_kinds = syntax_kinds.len();
+  let grammar_hash = util::grammar_hash(&grammar, &syntax_kinds);
+  let last_token_kind = &syntax_kinds[num_tokens - 1];
+  let first_node_kind = &syntax_kinds[num_tokens];
+  if let Some(path) = &options.grammar_json {
+    let api = json::Api {
+      lang: &lang.to_string(),
+      grammar: &grammar,
+      transparent: &options.transparent,
+      kinds: &syntax_kinds,
+      trivia: &trivia,
+      punctuation: &punctuation,
+      keywords: &keywords,
+      special: &special,
+    };
+    std::fs::write(path, json::get(&api))?;
+  }
+  if let Some(path) = options.c_header.as_ref().filter(|_| write_kind) {
+    std::fs::write(path, header::get(&lang.to_string(), &syntax_kinds))?;
+  }
+  let non_exhaustive = util::non_exhaustive(&options);
+  let tree = util::tree_lib(&options);
   let last_syntax_kind = syntax_kinds.last().unwrap();
+  let name_arms = syntax_kinds.iter().map(|kind| {
+    let name = kind.to_string();
+    quote! { Self::#kind => #name }
+  });
+  let from_name_arms = syntax_kinds.iter().map(|kind| {
+    let name = kind.to_string();
+    quote! { #name => Self::#kind }
+  });
+  // unused `pub(crate)` items are warned about, and some lints only apply to
+  // items which are not `pub`.
+  let allow_unused = match options.visibility {
+    Visibility::Pub => quote! {},
+    Visibility::Crate => quote! {
+      #![allow(dead_code)]
+      #![allow(clippy::enum_variant_names)]
+      #![allow(clippy::wrong_self_convention)]
+    },
+  };
   let kind = quote! {
+    #allow_unused
+
     #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
     #[repr(u16)]
-    pub enum SyntaxKind {
+    #non_exhaustive
+    #vis enum SyntaxKind {
       #(#syntax_kinds ,)*
     }
 
     impl SyntaxKind {
-      pub const PUNCTUATION: [(&'static [u8], Self); #punctuation_len] = [
+      /// The number of kinds.
+      #vis const N: usize = #num_kinds;
+
+      /// A hash of the grammar and the kinds, which changes whenever either
+      /// does, for e.g. the keys of on-disk caches of syntax trees.
+      #vis const GRAMMAR_HASH: u64 = #grammar_hash;
+
+      /// All the kinds, in order.
+      #vis const ALL: [Self; Self::N] = [
+        #(Self::#syntax_kinds ,)*
+      ];
+
+      /// The last kind for a token. All the kinds for tokens are at most this.
+      #vis const LAST_TOKEN_KIND: Self = Self::#last_token_kind;
+
+      /// The first kind for a node. All the kinds for nodes are at least this.
+      #vis const FIRST_NODE_KIND: Self = Self::#first_node_kind;
+
+      #vis const fn is_token(&self) -> bool {
+        *self as u16 <= Self::LAST_TOKEN_KIND as u16
+      }
+
+      #vis const fn is_node(&self) -> bool {
+        *self as u16 >= Self::FIRST_NODE_KIND as u16
+      }
+
+      /// All the punctuation, longest first.
+      #vis const PUNCTUATION: [(&'static [u8], Self); #punctuation_len] = [
         #(#punctuation_elements ,)*
       ];
 
-      pub fn keyword(bs: &[u8]) -> Option<Self> {
+      /// The length of the longest punctuation.
+      #vis const MAX_PUNCTUATION_LEN: usize = #max_punctuation_len;
+
+      /// The punctuation of each length, at that index, sorted.
+      #vis const PUNCTUATION_BY_LEN: [
+        &'static [(&'static [u8], Self)];
+        Self::MAX_PUNCTUATION_LEN + 1
+      ] = [
+        #(#punctuation_by_len ,)*
+      ];
+
+      /// The length of the longest keyword.
+      #vis const MAX_KEYWORD_LEN: usize = #max_keyword_len;
+
+      /// The keywords of each length, at that index, sorted.
+      #vis const KEYWORDS_BY_LEN: [
+        &'static [(&'static [u8], Self)];
+        Self::MAX_KEYWORD_LEN + 1
+      ] = [
+        #(#keywords_by_len ,)*
+      ];
+
+      #vis const fn keyword(bs: &[u8]) -> Option<Self> {
         let ret = match bs {
           #(#keyword_arms ,)*
           _ => return None,
@@ -175,13 +557,93 @@ where
         Some(ret)
       }
 
-      pub fn token_desc(&self) -> Option<&'static str> {
+      /// Returns the kind of the punctuation which is exactly `bs`.
+      #vis const fn punctuation(bs: &[u8]) -> Option<Self> {
+        let ret = match bs {
+          #(#punctuation_arms ,)*
+          _ => return None,
+        };
+        Some(ret)
+      }
+
+      #vis const fn token_desc(&self) -> Option<&'static str> {
         let ret = match *self {
           #(#desc_arms ,)*
           _ => return None,
         };
         Some(ret)
       }
+
+      #vis const fn canonical_text(&self) -> Option<&'static str> {
+        let ret = match *self {
+          #(#canonical_text_arms ,)*
+          _ => return None,
+        };
+        Some(ret)
+      }
+
+      #vis const fn example_text(&self) -> Option<&'static str> {
+        let ret = match *self {
+          #(#example_text_arms ,)*
+          _ => return self.canonical_text(),
+        };
+        Some(ret)
+      }
+
+      /// Returns the text in the grammar of the token with this kind and the
+      /// `text`. If several tokens in the grammar share this kind, the `text`
+      /// tells them apart.
+      #[allow(unused_variables)]
+      #vis fn grammar_token(&self, text: &str) -> Option<&'static str> {
+        let ret = match *self {
+          #(#grammar_token_arms ,)*
+          _ => return None,
+        };
+        Some(ret)
+      }
+
+      /// Returns a short description of the node, generated from its rule in
+      /// the grammar, for messages like "expected X while parsing Y".
+      #vis fn grammar_desc(&self) -> Option<&'static str> {
+        let ret = match *self {
+          #(#grammar_desc_arms ,)*
+          _ => return None,
+        };
+        Some(ret)
+      }
+
+      /// Returns the kinds of the tokens which may appear directly inside a
+      /// node of this kind, besides trivia, sorted. Tokens in child nodes are
+      /// not directly inside.
+      #vis fn child_tokens(&self) -> &'static [Self] {
+        match *self {
+          #(#child_tokens_arms ,)*
+          _ => &[],
+        }
+      }
+
+      /// Returns whether a token of the `token` kind may appear directly
+      /// inside a node of this kind. Trivia is not considered.
+      #vis fn can_contain(&self, token: Self) -> bool {
+        self.child_tokens().binary_search(&token).is_ok()
+      }
+
+      #vis fn name(&self) -> &'static str {
+        match *self {
+          #(#name_arms ,)*
+        }
+      }
+
+      #vis fn from_name(s: &str) -> Option<Self> {
+        let ret = match s {
+          #(#from_name_arms ,)*
+          _ => return None,
+        };
+        Some(ret)
+      }
+
+      #stable_id_fns
+      #tree_sitter_fns
     }
 
     impl token::Triviable for SyntaxKind {
@@ -190,76 +652,459 @@ where
       }
     }
 
-    impl From<SyntaxKind> for rowan::SyntaxKind {
+    #stable_kind_impl
+
+    /// A map from every `SyntaxKind` to a `T`.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #vis struct KindMap<T>([T; SyntaxKind::N]);
+
+    impl<T> KindMap<T> {
+      /// Returns a map with `f(kind)` for every `kind`, called in order.
+      #vis fn from_fn<F>(f: F) -> Self
+      where
+        F: FnMut(SyntaxKind) -> T,
+      {
+        let xs: Vec<T> = SyntaxKind::ALL.iter().copied().map(f).collect();
+        match std::convert::TryFrom::try_from(xs) {
+          Ok(xs) => Self(xs),
+          Err(_) => unreachable!("not one value for each kind"),
+        }
+      }
+
+      /// Returns an iterator over every kind and its value, in order.
+      #vis fn iter(&self) -> impl Iterator<Item = (SyntaxKind, &T)> {
+        SyntaxKind::ALL.iter().copied().zip(self.0.iter())
+      }
+    }
+
+    impl<T> std::ops::Index<SyntaxKind> for KindMap<T> {
+      type Output = T;
+
+      fn index(&self, kind: SyntaxKind) -> &T {
+        &self.0[kind as usize]
+      }
+    }
+
+    impl<T> std::ops::IndexMut<SyntaxKind> for KindMap<T> {
+      fn index_mut(&mut self, kind: SyntaxKind) -> &mut T {
+        &mut self.0[kind as usize]
+      }
+    }
+
+    impl From<SyntaxKind> for #tree::SyntaxKind {
       fn from(kind: SyntaxKind) -> Self {
         Self(kind as u16)
       }
     }
 
     #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-    pub enum #lang {}
+    #vis enum #lang {}
 
-    impl rowan::Language for #lang {
+    impl #tree::Language for #lang {
       type Kind = SyntaxKind;
 
-      fn kind_from_raw(raw: rowan::SyntaxKind) -> Self::Kind {
+      fn kind_from_raw(raw: #tree::SyntaxKind) -> Self::Kind {
         assert!(raw.0 <= SyntaxKind::#last_syntax_kind as u16);
         unsafe { std::mem::transmute::<u16, SyntaxKind>(raw.0) }
       }
 
-      fn kind_to_raw(kind: Self::Kind) -> rowan::SyntaxKind {
+      fn kind_to_raw(kind: Self::Kind) -> #tree::SyntaxKind {
         kind.into()
       }
     }
 
-    pub type SyntaxNode = rowan::SyntaxNode<#lang>;
-    pub type SyntaxToken = rowan::SyntaxToken<#lang>;
-    pub type SyntaxElement = rowan::SyntaxElement<#lang>;
+    #vis type SyntaxNode = #tree::SyntaxNode<#lang>;
+    #vis type SyntaxToken = #tree::SyntaxToken<#lang>;
+    #vis type SyntaxElement = #tree::SyntaxElement<#lang>;
   };
-  let ast = quote! {
-    #![allow(clippy::iter_nth_zero)]
+  // the language is only used for `HasLanguage`.
+  let lang_import = match options.tree_lib {
+    TreeLib::Rowan => quote! { #lang, },
+    TreeLib::Cstree => quote! {},
+  };
+  let helpers = match options.tree_lib {
+    TreeLib::Rowan => quote! {
+      use ast_ptr::{CanCast, HasLanguage};
 
-    use crate::kind::{SyntaxKind as SK, SyntaxNode, SyntaxToken, #lang};
-    use ast_ptr::HasLanguage;
-    use std::convert::{TryFrom, TryInto};
+      #[allow(unused)]
+      fn tokens<P>(parent: &P, kind: SK) -> impl Iterator<Item = SyntaxToken>
+      where
+        P: AsRef<SyntaxNode>,
+      {
+        parent
+          .as_ref()
+          .children_with_tokens()
+          .filter_map(rowan::NodeOrToken::into_token)
+          .filter(move |tok| tok.kind() == kind)
+      }
 
-    #[allow(unused)]
-    fn tokens<P>(parent: &P, kind: SK) -> impl Iterator<Item = SyntaxToken>
-    where
-      P: AsRef<SyntaxNode>,
-    {
-      parent
-        .as_ref()
-        .children_with_tokens()
-        .filter_map(rowan::NodeOrToken::into_token)
-        .filter(move |tok| tok.kind() == kind)
-    }
+      #[allow(unused)]
+      fn token_children<P, C>(parent: &P) -> impl Iterator<Item = C>
+      where
+        P: AsRef<SyntaxNode>,
+        SyntaxToken: TryInto<C>,
+      {
+        parent
+          .as_ref()
+          .children_with_tokens()
+          .filter_map(rowan::NodeOrToken::into_token)
+          .filter_map(|x| x.try_into().ok())
+      }
 
-    #[allow(unused)]
-    fn token_children<P, C>(parent: &P) -> impl Iterator<Item = C>
-    where
-      P: AsRef<SyntaxNode>,
-      SyntaxToken: TryInto<C>,
-    {
-      parent
-        .as_ref()
-        .children_with_tokens()
-        .filter_map(rowan::NodeOrToken::into_token)
-        .filter_map(|x| x.try_into().ok())
-    }
+      #[allow(unused)]
+      fn node_children<P, C>(parent: &P) -> impl Iterator<Item = C>
+      where
+        P: AsRef<SyntaxNode>,
+        SyntaxNode: TryInto<C>,
+      {
+        parent.as_ref().children().filter_map(|x| x.try_into().ok())
+      }
 
-    #[allow(unused)]
-    fn node_children<P, C>(parent: &P) -> impl Iterator<Item = C>
-    where
-      P: AsRef<SyntaxNode>,
-      SyntaxNode: TryInto<C>,
-    {
-      parent.as_ref().children().filter_map(|x| x.try_into().ok())
-    }
+      /// Returns the `n`th child of the `parent` for which `f` returns true,
+      /// given the kind of a node or the kind and text of a token.
+      ///
+      /// The children are checked in the green tree, so only the one returned
+      /// is constructed, unless it is empty.
+      #[allow(unused)]
+      fn nth_child<P, F>(parent: &P, n: usize, f: F) -> Option<SyntaxElement>
+      where
+        P: AsRef<SyntaxNode>,
+        F: Fn(rowan::NodeOrToken<SK, (SK, &str)>) -> bool,
+      {
+        let parent = parent.as_ref();
+        let mut offset = parent.text_range().start();
+        let mut n = n;
+        for (idx, child) in parent.green().children().enumerate() {
+          let len = child.text_len();
+          let kind = <#lang as rowan::Language>::kind_from_raw(child.kind());
+          let arg = match child {
+            rowan::NodeOrToken::Node(_) => rowan::NodeOrToken::Node(kind),
+            rowan::NodeOrToken::Token(tok) => {
+              rowan::NodeOrToken::Token((kind, tok.text()))
+            }
+          };
+          if !f(arg) {
+            offset += len;
+            continue;
+          }
+          if n != 0 {
+            n -= 1;
+            offset += len;
+            continue;
+          }
+          // empty children may share a range with others.
+          return if len == 0.into() {
+            parent.children_with_tokens().nth(idx)
+          } else {
+            let range = rowan::TextRange::at(offset, len);
+            parent.child_or_token_at_range(range)
+          };
+        }
+        None
+      }
+
+      #[allow(unused)]
+      fn nth_node_child<P, C>(parent: &P, n: usize) -> Option<C>
+      where
+        P: AsRef<SyntaxNode>,
+        C: CanCast<Language = #lang> + TryFrom<SyntaxNode>,
+      {
+        let elem = nth_child(parent, n, |x| match x {
+          rowan::NodeOrToken::Node(kind) => C::can_cast(kind),
+          rowan::NodeOrToken::Token(_) => false,
+        });
+        elem?.into_node()?.try_into().ok()
+      }
+
+      #[allow(unused)]
+      fn nth_token_child<P, C>(parent: &P, n: usize) -> Option<C>
+      where
+        P: AsRef<SyntaxNode>,
+        C: CanCast<Language = #lang> + TryFrom<SyntaxToken>,
+      {
+        let elem = nth_child(parent, n, |x| match x {
+          rowan::NodeOrToken::Node(_) => false,
+          rowan::NodeOrToken::Token((kind, _)) => C::can_cast(kind),
+        });
+        elem?.into_token()?.try_into().ok()
+      }
+
+      #[allow(unused)]
+      fn nth_token<P>(
+        parent: &P,
+        kind: SK,
+        n: usize,
+        pred: fn(&str) -> bool,
+      ) -> Option<SyntaxToken>
+      where
+        P: AsRef<SyntaxNode>,
+      {
+        let elem = nth_child(parent, n, |x| match x {
+          rowan::NodeOrToken::Node(_) => false,
+          rowan::NodeOrToken::Token((k, text)) => k == kind && pred(text),
+        });
+        elem?.into_token()
+      }
+
+      /// An error from checking the children of a new node against the
+      /// grammar.
+      #[derive(Debug, Clone, PartialEq, Eq)]
+      #vis struct BuildError {
+        /// The kind of the new node.
+        #vis node: SK,
+        /// The index of the first child which did not fit, or the number of
+        /// children if the children ended too soon.
+        #vis idx: usize,
+        /// The kinds which could have been at `idx`. Empty if the children
+        /// should have ended there.
+        #vis expected: Vec<SK>,
+      }
+
+      impl std::fmt::Display for BuildError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+          write!(f, "bad child {} for {:?}: ", self.idx, self.node)?;
+          if self.expected.is_empty() {
+            f.write_str("expected no more children")
+          } else {
+            write!(f, "expected one of {:?}", self.expected)
+          }
+        }
+      }
+
+      impl std::error::Error for BuildError {}
+
+      /// Returns a hash of the kinds of the nodes and the kinds and text of the
+      /// non-trivia tokens in `node`, so two nodes have the same hash if they
+      /// differ only in trivia.
+      ///
+      /// The hash is the same across runs and platforms, but changes when the
+      /// discriminants of the `SyntaxKind` do.
+      #vis fn fingerprint(node: &SyntaxNode) -> u64 {
+        // FNV-1a.
+        fn write(hash: &mut u64, bytes: &[u8]) {
+          for &b in bytes {
+            *hash ^= u64::from(b);
+            *hash = hash.wrapping_mul(0x0100_0000_01b3);
+          }
+        }
+        let mut ret: u64 = 0xcbf2_9ce4_8422_2325;
+        for event in node.preorder_with_tokens() {
+          match event {
+            rowan::WalkEvent::Enter(rowan::NodeOrToken::Node(node)) => {
+              write(&mut ret, &[0]);
+              write(&mut ret, &(node.kind() as u16).to_le_bytes());
+            }
+            rowan::WalkEvent::Leave(rowan::NodeOrToken::Node(_)) => {
+              write(&mut ret, &[1]);
+            }
+            rowan::WalkEvent::Enter(rowan::NodeOrToken::Token(tok)) => {
+              if token::Triviable::is_trivia(&tok.kind()) {
+                continue;
+              }
+              let text = tok.text();
+              write(&mut ret, &[2]);
+              write(&mut ret, &(tok.kind() as u16).to_le_bytes());
+              write(&mut ret, &(text.len() as u64).to_le_bytes());
+              write(&mut ret, text.as_bytes());
+            }
+            rowan::WalkEvent::Leave(rowan::NodeOrToken::Token(_)) => {}
+          }
+        }
+        ret
+      }
+
+      #[allow(unused)]
+      #[derive(Clone, Copy)]
+      enum Card {
+        One,
+        Opt,
+        Many,
+      }
+
+      /// A child in the rule for a node.
+      struct ChildRule {
+        kinds: &'static [SK],
+        /// whether the text of a token fits, for tokens which share a kind.
+        pred: fn(&str) -> bool,
+        card: Card,
+      }
+
+      /// Checks the `children` of a new node with kind `node` against the
+      /// `rules`, skipping trivia. This is greedy, so a child which could fit
+      /// either of two adjacent rules fits the first.
+      #[allow(unused)]
+      fn check_children(
+        node: SK,
+        rules: &[ChildRule],
+        children: &[rowan::NodeOrToken<rowan::GreenNode, rowan::GreenToken>],
+      ) -> Result<(), BuildError> {
+        let mut iter = children
+          .iter()
+          .map(|child| {
+            let kind = <#lang as rowan::Language>::kind_from_raw(child.kind());
+            let text = match child {
+              rowan::NodeOrToken::Node(_) => None,
+              rowan::NodeOrToken::Token(tok) => Some(tok.text()),
+            };
+            (kind, text)
+          })
+          .enumerate()
+          .filter(|(_, (kind, _))| !token::Triviable::is_trivia(kind))
+          .peekable();
+        // the kinds of the optional children skipped since the last child.
+        let mut expected = Vec::new();
+        for rule in rules {
+          let mut any = false;
+          while let Some(&(_, (kind, text))) = iter.peek() {
+            let fits = rule.kinds.contains(&kind)
+              && match text {
+                None => true,
+                Some(text) => (rule.pred)(text),
+              };
+            if !fits {
+              break;
+            }
+            iter.next();
+            any = true;
+            expected.clear();
+            if let Card::One | Card::Opt = rule.card {
+              break;
+            }
+          }
+          if any {
+            continue;
+          }
+          expected.extend_from_slice(rule.kinds);
+          if let Card::One = rule.card {
+            let idx = iter.peek().map_or(children.len(), |&(idx, _)| idx);
+            return Err(BuildError { node, idx, expected });
+          }
+        }
+        match iter.next() {
+          None => Ok(()),
+          Some((idx, _)) => Err(BuildError { node, idx, expected }),
+        }
+      }
+    },
+    // the children are iterated by reference, so we cast by reference.
+    TreeLib::Cstree => quote! {
+      #[allow(unused)]
+      fn tokens<P>(
+        parent: &P,
+        kind: SK,
+      ) -> impl Iterator<Item = SyntaxToken> + '_
+      where
+        P: AsRef<SyntaxNode>,
+      {
+        parent
+          .as_ref()
+          .children_with_tokens()
+          .filter_map(cstree::NodeOrToken::into_token)
+          .filter(move |tok| tok.kind() == kind)
+          .cloned()
+      }
+
+      #[allow(unused)]
+      fn token_children<P, C>(parent: &P) -> impl Iterator<Item = C> + '_
+      where
+        P: AsRef<SyntaxNode>,
+        for<'a> &'a SyntaxToken: TryInto<C>,
+      {
+        parent
+          .as_ref()
+          .children_with_tokens()
+          .filter_map(cstree::NodeOrToken::into_token)
+          .filter_map(|x| x.try_into().ok())
+      }
+
+      #[allow(unused)]
+      fn node_children<P, C>(parent: &P) -> impl Iterator<Item = C> + '_
+      where
+        P: AsRef<SyntaxNode>,
+        for<'a> &'a SyntaxNode: TryInto<C>,
+      {
+        parent.as_ref().children().filter_map(|x| x.try_into().ok())
+      }
+    },
+  };
+  let kind_module = match &options.output {
+    Output::All | Output::Kind => quote! { crate::kind },
+    Output::Ast(path) => util::path(path),
+  };
+  // only cstree uses `nth` with constant indices.
+  let allow_nth_zero = match options.tree_lib {
+    TreeLib::Rowan => quote! {},
+    TreeLib::Cstree => quote! { #![allow(clippy::iter_nth_zero)] },
+  };
+  let ast = quote! {
+    #allow_nth_zero
+    #allow_unused
+
+    use #kind_module::{
+      SyntaxElement, SyntaxKind as SK, SyntaxNode, SyntaxToken, #lang_import
+    };
+    use std::convert::{TryFrom, TryInto};
+
+    #helpers
 
     #(#types)*
   };
-  util::write_rust_file("src/kind.rs", kind.to_string().as_ref())?;
-  util::write_rust_file("src/ast.rs", ast.to_string().as_ref())?;
-  Ok(())
+  if write_ast && options.ast_ext && !std::path::Path::new(AST_EXT).exists() {
+    let impls = grammar.iter().filter_map(|node| {
+      let name = grammar[node].name.as_str();
+      if options.transparent.iter().any(|x| x == name)
+        || !options.ast_nodes.contains(name)
+      {
+        return None;
+      }
+      let name = ident(name);
+      Some(quote! { impl #name {} })
+    });
+    let ast_ext = quote! {
+      use crate::ast::*;
+
+      #(#impls)*
+    };
+    let contents = format!(
+      "// Hand-written helper methods for the AST. This file was generated, but it\n\
+       // will not be overwritten by re-generation, so it may be edited.\n\n{}",
+      ast_ext
+    );
+    util::write_rust_file(AST_EXT, &contents)?;
+  }
+  if options.facade {
+    let ptr = match options.tree_lib {
+      TreeLib::Rowan => quote! { #vis use ast_ptr::AstPtr; },
+      TreeLib::Cstree => quote! {},
+    };
+    let allow_unused = match options.visibility {
+      Visibility::Pub => quote! {},
+      Visibility::Crate => quote! { #![allow(unused_imports)] },
+    };
+    let ast = if write_ast {
+      quote! { #vis use crate::ast; }
+    } else {
+      quote! {}
+    };
+    let facade = quote! {
+      #allow_unused
+      #ast
+      #vis use #kind_module::{
+        KindMap, SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken, #lang
+      };
+      #ptr
+    };
+    let contents = format!(
+      "//! The public surface of the generated syntax code.\n\n{}",
+      facade
+    );
+    util::write_rust_file(FACADE, &contents)?;
+  }
+  if write_kind {
+    util::write_rust_file("src/kind.rs", kind.to_string().as_ref())?;
+  }
+  if write_ast {
+    util::write_rust_file("src/ast.rs", ast.to_string().as_ref())?;
+  }
+  Ok(report)
 }
@@ -0,0 +1,106 @@
+use rustc_hash::FxHashMap;
+use std::fmt;
+use ungrammar::Grammar;
+
+/// An error when merging grammar fragments.
+#[derive(Debug)]
+pub enum MergeError {
+  /// A node was defined in more than one fragment.
+  Redefined {
+    /// The name of the node.
+    node: String,
+    /// The name of the fragment with the first definition.
+    first: String,
+    /// The name of the fragment with the second definition.
+    second: String,
+  },
+  /// The merged grammar could not be parsed.
+  Parse(ungrammar::Error),
+}
+
+impl fmt::Display for MergeError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      MergeError::Redefined {
+        node,
+        first,
+        second,
+      } => write!(f, "{} defined in both {} and {}", node, first, second),
+      MergeError::Parse(e) => write!(f, "couldn't parse merged grammar: {}", e),
+    }
+  }
+}
+
+impl std::error::Error for MergeError {}
+
+/// Merges ungrammar fragments into one grammar.
+///
+/// Each fragment is a pair `(name, text)`, where `name` identifies the
+/// fragment in errors, like a file name, and `text` is ungrammar source. The
+/// fragments may use nodes defined in each other, but no node may be defined in
+/// more than one fragment.
+pub fn merge_grammars(
+  fragments: &[(&str, &str)],
+) -> Result<Grammar, MergeError> {
+  let mut defined = FxHashMap::<&str, &str>::default();
+  let mut text = String::new();
+  for &(name, fragment) in fragments {
+    for node in definitions(fragment) {
+      if let Some(&first) = defined.get(node) {
+        return Err(MergeError::Redefined {
+          node: node.to_owned(),
+          first: first.to_owned(),
+          second: name.to_owned(),
+        });
+      }
+      defined.insert(node, name);
+    }
+    text.push_str(fragment);
+    text.push('\n');
+  }
+  text.parse().map_err(MergeError::Parse)
+}
+
+/// Returns the names of the nodes defined in the ungrammar source.
+fn definitions(s: &str) -> Vec<&str> {
+  let bs = s.as_bytes();
+  let mut ret = Vec::new();
+  let mut idx = 0;
+  while let Some(&b) = bs.get(idx) {
+    if b == b'\'' {
+      // a token. skip to the closing quote.
+      idx += 1;
+      while let Some(&b) = bs.get(idx) {
+        idx += 1;
+        match b {
+          b'\\' => idx += 1,
+          b'\'' => break,
+          _ => {}
+        }
+      }
+    } else if bs[idx..].starts_with(b"//") {
+      while bs.get(idx).map_or(false, |&b| b != b'\n') {
+        idx += 1;
+      }
+    } else if is_ident(b) {
+      let start = idx;
+      while bs.get(idx).copied().map_or(false, is_ident) {
+        idx += 1;
+      }
+      let end = idx;
+      while bs.get(idx).map_or(false, u8::is_ascii_whitespace) {
+        idx += 1;
+      }
+      if bs.get(idx) == Some(&b'=') {
+        ret.push(&s[start..end]);
+      }
+    } else {
+      idx += 1;
+    }
+  }
+  ret
+}
+
+fn is_ident(b: u8) -> bool {
+  b.is_ascii_alphanumeric() || b == b'_'
+}
@@ -0,0 +1,342 @@
+use rustc_hash::FxHashMap;
+
+/// Options for generation.
+///
+/// The `Default` options are the most conservative.
+#[derive(Debug, Default)]
+pub struct Options {
+  /// Whether to mark the generated `SyntaxKind` and the generated enums in
+  /// `src/ast.rs` as `#[non_exhaustive]`.
+  ///
+  /// This means that adding a rule to the grammar won't break downstream
+  /// exhaustive matches on those enums, since downstream crates must already
+  /// have a `_` catch-all arm. It does not keep the discriminants of the
+  /// `SyntaxKind` from shifting, so downstream crates which store kinds should
+  /// use [`Self::stable_ids`] instead.
+  pub non_exhaustive: bool,
+  /// If set, also generate `SyntaxKind::to_stable_u16` and
+  /// `SyntaxKind::from_stable_u16`, using the IDs recorded in the file at this
+  /// path, like `src/kind_ids.txt`.
+  ///
+  /// The discriminants of the `SyntaxKind` shift when the grammar is edited.
+  /// Unlike them, the stable IDs never change across re-generations, so they
+  /// may be used in e.g. on-disk caches or published APIs. Each line of the
+  /// file is a `SyntaxKind` variant name and its ID. New variants get new IDs,
+  /// and the IDs of variants which no longer exist are never re-used. The file
+  /// should be checked in.
+  ///
+  /// With [`TreeLib::Rowan`], also implement `ast_ptr::StableKind` with them,
+  /// so that `ast_ptr::AstPtr`s may be persisted.
+  pub stable_ids: Option<String>,
+  /// The IDs of the symbols in a tree-sitter grammar for the same language
+  /// which correspond to `SyntaxKind` variants.
+  ///
+  /// The keys are the names of `SyntaxKind` variants, like `BinExpr`, and the
+  /// values are the IDs of the corresponding tree-sitter symbols, as in the
+  /// `ts_symbol_identifiers` enum of the tree-sitter grammar's `parser.c`. If
+  /// this is not empty, also generate `SyntaxKind::to_tree_sitter` and
+  /// `SyntaxKind::from_tree_sitter` to convert between them, so that tooling
+  /// for tree-sitter trees may also be used with the generated trees.
+  ///
+  /// Variants which are not keys have no tree-sitter ID. Generation panics if a
+  /// key is not a variant, or if two keys have the same ID.
+  pub tree_sitter_ids: FxHashMap<String, u16>,
+  /// If set, also write a Markdown reference for the grammar to this path.
+  ///
+  /// The reference has a section for each node, with its rule (linking to the
+  /// other nodes in the rule) and the nodes which use it, and tables of the
+  /// tokens.
+  pub grammar_doc: Option<String>,
+  /// If set, also write a JSON description of the generated code to this
+  /// path.
+  ///
+  /// The description is an object with the keys:
+  ///
+  /// - `lang`: the name of the language.
+  /// - `kinds`: the names of the `SyntaxKind` variants, in discriminant order.
+  /// - `trivia`: the names of the trivia kinds.
+  /// - `nodes`: the nodes, each with its `name`, whether it is `transparent`,
+  ///   and its `rule`. A rule is one of `{"node": name}`, `{"token": text}`,
+  ///   `{"label": label, "rule": rule}`, `{"opt": rule}`, `{"rep": rule}`,
+  ///   `{"seq": [rule]}`, or `{"alt": [rule]}`.
+  /// - `punctuation` and `keywords`: the `text` and `kind` of each token.
+  /// - `special`: the `text`, `kind`, `desc`, and `example` (or `null`) of
+  ///   each token.
+  pub grammar_json: Option<String>,
+  /// If set, also write a C header for the `SyntaxKind` to this path, like
+  /// `include/syntax_kinds.h`, for tooling not written in Rust which reads
+  /// serialized syntax trees.
+  ///
+  /// For a language `Lang`, the header has an `enum lang_syntax_kind` with a
+  /// constant like `LANG_BIN_EXPR` for each variant, whose value is its
+  /// discriminant, the raw kind stored in the syntax trees. It also has
+  /// `LANG_SYNTAX_KIND_COUNT` and `lang_syntax_kind_names`, the variant names
+  /// indexed by discriminant. The header is regenerated along with the
+  /// `SyntaxKind`, so the values never drift from it.
+  pub c_header: Option<String>,
+  /// Whether to also write `src/ast_ext.rs`, with an empty `impl` block for
+  /// each type in `src/ast.rs`, for hand-written helper methods.
+  ///
+  /// The file is only written if it does not already exist, so it is never
+  /// overwritten by re-generation. Since it is not re-generated, an `impl`
+  /// block must be added or removed by hand when a node is added or removed.
+  pub ast_ext: bool,
+  /// Whether to also write `src/syntax.rs`, which re-exports the public
+  /// surface of the generated code: `ast`, the `SyntaxKind` and associated
+  /// types, and `AstPtr` if using [`TreeLib::Rowan`].
+  ///
+  /// It should be declared as a public module, like `pub mod syntax;`.
+  /// Downstream crates which only use the generated code through it do not
+  /// depend on how the code is split into files, which may change.
+  pub facade: bool,
+  /// The visibility of the generated items.
+  ///
+  /// With [`Visibility::Crate`], the generated code may be embedded in a
+  /// larger crate without becoming part of its public API. The facade, if
+  /// written, then re-exports with `pub(crate) use`, so the crate may choose
+  /// what to expose itself.
+  pub visibility: Visibility,
+  /// The names of nodes which are 'transparent'.
+  ///
+  /// A transparent node has no `SyntaxKind` and no generated type. Instead,
+  /// its rule is inlined into the rules of the nodes which use it, so the
+  /// nodes which use it get accessors for the children of the transparent
+  /// node. This is useful for nodes which only exist to make the grammar more
+  /// readable.
+  ///
+  /// Transparent nodes cannot be alternatives, nor used by alternatives, nor
+  /// be labeled where they are used. Nor can they refer to themselves,
+  /// directly or through other transparent nodes, since inlining them would
+  /// never end. Generation returns an error if they do.
+  pub transparent: Vec<String>,
+  /// The name of the root node of the grammar.
+  ///
+  /// If set, the [`crate::Report`] will list the nodes which are not
+  /// reachable from the root node.
+  pub root: Option<String>,
+  /// The case of the names of the generated accessor methods.
+  pub accessor_case: AccessorCase,
+  /// How to name the generated accessor methods for repeated children.
+  pub repeated_accessor: RepeatedAccessor,
+  /// Overrides for individual generated accessor methods.
+  ///
+  /// The keys are like `Node.child`, where `Node` is the name of a node and
+  /// `child` is the `snake_case` name the accessor would have with the default
+  /// [`Self::repeated_accessor`], like `BinExpr.exprs`. Accessors of children
+  /// inlined from transparent nodes belong to the node they are inlined into.
+  ///
+  /// Generation panics if a key does not refer to a generated accessor.
+  pub accessor_overrides: FxHashMap<String, AccessorOverride>,
+  /// Predicates for the tokens which share a `SyntaxKind`.
+  ///
+  /// By default, `get_token` must return a different name for every token in
+  /// the grammar. With this, several special tokens may have the same name, so
+  /// they share one `SyntaxKind` variant, as when a lexer emits one kind for
+  /// both `'name'` and `'lifetime'`. The keys are the text of each such token
+  /// in the grammar, like `lifetime`, and the values are paths to functions
+  /// with type `fn(&str) -> bool`, like `crate::lex::is_lifetime`, which
+  /// return whether the text of a token of the shared kind is that token.
+  ///
+  /// Every token which shares a kind must have a predicate, and the predicates
+  /// for a kind should be mutually exclusive. Generated accessors for such a
+  /// token only return tokens which satisfy its predicate, and the generated
+  /// `SyntaxKind::grammar_token` uses the predicates to tell the tokens apart.
+  /// Such tokens cannot be used in alternatives.
+  ///
+  /// Requires [`TreeLib::Rowan`]. Generation panics if a key is not a special
+  /// token in the grammar.
+  pub token_aliases: FxHashMap<String, String>,
+  /// The syntax tree library the generated code uses.
+  pub tree_lib: TreeLib,
+  /// If set, generate a `parse` constructor for every node type, which parses
+  /// text as that node using the given hooks.
+  pub parse_entry: Option<ParseEntry>,
+  /// Which of the generated files to write.
+  pub output: Output,
+  /// Which nodes get a type in `src/ast.rs`.
+  ///
+  /// Every node still gets a `SyntaxKind` variant. Accessors whose type would
+  /// be that of a node without a type are not generated, and neither are the
+  /// overrides for the accessors of such a node checked. Every node used by an
+  /// alternative with a type must have a type.
+  pub ast_nodes: AstNodes,
+}
+
+/// Which of the generated files to write.
+///
+/// This allows several languages in different crates to share one
+/// `SyntaxKind`, and thus one kind of syntax tree. One crate has the shared
+/// `SyntaxKind`, generated with [`Output::Kind`] from the grammars of all the
+/// languages, merged with [`crate::merge_grammars`]. Each language crate has an
+/// AST, generated with [`Output::Ast`] from the grammar of only that language,
+/// using that `SyntaxKind`. Every generation should use the same `lang` and
+/// `get_token`.
+///
+/// Splitting the output requires [`Visibility::Pub`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Output {
+  /// Write both `src/kind.rs` and `src/ast.rs`. The default.
+  All,
+  /// Write only `src/kind.rs`, and the other files about the `SyntaxKind`,
+  /// like for [`Options::stable_ids`].
+  Kind,
+  /// Write only `src/ast.rs`, and the other files about the AST, like for
+  /// [`Options::ast_ext`], using the `SyntaxKind` and associated types in the
+  /// module at this path, like `lang_core::kind`.
+  ///
+  /// The options about the `SyntaxKind`, like [`Options::stable_ids`], are
+  /// ignored.
+  Ast(String),
+}
+
+impl Default for Output {
+  fn default() -> Self {
+    Self::All
+  }
+}
+
+/// Which nodes get a type in `src/ast.rs`.
+///
+/// Generation panics if a name is not a node in the grammar, or is a
+/// transparent node, which never has a type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AstNodes {
+  /// Every node. The default.
+  All,
+  /// Only the nodes with these names.
+  Only(Vec<String>),
+  /// Every node except those with these names.
+  Except(Vec<String>),
+}
+
+impl AstNodes {
+  /// Returns whether the node `name` is in this.
+  pub(crate) fn contains(&self, name: &str) -> bool {
+    match self {
+      Self::All => true,
+      Self::Only(names) => names.iter().any(|x| x == name),
+      Self::Except(names) => !names.iter().any(|x| x == name),
+    }
+  }
+
+  /// Returns the names listed in this.
+  pub(crate) fn names(&self) -> &[String] {
+    match self {
+      Self::All => &[],
+      Self::Only(names) | Self::Except(names) => names,
+    }
+  }
+}
+
+impl Default for AstNodes {
+  fn default() -> Self {
+    Self::All
+  }
+}
+
+/// Hooks for parsing text as a node, used by the generated `parse`
+/// constructors.
+///
+/// For a node `Expr`, the generated constructor is:
+///
+/// ```ignore
+/// impl Expr {
+///   pub fn parse(text: &str) -> (Self, Vec<Error>) { ... }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ParseEntry {
+  /// The path to a function to parse text as a node, like
+  /// `crate::parse::entry`.
+  ///
+  /// The function should have type `fn(&str, &str) -> (SyntaxNode,
+  /// Vec<Error>)`. It is called with the text and the name of the node in the
+  /// grammar, and should return a node which can be converted to the type for
+  /// that node (else the constructor panics), and the errors from parsing.
+  pub func: String,
+  /// The path to the type of errors returned by [`Self::func`], like
+  /// `crate::parse::Error`.
+  pub error: String,
+}
+
+/// A syntax tree library.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeLib {
+  /// [`rowan`](https://crates.io/crates/rowan) 0.12. The default.
+  Rowan,
+  /// [`cstree`](https://crates.io/crates/cstree) 0.10, which has interned
+  /// green trees and `Send` syntax nodes.
+  ///
+  /// The generated code does not implement `ast_ptr::HasLanguage` or
+  /// `ast_ptr::CanCast`, which are for `rowan`. Since `cstree` iterates over
+  /// the children of a node by reference, the iterators returned by generated
+  /// accessors borrow the node.
+  Cstree,
+}
+
+impl Default for TreeLib {
+  fn default() -> Self {
+    Self::Rowan
+  }
+}
+
+/// The visibility of generated items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+  /// `pub`. The default.
+  Pub,
+  /// `pub(crate)`.
+  Crate,
+}
+
+impl Default for Visibility {
+  fn default() -> Self {
+    Self::Pub
+  }
+}
+
+/// The case of the names of the generated accessor methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessorCase {
+  /// `snake_case`, like `param_list`. The default.
+  Snake,
+  /// `camelCase`, like `paramList`.
+  Camel,
+}
+
+impl Default for AccessorCase {
+  fn default() -> Self {
+    Self::Snake
+  }
+}
+
+/// An override for a generated accessor method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccessorOverride {
+  /// Do not generate the accessor.
+  Skip,
+  /// Generate the accessor with this name instead, used as-is regardless of
+  /// [`Options::accessor_case`].
+  Rename(String),
+}
+
+/// How to name the generated accessor methods for repeated children, which
+/// return iterators.
+///
+/// This does not apply to labeled children, which are always named after their
+/// label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepeatedAccessor {
+  /// Add `s`, like `params`. The default.
+  Plural,
+  /// Add `_list`, like `param_list`.
+  List,
+  /// Add `s_iter`, like `params_iter`.
+  PluralIter,
+}
+
+impl Default for RepeatedAccessor {
+  fn default() -> Self {
+    Self::Plural
+  }
+}
@@ -0,0 +1,264 @@
+use crate::token::TokenDb;
+use crate::util::each_node;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::fmt;
+use ungrammar::{Grammar, Node, Rule};
+
+/// A report about the grammar, computed during generation.
+///
+/// Nothing in the report prevents generation, but it may point out problems
+/// with the grammar. The `Display` impl shows the report as warnings.
+#[derive(Debug, Default)]
+pub struct Report {
+  /// The nodes which are not reachable from the root node, sorted.
+  ///
+  /// Empty if no root node was configured with [`crate::Options::root`].
+  pub unreachable: Vec<String>,
+  /// Groups of nodes which are left-recursive, i.e. can start with themselves
+  /// without any tokens in between. Each group and the nodes in it are sorted.
+  pub left_recursive: Vec<Vec<String>>,
+  /// The punctuation tokens which are proper prefixes of other punctuation
+  /// tokens, like `=` of `==`, each with the longer tokens. Sorted.
+  ///
+  /// A lexer must try the longer tokens first.
+  pub prefixes: Vec<(String, Vec<String>)>,
+  /// The punctuation tokens which are also a sequence of shorter punctuation
+  /// tokens, like `==` is `=` then `=`, each with the fewest such shorter
+  /// tokens. Sorted.
+  ///
+  /// A lexer which always takes the longest match will lex the shorter tokens
+  /// written next to each other as the longer token, so they must be
+  /// separated, e.g. with whitespace.
+  pub splits: Vec<(String, Vec<String>)>,
+}
+
+impl Report {
+  /// Returns whether there is nothing to report.
+  pub fn is_empty(&self) -> bool {
+    self.unreachable.is_empty()
+      && self.left_recursive.is_empty()
+      && self.prefixes.is_empty()
+      && self.splits.is_empty()
+  }
+}
+
+impl fmt::Display for Report {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for name in self.unreachable.iter() {
+      writeln!(f, "warning: unreachable node: {}", name)?;
+    }
+    for names in self.left_recursive.iter() {
+      writeln!(f, "warning: left-recursive nodes: {}", names.join(", "))?;
+    }
+    for (short, long) in self.prefixes.iter() {
+      let long: Vec<_> = long.iter().map(|x| format!("`{}`", x)).collect();
+      writeln!(f, "note: `{}` is a prefix of {}", short, long.join(", "))?;
+    }
+    for (token, parts) in self.splits.iter() {
+      let parts: Vec<_> = parts.iter().map(|x| format!("`{}`", x)).collect();
+      writeln!(f, "note: `{}` is also {}", token, parts.join(" "))?;
+    }
+    Ok(())
+  }
+}
+
+pub(crate) fn get(
+  grammar: &Grammar,
+  tokens: &TokenDb,
+  root: Option<&str>,
+) -> Report {
+  let nodes: FxHashMap<_, _> = grammar
+    .iter()
+    .map(|x| (grammar[x].name.as_str(), x))
+    .collect();
+  let mut unreachable = Vec::new();
+  if let Some(root) = root {
+    let root = match nodes.get(root) {
+      Some(&x) => x,
+      None => panic!("no root node {}", root),
+    };
+    let reachable =
+      reachable_from(root, |node, f| each_node(&grammar[node].rule, f));
+    unreachable = grammar
+      .iter()
+      .filter(|node| !reachable.contains(node))
+      .map(|node| grammar[node].name.clone())
+      .collect();
+    unreachable.sort_unstable();
+  }
+  let nullable = get_nullable(grammar);
+  let left: FxHashMap<Node, FxHashSet<Node>> = grammar
+    .iter()
+    .map(|node| {
+      let mut ac = FxHashSet::default();
+      left_nodes(&nullable, &grammar[node].rule, &mut ac);
+      (node, ac)
+    })
+    .collect();
+  let reach: FxHashMap<Node, FxHashSet<Node>> = grammar
+    .iter()
+    .map(|node| {
+      let ret =
+        reachable_from(node, |node, f| left[&node].iter().copied().for_each(f));
+      (node, ret)
+    })
+    .collect();
+  let mut done = FxHashSet::default();
+  let mut left_recursive = Vec::new();
+  for node in grammar.iter() {
+    if done.contains(&node)
+      || !left[&node].iter().any(|x| reach[x].contains(&node))
+    {
+      continue;
+    }
+    // all the nodes in the same strongly connected component as `node`.
+    let mut names: Vec<_> = reach[&node]
+      .iter()
+      .filter(|&other| reach[other].contains(&node))
+      .map(|&other| {
+        done.insert(other);
+        grammar[other].name.clone()
+      })
+      .collect();
+    names.sort_unstable();
+    left_recursive.push(names);
+  }
+  left_recursive.sort_unstable();
+  let mut punctuation: Vec<&str> = tokens
+    .punctuation
+    .keys()
+    .map(|&tok| grammar[tok].name.as_str())
+    .collect();
+  punctuation.sort_unstable();
+  let prefixes = punctuation
+    .iter()
+    .filter_map(|&short| {
+      let long: Vec<_> = punctuation
+        .iter()
+        .filter(|&&long| long != short && long.starts_with(short))
+        .map(|&long| long.to_owned())
+        .collect();
+      (!long.is_empty()).then(|| (short.to_owned(), long))
+    })
+    .collect();
+  let splits = punctuation
+    .iter()
+    .filter_map(|&token| {
+      let parts = split(&punctuation, token)?;
+      Some((token.to_owned(), parts))
+    })
+    .collect();
+  Report {
+    unreachable,
+    left_recursive,
+    prefixes,
+    splits,
+  }
+}
+
+/// Returns the fewest `tokens`, other than `token` itself, which together make
+/// up `token`, if any.
+fn split(tokens: &[&str], token: &str) -> Option<Vec<String>> {
+  // best[i] is the fewest tokens making up token[..i], as the index after the
+  // last token's start and that token.
+  let mut best: Vec<Option<(usize, usize, &str)>> = vec![None; token.len() + 1];
+  best[0] = Some((0, 0, ""));
+  for start in 0..token.len() {
+    let count = match best[start] {
+      Some((count, _, _)) => count,
+      None => continue,
+    };
+    for &part in tokens {
+      if part.is_empty() || part == token || !token[start..].starts_with(part) {
+        continue;
+      }
+      let end = start + part.len();
+      let better = best[end].map_or(true, |(c, _, _)| count + 1 < c);
+      if better {
+        best[end] = Some((count + 1, start, part));
+      }
+    }
+  }
+  let mut ret = Vec::new();
+  let mut end = token.len();
+  while end != 0 {
+    let (_, start, part) = best[end]?;
+    ret.push(part.to_owned());
+    end = start;
+  }
+  ret.reverse();
+  Some(ret)
+}
+
+/// Returns the nodes reachable from `start`, including `start`, where `edges`
+/// calls its function argument on each node directly reachable from a node.
+fn reachable_from<F>(start: Node, edges: F) -> FxHashSet<Node>
+where
+  F: Fn(Node, &mut dyn FnMut(Node)),
+{
+  let mut ret = FxHashSet::default();
+  let mut stack = vec![start];
+  while let Some(node) = stack.pop() {
+    if ret.insert(node) {
+      edges(node, &mut |x| stack.push(x));
+    }
+  }
+  ret
+}
+
+/// Returns the nodes which may match no tokens at all.
+fn get_nullable(grammar: &Grammar) -> FxHashSet<Node> {
+  let mut ret = FxHashSet::default();
+  loop {
+    let old_len = ret.len();
+    for node in grammar.iter() {
+      if is_nullable(&ret, &grammar[node].rule) {
+        ret.insert(node);
+      }
+    }
+    if ret.len() == old_len {
+      return ret;
+    }
+  }
+}
+
+fn is_nullable(nullable: &FxHashSet<Node>, rule: &Rule) -> bool {
+  match rule {
+    Rule::Node(node) => nullable.contains(node),
+    Rule::Token(_) => false,
+    Rule::Labeled { rule, .. } => is_nullable(nullable, rule),
+    Rule::Opt(_) | Rule::Rep(_) => true,
+    Rule::Seq(rules) => rules.iter().all(|r| is_nullable(nullable, r)),
+    Rule::Alt(rules) => rules.iter().any(|r| is_nullable(nullable, r)),
+  }
+}
+
+/// Adds the nodes which the rule may start with to `ac`.
+fn left_nodes(
+  nullable: &FxHashSet<Node>,
+  rule: &Rule,
+  ac: &mut FxHashSet<Node>,
+) {
+  match rule {
+    Rule::Node(node) => {
+      ac.insert(*node);
+    }
+    Rule::Token(_) => {}
+    Rule::Labeled { rule, .. } | Rule::Opt(rule) | Rule::Rep(rule) => {
+      left_nodes(nullable, rule, ac)
+    }
+    Rule::Seq(rules) => {
+      for rule in rules {
+        left_nodes(nullable, rule, ac);
+        if !is_nullable(nullable, rule) {
+          break;
+        }
+      }
+    }
+    Rule::Alt(rules) => {
+      for rule in rules {
+        left_nodes(nullable, rule, ac);
+      }
+    }
+  }
+}
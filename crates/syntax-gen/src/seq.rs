@@ -1,28 +1,64 @@
-use crate::util::{ident, Cx};
-use identifier_case::pascal_to_snake;
+use crate::options::{
+  AccessorCase, AccessorOverride, RepeatedAccessor, TreeLib,
+};
+use crate::util::{self, ident, Cx};
+use identifier_case::{pascal_to_snake, snake_to_camel};
 use proc_macro2::{Ident, TokenStream};
-use quote::{format_ident, quote};
+use quote::quote;
+use rustc_hash::FxHashSet;
 use std::hash::Hash;
 use ungrammar::Rule;
 
-pub(crate) fn get(cx: &Cx, name: Ident, rules: &[Rule]) -> TokenStream {
-  let lang = &cx.lang;
+/// Returns the type for the node `name` with the `rules`. Adds the keys of the
+/// accessor overrides which were applied to `overridden`.
+pub(crate) fn get(
+  cx: &Cx,
+  overridden: &mut FxHashSet<String>,
+  name: Ident,
+  rules: &[Rule],
+) -> TokenStream {
+  let vis = util::vis(&cx.options);
+  let has_language = util::has_language(cx, &name, std::slice::from_ref(&name));
+  let display = util::display(cx, &name, quote! { &self.0.text() });
+  let fingerprint = util::fingerprint(cx, &name);
   let mut counts = Counts::default();
-  let fields = rules.iter().map(|rule| field(cx, &mut counts, rule));
+  let mut fields = Vec::with_capacity(rules.len());
+  let mut f = Field {
+    cx,
+    parent: name.to_string(),
+    counts: &mut counts,
+    overridden,
+    out: &mut fields,
+  };
+  for rule in rules {
+    field(&mut f, Modifier::Regular, rule);
+  }
   let derives = if name == "Root" {
     quote! { #[derive(Debug, Clone)] }
   } else {
     quote! {}
   };
+  let (empty, checked_new) = match cx.options.tree_lib {
+    TreeLib::Rowan => (
+      get_empty(cx, &name, rules),
+      get_checked_new(cx, &name, rules),
+    ),
+    TreeLib::Cstree => (quote! {}, quote! {}),
+  };
+  let allow = match cx.options.accessor_case {
+    AccessorCase::Snake => quote! {},
+    AccessorCase::Camel => quote! { #[allow(non_snake_case)] },
+  };
   quote! {
     #derives
-    pub struct #name(SyntaxNode);
+    #vis struct #name(SyntaxNode);
+    #allow
     impl #name {
       #(#fields)*
+      #empty
+      #checked_new
     }
-    impl HasLanguage for #name {
-      type Language = #lang;
-    }
+    #has_language
     impl TryFrom<SyntaxNode> for #name {
       type Error = ();
       fn try_from(node: SyntaxNode) -> Result<Self, Self::Error> {
@@ -33,12 +69,172 @@ pub(crate) fn get(cx: &Cx, name: Ident, rules: &[Rule]) -> TokenStream {
         }
       }
     }
+    impl TryFrom<&SyntaxNode> for #name {
+      type Error = ();
+      fn try_from(node: &SyntaxNode) -> Result<Self, Self::Error> {
+        if node.kind() == SK::#name {
+          Ok(Self(node.clone()))
+        } else {
+          Err(())
+        }
+      }
+    }
+    impl TryFrom<&SyntaxElement> for #name {
+      type Error = ();
+      fn try_from(elem: &SyntaxElement) -> Result<Self, Self::Error> {
+        Self::try_from(elem.as_node().ok_or(())?)
+      }
+    }
     impl AsRef<SyntaxNode> for #name {
       fn as_ref(&self) -> &SyntaxNode {
         &self.0
       }
     }
+    #display
+    #fingerprint
+  }
+}
+
+/// Returns the `empty` constructor for the node `name` with the `rules`, if
+/// every child which must be present is a token with fixed text.
+fn get_empty(cx: &Cx, name: &Ident, rules: &[Rule]) -> TokenStream {
+  let mut children = Vec::new();
+  if !empty_children(cx, rules, &mut children) {
+    return quote! {};
+  }
+  let vis = util::vis(&cx.options);
+  quote! {
+    /// Returns a new node with only the tokens which must be present.
+    #vis fn empty() -> Self {
+      let children: Vec<rowan::NodeOrToken<rowan::GreenNode, rowan::GreenToken>> =
+        vec![#(#children ,)*];
+      Self(SyntaxNode::new_root(rowan::GreenNode::new(SK::#name.into(), children)))
+    }
+  }
+}
+
+/// Pushes the children which must be present for the `rules` to `ac`. Returns
+/// whether they all could be.
+fn empty_children(cx: &Cx, rules: &[Rule], ac: &mut Vec<TokenStream>) -> bool {
+  rules.iter().all(|rule| empty_child(cx, rule, ac))
+}
+
+fn empty_child(cx: &Cx, rule: &Rule, ac: &mut Vec<TokenStream>) -> bool {
+  match rule {
+    Rule::Opt(_) | Rule::Rep(_) => true,
+    Rule::Labeled { rule, .. } => empty_child(cx, rule, ac),
+    Rule::Token(tok) => {
+      let fixed = cx.tokens.punctuation.contains_key(tok)
+        || cx.tokens.keywords.contains_key(tok);
+      if fixed {
+        let kind = ident(cx.tokens.name(*tok));
+        let text = cx.grammar[*tok].name.as_str();
+        ac.push(quote! {
+          rowan::NodeOrToken::Token(rowan::GreenToken::new(SK::#kind.into(), #text))
+        });
+      }
+      fixed
+    }
+    Rule::Node(node) => {
+      let data = &cx.grammar[*node];
+      if !cx.is_transparent(&data.name) {
+        return false;
+      }
+      match &data.rule {
+        Rule::Seq(rules) => empty_children(cx, rules, ac),
+        rule => empty_child(cx, rule, ac),
+      }
+    }
+    Rule::Seq(_) | Rule::Alt(_) => false,
+  }
+}
+
+/// Returns the `checked_new` constructor for the node `name` with the `rules`.
+fn get_checked_new(cx: &Cx, name: &Ident, rules: &[Rule]) -> TokenStream {
+  let mut children = Vec::new();
+  for rule in rules {
+    child_rules(cx, Modifier::Regular, rule, &mut children);
+  }
+  let vis = util::vis(&cx.options);
+  quote! {
+    /// Returns a new node with the children, or an error if they do not fit
+    /// the grammar. Trivia may appear anywhere among the children.
+    #vis fn checked_new(
+      children: Vec<rowan::NodeOrToken<rowan::GreenNode, rowan::GreenToken>>,
+    ) -> Result<Self, BuildError> {
+      check_children(SK::#name, &[#(#children ,)*], &children)?;
+      Ok(Self(SyntaxNode::new_root(rowan::GreenNode::new(SK::#name.into(), children))))
+    }
+  }
+}
+
+/// Pushes the `ChildRule`s for the `rule`, used with the `outer` modifier, to
+/// `ac`. If the rule is a transparent node, instead pushes them for each of the
+/// rules of that node.
+fn child_rules(
+  cx: &Cx,
+  outer: Modifier,
+  mut rule: &Rule,
+  ac: &mut Vec<TokenStream>,
+) {
+  let mut modifier = Modifier::Regular;
+  let kinds: Vec<Ident>;
+  let mut pred = quote! { |_| true };
+  loop {
+    match rule {
+      Rule::Node(node) => {
+        let data = &cx.grammar[*node];
+        if cx.is_transparent(&data.name) {
+          let outer = outer.combine(modifier);
+          let rules = match &data.rule {
+            Rule::Seq(rules) => rules.as_slice(),
+            rule => std::slice::from_ref(rule),
+          };
+          for rule in rules {
+            child_rules(cx, outer, rule, ac);
+          }
+          return;
+        }
+        kinds = match &data.rule {
+          Rule::Alt(rules) => rules
+            .iter()
+            .map(|rule| match rule {
+              Rule::Node(node) => ident(&cx.grammar[*node].name),
+              Rule::Token(tok) => ident(cx.tokens.name(*tok)),
+              bad => panic!("bad alt rule {:?}", bad),
+            })
+            .collect(),
+          _ => vec![ident(&data.name)],
+        };
+        break;
+      }
+      Rule::Token(tok) => {
+        kinds = vec![ident(cx.tokens.name(*tok))];
+        if let Some(p) = cx.tokens.aliases.get(tok) {
+          pred = util::path(p);
+        }
+        break;
+      }
+      Rule::Labeled { rule: r, .. } => rule = r.as_ref(),
+      Rule::Opt(r) => {
+        modifier = Modifier::Optional;
+        rule = r.as_ref();
+      }
+      Rule::Rep(r) => {
+        modifier = Modifier::Repeated;
+        rule = r.as_ref();
+      }
+      Rule::Seq(_) | Rule::Alt(_) => panic!("bad field rule: {:?}", rule),
+    }
   }
+  let card = match outer.combine(modifier) {
+    Modifier::Regular => quote! { Card::One },
+    Modifier::Optional => quote! { Card::Opt },
+    Modifier::Repeated => quote! { Card::Many },
+  };
+  ac.push(quote! {
+    ChildRule { kinds: &[#(SK::#kinds),*], pred: #pred, card: #card }
+  });
 }
 
 type Counts<T> = rustc_hash::FxHashMap<T, usize>;
@@ -53,7 +249,7 @@ where
   ret
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 enum Modifier {
   Regular,
   Repeated,
@@ -64,35 +260,91 @@ impl Modifier {
   fn is_regular(&self) -> bool {
     matches!(self, Self::Regular)
   }
+
+  /// Returns the modifier for a field with modifier `inner` inside a
+  /// transparent node that was used with modifier `self`.
+  fn combine(self, inner: Self) -> Self {
+    match (self, inner) {
+      (Self::Regular, x) | (x, Self::Regular) => x,
+      (Self::Repeated, _) | (_, Self::Repeated) => Self::Repeated,
+      (Self::Optional, Self::Optional) => Self::Optional,
+    }
+  }
 }
 
-fn field<'cx>(
+/// The state for generating the accessors of a node.
+struct Field<'cx, 'a> {
   cx: &'cx Cx,
-  counts: &mut Counts<&'cx str>,
-  mut rule: &Rule,
-) -> TokenStream {
+  /// The name of the node.
+  parent: String,
+  counts: &'a mut Counts<&'cx str>,
+  overridden: &'a mut FxHashSet<String>,
+  out: &'a mut Vec<TokenStream>,
+}
+
+/// Pushes the accessor for the `rule` to `f.out`. If the rule is a transparent
+/// node, instead pushes the accessors for each of the rules of that node.
+fn field<'cx>(f: &mut Field<'cx, '_>, outer: Modifier, mut rule: &'cx Rule) {
+  let cx = f.cx;
   let mut modifier = Modifier::Regular;
   let mut label: Option<&str> = None;
   let name: &str;
   let base_ty: Ident;
   let base_body: TokenStream;
+  // the body to get the nth child, for which only the child is constructed.
+  let nth_body: Box<dyn Fn(usize) -> TokenStream>;
+  // whether the type of the accessor is generated.
+  let mut has_type = true;
   loop {
     match rule {
       Rule::Node(node) => {
         name = cx.grammar[*node].name.as_str();
+        if cx.is_transparent(name) {
+          assert!(label.is_none(), "cannot label transparent node {}", name);
+          let outer = outer.combine(modifier);
+          let rules = match &cx.grammar[*node].rule {
+            Rule::Seq(rules) => rules.as_slice(),
+            rule => std::slice::from_ref(rule),
+          };
+          for rule in rules {
+            field(f, outer, rule);
+          }
+          return;
+        }
+        has_type = cx.has_type(name);
         base_ty = ident(name);
-        base_body = if cx.token_alts.contains(&base_ty) {
-          quote! { token_children(self) }
+        if cx.token_alts.contains(&base_ty) {
+          base_body = quote! { token_children(self) };
+          nth_body = Box::new(|idx| quote! { nth_token_child(self, #idx) });
         } else {
-          quote! { node_children(self) }
-        };
+          base_body = quote! { node_children(self) };
+          nth_body = Box::new(|idx| quote! { nth_node_child(self, #idx) });
+        }
         break;
       }
       Rule::Token(tok) => {
-        name = cx.tokens.name(*tok);
+        let kind = ident(cx.tokens.name(*tok));
         base_ty = ident("SyntaxToken");
-        let name_ident = ident(name);
-        base_body = quote! { tokens(self, SK::#name_ident) };
+        match cx.tokens.aliases.get(tok) {
+          None => {
+            name = cx.tokens.name(*tok);
+            base_body = quote! { tokens(self, SK::#kind) };
+            nth_body = Box::new(move |idx| {
+              quote! { nth_token(self, SK::#kind, #idx, |_| true) }
+            });
+          }
+          // the kind is shared, so name and count this by its text instead.
+          Some(pred) => {
+            name = cx.grammar[*tok].name.as_str();
+            let pred = util::path(pred);
+            base_body = quote! {
+              tokens(self, SK::#kind).filter(|tok| #pred(tok.text()))
+            };
+            nth_body = Box::new(move |idx| {
+              quote! { nth_token(self, SK::#kind, #idx, #pred) }
+            });
+          }
+        }
         break;
       }
       Rule::Labeled { label: l, rule: r } => {
@@ -115,32 +367,69 @@ fn field<'cx>(
       Rule::Seq(_) | Rule::Alt(_) => panic!("bad field rule: {:?}", rule),
     }
   }
+  let modifier = outer.combine(modifier);
   let field_name = match label {
-    Some(x) => ident(x),
+    Some(x) => x.to_owned(),
     None => {
       let to_snake = pascal_to_snake(name);
-      match modifier {
-        Modifier::Repeated => format_ident!("{}s", to_snake),
-        Modifier::Optional | Modifier::Regular => ident(&to_snake),
+      match (modifier, cx.options.repeated_accessor) {
+        (Modifier::Repeated, RepeatedAccessor::Plural) => {
+          format!("{}s", to_snake)
+        }
+        (Modifier::Repeated, RepeatedAccessor::List) => {
+          format!("{}_list", to_snake)
+        }
+        (Modifier::Repeated, RepeatedAccessor::PluralIter) => {
+          format!("{}s_iter", to_snake)
+        }
+        (Modifier::Optional, _) | (Modifier::Regular, _) => to_snake,
+      }
+    }
+  };
+  // count this child even if its accessor is skipped, so the indices of the
+  // later children of the same kind are still right.
+  let idx = get_idx(f.counts, name);
+  let default_name = match (label, modifier) {
+    (None, Modifier::Repeated) => format!("{}s", pascal_to_snake(name)),
+    _ => field_name.clone(),
+  };
+  let key = format!("{}.{}", f.parent, default_name);
+  let field_name = match cx.options.accessor_overrides.get(&key) {
+    Some(x) => {
+      f.overridden.insert(key);
+      match x {
+        AccessorOverride::Skip => return,
+        AccessorOverride::Rename(x) => ident(x),
       }
     }
+    None => match cx.options.accessor_case {
+      AccessorCase::Snake => ident(&field_name),
+      AccessorCase::Camel => ident(&snake_to_camel(&field_name)),
+    },
   };
-  let idx = get_idx(counts, name);
+  if !has_type {
+    return;
+  }
   let ret_ty: TokenStream;
   let body: TokenStream;
   match modifier {
     Modifier::Repeated => {
-      ret_ty = quote! { impl Iterator<Item = #base_ty> };
+      let lifetime = util::children_lifetime(&cx.options);
+      ret_ty = quote! { impl Iterator<Item = #base_ty> #lifetime };
       body = base_body;
     }
     Modifier::Optional | Modifier::Regular => {
       ret_ty = quote! { Option<#base_ty> };
-      body = quote! { #base_body.nth(#idx) };
+      body = match cx.options.tree_lib {
+        TreeLib::Rowan => nth_body(idx),
+        TreeLib::Cstree => quote! { #base_body.nth(#idx) },
+      };
     }
   };
-  quote! {
-    pub fn #field_name(&self) -> #ret_ty {
+  let vis = util::vis(&cx.options);
+  f.out.push(quote! {
+    #vis fn #field_name(&self) -> #ret_ty {
       #body
     }
-  }
+  });
 }
@@ -0,0 +1,281 @@
+use crate::token::TokenDb;
+use crate::util::{self, ident, Cx};
+use crate::{
+  alt, merge_grammars, report, MergeError, Options, Report, TokenKind,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use ungrammar::{Grammar, Rule};
+
+#[test]
+fn merge() {
+  let core = "Root = Item*\nItem = Fn | Struct\nFn = 'fn' Name\nName = 'ident'";
+  // uses `Name` from the core, and `Item` in the core uses `Struct`. the
+  // comment and the token are not definitions.
+  let ext = "// Fn = nope\nStruct = 'struct' Name '='";
+  let grammar = merge_grammars(&[("core", core), ("ext", ext)]).unwrap();
+  let mut names: Vec<_> =
+    grammar.iter().map(|x| grammar[x].name.as_str()).collect();
+  names.sort_unstable();
+  assert_eq!(names, ["Fn", "Item", "Name", "Root", "Struct"]);
+}
+
+#[test]
+fn merge_redefined() {
+  let err = merge_grammars(&[
+    ("core", "Root = Item*\nItem = 'x'"),
+    ("ext", "Other = 'y'\nItem\n  = 'z'"),
+  ])
+  .unwrap_err();
+  assert_eq!(err.to_string(), "Item defined in both core and ext");
+  match err {
+    MergeError::Redefined {
+      node,
+      first,
+      second,
+    } => {
+      assert_eq!(node, "Item");
+      assert_eq!(first, "core");
+      assert_eq!(second, "ext");
+    }
+    MergeError::Parse(e) => panic!("unexpected parse error: {}", e),
+  }
+}
+
+#[test]
+fn merge_redefined_same_fragment() {
+  let err = merge_grammars(&[("core", "Root = 'x'\nRoot = 'y'")]).unwrap_err();
+  assert_eq!(err.to_string(), "Root defined in both core and core");
+}
+
+#[test]
+fn merge_parse() {
+  // `Missing` is defined in no fragment.
+  let err =
+    merge_grammars(&[("core", "Root = Item"), ("ext", "Item = Missing")])
+      .unwrap_err();
+  assert!(matches!(err, MergeError::Parse(_)), "{}", err);
+}
+
+/// Punctuation is named after its text, and everything else is a keyword,
+/// except the tokens in `unknown`.
+fn get_token(unknown: &[&str], s: &str) -> (TokenKind, String) {
+  let kind = if unknown.contains(&s) {
+    TokenKind::Unknown
+  } else if s.chars().all(|c| c.is_ascii_alphabetic()) {
+    TokenKind::Keyword
+  } else {
+    TokenKind::Punctuation
+  };
+  (kind, format!("T{:?}", s))
+}
+
+fn report(grammar: &str, root: &str) -> Report {
+  let grammar: Grammar = grammar.parse().unwrap();
+  let tokens =
+    TokenDb::new(&grammar, |s| get_token(&[], s), &FxHashMap::default());
+  report::get(&grammar, &tokens, Some(root))
+}
+
+#[test]
+fn report_unreachable() {
+  let got = report(
+    r#"
+Root = Item*
+Item = 'a' Used
+Used = 'b'
+Dead = 'c' DeadToo
+DeadToo = 'd' Used
+"#,
+    "Root",
+  );
+  assert_eq!(got.unreachable, ["Dead", "DeadToo"]);
+  assert!(got.left_recursive.is_empty());
+}
+
+#[test]
+fn report_left_recursive() {
+  let got = report(
+    r#"
+Root = Expr*
+Expr = Add | Num | Paren
+Add = Expr '+' Num
+Num = 'num'
+Paren = '(' Expr ')'
+Seq = Empty Seq 'x'
+Empty = 'e'?
+Rep = 'x'* Nested
+Nested = Rep? 'y'
+"#,
+    "Root",
+  );
+  assert_eq!(got.unreachable, ["Empty", "Nested", "Rep", "Seq"]);
+  // `Paren` uses `Expr` only after a token. `Seq` starts with itself after
+  // `Empty`, which may be empty, and so does `Rep` after `'x'*`.
+  let want = [vec!["Add", "Expr"], vec!["Nested", "Rep"], vec!["Seq"]];
+  assert_eq!(got.left_recursive, want);
+}
+
+#[test]
+fn report_tokens() {
+  let got = report("Root = ('+' | '+=' | '=' | '==' | '!' | 'num')*", "Root");
+  let prefixes = [("+", vec!["+="]), ("=", vec!["=="])];
+  let want: Vec<_> = prefixes
+    .iter()
+    .map(|(short, long)| {
+      let long = long.iter().map(|&x| x.to_owned()).collect();
+      (short.to_string(), long)
+    })
+    .collect();
+  assert_eq!(got.prefixes, want);
+  let splits = [("+=", vec!["+", "="]), ("==", vec!["=", "="])];
+  let want: Vec<_> = splits
+    .iter()
+    .map(|(token, parts)| {
+      let parts = parts.iter().map(|&x| x.to_owned()).collect();
+      (token.to_string(), parts)
+    })
+    .collect();
+  assert_eq!(got.splits, want);
+  assert_eq!(
+    got.to_string(),
+    "note: `+` is a prefix of `+=`\nnote: `=` is a prefix of `==`\n\
+     note: `+=` is also `+` `=`\nnote: `==` is also `=` `=`\n"
+  );
+}
+
+#[test]
+fn unknown_tokens() {
+  let grammar: Grammar = r#"
+Root = 'fn' Name Body
+Name = 'idnet' | 'ident'
+Body = '{' 'zzzz' '}' 'idnet'
+"#
+  .parse()
+  .unwrap();
+  let got = std::panic::catch_unwind(|| {
+    let get_token = |s: &str| get_token(&["idnet", "zzzz"], s);
+    TokenDb::new(&grammar, get_token, &FxHashMap::default())
+  })
+  .unwrap_err();
+  let got = got.downcast::<String>().unwrap();
+  // the unknown tokens are in the order they first appear in the grammar.
+  let lines: Vec<_> = got.lines().collect();
+  let want = [
+    "unknown token: idnet",
+    "  used by: Body, Name",
+    "  similar to: ident",
+    "unknown token: zzzz",
+    "  used by: Body",
+  ];
+  assert_eq!(lines, want);
+}
+
+#[test]
+fn stable_ids() {
+  let path = std::env::temp_dir()
+    .join(format!("syntax-gen-stable-ids-{}.txt", std::process::id()));
+  let path = path.to_str().unwrap();
+  let ids = |kinds: &[&str]| {
+    let kinds: Vec<_> = kinds.iter().map(|&x| ident(x)).collect();
+    util::stable_ids(path, &kinds).unwrap()
+  };
+  assert_eq!(ids(&["A", "B", "C"]), [0, 1, 2]);
+  // reordered, with a new kind and without an old one.
+  assert_eq!(ids(&["C", "D", "A"]), [2, 3, 0]);
+  // the ID of a removed kind is kept, and never re-used.
+  assert_eq!(ids(&["E", "B"]), [4, 1]);
+  let file = std::fs::read_to_string(path).unwrap();
+  std::fs::remove_file(path).unwrap();
+  assert_eq!(file, "A 0\nB 1\nC 2\nD 3\nE 4\n");
+}
+
+#[test]
+fn transparent_cycle() {
+  let grammar: Grammar = r#"
+Root = Path Rec 'x'
+Path = Segment ('::' Segment)*
+Segment = 'name'
+Rec = 'rec' Rec?
+A = 'a' B
+B = 'b' C
+C = 'c' A?
+"#
+  .parse()
+  .unwrap();
+  let check = |names: &[&str]| {
+    let names: Vec<_> = names.iter().map(|&x| x.to_owned()).collect();
+    util::check_transparent(&grammar, &names).map_err(|e| e.to_string())
+  };
+  assert_eq!(check(&["Path", "Segment"]), Ok(()));
+  // `C` is not transparent, so it is not inlined, so the cycle ends there.
+  assert_eq!(check(&["A", "B"]), Ok(()));
+  assert_eq!(
+    check(&["Rec"]),
+    Err("transparent node refers to itself: Rec -> Rec".to_owned())
+  );
+  assert_eq!(
+    check(&["Path", "B", "C", "A"]),
+    Err("transparent node refers to itself: A -> B -> C -> A".to_owned())
+  );
+}
+
+#[test]
+fn non_exhaustive() {
+  let alt = |non_exhaustive: bool| {
+    let grammar: Grammar = "Expr = Lit | Name\nLit = 'lit'\nName = 'name'"
+      .parse()
+      .unwrap();
+    let tokens =
+      TokenDb::new(&grammar, |s| get_token(&[], s), &FxHashMap::default());
+    let cx = Cx {
+      lang: ident("Lang"),
+      grammar,
+      tokens,
+      token_alts: FxHashSet::default(),
+      options: Options {
+        non_exhaustive,
+        ..Options::default()
+      },
+    };
+    let expr = cx.grammar.iter().next().unwrap();
+    let rules = match &cx.grammar[expr].rule {
+      Rule::Alt(rules) => rules,
+      rule => panic!("not an alt: {:?}", rule),
+    };
+    alt::get(&cx, &mut FxHashSet::default(), ident("Expr"), rules).to_string()
+  };
+  assert!(alt(true).contains("# [non_exhaustive] pub enum Expr"));
+  assert!(!alt(false).contains("non_exhaustive"));
+}
+
+#[test]
+fn stable_ids_not_discriminants() {
+  let path = std::env::temp_dir().join(format!(
+    "syntax-gen-stable-ids-shift-{}.txt",
+    std::process::id()
+  ));
+  let path = path.to_str().unwrap();
+  let ids = |kinds: &[&str]| {
+    let kinds: Vec<_> = kinds.iter().map(|&x| ident(x)).collect();
+    util::stable_ids(path, &kinds).unwrap()
+  };
+  // the tokens come before the nodes, so a new token shifts the discriminants
+  // of all the nodes, but not their stable IDs.
+  assert_eq!(ids(&["Plus", "Expr", "Root"]), [0, 1, 2]);
+  assert_eq!(ids(&["Minus", "Plus", "Expr", "Root"]), [3, 0, 1, 2]);
+  std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn grammar_hash() {
+  let hash = |grammar: &str, kinds: &[&str]| {
+    let grammar: Grammar = grammar.parse().unwrap();
+    let kinds: Vec<_> = kinds.iter().map(|&x| ident(x)).collect();
+    util::grammar_hash(&grammar, &kinds)
+  };
+  let kinds = ["Plus", "Num", "Expr", "Root"];
+  let a = hash("Root = Expr\nExpr = 'num' '+' 'num'", &kinds);
+  assert_eq!(a, hash("Root = Expr\nExpr = 'num' '+' 'num'", &kinds));
+  assert_ne!(a, hash("Root = Expr\nExpr = 'num' '+' 'num'?", &kinds));
+  assert_ne!(a, hash("Root = Expr\nExpr = 'num' '+' 'num'", &kinds[1..]));
+}
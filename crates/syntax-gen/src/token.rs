@@ -1,11 +1,24 @@
+use crate::util::each_token;
 use rustc_hash::FxHashMap;
+use std::fmt::Write as _;
 use ungrammar::{Grammar, Token};
 
 #[derive(Debug)]
 pub(crate) struct TokenDb {
   pub(crate) punctuation: FxHashMap<Token, String>,
   pub(crate) keywords: FxHashMap<Token, String>,
-  pub(crate) special: FxHashMap<Token, (String, &'static str)>,
+  pub(crate) special: FxHashMap<Token, Special>,
+  /// the paths to the predicates for the tokens which share a kind.
+  pub(crate) aliases: FxHashMap<Token, String>,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Special {
+  pub(crate) name: String,
+  pub(crate) desc: &'static str,
+  pub(crate) example: Option<&'static str>,
+  /// the text of the token in the grammar.
+  pub(crate) text: String,
 }
 
 /// A token kind.
@@ -17,16 +30,27 @@ pub enum TokenKind {
   Keyword,
   /// Special tokens, with a given description.
   Special(&'static str),
+  /// Special tokens, with a given description and example text, like `"foo"`
+  /// for identifiers.
+  SpecialWithExample(&'static str, &'static str),
+  /// Not a token `get_token` recognizes. Generation panics after all the tokens
+  /// have been processed, listing every unknown token.
+  Unknown,
 }
 
 impl TokenDb {
-  pub(crate) fn new<F>(grammar: &Grammar, get_token: F) -> Self
+  pub(crate) fn new<F>(
+    grammar: &Grammar,
+    get_token: F,
+    token_aliases: &FxHashMap<String, String>,
+  ) -> Self
   where
     F: Fn(&str) -> (TokenKind, String),
   {
     let mut punctuation = FxHashMap::default();
     let mut keywords = FxHashMap::default();
     let mut special = FxHashMap::default();
+    let mut unknown = Vec::new();
     for token in grammar.tokens() {
       let (kind, name) = get_token(grammar[token].name.as_ref());
       match kind {
@@ -37,14 +61,69 @@ impl TokenDb {
           assert!(keywords.insert(token, name).is_none());
         }
         TokenKind::Special(desc) => {
-          assert!(special.insert(token, (name, desc)).is_none());
+          let sp = Special {
+            name,
+            desc,
+            example: None,
+            text: grammar[token].name.clone(),
+          };
+          assert!(special.insert(token, sp).is_none());
+        }
+        TokenKind::SpecialWithExample(desc, example) => {
+          let sp = Special {
+            name,
+            desc,
+            example: Some(example),
+            text: grammar[token].name.clone(),
+          };
+          assert!(special.insert(token, sp).is_none());
         }
+        TokenKind::Unknown => unknown.push(token),
+      }
+    }
+    if !unknown.is_empty() {
+      panic!("{}", unknown_report(grammar, &unknown));
+    }
+    let mut aliases = FxHashMap::default();
+    for (text, pred) in token_aliases {
+      let token = grammar
+        .tokens()
+        .find(|&tok| grammar[tok].name == *text)
+        .unwrap_or_else(|| panic!("no token to alias: {}", text));
+      assert!(
+        special.contains_key(&token),
+        "cannot alias non-special token {}",
+        text
+      );
+      aliases.insert(token, pred.clone());
+    }
+    let mut by_name: FxHashMap<&str, Vec<Token>> = FxHashMap::default();
+    let names = punctuation
+      .iter()
+      .chain(keywords.iter())
+      .map(|(&tok, name)| (tok, name.as_str()))
+      .chain(special.iter().map(|(&tok, sp)| (tok, sp.name.as_str())));
+    for (tok, name) in names {
+      by_name.entry(name).or_default().push(tok);
+    }
+    for (name, toks) in by_name {
+      if toks.len() == 1 {
+        continue;
+      }
+      for tok in toks {
+        assert!(
+          aliases.contains_key(&tok),
+          "{} shares the kind {} with other tokens, but is not aliased",
+          grammar[tok].name,
+          name
+        );
       }
     }
     Self {
       punctuation,
       keywords,
       special,
+      aliases,
     }
   }
 
@@ -53,10 +132,64 @@ impl TokenDb {
       x.as_str()
     } else if let Some(x) = self.keywords.get(&token) {
       x.as_str()
-    } else if let Some(&(ref x, _)) = self.special.get(&token) {
-      x.as_str()
+    } else if let Some(x) = self.special.get(&token) {
+      x.name.as_str()
     } else {
       panic!("{:?} does not have a name", token)
     }
   }
 }
+
+/// Returns a message about the `unknown` tokens, with the nodes which use each
+/// one, and the known tokens with similar text.
+fn unknown_report(grammar: &Grammar, unknown: &[Token]) -> String {
+  let known: Vec<&str> = grammar
+    .tokens()
+    .filter(|x| !unknown.contains(x))
+    .map(|x| grammar[x].name.as_str())
+    .collect();
+  let mut ret = String::new();
+  for &token in unknown {
+    let text = grammar[token].name.as_str();
+    writeln!(ret, "unknown token: {}", text).unwrap();
+    let mut nodes: Vec<&str> = grammar
+      .iter()
+      .filter(|&node| {
+        let mut uses = false;
+        each_token(&grammar[node].rule, &mut |x| uses |= x == token);
+        uses
+      })
+      .map(|node| grammar[node].name.as_str())
+      .collect();
+    nodes.sort_unstable();
+    writeln!(ret, "  used by: {}", nodes.join(", ")).unwrap();
+    let max = (text.chars().count() + 1) / 3;
+    let mut similar: Vec<(usize, &str)> = known
+      .iter()
+      .map(|&x| (edit_distance(text, x), x))
+      .filter(|&(d, _)| d <= max)
+      .collect();
+    similar.sort_unstable();
+    if !similar.is_empty() {
+      let similar: Vec<_> = similar.iter().take(3).map(|&(_, x)| x).collect();
+      writeln!(ret, "  similar to: {}", similar.join(", ")).unwrap();
+    }
+  }
+  ret
+}
+
+/// Returns the Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+  let b: Vec<char> = b.chars().collect();
+  let mut prev: Vec<usize> = (0..=b.len()).collect();
+  let mut cur = vec![0; b.len() + 1];
+  for (i, ca) in a.chars().enumerate() {
+    cur[0] = i + 1;
+    for (j, &cb) in b.iter().enumerate() {
+      let sub = prev[j] + usize::from(ca != cb);
+      cur[j + 1] = sub.min(prev[j + 1] + 1).min(cur[j] + 1);
+    }
+    std::mem::swap(&mut prev, &mut cur);
+  }
+  prev[b.len()]
+}
@@ -1,9 +1,11 @@
+use crate::options::{Options, TreeLib, Visibility};
 use crate::token::TokenDb;
-use proc_macro2::Ident;
-use quote::format_ident;
-use rustc_hash::FxHashSet;
+use proc_macro2::{Ident, Literal, TokenStream};
+use quote::{format_ident, quote};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::fmt::Write as _;
 use std::fs::OpenOptions;
-use std::io::{Result, Write as _};
+use std::io::{ErrorKind, Result, Write as _};
 use std::process::{Command, Stdio};
 use ungrammar::{Grammar, Node, Rule, Token};
 
@@ -13,12 +15,282 @@ pub(crate) struct Cx {
   pub(crate) grammar: Grammar,
   pub(crate) tokens: TokenDb,
   pub(crate) token_alts: FxHashSet<Ident>,
+  pub(crate) options: Options,
+}
+
+impl Cx {
+  pub(crate) fn is_transparent(&self, name: &str) -> bool {
+    self.options.transparent.iter().any(|x| x == name)
+  }
+
+  /// Returns whether the node `name` gets a type in `src/ast.rs`.
+  pub(crate) fn has_type(&self, name: &str) -> bool {
+    !self.is_transparent(name) && self.options.ast_nodes.contains(name)
+  }
 }
 
 pub(crate) fn ident(s: &str) -> Ident {
   format_ident!("{}", s)
 }
 
+/// Parses a path to an item, like `crate::foo::bar`.
+pub(crate) fn path(s: &str) -> TokenStream {
+  match s.parse() {
+    Ok(x) => x,
+    Err(e) => panic!("invalid path {}: {}", s, e),
+  }
+}
+
+pub(crate) fn non_exhaustive(options: &Options) -> TokenStream {
+  if options.non_exhaustive {
+    quote! { #[non_exhaustive] }
+  } else {
+    quote! {}
+  }
+}
+
+/// Returns the name of the crate for the tree library.
+pub(crate) fn tree_lib(options: &Options) -> Ident {
+  match options.tree_lib {
+    TreeLib::Rowan => ident("rowan"),
+    TreeLib::Cstree => ident("cstree"),
+  }
+}
+
+/// Returns the visibility of the generated items.
+pub(crate) fn vis(options: &Options) -> TokenStream {
+  match options.visibility {
+    Visibility::Pub => quote! { pub },
+    Visibility::Crate => quote! { pub(crate) },
+  }
+}
+
+/// Returns what to add to the return type of a function returning an iterator
+/// over the children of a node, since with some tree libraries the iterator
+/// borrows the node.
+pub(crate) fn children_lifetime(options: &Options) -> TokenStream {
+  match options.tree_lib {
+    TreeLib::Rowan => quote! {},
+    TreeLib::Cstree => quote! { + '_ },
+  }
+}
+
+/// Returns the `HasLanguage` and `CanCast` impls for the type, which is any of
+/// the `kinds`, if the tree library supports
+/// it.
+pub(crate) fn has_language(
+  cx: &Cx,
+  name: &Ident,
+  kinds: &[Ident],
+) -> TokenStream {
+  let lang = &cx.lang;
+  match cx.options.tree_lib {
+    TreeLib::Rowan => quote! {
+      impl HasLanguage for #name {
+        type Language = #lang;
+      }
+      impl CanCast for #name {
+        fn can_cast(kind: SK) -> bool {
+          matches!(kind, #(SK::#kinds)|*)
+        }
+      }
+    },
+    TreeLib::Cstree => quote! {},
+  }
+}
+
+/// Returns the `Display` impl for the type, writing the `text`, if the tree
+/// library supports it without a separate interner.
+pub(crate) fn display(cx: &Cx, name: &Ident, text: TokenStream) -> TokenStream {
+  match cx.options.tree_lib {
+    TreeLib::Rowan => quote! {
+      impl std::fmt::Display for #name {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+          std::fmt::Display::fmt(#text, f)
+        }
+      }
+    },
+    TreeLib::Cstree => quote! {},
+  }
+}
+
+/// Returns the `fingerprint` method for the node type `name`.
+pub(crate) fn fingerprint(cx: &Cx, name: &Ident) -> TokenStream {
+  let vis = vis(&cx.options);
+  match cx.options.tree_lib {
+    TreeLib::Rowan => quote! {
+      impl #name {
+        /// Returns a hash of the kinds of the nodes and the kinds and text of
+        /// the non-trivia tokens in this. See the free `fingerprint`.
+        #vis fn fingerprint(&self) -> u64 {
+          fingerprint(self.as_ref())
+        }
+      }
+    },
+    TreeLib::Cstree => quote! {},
+  }
+}
+
+/// Returns a stable ID for each of the `kinds`, using and updating the IDs
+/// recorded in the file `name`.
+///
+/// Each line of the file is a kind name and its ID. Kinds already in the file
+/// keep their IDs. New kinds get IDs greater than every ID in the file. Kinds
+/// which no longer exist are kept in the file, so their IDs are never re-used.
+pub(crate) fn stable_ids(name: &str, kinds: &[Ident]) -> Result<Vec<u16>> {
+  let old = match std::fs::read_to_string(name) {
+    Ok(x) => x,
+    Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+    Err(e) => return Err(e),
+  };
+  let mut entries = Vec::<(String, u16)>::new();
+  for line in old.lines() {
+    let mut iter = line.split_whitespace();
+    let entry = match (iter.next(), iter.next(), iter.next()) {
+      (Some(kind), Some(id), None) => id.parse().ok().map(|id| (kind, id)),
+      _ => None,
+    };
+    match entry {
+      Some((kind, id)) => entries.push((kind.to_owned(), id)),
+      None => panic!("bad line in {}: {}", name, line),
+    }
+  }
+  let mut ids: FxHashMap<String, u16> = entries.iter().cloned().collect();
+  let mut next = entries.iter().map(|&(_, id)| id + 1).max().unwrap_or(0);
+  let ret = kinds
+    .iter()
+    .map(|kind| {
+      let kind = kind.to_string();
+      if let Some(&id) = ids.get(&kind) {
+        return id;
+      }
+      let id = next;
+      next = next.checked_add(1).expect("too many stable IDs");
+      ids.insert(kind.clone(), id);
+      entries.push((kind, id));
+      id
+    })
+    .collect();
+  let mut new = String::new();
+  for (kind, id) in entries {
+    writeln!(new, "{} {}", kind, id).unwrap();
+  }
+  std::fs::write(name, new)?;
+  Ok(ret)
+}
+
+/// Returns an error if a transparent node refers to itself, directly or through
+/// other transparent nodes, since inlining it would never end.
+pub(crate) fn check_transparent(
+  grammar: &Grammar,
+  transparent: &[String],
+) -> Result<()> {
+  let is_transparent =
+    |node: Node| transparent.iter().any(|x| *x == grammar[node].name);
+  for node in grammar.iter().filter(|&node| is_transparent(node)) {
+    let mut path = vec![node];
+    let mut seen = FxHashSet::default();
+    if transparent_cycle(grammar, &is_transparent, &mut path, &mut seen) {
+      let names: Vec<_> =
+        path.iter().map(|&x| grammar[x].name.as_str()).collect();
+      let msg =
+        format!("transparent node refers to itself: {}", names.join(" -> "));
+      return Err(std::io::Error::new(ErrorKind::InvalidInput, msg));
+    }
+  }
+  Ok(())
+}
+
+/// Returns whether the last node of the `path` refers to the first, through
+/// transparent nodes not in `seen`. If so, the rest of the way is pushed to
+/// `path`.
+fn transparent_cycle<F>(
+  grammar: &Grammar,
+  is_transparent: &F,
+  path: &mut Vec<Node>,
+  seen: &mut FxHashSet<Node>,
+) -> bool
+where
+  F: Fn(Node) -> bool,
+{
+  let mut next = Vec::new();
+  each_node(&grammar[*path.last().unwrap()].rule, &mut |node| {
+    if is_transparent(node) {
+      next.push(node);
+    }
+  });
+  for node in next {
+    path.push(node);
+    if node == path[0] {
+      return true;
+    }
+    if seen.insert(node)
+      && transparent_cycle(grammar, is_transparent, path, seen)
+    {
+      return true;
+    }
+    path.pop();
+  }
+  false
+}
+
+/// Returns a hash of the names of the `kinds` and the rules of the `grammar`,
+/// which is the same across runs and platforms.
+pub(crate) fn grammar_hash(grammar: &Grammar, kinds: &[Ident]) -> u64 {
+  let mut s = String::new();
+  for kind in kinds {
+    writeln!(s, "{}", kind).unwrap();
+  }
+  for node in grammar.iter() {
+    write!(s, "{} = ", grammar[node].name).unwrap();
+    crate::json::rule(grammar, &grammar[node].rule, &mut s);
+    s.push('\n');
+  }
+  // FNV-1a.
+  let mut ret: u64 = 0xcbf2_9ce4_8422_2325;
+  for b in s.bytes() {
+    ret ^= u64::from(b);
+    ret = ret.wrapping_mul(0x0100_0000_01b3);
+  }
+  ret
+}
+
+pub(crate) fn each_node<F>(rule: &Rule, f: &mut F)
+where
+  F: FnMut(Node) + ?Sized,
+{
+  match rule {
+    Rule::Node(node) => f(*node),
+    Rule::Token(_) => {}
+    Rule::Labeled { rule, .. } | Rule::Opt(rule) | Rule::Rep(rule) => {
+      each_node(rule, f)
+    }
+    Rule::Seq(rules) | Rule::Alt(rules) => {
+      for rule in rules {
+        each_node(rule, f);
+      }
+    }
+  }
+}
+
+pub(crate) fn each_token<F>(rule: &Rule, f: &mut F)
+where
+  F: FnMut(Token) + ?Sized,
+{
+  match rule {
+    Rule::Node(_) => {}
+    Rule::Token(token) => f(*token),
+    Rule::Labeled { rule, .. } | Rule::Opt(rule) | Rule::Rep(rule) => {
+      each_token(rule, f)
+    }
+    Rule::Seq(rules) | Rule::Alt(rules) => {
+      for rule in rules {
+        each_token(rule, f);
+      }
+    }
+  }
+}
+
 pub(crate) fn unwrap_node(rule: &Rule) -> Node {
   match rule {
     Rule::Node(node) => *node,
@@ -49,3 +321,21 @@ pub(crate) fn write_rust_file(name: &str, contents: &str) -> Result<()> {
   assert!(prog.wait()?.success());
   Ok(())
 }
+
+/// Returns the length of the longest of the `tokens`, and a table with the
+/// tokens of each length at that index, in the order of `tokens`.
+pub(crate) fn by_len(tokens: &[(&str, Ident)]) -> (usize, Vec<TokenStream>) {
+  let max = tokens.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+  let table = (0..=max)
+    .map(|len| {
+      let elements = tokens.iter().filter(|(name, _)| name.len() == len).map(
+        |(name, kind)| {
+          let bs = Literal::byte_string(name.as_bytes());
+          quote! { (#bs, Self::#kind) }
+        },
+      );
+      quote! { &[#(#elements ,)*] }
+    })
+    .collect();
+  (max, table)
+}
@@ -0,0 +1,117 @@
+//! Folding ranges and selection ranges, computed from the shape of a syntax
+//! tree.
+
+#![deny(missing_debug_implementations)]
+#![deny(missing_docs)]
+#![deny(rust_2018_idioms)]
+
+#[cfg(test)]
+mod tests;
+
+use rowan::{Language, NodeOrToken, SyntaxNode, TextRange, TextSize};
+use text_pos::PositionDb;
+
+/// A range of text which may be folded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fold {
+  /// The range.
+  pub range: TextRange,
+  /// The kind of fold.
+  pub kind: FoldKind,
+}
+
+impl Fold {
+  /// Returns the zero-based start and end lines of the fold.
+  pub fn lines(&self, db: &PositionDb) -> (u32, u32) {
+    let range = db.range(self.range);
+    (range.start.line, range.end.line)
+  }
+}
+
+/// A kind of fold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoldKind {
+  /// A foldable node.
+  Node,
+  /// A run of comments.
+  Comment,
+}
+
+/// Returns the folds in the tree rooted at `root`, in order of their start.
+///
+/// Every node whose kind satisfies `is_foldable` is a fold. Also, each run of
+/// consecutive tokens whose kinds satisfy `is_comment`, separated only by
+/// whitespace, is a fold. Folds which would not span more than one line are
+/// omitted.
+pub fn folds<L, F, G>(
+  root: &SyntaxNode<L>,
+  is_foldable: F,
+  is_comment: G,
+) -> Vec<Fold>
+where
+  L: Language,
+  F: Fn(L::Kind) -> bool,
+  G: Fn(L::Kind) -> bool,
+{
+  let mut ret = Vec::new();
+  let mut comments: Option<(TextRange, bool)> = None;
+  for elem in root.descendants_with_tokens() {
+    let tok = match elem {
+      NodeOrToken::Node(node) => {
+        if is_foldable(node.kind()) && node.text().contains_char('\n') {
+          ret.push(Fold {
+            range: node.text_range(),
+            kind: FoldKind::Node,
+          });
+        }
+        continue;
+      }
+      NodeOrToken::Token(tok) => tok,
+    };
+    if is_comment(tok.kind()) {
+      comments = Some(match comments {
+        None => (tok.text_range(), false),
+        Some((range, _)) => (range.cover(tok.text_range()), true),
+      });
+    } else if !tok.text().trim().is_empty() {
+      push_comments(&mut ret, comments.take());
+    }
+  }
+  push_comments(&mut ret, comments);
+  ret.sort_by_key(|x| x.range.start());
+  ret
+}
+
+fn push_comments(ac: &mut Vec<Fold>, comments: Option<(TextRange, bool)>) {
+  // only fold runs of at least two comments.
+  if let Some((range, true)) = comments {
+    ac.push(Fold {
+      range,
+      kind: FoldKind::Comment,
+    });
+  }
+}
+
+/// Returns the ranges to select when expanding a selection starting at
+/// `offset`, from smallest to largest.
+///
+/// The first range is the range of the token at `offset`, and each subsequent
+/// range is the range of an ancestor of that token. No two ranges are the
+/// same.
+pub fn selections<L>(root: &SyntaxNode<L>, offset: TextSize) -> Vec<TextRange>
+where
+  L: Language,
+{
+  let tok = match root.token_at_offset(offset).right_biased() {
+    Some(x) => x,
+    None => return Vec::new(),
+  };
+  let mut ret = vec![tok.text_range()];
+  for node in tok.ancestors() {
+    let range = node.text_range();
+    if ret.last() != Some(&range) {
+      ret.push(range);
+    }
+  }
+  ret
+}
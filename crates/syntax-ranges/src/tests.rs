@@ -0,0 +1,84 @@
+use crate::{folds, selections, Fold, FoldKind};
+use rowan::{GreenNodeBuilder, Language, SyntaxNode, TextRange, TextSize};
+use text_pos::PositionDb;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum SK {
+  Whitespace,
+  Comment,
+  Word,
+  Block,
+  Root,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Lang {}
+
+impl Language for Lang {
+  type Kind = SK;
+
+  fn kind_from_raw(raw: rowan::SyntaxKind) -> Self::Kind {
+    [SK::Whitespace, SK::Comment, SK::Word, SK::Block, SK::Root][raw.0 as usize]
+  }
+
+  fn kind_to_raw(kind: Self::Kind) -> rowan::SyntaxKind {
+    rowan::SyntaxKind(kind as u16)
+  }
+}
+
+/// Each line is a token: `{` starts a block, `}` ends it, `#` is a comment,
+/// else it's a word.
+fn tree(s: &str) -> SyntaxNode<Lang> {
+  let mut b = GreenNodeBuilder::new();
+  b.start_node(Lang::kind_to_raw(SK::Root));
+  for (idx, line) in s.lines().enumerate() {
+    if idx != 0 {
+      b.token(Lang::kind_to_raw(SK::Whitespace), "\n");
+    }
+    if line == "{" {
+      b.start_node(Lang::kind_to_raw(SK::Block));
+    }
+    let kind = if line.starts_with('#') {
+      SK::Comment
+    } else {
+      SK::Word
+    };
+    b.token(Lang::kind_to_raw(kind), line);
+    if line == "}" {
+      b.finish_node();
+    }
+  }
+  b.finish_node();
+  SyntaxNode::new_root(b.finish())
+}
+
+fn range(start: u32, end: u32) -> TextRange {
+  TextRange::new(start.into(), end.into())
+}
+
+#[test]
+fn fold() {
+  let s = "# a\n# b\nfoo\n{\nbar\n}\n# c\n{ }";
+  let root = tree(s);
+  let got = folds(&root, |k| k == SK::Block, |k| k == SK::Comment);
+  let want = [
+    Fold {
+      range: range(0, 7),
+      kind: FoldKind::Comment,
+    },
+    Fold {
+      range: range(12, 19),
+      kind: FoldKind::Node,
+    },
+  ];
+  assert_eq!(got, want);
+  let db = PositionDb::new(s);
+  assert_eq!(got[1].lines(&db), (3, 5));
+}
+
+#[test]
+fn select() {
+  let root = tree("foo\n{\nbar\n}");
+  let got = selections(&root, TextSize::from(7));
+  assert_eq!(got, [range(6, 9), range(4, 11), range(0, 11)]);
+}
@@ -16,6 +16,10 @@ struct Line {
   /// pairs of (where this char was in the line, the difference between the
   /// number of bytes needed to represent this char in utf8 and utf16)
   non_ascii: Vec<(TextSize, u32)>,
+  /// the utf16 columns between the two halves of a surrogate pair, in order.
+  mid_char: Vec<u32>,
+  /// whether the line ends with `\r\n` instead of `\n`.
+  cr: bool,
 }
 
 /// A database allowing translations between [`Position`]s and [`TextSize`]s.
@@ -29,8 +33,11 @@ impl PositionDb {
   pub fn new(s: &str) -> Self {
     let mut end = TextSize::from(0);
     let mut col = TextSize::from(0);
+    let mut col_utf16: u32 = 0;
     let mut lines = Vec::new();
     let mut non_ascii = Vec::new();
+    let mut mid_char = Vec::new();
+    let mut prev = None;
     for c in s.chars() {
       if !c.is_ascii() {
         // it should never happen that for a given c, the len_utf16 for c is
@@ -38,16 +45,35 @@ impl PositionDb {
         let diff = c.len_utf8() - c.len_utf16();
         non_ascii.push((col, diff as u32));
       }
+      if c.len_utf16() == 2 {
+        mid_char.push(col_utf16 + 1);
+      }
       if c == '\n' {
-        lines.push(Line { end, non_ascii });
+        let cr = prev == Some('\r');
+        lines.push(Line {
+          end,
+          non_ascii,
+          mid_char,
+          cr,
+        });
         non_ascii = Vec::new();
+        mid_char = Vec::new();
         col = TextSize::from(0);
+        col_utf16 = 0;
+      } else {
+        col_utf16 += c.len_utf16() as u32;
       }
       let ts = TextSize::of(c);
       end += ts;
       col += ts;
+      prev = Some(c);
     }
-    lines.push(Line { end, non_ascii });
+    lines.push(Line {
+      end,
+      non_ascii,
+      mid_char,
+      cr: false,
+    });
     lines.shrink_to_fit();
     Self { lines }
   }
@@ -115,6 +141,40 @@ impl PositionDb {
     TextRange::new(self.text_size(range.start), self.text_size(range.end))
   }
 
+  /// Returns the end of the original input.
+  pub fn end(&self) -> TextSize {
+    // there is always at least one line.
+    self.lines.last().unwrap().end
+  }
+
+  /// Returns the closest `Position` to `pos` within the bounds of the original
+  /// input.
+  ///
+  /// A line past the last line becomes the end of the input, and a character
+  /// past the end of its line becomes the end of that line, not counting the
+  /// `\r` of a `\r\n`. A character in the middle of a surrogate pair becomes
+  /// the start of that pair.
+  pub fn clamp(&self, pos: Position) -> Position {
+    let line = pos.line as usize;
+    let data = match self.lines.get(line) {
+      None => return self.position(self.end()),
+      Some(x) => x,
+    };
+    let start = line
+      .checked_sub(1)
+      .map_or(TextSize::from(0), |line| self.start(line));
+    let diff: u32 = data.non_ascii.iter().map(|&(_, diff)| diff).sum();
+    let len = u32::from(data.end - start) - diff - u32::from(data.cr);
+    let mut character = pos.character.min(len);
+    if data.mid_char.binary_search(&character).is_ok() {
+      character -= 1;
+    }
+    Position {
+      line: pos.line,
+      character,
+    }
+  }
+
   fn start(&self, line: usize) -> TextSize {
     // 1 for the newline
     self.lines[line].end + TextSize::from(1)
@@ -60,3 +60,63 @@ fn lsp_spec_example() {
     ],
   );
 }
+
+#[test]
+fn clamp() {
+  let lines = PositionDb::new("a𐐀b\nhi\n");
+  assert_eq!(lines.end(), TextSize::from(10));
+  let tests = [
+    ((0, 2), (0, 1)),
+    ((0, 3), (0, 3)),
+    ((0, 4), (0, 4)),
+    ((0, 9), (0, 4)),
+    ((1, 3), (1, 2)),
+    ((2, 0), (2, 0)),
+    ((2, 5), (2, 0)),
+    ((3, 0), (2, 0)),
+    ((9, 9), (2, 0)),
+  ];
+  for &((line, character), (want_line, want_character)) in tests.iter() {
+    let pos = Position { line, character };
+    let want = Position {
+      line: want_line,
+      character: want_character,
+    };
+    assert_eq!(lines.clamp(pos), want);
+  }
+}
+
+#[test]
+fn clamp_mid_char() {
+  let lines = PositionDb::new("𐐀");
+  let pos = Position {
+    line: 0,
+    character: 1,
+  };
+  let want = Position {
+    line: 0,
+    character: 0,
+  };
+  assert_eq!(lines.clamp(pos), want);
+  assert_eq!(lines.text_size(want), TextSize::from(0));
+}
+
+#[test]
+fn clamp_crlf() {
+  let lines = PositionDb::new("ab\r\ncd\r");
+  let tests = [
+    ((0, 2), (0, 2)),
+    ((0, 3), (0, 2)),
+    ((0, 9), (0, 2)),
+    ((1, 3), (1, 3)),
+    ((1, 9), (1, 3)),
+  ];
+  for &((line, character), (want_line, want_character)) in tests.iter() {
+    let pos = Position { line, character };
+    let want = Position {
+      line: want_line,
+      character: want_character,
+    };
+    assert_eq!(lines.clamp(pos), want);
+  }
+}
@@ -0,0 +1,159 @@
+//! Exports a basic [TextMate grammar][1] for a language from its ungrammar, for
+//! highlighting in editors before (or without) a language server.
+//!
+//! Only TextMate grammars are exported, not tree-sitter highlight queries.
+//!
+//! [1]: https://macromates.com/manual/en/language_grammars
+
+#![deny(missing_debug_implementations)]
+#![deny(missing_docs)]
+#![deny(rust_2018_idioms)]
+
+#[cfg(test)]
+mod tests;
+
+use std::cmp::Reverse;
+use std::fmt::Write as _;
+use syntax_gen::{json_string, TokenKind};
+use ungrammar::Grammar;
+
+/// Configuration for the TextMate grammar.
+#[derive(Debug, Default)]
+pub struct Config {
+  /// The name of the language, like `C0`.
+  pub name: String,
+  /// The scope name of the grammar, like `source.c0`. The last component is
+  /// used as the suffix of every other scope name in the grammar.
+  pub scope: String,
+  /// The file extensions for the language, without the `.`, like `c0`.
+  pub file_types: Vec<String>,
+  /// The start of a line comment, if any, like `//`.
+  pub line_comment: Option<String>,
+  /// The start and end of a block comment, if any, like `/*` and `*/`.
+  pub block_comment: Option<(String, String)>,
+  /// The quote for strings, if any, like `"`. A `\` escapes the next character
+  /// in a string.
+  pub string_quote: Option<char>,
+}
+
+/// Returns the TextMate grammar, in JSON.
+///
+/// `get_token` is as for [`syntax_gen::gen`]. The keywords and punctuation are
+/// taken from the tokens in the `grammar`. Special tokens are ignored.
+pub fn get<F>(config: &Config, grammar: &Grammar, get_token: F) -> String
+where
+  F: Fn(&str) -> (TokenKind, String),
+{
+  let mut keywords = Vec::new();
+  let mut punctuation = Vec::new();
+  for tok in grammar.tokens() {
+    let name = grammar[tok].name.as_str();
+    match get_token(name).0 {
+      TokenKind::Keyword => keywords.push(name),
+      TokenKind::Punctuation => punctuation.push(name),
+      TokenKind::Special(_)
+      | TokenKind::SpecialWithExample(_, _)
+      | TokenKind::Unknown => {}
+    }
+  }
+  // longest first, so e.g. `==` is matched before `=`.
+  keywords.sort_unstable_by_key(|&x| (Reverse(x.len()), x));
+  punctuation.sort_unstable_by_key(|&x| (Reverse(x.len()), x));
+  let suffix = config.scope.rsplit('.').next().unwrap_or_default();
+  let mut repository = Vec::new();
+  let mut comments = Vec::new();
+  if let Some(start) = &config.line_comment {
+    comments.push(rule_match(
+      &format!("comment.line.{}", suffix),
+      &format!("{}.*$", escape(start)),
+    ));
+  }
+  if let Some((start, end)) = &config.block_comment {
+    comments.push(format!(
+      "{{\"name\": {}, \"begin\": {}, \"end\": {}}}",
+      json_string(&format!("comment.block.{}", suffix)),
+      json_string(&escape(start)),
+      json_string(&escape(end))
+    ));
+  }
+  if !comments.is_empty() {
+    repository.push(("comments", comments));
+  }
+  if let Some(quote) = config.string_quote {
+    let quote = escape(&quote.to_string());
+    let escape_rule =
+      rule_match(&format!("constant.character.escape.{}", suffix), "\\\\.");
+    let strings = format!(
+      "{{\"name\": {}, \"begin\": {}, \"end\": {}, \"patterns\": [{}]}}",
+      json_string(&format!("string.quoted.{}", suffix)),
+      json_string(&quote),
+      json_string(&quote),
+      escape_rule
+    );
+    repository.push(("strings", vec![strings]));
+  }
+  if !keywords.is_empty() {
+    let alts: Vec<_> = keywords.iter().map(|x| escape(x)).collect();
+    let keywords = rule_match(
+      &format!("keyword.other.{}", suffix),
+      &format!("\\b(?:{})\\b", alts.join("|")),
+    );
+    repository.push(("keywords", vec![keywords]));
+  }
+  if !punctuation.is_empty() {
+    let alts: Vec<_> = punctuation.iter().map(|x| escape(x)).collect();
+    let punctuation =
+      rule_match(&format!("punctuation.other.{}", suffix), &alts.join("|"));
+    repository.push(("punctuation", vec![punctuation]));
+  }
+  let mut ret = String::new();
+  writeln!(ret, "{{").unwrap();
+  writeln!(ret, "  \"name\": {},", json_string(&config.name)).unwrap();
+  writeln!(ret, "  \"scopeName\": {},", json_string(&config.scope)).unwrap();
+  let file_types: Vec<_> =
+    config.file_types.iter().map(|x| json_string(x)).collect();
+  writeln!(ret, "  \"fileTypes\": [{}],", file_types.join(", ")).unwrap();
+  let includes: Vec<_> = repository
+    .iter()
+    .map(|&(name, _)| format!("    {{\"include\": \"#{}\"}}", name))
+    .collect();
+  writeln!(ret, "  \"patterns\": [\n{}\n  ],", includes.join(",\n")).unwrap();
+  let repository: Vec<_> = repository
+    .iter()
+    .map(|(name, patterns)| {
+      format!(
+        "    \"{}\": {{\"patterns\": [\n      {}\n    ]}}",
+        name,
+        patterns.join(",\n      ")
+      )
+    })
+    .collect();
+  writeln!(
+    ret,
+    "  \"repository\": {{\n{}\n  }}",
+    repository.join(",\n")
+  )
+  .unwrap();
+  writeln!(ret, "}}").unwrap();
+  ret
+}
+
+fn rule_match(name: &str, re: &str) -> String {
+  format!(
+    "{{\"name\": {}, \"match\": {}}}",
+    json_string(name),
+    json_string(re)
+  )
+}
+
+/// Returns `s` escaped for use in a regular expression.
+fn escape(s: &str) -> String {
+  let mut ret = String::with_capacity(s.len());
+  for c in s.chars() {
+    if "\\^$.|?*+()[]{}/-".contains(c) {
+      ret.push('\\');
+    }
+    ret.push(c);
+  }
+  ret
+}
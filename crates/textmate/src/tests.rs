@@ -0,0 +1,57 @@
+use crate::{get, Config};
+use syntax_gen::TokenKind;
+
+fn get_token(name: &str) -> (TokenKind, String) {
+  let kind = if name == "Name" {
+    TokenKind::Special("a name")
+  } else if name.chars().all(|c| c.is_ascii_lowercase()) {
+    TokenKind::Keyword
+  } else {
+    TokenKind::Punctuation
+  };
+  (kind, String::new())
+}
+
+#[test]
+fn smoke() {
+  let grammar =
+    "Root = Item*\nItem = 'let' 'Name' '=' 'Name' ('+' 'Name')* ';'"
+      .parse()
+      .unwrap();
+  let config = Config {
+    name: "Lang".to_owned(),
+    scope: "source.lang".to_owned(),
+    file_types: vec!["lang".to_owned()],
+    line_comment: Some("//".to_owned()),
+    block_comment: None,
+    string_quote: Some('"'),
+  };
+  let got = get(&config, &grammar, get_token);
+  let want = r##"{
+  "name": "Lang",
+  "scopeName": "source.lang",
+  "fileTypes": ["lang"],
+  "patterns": [
+    {"include": "#comments"},
+    {"include": "#strings"},
+    {"include": "#keywords"},
+    {"include": "#punctuation"}
+  ],
+  "repository": {
+    "comments": {"patterns": [
+      {"name": "comment.line.lang", "match": "\\/\\/.*$"}
+    ]},
+    "strings": {"patterns": [
+      {"name": "string.quoted.lang", "begin": "\"", "end": "\"", "patterns": [{"name": "constant.character.escape.lang", "match": "\\\\."}]}
+    ]},
+    "keywords": {"patterns": [
+      {"name": "keyword.other.lang", "match": "\\b(?:let)\\b"}
+    ]},
+    "punctuation": {"patterns": [
+      {"name": "punctuation.other.lang", "match": "\\+|;|="}
+    ]}
+  }
+}
+"##;
+  assert_eq!(got, want);
+}
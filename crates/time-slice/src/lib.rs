@@ -0,0 +1,176 @@
+//! Running analysis tasks in small time slices between handling messages, for
+//! servers which run everything on one thread.
+//!
+//! A main loop which uses this handles every message which is ready, then runs
+//! a slice with [`Executor::run_slice`], and only blocks waiting for the next
+//! message when there are no more tasks.
+
+#![deny(missing_debug_implementations)]
+#![deny(missing_docs)]
+#![deny(rust_2018_idioms)]
+
+#[cfg(test)]
+mod tests;
+
+use rustc_hash::FxHashMap;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+/// What a task returns after running for a while.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+  /// The task has more work to do, and should be run again.
+  Continue,
+  /// The task is done, and should be removed.
+  Done,
+}
+
+/// The time a task may run for in the current slice.
+#[derive(Debug, Clone, Copy)]
+pub struct Budget {
+  deadline: Instant,
+}
+
+impl Budget {
+  /// Returns whether the time is up, so the task should return
+  /// [`Step::Continue`] soon.
+  pub fn is_spent(&self) -> bool {
+    Instant::now() >= self.deadline
+  }
+}
+
+type Run = Box<dyn FnMut(&Budget) -> Step>;
+
+struct Task {
+  priority: u32,
+  seq: u64,
+  run: Run,
+}
+
+/// An executor for tasks identified by `K`, like the documents they analyze.
+///
+/// A task is a closure which does some of its work each time it is called,
+/// returning whether it is done. It should return [`Step::Continue`] when the
+/// [`Budget`] it is given is spent, to let the main loop handle messages.
+///
+/// Tasks with higher priority run first, and tasks with the same priority run
+/// in the order they were spawned. A task runs until it is done before the next
+/// task of the same priority starts.
+pub struct Executor<K> {
+  tasks: FxHashMap<K, Task>,
+  /// the order to run the tasks in.
+  queue: BTreeMap<(Reverse<u32>, u64), K>,
+  next_seq: u64,
+  slice: Duration,
+}
+
+impl<K> fmt::Debug for Executor<K> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Executor")
+      .field("tasks", &self.tasks.len())
+      .field("slice", &self.slice)
+      .finish()
+  }
+}
+
+impl<K> Executor<K>
+where
+  K: Eq + Hash + Clone,
+{
+  /// Returns a new executor with no tasks, whose slices last for `slice`.
+  pub fn new(slice: Duration) -> Self {
+    Self {
+      tasks: FxHashMap::default(),
+      queue: BTreeMap::new(),
+      next_seq: 0,
+      slice,
+    }
+  }
+
+  /// Adds the task `key` with the `priority`, replacing any task already with
+  /// that key. Higher priorities run first.
+  pub fn spawn<F>(&mut self, key: K, priority: u32, run: F)
+  where
+    F: 'static + FnMut(&Budget) -> Step,
+  {
+    self.cancel(&key);
+    let seq = self.next_seq;
+    self.next_seq += 1;
+    self.queue.insert((Reverse(priority), seq), key.clone());
+    let run = Box::new(run);
+    self.tasks.insert(key, Task { priority, seq, run });
+  }
+
+  /// Sets the priority of the task `key`, like to run the task for the
+  /// document the user is looking at first. Returns whether there was such a
+  /// task.
+  ///
+  /// The task keeps its place among the tasks with the same priority.
+  pub fn set_priority(&mut self, key: &K, priority: u32) -> bool {
+    let task = match self.tasks.get_mut(key) {
+      None => return false,
+      Some(x) => x,
+    };
+    let key = self
+      .queue
+      .remove(&(Reverse(task.priority), task.seq))
+      .unwrap();
+    task.priority = priority;
+    self.queue.insert((Reverse(priority), task.seq), key);
+    true
+  }
+
+  /// Removes the task `key` without running it any more. Returns whether there
+  /// was such a task.
+  pub fn cancel(&mut self, key: &K) -> bool {
+    match self.tasks.remove(key) {
+      None => false,
+      Some(task) => {
+        self.queue.remove(&(Reverse(task.priority), task.seq));
+        true
+      }
+    }
+  }
+
+  /// Returns whether there is a task `key`.
+  pub fn contains(&self, key: &K) -> bool {
+    self.tasks.contains_key(key)
+  }
+
+  /// Returns the number of tasks.
+  pub fn len(&self) -> usize {
+    self.tasks.len()
+  }
+
+  /// Returns whether there are no tasks.
+  pub fn is_empty(&self) -> bool {
+    self.tasks.is_empty()
+  }
+
+  /// Runs tasks, in order, until the slice is spent or there are no more tasks.
+  /// Returns whether there are tasks left.
+  ///
+  /// At least one task is run, if there are any, so every slice makes progress.
+  pub fn run_slice(&mut self) -> bool {
+    let budget = Budget {
+      deadline: Instant::now() + self.slice,
+    };
+    loop {
+      let (&order, key) = match self.queue.iter().next() {
+        None => return false,
+        Some(x) => x,
+      };
+      let task = self.tasks.get_mut(key).unwrap();
+      if (task.run)(&budget) == Step::Done {
+        let key = self.queue.remove(&order).unwrap();
+        self.tasks.remove(&key);
+      }
+      if budget.is_spent() {
+        return !self.tasks.is_empty();
+      }
+    }
+  }
+}
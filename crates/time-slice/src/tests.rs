@@ -0,0 +1,94 @@
+use crate::{Executor, Step};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+type Log = Rc<RefCell<Vec<String>>>;
+
+/// A task which logs each of its `steps` before it is done.
+fn task(
+  log: &Log,
+  name: &'static str,
+  steps: usize,
+) -> impl FnMut(&crate::Budget) -> Step {
+  let log = Rc::clone(log);
+  let mut done = 0;
+  move |_| {
+    done += 1;
+    log.borrow_mut().push(format!("{} {}", name, done));
+    if done == steps {
+      Step::Done
+    } else {
+      Step::Continue
+    }
+  }
+}
+
+fn take(log: &Log) -> Vec<String> {
+  std::mem::take(&mut *log.borrow_mut())
+}
+
+/// Each slice runs exactly one step.
+fn zero() -> Executor<&'static str> {
+  Executor::new(Duration::from_secs(0))
+}
+
+#[test]
+fn order() {
+  let log = Log::default();
+  let mut ex = zero();
+  ex.spawn("a", 0, task(&log, "a", 2));
+  ex.spawn("b", 1, task(&log, "b", 1));
+  ex.spawn("c", 0, task(&log, "c", 1));
+  assert_eq!(ex.len(), 3);
+  while ex.run_slice() {}
+  assert!(ex.is_empty());
+  assert_eq!(take(&log), ["b 1", "a 1", "a 2", "c 1"]);
+  assert!(!ex.run_slice());
+}
+
+#[test]
+fn set_priority() {
+  let log = Log::default();
+  let mut ex = zero();
+  ex.spawn("a", 0, task(&log, "a", 2));
+  ex.spawn("b", 0, task(&log, "b", 1));
+  assert!(ex.run_slice());
+  assert!(ex.set_priority(&"b", 1));
+  assert!(!ex.set_priority(&"c", 1));
+  while ex.run_slice() {}
+  assert_eq!(take(&log), ["a 1", "b 1", "a 2"]);
+}
+
+#[test]
+fn replace_and_cancel() {
+  let log = Log::default();
+  let mut ex = zero();
+  ex.spawn("a", 0, task(&log, "old", 2));
+  ex.spawn("b", 0, task(&log, "b", 1));
+  assert!(ex.run_slice());
+  ex.spawn("a", 0, task(&log, "new", 1));
+  assert_eq!(ex.len(), 2);
+  assert!(ex.cancel(&"b"));
+  assert!(!ex.cancel(&"b"));
+  assert!(!ex.contains(&"b"));
+  while ex.run_slice() {}
+  assert_eq!(take(&log), ["old 1", "new 1"]);
+}
+
+#[test]
+fn budget() {
+  let log = Log::default();
+  let mut ex = Executor::new(Duration::from_secs(60 * 60));
+  ex.spawn("a", 0, task(&log, "a", 3));
+  ex.spawn("b", 0, task(&log, "b", 1));
+  assert!(!ex.run_slice());
+  assert_eq!(take(&log), ["a 1", "a 2", "a 3", "b 1"]);
+  let mut ex = Executor::new(Duration::from_millis(1));
+  ex.spawn("a", 0, |budget: &crate::Budget| {
+    while !budget.is_spent() {}
+    Step::Continue
+  });
+  assert!(ex.run_slice());
+  assert!(ex.contains(&"a"));
+}
@@ -1,12 +1,81 @@
 //! The bridge between lexing and parsing.
 
-/// A token, a pair of syntax kind and text.
+use std::ops::{BitOr, Range};
+
+/// A token, a pair of syntax kind and text, with some flags and maybe where it
+/// starts.
 #[derive(Debug, Clone, Copy)]
 pub struct Token<'a, K> {
   /// The kind of token.
   pub kind: K,
   /// The text of the token.
   pub text: &'a str,
+  /// The flags of the token, set by the lexer.
+  pub flags: Flags,
+  /// The byte offset of the start of the token in the input, if known.
+  pub start: Option<usize>,
+}
+
+impl<'a, K> Token<'a, K> {
+  /// Returns a new token with no flags and no start.
+  pub fn new(kind: K, text: &'a str) -> Self {
+    Self {
+      kind,
+      text,
+      flags: Flags::NONE,
+      start: None,
+    }
+  }
+
+  /// Returns the byte range of the token in the input, if its start is known.
+  pub fn range(&self) -> Option<Range<usize>> {
+    let start = self.start?;
+    Some(start..start + self.text.len())
+  }
+}
+
+/// A small set of flags on a token, for layout information the parser needs
+/// without looking at trivia.
+///
+/// Apart from [`Self::NEWLINE_BEFORE`], the meaning of each bit is up to the
+/// lexer and parser.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Flags(u8);
+
+impl Flags {
+  /// No flags.
+  pub const NONE: Self = Self(0);
+
+  /// There was a newline in the trivia before this token.
+  pub const NEWLINE_BEFORE: Self = Self(1);
+
+  /// Returns the flags with these bits set.
+  pub const fn from_bits(bits: u8) -> Self {
+    Self(bits)
+  }
+
+  /// Returns the bits which are set.
+  pub const fn bits(self) -> u8 {
+    self.0
+  }
+
+  /// Returns whether all of the flags in `other` are set in `self`.
+  pub const fn contains(self, other: Self) -> bool {
+    self.0 & other.0 == other.0
+  }
+
+  /// Sets the flags in `other`.
+  pub fn insert(&mut self, other: Self) {
+    self.0 |= other.0;
+  }
+}
+
+impl BitOr for Flags {
+  type Output = Self;
+
+  fn bitor(self, rhs: Self) -> Self {
+    Self(self.0 | rhs.0)
+  }
 }
 
 /// Types whose values can report whether they are trivia or not.
@@ -0,0 +1,42 @@
+//! Getting the `)` of a wide list of arguments, with the generated accessor,
+//! which finds it in the green tree, versus by iterating over the children.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use toy::ast::{ArgList, Expr, Stmt};
+use toy::kind::{SyntaxKind as SK, SyntaxToken};
+
+fn input() -> ArgList {
+  let mut s = "f(".to_owned();
+  for i in 0..1000 {
+    s.push_str(&format!("{}, ", i));
+  }
+  s.push_str(");");
+  let stmt = toy::analyze(&s).root.stmts().next().unwrap();
+  match stmt {
+    Stmt::ExprStmt(x) => match x.expr().unwrap() {
+      Expr::CallExpr(x) => x.arg_list().unwrap(),
+      _ => panic!("not a call"),
+    },
+    Stmt::LetStmt(_) => panic!("not an expr"),
+  }
+}
+
+fn scan(args: &ArgList) -> Option<SyntaxToken> {
+  args
+    .as_ref()
+    .children_with_tokens()
+    .filter_map(rowan::NodeOrToken::into_token)
+    .find(|tok| tok.kind() == SK::RRound)
+}
+
+fn accessor(c: &mut Criterion) {
+  let args = input();
+  assert_eq!(args.r_round(), scan(&args));
+  let mut group = c.benchmark_group("accessor");
+  group.bench_function("generated", |b| b.iter(|| black_box(&args).r_round()));
+  group.bench_function("scan", |b| b.iter(|| scan(black_box(&args))));
+  group.finish();
+}
+
+criterion_group!(benches, accessor);
+criterion_main!(benches);
@@ -0,0 +1,47 @@
+//! Casting every node in a large tree to an AST type, by value (cloning nodes
+//! which are still needed after) versus by reference.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::convert::TryFrom as _;
+use toy::ast::Expr;
+use toy::kind::{SyntaxKind as SK, SyntaxNode};
+
+fn input() -> SyntaxNode {
+  let mut s = String::new();
+  for i in 0..2000 {
+    s.push_str(&format!("let x{} = (1 + x) + (2 + (y + 3));\n", i));
+  }
+  toy::analyze(&s).root.as_ref().clone()
+}
+
+fn by_value(root: &SyntaxNode) -> usize {
+  root
+    .descendants()
+    .filter(|node| {
+      let is_expr = Expr::try_from(node.clone()).is_ok();
+      is_expr && node.kind() != SK::NameExpr
+    })
+    .count()
+}
+
+fn by_ref(root: &SyntaxNode) -> usize {
+  root
+    .descendants()
+    .filter(|node| {
+      let is_expr = Expr::try_from(node).is_ok();
+      is_expr && node.kind() != SK::NameExpr
+    })
+    .count()
+}
+
+fn cast(c: &mut Criterion) {
+  let root = input();
+  assert_eq!(by_value(&root), by_ref(&root));
+  let mut group = c.benchmark_group("cast");
+  group.bench_function("by_value", |b| b.iter(|| by_value(black_box(&root))));
+  group.bench_function("by_ref", |b| b.iter(|| by_ref(black_box(&root))));
+  group.finish();
+}
+
+criterion_group!(benches, cast);
+criterion_main!(benches);
@@ -0,0 +1,1326 @@
+use crate::kind::{
+  SyntaxElement, SyntaxKind as SK, SyntaxNode, SyntaxToken, Toy,
+};
+use ast_ptr::{CanCast, HasLanguage};
+use std::convert::{TryFrom, TryInto};
+#[allow(unused)]
+fn tokens<P>(parent: &P, kind: SK) -> impl Iterator<Item = SyntaxToken>
+where
+  P: AsRef<SyntaxNode>,
+{
+  parent
+    .as_ref()
+    .children_with_tokens()
+    .filter_map(rowan::NodeOrToken::into_token)
+    .filter(move |tok| tok.kind() == kind)
+}
+#[allow(unused)]
+fn token_children<P, C>(parent: &P) -> impl Iterator<Item = C>
+where
+  P: AsRef<SyntaxNode>,
+  SyntaxToken: TryInto<C>,
+{
+  parent
+    .as_ref()
+    .children_with_tokens()
+    .filter_map(rowan::NodeOrToken::into_token)
+    .filter_map(|x| x.try_into().ok())
+}
+#[allow(unused)]
+fn node_children<P, C>(parent: &P) -> impl Iterator<Item = C>
+where
+  P: AsRef<SyntaxNode>,
+  SyntaxNode: TryInto<C>,
+{
+  parent.as_ref().children().filter_map(|x| x.try_into().ok())
+}
+#[doc = r" Returns the `n`th child of the `parent` for which `f` returns true,"]
+#[doc = r" given the kind of a node or the kind and text of a token."]
+#[doc = r""]
+#[doc = r" The children are checked in the green tree, so only the one returned"]
+#[doc = r" is constructed, unless it is empty."]
+#[allow(unused)]
+fn nth_child<P, F>(parent: &P, n: usize, f: F) -> Option<SyntaxElement>
+where
+  P: AsRef<SyntaxNode>,
+  F: Fn(rowan::NodeOrToken<SK, (SK, &str)>) -> bool,
+{
+  let parent = parent.as_ref();
+  let mut offset = parent.text_range().start();
+  let mut n = n;
+  for (idx, child) in parent.green().children().enumerate() {
+    let len = child.text_len();
+    let kind = <Toy as rowan::Language>::kind_from_raw(child.kind());
+    let arg = match child {
+      rowan::NodeOrToken::Node(_) => rowan::NodeOrToken::Node(kind),
+      rowan::NodeOrToken::Token(tok) => {
+        rowan::NodeOrToken::Token((kind, tok.text()))
+      }
+    };
+    if !f(arg) {
+      offset += len;
+      continue;
+    }
+    if n != 0 {
+      n -= 1;
+      offset += len;
+      continue;
+    }
+    return if len == 0.into() {
+      parent.children_with_tokens().nth(idx)
+    } else {
+      let range = rowan::TextRange::at(offset, len);
+      parent.child_or_token_at_range(range)
+    };
+  }
+  None
+}
+#[allow(unused)]
+fn nth_node_child<P, C>(parent: &P, n: usize) -> Option<C>
+where
+  P: AsRef<SyntaxNode>,
+  C: CanCast<Language = Toy> + TryFrom<SyntaxNode>,
+{
+  let elem = nth_child(parent, n, |x| match x {
+    rowan::NodeOrToken::Node(kind) => C::can_cast(kind),
+    rowan::NodeOrToken::Token(_) => false,
+  });
+  elem?.into_node()?.try_into().ok()
+}
+#[allow(unused)]
+fn nth_token_child<P, C>(parent: &P, n: usize) -> Option<C>
+where
+  P: AsRef<SyntaxNode>,
+  C: CanCast<Language = Toy> + TryFrom<SyntaxToken>,
+{
+  let elem = nth_child(parent, n, |x| match x {
+    rowan::NodeOrToken::Node(_) => false,
+    rowan::NodeOrToken::Token((kind, _)) => C::can_cast(kind),
+  });
+  elem?.into_token()?.try_into().ok()
+}
+#[allow(unused)]
+fn nth_token<P>(
+  parent: &P,
+  kind: SK,
+  n: usize,
+  pred: fn(&str) -> bool,
+) -> Option<SyntaxToken>
+where
+  P: AsRef<SyntaxNode>,
+{
+  let elem = nth_child(parent, n, |x| match x {
+    rowan::NodeOrToken::Node(_) => false,
+    rowan::NodeOrToken::Token((k, text)) => k == kind && pred(text),
+  });
+  elem?.into_token()
+}
+#[doc = r" An error from checking the children of a new node against the"]
+#[doc = r" grammar."]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildError {
+  #[doc = r" The kind of the new node."]
+  pub node: SK,
+  #[doc = r" The index of the first child which did not fit, or the number of"]
+  #[doc = r" children if the children ended too soon."]
+  pub idx: usize,
+  #[doc = r" The kinds which could have been at `idx`. Empty if the children"]
+  #[doc = r" should have ended there."]
+  pub expected: Vec<SK>,
+}
+impl std::fmt::Display for BuildError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "bad child {} for {:?}: ", self.idx, self.node)?;
+    if self.expected.is_empty() {
+      f.write_str("expected no more children")
+    } else {
+      write!(f, "expected one of {:?}", self.expected)
+    }
+  }
+}
+impl std::error::Error for BuildError {}
+#[doc = r" Returns a hash of the kinds of the nodes and the kinds and text of the"]
+#[doc = r" non-trivia tokens in `node`, so two nodes have the same hash if they"]
+#[doc = r" differ only in trivia."]
+#[doc = r""]
+#[doc = r" The hash is the same across runs and platforms, but changes when the"]
+#[doc = r" discriminants of the `SyntaxKind` do."]
+pub fn fingerprint(node: &SyntaxNode) -> u64 {
+  fn write(hash: &mut u64, bytes: &[u8]) {
+    for &b in bytes {
+      *hash ^= u64::from(b);
+      *hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+  }
+  let mut ret: u64 = 0xcbf2_9ce4_8422_2325;
+  for event in node.preorder_with_tokens() {
+    match event {
+      rowan::WalkEvent::Enter(rowan::NodeOrToken::Node(node)) => {
+        write(&mut ret, &[0]);
+        write(&mut ret, &(node.kind() as u16).to_le_bytes());
+      }
+      rowan::WalkEvent::Leave(rowan::NodeOrToken::Node(_)) => {
+        write(&mut ret, &[1]);
+      }
+      rowan::WalkEvent::Enter(rowan::NodeOrToken::Token(tok)) => {
+        if token::Triviable::is_trivia(&tok.kind()) {
+          continue;
+        }
+        let text = tok.text();
+        write(&mut ret, &[2]);
+        write(&mut ret, &(tok.kind() as u16).to_le_bytes());
+        write(&mut ret, &(text.len() as u64).to_le_bytes());
+        write(&mut ret, text.as_bytes());
+      }
+      rowan::WalkEvent::Leave(rowan::NodeOrToken::Token(_)) => {}
+    }
+  }
+  ret
+}
+#[allow(unused)]
+#[derive(Clone, Copy)]
+enum Card {
+  One,
+  Opt,
+  Many,
+}
+#[doc = r" A child in the rule for a node."]
+struct ChildRule {
+  kinds: &'static [SK],
+  #[doc = r" whether the text of a token fits, for tokens which share a kind."]
+  pred: fn(&str) -> bool,
+  card: Card,
+}
+#[doc = r" Checks the `children` of a new node with kind `node` against the"]
+#[doc = r" `rules`, skipping trivia. This is greedy, so a child which could fit"]
+#[doc = r" either of two adjacent rules fits the first."]
+#[allow(unused)]
+fn check_children(
+  node: SK,
+  rules: &[ChildRule],
+  children: &[rowan::NodeOrToken<rowan::GreenNode, rowan::GreenToken>],
+) -> Result<(), BuildError> {
+  let mut iter = children
+    .iter()
+    .map(|child| {
+      let kind = <Toy as rowan::Language>::kind_from_raw(child.kind());
+      let text = match child {
+        rowan::NodeOrToken::Node(_) => None,
+        rowan::NodeOrToken::Token(tok) => Some(tok.text()),
+      };
+      (kind, text)
+    })
+    .enumerate()
+    .filter(|(_, (kind, _))| !token::Triviable::is_trivia(kind))
+    .peekable();
+  let mut expected = Vec::new();
+  for rule in rules {
+    let mut any = false;
+    while let Some(&(_, (kind, text))) = iter.peek() {
+      let fits = rule.kinds.contains(&kind)
+        && match text {
+          None => true,
+          Some(text) => (rule.pred)(text),
+        };
+      if !fits {
+        break;
+      }
+      iter.next();
+      any = true;
+      expected.clear();
+      if let Card::One | Card::Opt = rule.card {
+        break;
+      }
+    }
+    if any {
+      continue;
+    }
+    expected.extend_from_slice(rule.kinds);
+    if let Card::One = rule.card {
+      let idx = iter.peek().map_or(children.len(), |&(idx, _)| idx);
+      return Err(BuildError {
+        node,
+        idx,
+        expected,
+      });
+    }
+  }
+  match iter.next() {
+    None => Ok(()),
+    Some((idx, _)) => Err(BuildError {
+      node,
+      idx,
+      expected,
+    }),
+  }
+}
+pub enum Stmt {
+  LetStmt(LetStmt),
+  ExprStmt(ExprStmt),
+}
+impl HasLanguage for Stmt {
+  type Language = Toy;
+}
+impl CanCast for Stmt {
+  fn can_cast(kind: SK) -> bool {
+    matches!(kind, SK::LetStmt | SK::ExprStmt)
+  }
+}
+impl TryFrom<SyntaxNode> for Stmt {
+  type Error = ();
+  fn try_from(node: SyntaxNode) -> Result<Self, Self::Error> {
+    let ret = match node.kind() {
+      SK::LetStmt => Self::LetStmt(LetStmt(node)),
+      SK::ExprStmt => Self::ExprStmt(ExprStmt(node)),
+      _ => return Err(()),
+    };
+    Ok(ret)
+  }
+}
+impl TryFrom<&SyntaxNode> for Stmt {
+  type Error = ();
+  fn try_from(node: &SyntaxNode) -> Result<Self, Self::Error> {
+    let ret = match node.kind() {
+      SK::LetStmt => Self::LetStmt(LetStmt(node.clone())),
+      SK::ExprStmt => Self::ExprStmt(ExprStmt(node.clone())),
+      _ => return Err(()),
+    };
+    Ok(ret)
+  }
+}
+impl TryFrom<&SyntaxElement> for Stmt {
+  type Error = ();
+  fn try_from(elem: &SyntaxElement) -> Result<Self, Self::Error> {
+    Self::try_from(elem.as_node().ok_or(())?)
+  }
+}
+impl AsRef<SyntaxNode> for Stmt {
+  fn as_ref(&self) -> &SyntaxNode {
+    match self {
+      Self::LetStmt(x) => x.as_ref(),
+      Self::ExprStmt(x) => x.as_ref(),
+    }
+  }
+}
+impl std::fmt::Display for Stmt {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    std::fmt::Display::fmt(&self.as_ref().text(), f)
+  }
+}
+impl Stmt {
+  #[doc = r" Returns a hash of the kinds of the nodes and the kinds and text of"]
+  #[doc = r" the non-trivia tokens in this. See the free `fingerprint`."]
+  pub fn fingerprint(&self) -> u64 {
+    fingerprint(self.as_ref())
+  }
+}
+pub enum Expr {
+  IntExpr(IntExpr),
+  NameExpr(NameExpr),
+  ParenExpr(ParenExpr),
+  AddExpr(AddExpr),
+  CallExpr(CallExpr),
+}
+impl HasLanguage for Expr {
+  type Language = Toy;
+}
+impl CanCast for Expr {
+  fn can_cast(kind: SK) -> bool {
+    matches!(
+      kind,
+      SK::IntExpr | SK::NameExpr | SK::ParenExpr | SK::AddExpr | SK::CallExpr
+    )
+  }
+}
+impl TryFrom<SyntaxNode> for Expr {
+  type Error = ();
+  fn try_from(node: SyntaxNode) -> Result<Self, Self::Error> {
+    let ret = match node.kind() {
+      SK::IntExpr => Self::IntExpr(IntExpr(node)),
+      SK::NameExpr => Self::NameExpr(NameExpr(node)),
+      SK::ParenExpr => Self::ParenExpr(ParenExpr(node)),
+      SK::AddExpr => Self::AddExpr(AddExpr(node)),
+      SK::CallExpr => Self::CallExpr(CallExpr(node)),
+      _ => return Err(()),
+    };
+    Ok(ret)
+  }
+}
+impl TryFrom<&SyntaxNode> for Expr {
+  type Error = ();
+  fn try_from(node: &SyntaxNode) -> Result<Self, Self::Error> {
+    let ret = match node.kind() {
+      SK::IntExpr => Self::IntExpr(IntExpr(node.clone())),
+      SK::NameExpr => Self::NameExpr(NameExpr(node.clone())),
+      SK::ParenExpr => Self::ParenExpr(ParenExpr(node.clone())),
+      SK::AddExpr => Self::AddExpr(AddExpr(node.clone())),
+      SK::CallExpr => Self::CallExpr(CallExpr(node.clone())),
+      _ => return Err(()),
+    };
+    Ok(ret)
+  }
+}
+impl TryFrom<&SyntaxElement> for Expr {
+  type Error = ();
+  fn try_from(elem: &SyntaxElement) -> Result<Self, Self::Error> {
+    Self::try_from(elem.as_node().ok_or(())?)
+  }
+}
+impl AsRef<SyntaxNode> for Expr {
+  fn as_ref(&self) -> &SyntaxNode {
+    match self {
+      Self::IntExpr(x) => x.as_ref(),
+      Self::NameExpr(x) => x.as_ref(),
+      Self::ParenExpr(x) => x.as_ref(),
+      Self::AddExpr(x) => x.as_ref(),
+      Self::CallExpr(x) => x.as_ref(),
+    }
+  }
+}
+impl std::fmt::Display for Expr {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    std::fmt::Display::fmt(&self.as_ref().text(), f)
+  }
+}
+impl Expr {
+  #[doc = r" Returns a hash of the kinds of the nodes and the kinds and text of"]
+  #[doc = r" the non-trivia tokens in this. See the free `fingerprint`."]
+  pub fn fingerprint(&self) -> u64 {
+    fingerprint(self.as_ref())
+  }
+}
+#[derive(Debug, Clone)]
+pub struct Root(SyntaxNode);
+impl Root {
+  pub fn stmts(&self) -> impl Iterator<Item = Stmt> {
+    node_children(self)
+  }
+  #[doc = r" Returns a new node with only the tokens which must be present."]
+  pub fn empty() -> Self {
+    let children: Vec<rowan::NodeOrToken<rowan::GreenNode, rowan::GreenToken>> =
+      vec![];
+    Self(SyntaxNode::new_root(rowan::GreenNode::new(
+      SK::Root.into(),
+      children,
+    )))
+  }
+  #[doc = r" Returns a new node with the children, or an error if they do not fit"]
+  #[doc = r" the grammar. Trivia may appear anywhere among the children."]
+  pub fn checked_new(
+    children: Vec<rowan::NodeOrToken<rowan::GreenNode, rowan::GreenToken>>,
+  ) -> Result<Self, BuildError> {
+    check_children(
+      SK::Root,
+      &[ChildRule {
+        kinds: &[SK::LetStmt, SK::ExprStmt],
+        pred: |_| true,
+        card: Card::Many,
+      }],
+      &children,
+    )?;
+    Ok(Self(SyntaxNode::new_root(rowan::GreenNode::new(
+      SK::Root.into(),
+      children,
+    ))))
+  }
+}
+impl HasLanguage for Root {
+  type Language = Toy;
+}
+impl CanCast for Root {
+  fn can_cast(kind: SK) -> bool {
+    matches!(kind, SK::Root)
+  }
+}
+impl TryFrom<SyntaxNode> for Root {
+  type Error = ();
+  fn try_from(node: SyntaxNode) -> Result<Self, Self::Error> {
+    if node.kind() == SK::Root {
+      Ok(Self(node))
+    } else {
+      Err(())
+    }
+  }
+}
+impl TryFrom<&SyntaxNode> for Root {
+  type Error = ();
+  fn try_from(node: &SyntaxNode) -> Result<Self, Self::Error> {
+    if node.kind() == SK::Root {
+      Ok(Self(node.clone()))
+    } else {
+      Err(())
+    }
+  }
+}
+impl TryFrom<&SyntaxElement> for Root {
+  type Error = ();
+  fn try_from(elem: &SyntaxElement) -> Result<Self, Self::Error> {
+    Self::try_from(elem.as_node().ok_or(())?)
+  }
+}
+impl AsRef<SyntaxNode> for Root {
+  fn as_ref(&self) -> &SyntaxNode {
+    &self.0
+  }
+}
+impl std::fmt::Display for Root {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    std::fmt::Display::fmt(&self.0.text(), f)
+  }
+}
+impl Root {
+  #[doc = r" Returns a hash of the kinds of the nodes and the kinds and text of"]
+  #[doc = r" the non-trivia tokens in this. See the free `fingerprint`."]
+  pub fn fingerprint(&self) -> u64 {
+    fingerprint(self.as_ref())
+  }
+}
+pub struct LetStmt(SyntaxNode);
+impl LetStmt {
+  pub fn let_kw(&self) -> Option<SyntaxToken> {
+    nth_token(self, SK::LetKw, 0usize, |_| true)
+  }
+  pub fn name(&self) -> Option<SyntaxToken> {
+    nth_token(self, SK::Name, 0usize, |_| true)
+  }
+  pub fn eq(&self) -> Option<SyntaxToken> {
+    nth_token(self, SK::Eq, 0usize, |_| true)
+  }
+  pub fn expr(&self) -> Option<Expr> {
+    nth_node_child(self, 0usize)
+  }
+  pub fn semicolon(&self) -> Option<SyntaxToken> {
+    nth_token(self, SK::Semicolon, 0usize, |_| true)
+  }
+  #[doc = r" Returns a new node with the children, or an error if they do not fit"]
+  #[doc = r" the grammar. Trivia may appear anywhere among the children."]
+  pub fn checked_new(
+    children: Vec<rowan::NodeOrToken<rowan::GreenNode, rowan::GreenToken>>,
+  ) -> Result<Self, BuildError> {
+    check_children(
+      SK::LetStmt,
+      &[
+        ChildRule {
+          kinds: &[SK::LetKw],
+          pred: |_| true,
+          card: Card::One,
+        },
+        ChildRule {
+          kinds: &[SK::Name],
+          pred: |_| true,
+          card: Card::One,
+        },
+        ChildRule {
+          kinds: &[SK::Eq],
+          pred: |_| true,
+          card: Card::One,
+        },
+        ChildRule {
+          kinds: &[
+            SK::IntExpr,
+            SK::NameExpr,
+            SK::ParenExpr,
+            SK::AddExpr,
+            SK::CallExpr,
+          ],
+          pred: |_| true,
+          card: Card::One,
+        },
+        ChildRule {
+          kinds: &[SK::Semicolon],
+          pred: |_| true,
+          card: Card::One,
+        },
+      ],
+      &children,
+    )?;
+    Ok(Self(SyntaxNode::new_root(rowan::GreenNode::new(
+      SK::LetStmt.into(),
+      children,
+    ))))
+  }
+}
+impl HasLanguage for LetStmt {
+  type Language = Toy;
+}
+impl CanCast for LetStmt {
+  fn can_cast(kind: SK) -> bool {
+    matches!(kind, SK::LetStmt)
+  }
+}
+impl TryFrom<SyntaxNode> for LetStmt {
+  type Error = ();
+  fn try_from(node: SyntaxNode) -> Result<Self, Self::Error> {
+    if node.kind() == SK::LetStmt {
+      Ok(Self(node))
+    } else {
+      Err(())
+    }
+  }
+}
+impl TryFrom<&SyntaxNode> for LetStmt {
+  type Error = ();
+  fn try_from(node: &SyntaxNode) -> Result<Self, Self::Error> {
+    if node.kind() == SK::LetStmt {
+      Ok(Self(node.clone()))
+    } else {
+      Err(())
+    }
+  }
+}
+impl TryFrom<&SyntaxElement> for LetStmt {
+  type Error = ();
+  fn try_from(elem: &SyntaxElement) -> Result<Self, Self::Error> {
+    Self::try_from(elem.as_node().ok_or(())?)
+  }
+}
+impl AsRef<SyntaxNode> for LetStmt {
+  fn as_ref(&self) -> &SyntaxNode {
+    &self.0
+  }
+}
+impl std::fmt::Display for LetStmt {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    std::fmt::Display::fmt(&self.0.text(), f)
+  }
+}
+impl LetStmt {
+  #[doc = r" Returns a hash of the kinds of the nodes and the kinds and text of"]
+  #[doc = r" the non-trivia tokens in this. See the free `fingerprint`."]
+  pub fn fingerprint(&self) -> u64 {
+    fingerprint(self.as_ref())
+  }
+}
+pub struct ExprStmt(SyntaxNode);
+impl ExprStmt {
+  pub fn expr(&self) -> Option<Expr> {
+    nth_node_child(self, 0usize)
+  }
+  pub fn semicolon(&self) -> Option<SyntaxToken> {
+    nth_token(self, SK::Semicolon, 0usize, |_| true)
+  }
+  #[doc = r" Returns a new node with the children, or an error if they do not fit"]
+  #[doc = r" the grammar. Trivia may appear anywhere among the children."]
+  pub fn checked_new(
+    children: Vec<rowan::NodeOrToken<rowan::GreenNode, rowan::GreenToken>>,
+  ) -> Result<Self, BuildError> {
+    check_children(
+      SK::ExprStmt,
+      &[
+        ChildRule {
+          kinds: &[
+            SK::IntExpr,
+            SK::NameExpr,
+            SK::ParenExpr,
+            SK::AddExpr,
+            SK::CallExpr,
+          ],
+          pred: |_| true,
+          card: Card::One,
+        },
+        ChildRule {
+          kinds: &[SK::Semicolon],
+          pred: |_| true,
+          card: Card::One,
+        },
+      ],
+      &children,
+    )?;
+    Ok(Self(SyntaxNode::new_root(rowan::GreenNode::new(
+      SK::ExprStmt.into(),
+      children,
+    ))))
+  }
+}
+impl HasLanguage for ExprStmt {
+  type Language = Toy;
+}
+impl CanCast for ExprStmt {
+  fn can_cast(kind: SK) -> bool {
+    matches!(kind, SK::ExprStmt)
+  }
+}
+impl TryFrom<SyntaxNode> for ExprStmt {
+  type Error = ();
+  fn try_from(node: SyntaxNode) -> Result<Self, Self::Error> {
+    if node.kind() == SK::ExprStmt {
+      Ok(Self(node))
+    } else {
+      Err(())
+    }
+  }
+}
+impl TryFrom<&SyntaxNode> for ExprStmt {
+  type Error = ();
+  fn try_from(node: &SyntaxNode) -> Result<Self, Self::Error> {
+    if node.kind() == SK::ExprStmt {
+      Ok(Self(node.clone()))
+    } else {
+      Err(())
+    }
+  }
+}
+impl TryFrom<&SyntaxElement> for ExprStmt {
+  type Error = ();
+  fn try_from(elem: &SyntaxElement) -> Result<Self, Self::Error> {
+    Self::try_from(elem.as_node().ok_or(())?)
+  }
+}
+impl AsRef<SyntaxNode> for ExprStmt {
+  fn as_ref(&self) -> &SyntaxNode {
+    &self.0
+  }
+}
+impl std::fmt::Display for ExprStmt {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    std::fmt::Display::fmt(&self.0.text(), f)
+  }
+}
+impl ExprStmt {
+  #[doc = r" Returns a hash of the kinds of the nodes and the kinds and text of"]
+  #[doc = r" the non-trivia tokens in this. See the free `fingerprint`."]
+  pub fn fingerprint(&self) -> u64 {
+    fingerprint(self.as_ref())
+  }
+}
+pub struct IntExpr(SyntaxNode);
+impl IntExpr {
+  pub fn int(&self) -> Option<SyntaxToken> {
+    nth_token(self, SK::Int, 0usize, |_| true)
+  }
+  #[doc = r" Returns a new node with the children, or an error if they do not fit"]
+  #[doc = r" the grammar. Trivia may appear anywhere among the children."]
+  pub fn checked_new(
+    children: Vec<rowan::NodeOrToken<rowan::GreenNode, rowan::GreenToken>>,
+  ) -> Result<Self, BuildError> {
+    check_children(
+      SK::IntExpr,
+      &[ChildRule {
+        kinds: &[SK::Int],
+        pred: |_| true,
+        card: Card::One,
+      }],
+      &children,
+    )?;
+    Ok(Self(SyntaxNode::new_root(rowan::GreenNode::new(
+      SK::IntExpr.into(),
+      children,
+    ))))
+  }
+}
+impl HasLanguage for IntExpr {
+  type Language = Toy;
+}
+impl CanCast for IntExpr {
+  fn can_cast(kind: SK) -> bool {
+    matches!(kind, SK::IntExpr)
+  }
+}
+impl TryFrom<SyntaxNode> for IntExpr {
+  type Error = ();
+  fn try_from(node: SyntaxNode) -> Result<Self, Self::Error> {
+    if node.kind() == SK::IntExpr {
+      Ok(Self(node))
+    } else {
+      Err(())
+    }
+  }
+}
+impl TryFrom<&SyntaxNode> for IntExpr {
+  type Error = ();
+  fn try_from(node: &SyntaxNode) -> Result<Self, Self::Error> {
+    if node.kind() == SK::IntExpr {
+      Ok(Self(node.clone()))
+    } else {
+      Err(())
+    }
+  }
+}
+impl TryFrom<&SyntaxElement> for IntExpr {
+  type Error = ();
+  fn try_from(elem: &SyntaxElement) -> Result<Self, Self::Error> {
+    Self::try_from(elem.as_node().ok_or(())?)
+  }
+}
+impl AsRef<SyntaxNode> for IntExpr {
+  fn as_ref(&self) -> &SyntaxNode {
+    &self.0
+  }
+}
+impl std::fmt::Display for IntExpr {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    std::fmt::Display::fmt(&self.0.text(), f)
+  }
+}
+impl IntExpr {
+  #[doc = r" Returns a hash of the kinds of the nodes and the kinds and text of"]
+  #[doc = r" the non-trivia tokens in this. See the free `fingerprint`."]
+  pub fn fingerprint(&self) -> u64 {
+    fingerprint(self.as_ref())
+  }
+}
+pub struct NameExpr(SyntaxNode);
+impl NameExpr {
+  pub fn name(&self) -> Option<SyntaxToken> {
+    nth_token(self, SK::Name, 0usize, |_| true)
+  }
+  #[doc = r" Returns a new node with the children, or an error if they do not fit"]
+  #[doc = r" the grammar. Trivia may appear anywhere among the children."]
+  pub fn checked_new(
+    children: Vec<rowan::NodeOrToken<rowan::GreenNode, rowan::GreenToken>>,
+  ) -> Result<Self, BuildError> {
+    check_children(
+      SK::NameExpr,
+      &[ChildRule {
+        kinds: &[SK::Name],
+        pred: |_| true,
+        card: Card::One,
+      }],
+      &children,
+    )?;
+    Ok(Self(SyntaxNode::new_root(rowan::GreenNode::new(
+      SK::NameExpr.into(),
+      children,
+    ))))
+  }
+}
+impl HasLanguage for NameExpr {
+  type Language = Toy;
+}
+impl CanCast for NameExpr {
+  fn can_cast(kind: SK) -> bool {
+    matches!(kind, SK::NameExpr)
+  }
+}
+impl TryFrom<SyntaxNode> for NameExpr {
+  type Error = ();
+  fn try_from(node: SyntaxNode) -> Result<Self, Self::Error> {
+    if node.kind() == SK::NameExpr {
+      Ok(Self(node))
+    } else {
+      Err(())
+    }
+  }
+}
+impl TryFrom<&SyntaxNode> for NameExpr {
+  type Error = ();
+  fn try_from(node: &SyntaxNode) -> Result<Self, Self::Error> {
+    if node.kind() == SK::NameExpr {
+      Ok(Self(node.clone()))
+    } else {
+      Err(())
+    }
+  }
+}
+impl TryFrom<&SyntaxElement> for NameExpr {
+  type Error = ();
+  fn try_from(elem: &SyntaxElement) -> Result<Self, Self::Error> {
+    Self::try_from(elem.as_node().ok_or(())?)
+  }
+}
+impl AsRef<SyntaxNode> for NameExpr {
+  fn as_ref(&self) -> &SyntaxNode {
+    &self.0
+  }
+}
+impl std::fmt::Display for NameExpr {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    std::fmt::Display::fmt(&self.0.text(), f)
+  }
+}
+impl NameExpr {
+  #[doc = r" Returns a hash of the kinds of the nodes and the kinds and text of"]
+  #[doc = r" the non-trivia tokens in this. See the free `fingerprint`."]
+  pub fn fingerprint(&self) -> u64 {
+    fingerprint(self.as_ref())
+  }
+}
+pub struct ParenExpr(SyntaxNode);
+impl ParenExpr {
+  pub fn l_round(&self) -> Option<SyntaxToken> {
+    nth_token(self, SK::LRound, 0usize, |_| true)
+  }
+  pub fn expr(&self) -> Option<Expr> {
+    nth_node_child(self, 0usize)
+  }
+  pub fn r_round(&self) -> Option<SyntaxToken> {
+    nth_token(self, SK::RRound, 0usize, |_| true)
+  }
+  #[doc = r" Returns a new node with the children, or an error if they do not fit"]
+  #[doc = r" the grammar. Trivia may appear anywhere among the children."]
+  pub fn checked_new(
+    children: Vec<rowan::NodeOrToken<rowan::GreenNode, rowan::GreenToken>>,
+  ) -> Result<Self, BuildError> {
+    check_children(
+      SK::ParenExpr,
+      &[
+        ChildRule {
+          kinds: &[SK::LRound],
+          pred: |_| true,
+          card: Card::One,
+        },
+        ChildRule {
+          kinds: &[
+            SK::IntExpr,
+            SK::NameExpr,
+            SK::ParenExpr,
+            SK::AddExpr,
+            SK::CallExpr,
+          ],
+          pred: |_| true,
+          card: Card::One,
+        },
+        ChildRule {
+          kinds: &[SK::RRound],
+          pred: |_| true,
+          card: Card::One,
+        },
+      ],
+      &children,
+    )?;
+    Ok(Self(SyntaxNode::new_root(rowan::GreenNode::new(
+      SK::ParenExpr.into(),
+      children,
+    ))))
+  }
+}
+impl HasLanguage for ParenExpr {
+  type Language = Toy;
+}
+impl CanCast for ParenExpr {
+  fn can_cast(kind: SK) -> bool {
+    matches!(kind, SK::ParenExpr)
+  }
+}
+impl TryFrom<SyntaxNode> for ParenExpr {
+  type Error = ();
+  fn try_from(node: SyntaxNode) -> Result<Self, Self::Error> {
+    if node.kind() == SK::ParenExpr {
+      Ok(Self(node))
+    } else {
+      Err(())
+    }
+  }
+}
+impl TryFrom<&SyntaxNode> for ParenExpr {
+  type Error = ();
+  fn try_from(node: &SyntaxNode) -> Result<Self, Self::Error> {
+    if node.kind() == SK::ParenExpr {
+      Ok(Self(node.clone()))
+    } else {
+      Err(())
+    }
+  }
+}
+impl TryFrom<&SyntaxElement> for ParenExpr {
+  type Error = ();
+  fn try_from(elem: &SyntaxElement) -> Result<Self, Self::Error> {
+    Self::try_from(elem.as_node().ok_or(())?)
+  }
+}
+impl AsRef<SyntaxNode> for ParenExpr {
+  fn as_ref(&self) -> &SyntaxNode {
+    &self.0
+  }
+}
+impl std::fmt::Display for ParenExpr {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    std::fmt::Display::fmt(&self.0.text(), f)
+  }
+}
+impl ParenExpr {
+  #[doc = r" Returns a hash of the kinds of the nodes and the kinds and text of"]
+  #[doc = r" the non-trivia tokens in this. See the free `fingerprint`."]
+  pub fn fingerprint(&self) -> u64 {
+    fingerprint(self.as_ref())
+  }
+}
+pub struct AddExpr(SyntaxNode);
+impl AddExpr {
+  pub fn lhs(&self) -> Option<Expr> {
+    nth_node_child(self, 0usize)
+  }
+  pub fn plus(&self) -> Option<SyntaxToken> {
+    nth_token(self, SK::Plus, 0usize, |_| true)
+  }
+  pub fn rhs(&self) -> Option<Expr> {
+    nth_node_child(self, 1usize)
+  }
+  #[doc = r" Returns a new node with the children, or an error if they do not fit"]
+  #[doc = r" the grammar. Trivia may appear anywhere among the children."]
+  pub fn checked_new(
+    children: Vec<rowan::NodeOrToken<rowan::GreenNode, rowan::GreenToken>>,
+  ) -> Result<Self, BuildError> {
+    check_children(
+      SK::AddExpr,
+      &[
+        ChildRule {
+          kinds: &[
+            SK::IntExpr,
+            SK::NameExpr,
+            SK::ParenExpr,
+            SK::AddExpr,
+            SK::CallExpr,
+          ],
+          pred: |_| true,
+          card: Card::One,
+        },
+        ChildRule {
+          kinds: &[SK::Plus],
+          pred: |_| true,
+          card: Card::One,
+        },
+        ChildRule {
+          kinds: &[
+            SK::IntExpr,
+            SK::NameExpr,
+            SK::ParenExpr,
+            SK::AddExpr,
+            SK::CallExpr,
+          ],
+          pred: |_| true,
+          card: Card::One,
+        },
+      ],
+      &children,
+    )?;
+    Ok(Self(SyntaxNode::new_root(rowan::GreenNode::new(
+      SK::AddExpr.into(),
+      children,
+    ))))
+  }
+}
+impl HasLanguage for AddExpr {
+  type Language = Toy;
+}
+impl CanCast for AddExpr {
+  fn can_cast(kind: SK) -> bool {
+    matches!(kind, SK::AddExpr)
+  }
+}
+impl TryFrom<SyntaxNode> for AddExpr {
+  type Error = ();
+  fn try_from(node: SyntaxNode) -> Result<Self, Self::Error> {
+    if node.kind() == SK::AddExpr {
+      Ok(Self(node))
+    } else {
+      Err(())
+    }
+  }
+}
+impl TryFrom<&SyntaxNode> for AddExpr {
+  type Error = ();
+  fn try_from(node: &SyntaxNode) -> Result<Self, Self::Error> {
+    if node.kind() == SK::AddExpr {
+      Ok(Self(node.clone()))
+    } else {
+      Err(())
+    }
+  }
+}
+impl TryFrom<&SyntaxElement> for AddExpr {
+  type Error = ();
+  fn try_from(elem: &SyntaxElement) -> Result<Self, Self::Error> {
+    Self::try_from(elem.as_node().ok_or(())?)
+  }
+}
+impl AsRef<SyntaxNode> for AddExpr {
+  fn as_ref(&self) -> &SyntaxNode {
+    &self.0
+  }
+}
+impl std::fmt::Display for AddExpr {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    std::fmt::Display::fmt(&self.0.text(), f)
+  }
+}
+impl AddExpr {
+  #[doc = r" Returns a hash of the kinds of the nodes and the kinds and text of"]
+  #[doc = r" the non-trivia tokens in this. See the free `fingerprint`."]
+  pub fn fingerprint(&self) -> u64 {
+    fingerprint(self.as_ref())
+  }
+}
+pub struct CallExpr(SyntaxNode);
+impl CallExpr {
+  pub fn name(&self) -> Option<SyntaxToken> {
+    nth_token(self, SK::Name, 0usize, |_| true)
+  }
+  pub fn arg_list(&self) -> Option<ArgList> {
+    nth_node_child(self, 0usize)
+  }
+  #[doc = r" Returns a new node with the children, or an error if they do not fit"]
+  #[doc = r" the grammar. Trivia may appear anywhere among the children."]
+  pub fn checked_new(
+    children: Vec<rowan::NodeOrToken<rowan::GreenNode, rowan::GreenToken>>,
+  ) -> Result<Self, BuildError> {
+    check_children(
+      SK::CallExpr,
+      &[
+        ChildRule {
+          kinds: &[SK::Name],
+          pred: |_| true,
+          card: Card::One,
+        },
+        ChildRule {
+          kinds: &[SK::ArgList],
+          pred: |_| true,
+          card: Card::One,
+        },
+      ],
+      &children,
+    )?;
+    Ok(Self(SyntaxNode::new_root(rowan::GreenNode::new(
+      SK::CallExpr.into(),
+      children,
+    ))))
+  }
+}
+impl HasLanguage for CallExpr {
+  type Language = Toy;
+}
+impl CanCast for CallExpr {
+  fn can_cast(kind: SK) -> bool {
+    matches!(kind, SK::CallExpr)
+  }
+}
+impl TryFrom<SyntaxNode> for CallExpr {
+  type Error = ();
+  fn try_from(node: SyntaxNode) -> Result<Self, Self::Error> {
+    if node.kind() == SK::CallExpr {
+      Ok(Self(node))
+    } else {
+      Err(())
+    }
+  }
+}
+impl TryFrom<&SyntaxNode> for CallExpr {
+  type Error = ();
+  fn try_from(node: &SyntaxNode) -> Result<Self, Self::Error> {
+    if node.kind() == SK::CallExpr {
+      Ok(Self(node.clone()))
+    } else {
+      Err(())
+    }
+  }
+}
+impl TryFrom<&SyntaxElement> for CallExpr {
+  type Error = ();
+  fn try_from(elem: &SyntaxElement) -> Result<Self, Self::Error> {
+    Self::try_from(elem.as_node().ok_or(())?)
+  }
+}
+impl AsRef<SyntaxNode> for CallExpr {
+  fn as_ref(&self) -> &SyntaxNode {
+    &self.0
+  }
+}
+impl std::fmt::Display for CallExpr {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    std::fmt::Display::fmt(&self.0.text(), f)
+  }
+}
+impl CallExpr {
+  #[doc = r" Returns a hash of the kinds of the nodes and the kinds and text of"]
+  #[doc = r" the non-trivia tokens in this. See the free `fingerprint`."]
+  pub fn fingerprint(&self) -> u64 {
+    fingerprint(self.as_ref())
+  }
+}
+pub struct ArgList(SyntaxNode);
+impl ArgList {
+  pub fn l_round(&self) -> Option<SyntaxToken> {
+    nth_token(self, SK::LRound, 0usize, |_| true)
+  }
+  pub fn args(&self) -> impl Iterator<Item = Arg> {
+    node_children(self)
+  }
+  pub fn r_round(&self) -> Option<SyntaxToken> {
+    nth_token(self, SK::RRound, 0usize, |_| true)
+  }
+  #[doc = r" Returns a new node with only the tokens which must be present."]
+  pub fn empty() -> Self {
+    let children: Vec<rowan::NodeOrToken<rowan::GreenNode, rowan::GreenToken>> = vec![
+      rowan::NodeOrToken::Token(rowan::GreenToken::new(SK::LRound.into(), "(")),
+      rowan::NodeOrToken::Token(rowan::GreenToken::new(SK::RRound.into(), ")")),
+    ];
+    Self(SyntaxNode::new_root(rowan::GreenNode::new(
+      SK::ArgList.into(),
+      children,
+    )))
+  }
+  #[doc = r" Returns a new node with the children, or an error if they do not fit"]
+  #[doc = r" the grammar. Trivia may appear anywhere among the children."]
+  pub fn checked_new(
+    children: Vec<rowan::NodeOrToken<rowan::GreenNode, rowan::GreenToken>>,
+  ) -> Result<Self, BuildError> {
+    check_children(
+      SK::ArgList,
+      &[
+        ChildRule {
+          kinds: &[SK::LRound],
+          pred: |_| true,
+          card: Card::One,
+        },
+        ChildRule {
+          kinds: &[SK::Arg],
+          pred: |_| true,
+          card: Card::Many,
+        },
+        ChildRule {
+          kinds: &[SK::RRound],
+          pred: |_| true,
+          card: Card::One,
+        },
+      ],
+      &children,
+    )?;
+    Ok(Self(SyntaxNode::new_root(rowan::GreenNode::new(
+      SK::ArgList.into(),
+      children,
+    ))))
+  }
+}
+impl HasLanguage for ArgList {
+  type Language = Toy;
+}
+impl CanCast for ArgList {
+  fn can_cast(kind: SK) -> bool {
+    matches!(kind, SK::ArgList)
+  }
+}
+impl TryFrom<SyntaxNode> for ArgList {
+  type Error = ();
+  fn try_from(node: SyntaxNode) -> Result<Self, Self::Error> {
+    if node.kind() == SK::ArgList {
+      Ok(Self(node))
+    } else {
+      Err(())
+    }
+  }
+}
+impl TryFrom<&SyntaxNode> for ArgList {
+  type Error = ();
+  fn try_from(node: &SyntaxNode) -> Result<Self, Self::Error> {
+    if node.kind() == SK::ArgList {
+      Ok(Self(node.clone()))
+    } else {
+      Err(())
+    }
+  }
+}
+impl TryFrom<&SyntaxElement> for ArgList {
+  type Error = ();
+  fn try_from(elem: &SyntaxElement) -> Result<Self, Self::Error> {
+    Self::try_from(elem.as_node().ok_or(())?)
+  }
+}
+impl AsRef<SyntaxNode> for ArgList {
+  fn as_ref(&self) -> &SyntaxNode {
+    &self.0
+  }
+}
+impl std::fmt::Display for ArgList {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    std::fmt::Display::fmt(&self.0.text(), f)
+  }
+}
+impl ArgList {
+  #[doc = r" Returns a hash of the kinds of the nodes and the kinds and text of"]
+  #[doc = r" the non-trivia tokens in this. See the free `fingerprint`."]
+  pub fn fingerprint(&self) -> u64 {
+    fingerprint(self.as_ref())
+  }
+}
+pub struct Arg(SyntaxNode);
+impl Arg {
+  pub fn expr(&self) -> Option<Expr> {
+    nth_node_child(self, 0usize)
+  }
+  pub fn comma(&self) -> Option<SyntaxToken> {
+    nth_token(self, SK::Comma, 0usize, |_| true)
+  }
+  #[doc = r" Returns a new node with the children, or an error if they do not fit"]
+  #[doc = r" the grammar. Trivia may appear anywhere among the children."]
+  pub fn checked_new(
+    children: Vec<rowan::NodeOrToken<rowan::GreenNode, rowan::GreenToken>>,
+  ) -> Result<Self, BuildError> {
+    check_children(
+      SK::Arg,
+      &[
+        ChildRule {
+          kinds: &[
+            SK::IntExpr,
+            SK::NameExpr,
+            SK::ParenExpr,
+            SK::AddExpr,
+            SK::CallExpr,
+          ],
+          pred: |_| true,
+          card: Card::One,
+        },
+        ChildRule {
+          kinds: &[SK::Comma],
+          pred: |_| true,
+          card: Card::Opt,
+        },
+      ],
+      &children,
+    )?;
+    Ok(Self(SyntaxNode::new_root(rowan::GreenNode::new(
+      SK::Arg.into(),
+      children,
+    ))))
+  }
+}
+impl HasLanguage for Arg {
+  type Language = Toy;
+}
+impl CanCast for Arg {
+  fn can_cast(kind: SK) -> bool {
+    matches!(kind, SK::Arg)
+  }
+}
+impl TryFrom<SyntaxNode> for Arg {
+  type Error = ();
+  fn try_from(node: SyntaxNode) -> Result<Self, Self::Error> {
+    if node.kind() == SK::Arg {
+      Ok(Self(node))
+    } else {
+      Err(())
+    }
+  }
+}
+impl TryFrom<&SyntaxNode> for Arg {
+  type Error = ();
+  fn try_from(node: &SyntaxNode) -> Result<Self, Self::Error> {
+    if node.kind() == SK::Arg {
+      Ok(Self(node.clone()))
+    } else {
+      Err(())
+    }
+  }
+}
+impl TryFrom<&SyntaxElement> for Arg {
+  type Error = ();
+  fn try_from(elem: &SyntaxElement) -> Result<Self, Self::Error> {
+    Self::try_from(elem.as_node().ok_or(())?)
+  }
+}
+impl AsRef<SyntaxNode> for Arg {
+  fn as_ref(&self) -> &SyntaxNode {
+    &self.0
+  }
+}
+impl std::fmt::Display for Arg {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    std::fmt::Display::fmt(&self.0.text(), f)
+  }
+}
+impl Arg {
+  #[doc = r" Returns a hash of the kinds of the nodes and the kinds and text of"]
+  #[doc = r" the non-trivia tokens in this. See the free `fingerprint`."]
+  pub fn fingerprint(&self) -> u64 {
+    fingerprint(self.as_ref())
+  }
+}
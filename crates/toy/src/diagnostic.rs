@@ -0,0 +1,35 @@
+//! Diagnostics from lexing and parsing.
+
+use crate::{lex, parse};
+use rowan::TextRange;
+
+/// A diagnostic, to be shown to the user.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+  /// The range.
+  pub range: TextRange,
+  /// The message.
+  pub message: String,
+}
+
+/// Returns the diagnostics for the errors, ordered by their start.
+pub fn get(lex: &[lex::Error], parse: &[parse::Error]) -> Vec<Diagnostic> {
+  let lex = lex.iter().map(|e| Diagnostic {
+    range: e.range,
+    message: "invalid character".to_owned(),
+  });
+  let parse = parse.iter().map(|e| {
+    let expected: Vec<_> = e
+      .expected
+      .iter()
+      .map(|k| k.token_desc().unwrap_or_else(|| k.name()))
+      .collect();
+    Diagnostic {
+      range: e.range,
+      message: format!("expected {}", expected.join(" or ")),
+    }
+  });
+  let mut ret: Vec<_> = lex.chain(parse).collect();
+  ret.sort_by_key(|d| d.range.start());
+  ret
+}
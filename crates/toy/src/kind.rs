@@ -0,0 +1,387 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u16)]
+pub enum SyntaxKind {
+  Whitespace,
+  LineComment,
+  Invalid,
+  Int,
+  Name,
+  LetKw,
+  LRound,
+  RRound,
+  Plus,
+  Comma,
+  Semicolon,
+  Eq,
+  Root,
+  LetStmt,
+  ExprStmt,
+  IntExpr,
+  NameExpr,
+  ParenExpr,
+  AddExpr,
+  CallExpr,
+  ArgList,
+  Arg,
+}
+impl SyntaxKind {
+  #[doc = r" The number of kinds."]
+  pub const N: usize = 22usize;
+  #[doc = r" A hash of the grammar and the kinds, which changes whenever either"]
+  #[doc = r" does, for e.g. the keys of on-disk caches of syntax trees."]
+  pub const GRAMMAR_HASH: u64 = 1461498971008268631u64;
+  #[doc = r" All the kinds, in order."]
+  pub const ALL: [Self; Self::N] = [
+    Self::Whitespace,
+    Self::LineComment,
+    Self::Invalid,
+    Self::Int,
+    Self::Name,
+    Self::LetKw,
+    Self::LRound,
+    Self::RRound,
+    Self::Plus,
+    Self::Comma,
+    Self::Semicolon,
+    Self::Eq,
+    Self::Root,
+    Self::LetStmt,
+    Self::ExprStmt,
+    Self::IntExpr,
+    Self::NameExpr,
+    Self::ParenExpr,
+    Self::AddExpr,
+    Self::CallExpr,
+    Self::ArgList,
+    Self::Arg,
+  ];
+  #[doc = r" The last kind for a token. All the kinds for tokens are at most this."]
+  pub const LAST_TOKEN_KIND: Self = Self::Eq;
+  #[doc = r" The first kind for a node. All the kinds for nodes are at least this."]
+  pub const FIRST_NODE_KIND: Self = Self::Root;
+  pub const fn is_token(&self) -> bool {
+    *self as u16 <= Self::LAST_TOKEN_KIND as u16
+  }
+  pub const fn is_node(&self) -> bool {
+    *self as u16 >= Self::FIRST_NODE_KIND as u16
+  }
+  #[doc = r" All the punctuation, longest first."]
+  pub const PUNCTUATION: [(&'static [u8], Self); 6usize] = [
+    (b"(", Self::LRound),
+    (b")", Self::RRound),
+    (b"+", Self::Plus),
+    (b",", Self::Comma),
+    (b";", Self::Semicolon),
+    (b"=", Self::Eq),
+  ];
+  #[doc = r" The length of the longest punctuation."]
+  pub const MAX_PUNCTUATION_LEN: usize = 1usize;
+  #[doc = r" The punctuation of each length, at that index, sorted."]
+  pub const PUNCTUATION_BY_LEN: [&'static [(&'static [u8], Self)];
+    Self::MAX_PUNCTUATION_LEN + 1] = [
+    &[],
+    &[
+      (b"(", Self::LRound),
+      (b")", Self::RRound),
+      (b"+", Self::Plus),
+      (b",", Self::Comma),
+      (b";", Self::Semicolon),
+      (b"=", Self::Eq),
+    ],
+  ];
+  #[doc = r" The length of the longest keyword."]
+  pub const MAX_KEYWORD_LEN: usize = 3usize;
+  #[doc = r" The keywords of each length, at that index, sorted."]
+  pub const KEYWORDS_BY_LEN: [&'static [(&'static [u8], Self)];
+    Self::MAX_KEYWORD_LEN + 1] = [&[], &[], &[], &[(b"let", Self::LetKw)]];
+  pub const fn keyword(bs: &[u8]) -> Option<Self> {
+    let ret = match bs {
+      b"let" => Self::LetKw,
+      _ => return None,
+    };
+    Some(ret)
+  }
+  #[doc = r" Returns the kind of the punctuation which is exactly `bs`."]
+  pub const fn punctuation(bs: &[u8]) -> Option<Self> {
+    let ret = match bs {
+      b"(" => Self::LRound,
+      b")" => Self::RRound,
+      b"+" => Self::Plus,
+      b"," => Self::Comma,
+      b";" => Self::Semicolon,
+      b"=" => Self::Eq,
+      _ => return None,
+    };
+    Some(ret)
+  }
+  pub const fn token_desc(&self) -> Option<&'static str> {
+    let ret = match *self {
+      Self::LRound => "`(`",
+      Self::RRound => "`)`",
+      Self::Plus => "`+`",
+      Self::Comma => "`,`",
+      Self::Semicolon => "`;`",
+      Self::Eq => "`=`",
+      Self::LetKw => "`let`",
+      Self::Int => "an integer",
+      Self::Name => "a name",
+      _ => return None,
+    };
+    Some(ret)
+  }
+  pub const fn canonical_text(&self) -> Option<&'static str> {
+    let ret = match *self {
+      Self::LRound => "(",
+      Self::RRound => ")",
+      Self::Plus => "+",
+      Self::Comma => ",",
+      Self::Semicolon => ";",
+      Self::Eq => "=",
+      Self::LetKw => "let",
+      _ => return None,
+    };
+    Some(ret)
+  }
+  pub const fn example_text(&self) -> Option<&'static str> {
+    let ret = match *self {
+      Self::Int => "1",
+      Self::Name => "x",
+      _ => return self.canonical_text(),
+    };
+    Some(ret)
+  }
+  #[doc = r" Returns the text in the grammar of the token with this kind and the"]
+  #[doc = r" `text`. If several tokens in the grammar share this kind, the `text`"]
+  #[doc = r" tells them apart."]
+  #[allow(unused_variables)]
+  pub fn grammar_token(&self, text: &str) -> Option<&'static str> {
+    let ret = match *self {
+      Self::LRound => "(",
+      Self::RRound => ")",
+      Self::Plus => "+",
+      Self::Comma => ",",
+      Self::Semicolon => ";",
+      Self::Eq => "=",
+      Self::LetKw => "let",
+      Self::Int => "Int",
+      Self::Name => "Name",
+      _ => return None,
+    };
+    Some(ret)
+  }
+  #[doc = r" Returns a short description of the node, generated from its rule in"]
+  #[doc = r#" the grammar, for messages like "expected X while parsing Y"."#]
+  pub fn grammar_desc(&self) -> Option<&'static str> {
+    let ret = match *self {
+      Self::Root => "a root: stmt*",
+      Self::LetStmt => "a let stmt: `let` a name `=` expr `;`",
+      Self::ExprStmt => "an expr stmt: expr `;`",
+      Self::IntExpr => "an int expr: an integer",
+      Self::NameExpr => "a name expr: a name",
+      Self::ParenExpr => "a paren expr: `(` expr `)`",
+      Self::AddExpr => "an add expr: lhs `+` rhs",
+      Self::CallExpr => "a call expr: a name arg list",
+      Self::ArgList => "an arg list: `(` arg* `)`",
+      Self::Arg => "an arg: expr `,`?",
+      _ => return None,
+    };
+    Some(ret)
+  }
+  #[doc = r" Returns the kinds of the tokens which may appear directly inside a"]
+  #[doc = r" node of this kind, besides trivia, sorted. Tokens in child nodes are"]
+  #[doc = r" not directly inside."]
+  pub fn child_tokens(&self) -> &'static [Self] {
+    match *self {
+      Self::LetStmt => &[Self::Name, Self::LetKw, Self::Semicolon, Self::Eq],
+      Self::ExprStmt => &[Self::Semicolon],
+      Self::IntExpr => &[Self::Int],
+      Self::NameExpr => &[Self::Name],
+      Self::ParenExpr => &[Self::LRound, Self::RRound],
+      Self::AddExpr => &[Self::Plus],
+      Self::CallExpr => &[Self::Name],
+      Self::ArgList => &[Self::LRound, Self::RRound],
+      Self::Arg => &[Self::Comma],
+      _ => &[],
+    }
+  }
+  #[doc = r" Returns whether a token of the `token` kind may appear directly"]
+  #[doc = r" inside a node of this kind. Trivia is not considered."]
+  pub fn can_contain(&self, token: Self) -> bool {
+    self.child_tokens().binary_search(&token).is_ok()
+  }
+  pub fn name(&self) -> &'static str {
+    match *self {
+      Self::Whitespace => "Whitespace",
+      Self::LineComment => "LineComment",
+      Self::Invalid => "Invalid",
+      Self::Int => "Int",
+      Self::Name => "Name",
+      Self::LetKw => "LetKw",
+      Self::LRound => "LRound",
+      Self::RRound => "RRound",
+      Self::Plus => "Plus",
+      Self::Comma => "Comma",
+      Self::Semicolon => "Semicolon",
+      Self::Eq => "Eq",
+      Self::Root => "Root",
+      Self::LetStmt => "LetStmt",
+      Self::ExprStmt => "ExprStmt",
+      Self::IntExpr => "IntExpr",
+      Self::NameExpr => "NameExpr",
+      Self::ParenExpr => "ParenExpr",
+      Self::AddExpr => "AddExpr",
+      Self::CallExpr => "CallExpr",
+      Self::ArgList => "ArgList",
+      Self::Arg => "Arg",
+    }
+  }
+  pub fn from_name(s: &str) -> Option<Self> {
+    let ret = match s {
+      "Whitespace" => Self::Whitespace,
+      "LineComment" => Self::LineComment,
+      "Invalid" => Self::Invalid,
+      "Int" => Self::Int,
+      "Name" => Self::Name,
+      "LetKw" => Self::LetKw,
+      "LRound" => Self::LRound,
+      "RRound" => Self::RRound,
+      "Plus" => Self::Plus,
+      "Comma" => Self::Comma,
+      "Semicolon" => Self::Semicolon,
+      "Eq" => Self::Eq,
+      "Root" => Self::Root,
+      "LetStmt" => Self::LetStmt,
+      "ExprStmt" => Self::ExprStmt,
+      "IntExpr" => Self::IntExpr,
+      "NameExpr" => Self::NameExpr,
+      "ParenExpr" => Self::ParenExpr,
+      "AddExpr" => Self::AddExpr,
+      "CallExpr" => Self::CallExpr,
+      "ArgList" => Self::ArgList,
+      "Arg" => Self::Arg,
+      _ => return None,
+    };
+    Some(ret)
+  }
+  #[doc = r" Returns the stable ID of this kind, which does not change across"]
+  #[doc = r" re-generations."]
+  pub fn to_stable_u16(&self) -> u16 {
+    match *self {
+      Self::Whitespace => 0u16,
+      Self::LineComment => 1u16,
+      Self::Invalid => 2u16,
+      Self::Int => 3u16,
+      Self::Name => 4u16,
+      Self::LetKw => 5u16,
+      Self::LRound => 6u16,
+      Self::RRound => 7u16,
+      Self::Plus => 8u16,
+      Self::Comma => 9u16,
+      Self::Semicolon => 10u16,
+      Self::Eq => 11u16,
+      Self::Root => 12u16,
+      Self::LetStmt => 13u16,
+      Self::ExprStmt => 14u16,
+      Self::IntExpr => 15u16,
+      Self::NameExpr => 16u16,
+      Self::ParenExpr => 17u16,
+      Self::AddExpr => 18u16,
+      Self::CallExpr => 19u16,
+      Self::ArgList => 20u16,
+      Self::Arg => 21u16,
+    }
+  }
+  #[doc = r" Returns the kind with this stable ID, if any."]
+  pub fn from_stable_u16(id: u16) -> Option<Self> {
+    let ret = match id {
+      0u16 => Self::Whitespace,
+      1u16 => Self::LineComment,
+      2u16 => Self::Invalid,
+      3u16 => Self::Int,
+      4u16 => Self::Name,
+      5u16 => Self::LetKw,
+      6u16 => Self::LRound,
+      7u16 => Self::RRound,
+      8u16 => Self::Plus,
+      9u16 => Self::Comma,
+      10u16 => Self::Semicolon,
+      11u16 => Self::Eq,
+      12u16 => Self::Root,
+      13u16 => Self::LetStmt,
+      14u16 => Self::ExprStmt,
+      15u16 => Self::IntExpr,
+      16u16 => Self::NameExpr,
+      17u16 => Self::ParenExpr,
+      18u16 => Self::AddExpr,
+      19u16 => Self::CallExpr,
+      20u16 => Self::ArgList,
+      21u16 => Self::Arg,
+      _ => return None,
+    };
+    Some(ret)
+  }
+}
+impl token::Triviable for SyntaxKind {
+  fn is_trivia(&self) -> bool {
+    matches!(*self, Self::Whitespace | Self::LineComment | Self::Invalid)
+  }
+}
+impl ast_ptr::StableKind for SyntaxKind {
+  fn to_stable_u16(&self) -> u16 {
+    SyntaxKind::to_stable_u16(self)
+  }
+  fn from_stable_u16(id: u16) -> Option<Self> {
+    SyntaxKind::from_stable_u16(id)
+  }
+}
+#[doc = r" A map from every `SyntaxKind` to a `T`."]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KindMap<T>([T; SyntaxKind::N]);
+impl<T> KindMap<T> {
+  #[doc = r" Returns a map with `f(kind)` for every `kind`, called in order."]
+  pub fn from_fn<F>(f: F) -> Self
+  where
+    F: FnMut(SyntaxKind) -> T,
+  {
+    let xs: Vec<T> = SyntaxKind::ALL.iter().copied().map(f).collect();
+    match std::convert::TryFrom::try_from(xs) {
+      Ok(xs) => Self(xs),
+      Err(_) => unreachable!("not one value for each kind"),
+    }
+  }
+  #[doc = r" Returns an iterator over every kind and its value, in order."]
+  pub fn iter(&self) -> impl Iterator<Item = (SyntaxKind, &T)> {
+    SyntaxKind::ALL.iter().copied().zip(self.0.iter())
+  }
+}
+impl<T> std::ops::Index<SyntaxKind> for KindMap<T> {
+  type Output = T;
+  fn index(&self, kind: SyntaxKind) -> &T {
+    &self.0[kind as usize]
+  }
+}
+impl<T> std::ops::IndexMut<SyntaxKind> for KindMap<T> {
+  fn index_mut(&mut self, kind: SyntaxKind) -> &mut T {
+    &mut self.0[kind as usize]
+  }
+}
+impl From<SyntaxKind> for rowan::SyntaxKind {
+  fn from(kind: SyntaxKind) -> Self {
+    Self(kind as u16)
+  }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Toy {}
+impl rowan::Language for Toy {
+  type Kind = SyntaxKind;
+  fn kind_from_raw(raw: rowan::SyntaxKind) -> Self::Kind {
+    assert!(raw.0 <= SyntaxKind::Arg as u16);
+    unsafe { std::mem::transmute::<u16, SyntaxKind>(raw.0) }
+  }
+  fn kind_to_raw(kind: Self::Kind) -> rowan::SyntaxKind {
+    kind.into()
+  }
+}
+pub type SyntaxNode = rowan::SyntaxNode<Toy>;
+pub type SyntaxToken = rowan::SyntaxToken<Toy>;
+pub type SyntaxElement = rowan::SyntaxElement<Toy>;
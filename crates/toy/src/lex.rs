@@ -0,0 +1,87 @@
+//! Lexing text into tokens.
+
+use crate::kind::SyntaxKind as SK;
+use rowan::{TextRange, TextSize};
+use token::Token;
+
+/// The result of lexing.
+#[derive(Debug)]
+pub struct Lex<'input> {
+  /// The tokens, which together are exactly the input.
+  pub tokens: Vec<Token<'input, SK>>,
+  /// The errors.
+  pub errors: Vec<Error>,
+}
+
+/// An error from lexing: an invalid character.
+#[derive(Debug)]
+pub struct Error {
+  /// The range of the character.
+  pub range: TextRange,
+}
+
+/// Lexes the input.
+pub fn get(s: &str) -> Lex<'_> {
+  let bs = s.as_bytes();
+  let mut tokens = Vec::new();
+  let mut errors = Vec::new();
+  let mut idx = 0;
+  while idx < bs.len() {
+    let start = idx;
+    let kind = go(bs, &mut idx);
+    if kind == SK::Invalid {
+      let range = TextRange::new(text_size(start), text_size(idx));
+      errors.push(Error { range });
+    }
+    tokens.push(Token::new(kind, &s[start..idx]));
+  }
+  Lex { tokens, errors }
+}
+
+/// Advances `idx` past a token starting at `idx`, and returns its kind.
+fn go(bs: &[u8], idx: &mut usize) -> SK {
+  let b = bs[*idx];
+  if b.is_ascii_whitespace() {
+    advance_while(bs, idx, |b| b.is_ascii_whitespace());
+    return SK::Whitespace;
+  }
+  if bs[*idx..].starts_with(b"//") {
+    advance_while(bs, idx, |b| b != b'\n');
+    return SK::LineComment;
+  }
+  if b.is_ascii_digit() {
+    advance_while(bs, idx, |b| b.is_ascii_digit());
+    return SK::Int;
+  }
+  if b.is_ascii_alphabetic() || b == b'_' {
+    let start = *idx;
+    advance_while(bs, idx, |b| b.is_ascii_alphanumeric() || b == b'_');
+    return SK::keyword(&bs[start..*idx]).unwrap_or(SK::Name);
+  }
+  for len in (1..=SK::MAX_PUNCTUATION_LEN).rev() {
+    if let Some(kind) = bs.get(*idx..*idx + len).and_then(SK::punctuation) {
+      *idx += len;
+      return kind;
+    }
+  }
+  // skip the whole (possibly multi-byte) char.
+  *idx += 1;
+  advance_while(bs, idx, |b| b & 0b1100_0000 == 0b1000_0000);
+  SK::Invalid
+}
+
+fn advance_while<P>(bs: &[u8], idx: &mut usize, p: P)
+where
+  P: Fn(u8) -> bool,
+{
+  while let Some(&b) = bs.get(*idx) {
+    if !p(b) {
+      break;
+    }
+    *idx += 1;
+  }
+}
+
+fn text_size(n: usize) -> TextSize {
+  TextSize::from(n as u32)
+}
@@ -0,0 +1,46 @@
+//! An end-to-end example language, toy, using the other crates together.
+//!
+//! - `src/toy.ungram` is the grammar, from which `syntax-gen` generates
+//!   `src/kind.rs` and `src/ast.rs`.
+//! - [`lex`] lexes text into `token`s.
+//! - [`parse`] parses the tokens with `event-parse` into a `rowan` tree.
+//! - [`diagnostic`] turns errors into diagnostics.
+//! - `src/main.rs` is a tiny language server, publishing the diagnostics and
+//!   the folding ranges from `syntax-ranges`.
+//!
+//! The tests re-generate `src/kind.rs` and `src/ast.rs`, and fail if they
+//! changed.
+
+#![deny(missing_debug_implementations)]
+#![deny(missing_docs)]
+#![deny(rust_2018_idioms)]
+
+#[allow(missing_debug_implementations, missing_docs)]
+pub mod ast;
+pub mod diagnostic;
+#[allow(missing_debug_implementations, missing_docs)]
+pub mod kind;
+pub mod lex;
+pub mod parse;
+
+#[cfg(test)]
+mod tests;
+
+/// The result of analyzing text.
+#[derive(Debug)]
+pub struct Analysis {
+  /// The root of the syntax tree.
+  pub root: ast::Root,
+  /// The diagnostics.
+  pub diagnostics: Vec<diagnostic::Diagnostic>,
+}
+
+/// Lexes, parses, and reports diagnostics for the text.
+pub fn analyze(s: &str) -> Analysis {
+  let lex = lex::get(s);
+  let parse = parse::get(&lex.tokens);
+  Analysis {
+    root: parse.root,
+    diagnostics: diagnostic::get(&lex.errors, &parse.errors),
+  }
+}
@@ -0,0 +1,114 @@
+//! A tiny language server for toy, publishing diagnostics and providing
+//! folding ranges.
+
+use lsp_server::{Connection, Message, Notification, Request, Response};
+use rowan::TextRange;
+use serde_json::{json, Value};
+use text_pos::PositionDb;
+use toy::kind::SyntaxKind as SK;
+
+fn main() {
+  let (conn, io_threads) = Connection::stdio();
+  let capabilities = json!({
+    "textDocumentSync": 1,
+    "foldingRangeProvider": true,
+  });
+  conn.initialize(capabilities).expect("initialize");
+  let mut files = std::collections::HashMap::<String, String>::new();
+  for msg in &conn.receiver {
+    match msg {
+      Message::Request(req) => {
+        if conn.handle_shutdown(&req).expect("shutdown") {
+          break;
+        }
+        let resp = request(&files, req);
+        conn.sender.send(Message::Response(resp)).expect("send");
+      }
+      Message::Response(_) => {}
+      Message::Notification(notif) => {
+        if let Some((uri, text)) = notification(notif) {
+          let params = diagnostics(&uri, &text);
+          files.insert(uri, text);
+          let notif = Notification {
+            method: "textDocument/publishDiagnostics".to_owned(),
+            params,
+          };
+          conn
+            .sender
+            .send(Message::Notification(notif))
+            .expect("send");
+        }
+      }
+    }
+  }
+  io_threads.join().expect("join");
+}
+
+/// Returns the URI and new text of the file opened or changed by the
+/// notification, if any.
+fn notification(notif: Notification) -> Option<(String, String)> {
+  let p = &notif.params;
+  let text = match notif.method.as_str() {
+    "textDocument/didOpen" => &p["textDocument"]["text"],
+    // we asked for full sync, so there is one change with the whole text.
+    "textDocument/didChange" => &p["contentChanges"][0]["text"],
+    _ => return None,
+  };
+  let uri = p["textDocument"]["uri"].as_str()?;
+  Some((uri.to_owned(), text.as_str()?.to_owned()))
+}
+
+fn request(
+  files: &std::collections::HashMap<String, String>,
+  req: Request,
+) -> Response {
+  if req.method != "textDocument/foldingRange" {
+    return Response::new_err(req.id, -32601, "unknown request".to_owned());
+  }
+  let text = req.params["textDocument"]["uri"]
+    .as_str()
+    .and_then(|uri| files.get(uri));
+  let text = match text {
+    Some(x) => x,
+    None => return Response::new_ok(req.id, Value::Null),
+  };
+  let db = PositionDb::new(text);
+  let root = toy::analyze(text).root;
+  let folds = syntax_ranges::folds(
+    root.as_ref(),
+    |k| k == SK::ParenExpr,
+    |k| k == SK::LineComment,
+  );
+  let folds: Vec<_> = folds
+    .iter()
+    .map(|fold| {
+      let (start, end) = fold.lines(&db);
+      json!({ "startLine": start, "endLine": end })
+    })
+    .collect();
+  Response::new_ok(req.id, folds)
+}
+
+fn diagnostics(uri: &str, text: &str) -> Value {
+  let db = PositionDb::new(text);
+  let diagnostics: Vec<_> = toy::analyze(text)
+    .diagnostics
+    .into_iter()
+    .map(|d| {
+      json!({
+        "range": range(&db, d.range),
+        "severity": 1,
+        "message": d.message,
+      })
+    })
+    .collect();
+  json!({ "uri": uri, "diagnostics": diagnostics })
+}
+
+fn range(db: &PositionDb, range: TextRange) -> Value {
+  let range = db.range(range);
+  json!({
+    "start": { "line": range.start.line, "character": range.start.character },
+    "end": { "line": range.end.line, "character": range.end.character },
+  })
+}
@@ -0,0 +1,156 @@
+//! Parsing tokens into a syntax tree, with `event-parse`.
+
+use crate::ast::Root;
+use crate::kind::{SyntaxKind as SK, SyntaxNode};
+use event_parse::{Exited, Parser, Sink};
+use rowan::{GreenNodeBuilder, TextRange, TextSize};
+use std::convert::TryFrom as _;
+use std::ops::Range;
+use token::Token;
+
+/// The result of parsing.
+#[derive(Debug)]
+pub struct Parse {
+  /// The root of the syntax tree.
+  pub root: Root,
+  /// The errors.
+  pub errors: Vec<Error>,
+}
+
+/// An error from parsing.
+#[derive(Debug)]
+pub struct Error {
+  /// The range of the token at which the error occurred. Empty if a token was
+  /// missing or the error was at the end.
+  pub range: TextRange,
+  /// The kinds of tokens which were expected here.
+  pub expected: Vec<SK>,
+}
+
+/// Parses the tokens.
+pub fn get(tokens: &[Token<'_, SK>]) -> Parse {
+  let mut p = Parser::new(tokens);
+  p.set_missing_follow(vec![SK::Eq, SK::Semicolon, SK::RRound]);
+  p.parse_file(SK::Root, stmt, &[SK::LetKw, SK::Semicolon]);
+  let mut sink = BuilderSink::default();
+  p.finish(&mut sink);
+  let root = SyntaxNode::new_root(sink.builder.finish());
+  Parse {
+    root: Root::try_from(root).expect("root was not a Root"),
+    errors: sink.errors,
+  }
+}
+
+#[derive(Default)]
+struct BuilderSink {
+  builder: GreenNodeBuilder<'static>,
+  errors: Vec<Error>,
+}
+
+impl Sink<SK> for BuilderSink {
+  fn enter(&mut self, kind: SK) {
+    self.builder.start_node(kind.into());
+  }
+
+  fn token(&mut self, token: Token<'_, SK>) {
+    self.builder.token(token.kind.into(), token.text);
+  }
+
+  fn exit(&mut self) {
+    self.builder.finish_node();
+  }
+
+  fn error(&mut self, expected: Vec<SK>, range: Range<usize>) {
+    let text_size = |n| TextSize::try_from(n).unwrap();
+    self.errors.push(Error {
+      range: TextRange::new(text_size(range.start), text_size(range.end)),
+      expected,
+    });
+  }
+}
+
+fn stmt(p: &mut Parser<'_, SK>) -> bool {
+  let en = p.enter();
+  if p.at(SK::LetKw) {
+    p.bump();
+    p.eat(SK::Name);
+    p.eat(SK::Eq);
+    must(p, expr);
+    p.eat(SK::Semicolon);
+    p.exit(en, SK::LetStmt);
+  } else if expr(p).is_some() {
+    p.eat(SK::Semicolon);
+    p.exit(en, SK::ExprStmt);
+  } else {
+    p.abandon(en);
+    return false;
+  }
+  true
+}
+
+fn expr(p: &mut Parser<'_, SK>) -> Option<Exited> {
+  let mut ex = atom(p)?;
+  while p.at(SK::Plus) {
+    let en = p.precede(ex);
+    p.bump();
+    must(p, atom);
+    ex = p.exit(en, SK::AddExpr);
+  }
+  Some(ex)
+}
+
+fn atom(p: &mut Parser<'_, SK>) -> Option<Exited> {
+  let kind = if p.at(SK::Int) {
+    SK::IntExpr
+  } else if p.at(SK::Name) {
+    let en = p.enter();
+    p.bump();
+    if !p.at(SK::LRound) {
+      return Some(p.exit(en, SK::NameExpr));
+    }
+    arg_list(p);
+    return Some(p.exit(en, SK::CallExpr));
+  } else if p.at(SK::LRound) {
+    let en = p.enter();
+    p.bump();
+    must(p, expr);
+    p.eat(SK::RRound);
+    return Some(p.exit(en, SK::ParenExpr));
+  } else {
+    return None;
+  };
+  let en = p.enter();
+  p.bump();
+  Some(p.exit(en, kind))
+}
+
+fn arg_list(p: &mut Parser<'_, SK>) {
+  let en = p.enter();
+  p.bump();
+  loop {
+    let arg = p.enter();
+    if expr(p).is_none() {
+      p.abandon(arg);
+      break;
+    }
+    let comma = p.at(SK::Comma);
+    if comma {
+      p.bump();
+    }
+    p.exit(arg, SK::Arg);
+    if !comma {
+      break;
+    }
+  }
+  p.eat(SK::RRound);
+  p.exit(en, SK::ArgList);
+}
+
+fn must<F>(p: &mut Parser<'_, SK>, f: F)
+where
+  F: FnOnce(&mut Parser<'_, SK>) -> Option<Exited>,
+{
+  if f(p).is_none() {
+    p.error();
+  }
+}
@@ -0,0 +1,325 @@
+use crate::ast::{AddExpr, Expr, IntExpr, LetStmt, Root, Stmt};
+use crate::diagnostic::Diagnostic;
+use crate::kind::{SyntaxKind as SK, SyntaxNode};
+use ast_ptr::AstPtr;
+use rowan::{TextRange, TextSize};
+use std::convert::TryFrom as _;
+use syntax_gen::TokenKind;
+
+/// Re-generates `src/kind.rs` and `src/ast.rs` from the grammar. Must be run
+/// from the crate root.
+fn gen() {
+  let grammar = include_str!("toy.ungram").parse().unwrap();
+  let trivia = &["Whitespace", "LineComment", "Invalid"];
+  let options = syntax_gen::Options {
+    root: Some("Root".to_owned()),
+    stable_ids: Some("src/kind_ids.txt".to_owned()),
+    ..Default::default()
+  };
+  let report =
+    syntax_gen::gen("Toy", trivia, grammar, options, get_token).unwrap();
+  assert_eq!(report.unreachable, Vec::<String>::new());
+}
+
+fn get_token(name: &str) -> (TokenKind, String) {
+  match name {
+    "Name" => {
+      let kind = TokenKind::SpecialWithExample("a name", "x");
+      return (kind, name.to_owned());
+    }
+    "Int" => {
+      let kind = TokenKind::SpecialWithExample("an integer", "1");
+      return (kind, name.to_owned());
+    }
+    _ => {}
+  }
+  if name.chars().all(|c| c.is_ascii_lowercase()) {
+    let mut ret = identifier_case::snake_to_pascal(name);
+    ret.push_str("Kw");
+    return (TokenKind::Keyword, ret);
+  }
+  let ret: String = name.chars().map(char_name::get).collect();
+  (TokenKind::Punctuation, ret)
+}
+
+#[test]
+fn generated_up_to_date() {
+  let old = (read("src/kind.rs"), read("src/ast.rs"));
+  gen();
+  let new = (read("src/kind.rs"), read("src/ast.rs"));
+  assert!(
+    old == new,
+    "generated files were out of date, re-run the tests"
+  );
+}
+
+fn read(path: &str) -> String {
+  std::fs::read_to_string(path).unwrap()
+}
+
+fn range(start: u32, end: u32) -> TextRange {
+  TextRange::new(TextSize::from(start), TextSize::from(end))
+}
+
+#[test]
+fn ok() {
+  let a = crate::analyze("let x = 1 + (y + 2); // sum\nx;\n");
+  assert_eq!(a.diagnostics, Vec::new());
+  let stmts: Vec<_> = a.root.stmts().collect();
+  assert_eq!(stmts.len(), 2);
+  let let_stmt = match &stmts[0] {
+    Stmt::LetStmt(x) => x,
+    Stmt::ExprStmt(_) => panic!("not a let"),
+  };
+  assert_eq!(let_stmt.name().unwrap().text(), "x");
+  let add = match let_stmt.expr().unwrap() {
+    Expr::AddExpr(x) => x,
+    _ => panic!("not an add"),
+  };
+  assert!(matches!(add.lhs(), Some(Expr::IntExpr(_))));
+  assert!(matches!(add.rhs(), Some(Expr::ParenExpr(_))));
+  assert_eq!(let_stmt.to_string(), "let x = 1 + (y + 2);");
+  assert_eq!(format!("{}", add), "1 + (y + 2)");
+}
+
+#[test]
+fn errors() {
+  let text = "let = 1 $;\n(3;";
+  let a = crate::analyze(text);
+  let want = vec![
+    Diagnostic {
+      range: range(3, 3),
+      message: "expected a name".to_owned(),
+    },
+    Diagnostic {
+      range: range(8, 9),
+      message: "invalid character".to_owned(),
+    },
+    Diagnostic {
+      range: range(13, 13),
+      message: "expected `)`".to_owned(),
+    },
+  ];
+  assert_eq!(a.diagnostics, want);
+  assert_eq!(a.root.stmts().count(), 2);
+  assert_eq!(a.root.as_ref().text().to_string(), text);
+  assert_eq!(SK::Int.example_text(), Some("1"));
+}
+
+#[test]
+fn error_range() {
+  let a = crate::analyze("let x = );");
+  let want = vec![Diagnostic {
+    range: range(8, 9),
+    message: "expected an integer or a name or `(`".to_owned(),
+  }];
+  assert_eq!(a.diagnostics, want);
+}
+
+#[test]
+fn calls() {
+  let a = crate::analyze("f(1, g(), (2));");
+  assert_eq!(a.diagnostics, Vec::new());
+  let call = match a.root.stmts().next().unwrap() {
+    Stmt::ExprStmt(x) => match x.expr().unwrap() {
+      Expr::CallExpr(x) => x,
+      _ => panic!("not a call"),
+    },
+    Stmt::LetStmt(_) => panic!("not an expr"),
+  };
+  assert_eq!(call.name().unwrap().text(), "f");
+  let args = call.arg_list().unwrap();
+  assert_eq!(args.l_round().unwrap().text_range(), range(1, 2));
+  assert_eq!(args.r_round().unwrap().text_range(), range(13, 14));
+  let args: Vec<_> = args.args().collect();
+  assert_eq!(args.len(), 3);
+  assert!(matches!(args[1].expr(), Some(Expr::CallExpr(_))));
+  assert!(args[1].comma().is_some());
+  assert!(args[2].comma().is_none());
+}
+
+#[test]
+fn nth_child_empty() {
+  use rowan::{GreenNode, GreenToken, NodeOrToken};
+  let node = |kind: SK, children: Vec<_>| {
+    NodeOrToken::Node(GreenNode::new(kind.into(), children))
+  };
+  let token = |kind: SK, text: &str| {
+    NodeOrToken::Token(GreenToken::new(kind.into(), text))
+  };
+  // `+2`, with empty nodes before and after the `+`.
+  let green = GreenNode::new(
+    SK::AddExpr.into(),
+    vec![
+      node(SK::NameExpr, vec![]),
+      token(SK::Plus, "+"),
+      node(SK::IntExpr, vec![]),
+      node(SK::IntExpr, vec![token(SK::Int, "2")]),
+    ],
+  );
+  let add = AddExpr::try_from(SyntaxNode::new_root(green)).unwrap();
+  assert!(matches!(add.lhs(), Some(Expr::NameExpr(_))));
+  assert_eq!(add.plus().unwrap().text_range(), range(0, 1));
+  let rhs = match add.rhs() {
+    Some(Expr::IntExpr(x)) => x,
+    _ => panic!("not an int"),
+  };
+  assert_eq!(rhs.as_ref().text_range(), range(1, 1));
+  let ints: Vec<IntExpr> = add
+    .as_ref()
+    .children()
+    .filter_map(|x| IntExpr::try_from(x).ok())
+    .collect();
+  assert_eq!(ints[1].int().unwrap().text(), "2");
+}
+
+#[test]
+fn checked_new() {
+  use crate::ast::{ArgList, BuildError};
+  use rowan::{GreenNode, GreenToken, NodeOrToken};
+  let node = |kind: SK, children: Vec<_>| {
+    NodeOrToken::Node(GreenNode::new(kind.into(), children))
+  };
+  let token = |kind: SK, text: &str| {
+    NodeOrToken::Token(GreenToken::new(kind.into(), text))
+  };
+  let int = || node(SK::IntExpr, vec![token(SK::Int, "1")]);
+  let stmt = LetStmt::checked_new(vec![
+    token(SK::LetKw, "let"),
+    token(SK::Whitespace, " "),
+    token(SK::Name, "x"),
+    token(SK::Eq, "="),
+    int(),
+    token(SK::Semicolon, ";"),
+  ])
+  .unwrap();
+  assert_eq!(stmt.as_ref().to_string(), "let x=1;");
+  assert!(matches!(stmt.expr(), Some(Expr::IntExpr(_))));
+  let missing = vec![token(SK::LetKw, "let"), token(SK::Name, "x")];
+  assert_eq!(
+    LetStmt::checked_new(missing).err().unwrap(),
+    BuildError {
+      node: SK::LetStmt,
+      idx: 2,
+      expected: vec![SK::Eq],
+    }
+  );
+  let args = vec![
+    token(SK::LRound, "("),
+    node(SK::Arg, vec![int(), token(SK::Comma, ",")]),
+    node(SK::Arg, vec![int()]),
+    token(SK::RRound, ")"),
+  ];
+  assert!(ArgList::checked_new(args).is_ok());
+  let unclosed = vec![token(SK::LRound, "("), int()];
+  let err = ArgList::checked_new(unclosed).err().unwrap();
+  assert_eq!(err.idx, 1);
+  assert_eq!(err.expected, [SK::Arg, SK::RRound]);
+  assert_eq!(
+    err.to_string(),
+    "bad child 1 for ArgList: expected one of [Arg, RRound]"
+  );
+  let extra = vec![token(SK::LRound, "("), token(SK::RRound, ")"), int()];
+  let err = ArgList::checked_new(extra).err().unwrap();
+  assert_eq!(err.idx, 2);
+  assert!(err.expected.is_empty());
+}
+
+#[test]
+fn empty() {
+  let root = Root::empty();
+  assert_eq!(root.as_ref().kind(), SK::Root);
+  assert_eq!(root.stmts().count(), 0);
+  assert_eq!(root.as_ref().text().to_string(), "");
+}
+
+#[test]
+fn child_tokens() {
+  assert_eq!(SK::ExprStmt.child_tokens(), [SK::Semicolon]);
+  assert!(SK::ParenExpr.can_contain(SK::RRound));
+  assert!(!SK::ParenExpr.can_contain(SK::Int));
+  assert!(SK::Root.child_tokens().is_empty());
+  assert!(SK::Int.child_tokens().is_empty());
+  for (kind, _) in crate::kind::KindMap::from_fn(|_| ()).iter() {
+    let toks = kind.child_tokens();
+    assert!(toks.windows(2).all(|w| w[0] < w[1]));
+    assert!(toks.iter().all(|tok| tok.is_token()));
+  }
+}
+
+#[test]
+fn kind_map() {
+  // the values need not be `Copy`.
+  let mut map = crate::kind::KindMap::from_fn(|kind| kind.name().to_owned());
+  assert_eq!(map[SK::Plus], "Plus");
+  map[SK::Plus].push('!');
+  assert_eq!(map[SK::Plus], "Plus!");
+  assert!(map.iter().all(|(kind, name)| name.starts_with(kind.name())));
+}
+
+#[test]
+fn const_lookups() {
+  const LET: Option<SK> = SK::keyword(b"let");
+  const PLUS: Option<SK> = SK::punctuation(b"+");
+  assert_eq!(LET, Some(SK::LetKw));
+  assert_eq!(PLUS, Some(SK::Plus));
+  assert_eq!(SK::punctuation(b"++"), None);
+  assert_eq!(SK::MAX_KEYWORD_LEN, 3);
+  assert_eq!(SK::KEYWORDS_BY_LEN[3], [(&b"let"[..], SK::LetKw)]);
+  assert!(SK::PUNCTUATION_BY_LEN[0].is_empty());
+  assert_eq!(SK::PUNCTUATION_BY_LEN[1].len(), SK::PUNCTUATION.len());
+}
+
+#[test]
+fn grammar_token() {
+  assert_eq!(SK::LetKw.grammar_token("let"), Some("let"));
+  assert_eq!(SK::Name.grammar_token("x"), Some("Name"));
+  assert_eq!(SK::Root.grammar_token(""), None);
+}
+
+#[test]
+fn ptr() {
+  let a = crate::analyze("1 + 2;");
+  let stmt = a.root.stmts().next().unwrap();
+  let stmt_ptr = AstPtr::new(&stmt);
+  assert!(stmt_ptr.cast::<LetStmt>().is_none());
+  let expr = match stmt {
+    Stmt::ExprStmt(x) => x.expr().unwrap(),
+    Stmt::LetStmt(_) => panic!("not an expr"),
+  };
+  let add_ptr = match &expr {
+    Expr::AddExpr(x) => AstPtr::new(x),
+    _ => panic!("not an add"),
+  };
+  let expr_ptr = add_ptr.cast::<Expr>().unwrap();
+  assert_eq!(expr_ptr, AstPtr::new(&expr));
+  assert_eq!(expr_ptr.cast::<AddExpr>(), Some(add_ptr));
+  assert!(add_ptr.cast::<Stmt>().is_none());
+  assert_eq!(expr_ptr.stable_hash(), add_ptr.stable_hash());
+  assert_ne!(stmt_ptr.stable_hash(), add_ptr.stable_hash());
+  let json = serde_json::to_value(add_ptr).unwrap();
+  let id = SK::AddExpr.to_stable_u16();
+  assert_eq!(json, serde_json::json!([id, 0, 5]));
+  let back: AstPtr<AddExpr> = serde_json::from_value(json).unwrap();
+  assert_eq!(back, add_ptr);
+  let bad = serde_json::json!([id, 5, 0]);
+  assert!(serde_json::from_value::<AstPtr<AddExpr>>(bad).is_err());
+}
+
+#[test]
+fn fingerprint() {
+  let expr = |text: &str| {
+    let a = crate::analyze(text);
+    match a.root.stmts().next().unwrap() {
+      Stmt::ExprStmt(x) => x.expr().unwrap(),
+      Stmt::LetStmt(_) => panic!("not an expr"),
+    }
+  };
+  let a = expr("1 + (y + 2);");
+  let b = expr("1+( y  // the y\n+2) ;");
+  assert_eq!(a.fingerprint(), b.fingerprint());
+  assert_eq!(a.fingerprint(), crate::ast::fingerprint(a.as_ref()));
+  assert_ne!(a.fingerprint(), expr("1 + (y + 3);").fingerprint());
+  assert_ne!(a.fingerprint(), expr("(1 + y) + 2;").fingerprint());
+  assert_ne!(a.fingerprint(), expr("1 + (y2);").fingerprint());
+}
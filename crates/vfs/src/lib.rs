@@ -0,0 +1,186 @@
+//! A virtual file system.
+//!
+//! Files are identified by cheap [`FileId`]s. The contents of a file are the
+//! contents of its in-memory editor buffer (the 'overlay') if the file is open
+//! in the editor, else the contents on disk. Every change to the contents of a
+//! file is recorded as a [`Change`], so that downstream analysis knows what to
+//! recompute.
+
+#![deny(missing_debug_implementations)]
+#![deny(missing_docs)]
+#![deny(rust_2018_idioms)]
+
+#[cfg(test)]
+mod tests;
+
+use rustc_hash::FxHashMap;
+use std::path::{Path, PathBuf};
+
+/// A virtual file system, which can only hold up to `u32::MAX` many files.
+#[derive(Debug, Default)]
+pub struct Vfs {
+  files: Vec<File>,
+  path_to_id: FxHashMap<PathBuf, FileId>,
+  changes: Vec<Change>,
+}
+
+impl Vfs {
+  /// Returns the ID for the path, interning the path if it has not been seen
+  /// before.
+  ///
+  /// Interning a path does not give it any contents.
+  pub fn intern(&mut self, path: PathBuf) -> FileId {
+    if let Some(ret) = self.get_id(&path) {
+      return ret;
+    }
+    let ret = FileId(self.files.len() as u32);
+    self.files.push(File {
+      path: path.clone(),
+      disk: None,
+      overlay: None,
+    });
+    assert!(self.path_to_id.insert(path, ret).is_none());
+    ret
+  }
+
+  /// Returns the ID associated with this path.
+  pub fn get_id(&self, path: &Path) -> Option<FileId> {
+    self.path_to_id.get(path).copied()
+  }
+
+  /// Returns the path associated with this ID.
+  pub fn path(&self, id: FileId) -> &Path {
+    self.files[id.0 as usize].path.as_path()
+  }
+
+  /// Returns the contents of the file, or `None` if it has no contents (e.g.
+  /// it does not exist on disk and is not open in the editor).
+  ///
+  /// The contents of the overlay take precedence over the contents on disk.
+  pub fn contents(&self, id: FileId) -> Option<&str> {
+    self.files[id.0 as usize].contents()
+  }
+
+  /// Returns whether the file is open in the editor.
+  pub fn is_open(&self, id: FileId) -> bool {
+    self.files[id.0 as usize].overlay.is_some()
+  }
+
+  /// Sets the contents of the file on disk. `None` means the file no longer
+  /// exists on disk.
+  pub fn set_disk(
+    &mut self,
+    path: PathBuf,
+    contents: Option<String>,
+  ) -> FileId {
+    let id = self.intern(path);
+    self.update(id, Layer::Disk, contents);
+    id
+  }
+
+  /// Reads the contents of the file on disk, and sets them with
+  /// [`Self::set_disk`].
+  ///
+  /// If the file does not exist, its disk contents are set to `None`.
+  pub fn read_disk(&mut self, path: PathBuf) -> std::io::Result<FileId> {
+    let contents = match std::fs::read_to_string(&path) {
+      Ok(x) => Some(x),
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+      Err(e) => return Err(e),
+    };
+    Ok(self.set_disk(path, contents))
+  }
+
+  /// Sets the contents of the in-memory editor buffer for the file, e.g. when
+  /// the file is opened or edited in the editor.
+  pub fn set_overlay(&mut self, path: PathBuf, contents: String) -> FileId {
+    let id = self.intern(path);
+    self.update(id, Layer::Overlay, Some(contents));
+    id
+  }
+
+  /// Removes the in-memory editor buffer for the file, e.g. when the file is
+  /// closed in the editor. The contents of the file are again the contents on
+  /// disk.
+  pub fn remove_overlay(&mut self, id: FileId) {
+    self.update(id, Layer::Overlay, None);
+  }
+
+  /// Returns the changes since the last call to this, in the order they
+  /// happened.
+  pub fn take_changes(&mut self) -> Vec<Change> {
+    std::mem::take(&mut self.changes)
+  }
+
+  /// Returns an iterator over the IDs.
+  pub fn iter(&self) -> impl Iterator<Item = FileId> {
+    (0..(self.files.len() as u32)).map(FileId)
+  }
+
+  fn update(&mut self, id: FileId, layer: Layer, contents: Option<String>) {
+    let file = &mut self.files[id.0 as usize];
+    let old_layer = match layer {
+      Layer::Disk => std::mem::replace(&mut file.disk, contents),
+      Layer::Overlay => std::mem::replace(&mut file.overlay, contents),
+    };
+    let old = match layer {
+      Layer::Disk => file.overlay.as_deref().or(old_layer.as_deref()),
+      Layer::Overlay => old_layer.as_deref().or(file.disk.as_deref()),
+    };
+    let kind = match (old, file.contents()) {
+      (None, None) => return,
+      (None, Some(_)) => ChangeKind::Create,
+      (Some(_), None) => ChangeKind::Delete,
+      (Some(old), Some(new)) => {
+        if old == new {
+          return;
+        }
+        ChangeKind::Modify
+      }
+    };
+    self.changes.push(Change { file_id: id, kind });
+  }
+}
+
+#[derive(Debug)]
+struct File {
+  path: PathBuf,
+  disk: Option<String>,
+  overlay: Option<String>,
+}
+
+impl File {
+  fn contents(&self) -> Option<&str> {
+    self.overlay.as_deref().or(self.disk.as_deref())
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Layer {
+  Disk,
+  Overlay,
+}
+
+/// A file identifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileId(u32);
+
+/// A change to the contents of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Change {
+  /// The file that changed.
+  pub file_id: FileId,
+  /// How it changed.
+  pub kind: ChangeKind,
+}
+
+/// A kind of change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+  /// The file now has contents, but it did not before.
+  Create,
+  /// The file had contents, and now has different contents.
+  Modify,
+  /// The file had contents, but now it does not.
+  Delete,
+}
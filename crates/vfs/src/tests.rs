@@ -0,0 +1,67 @@
+use crate::{Change, ChangeKind, Vfs};
+use std::path::PathBuf;
+
+fn path(s: &str) -> PathBuf {
+  PathBuf::from(s)
+}
+
+#[test]
+fn intern() {
+  let mut vfs = Vfs::default();
+  let a = vfs.intern(path("a.txt"));
+  let b = vfs.intern(path("b.txt"));
+  assert_ne!(a, b);
+  assert_eq!(vfs.intern(path("a.txt")), a);
+  assert_eq!(vfs.path(b), path("b.txt"));
+  assert!(vfs.contents(a).is_none());
+  assert!(vfs.take_changes().is_empty());
+}
+
+#[test]
+fn overlay() {
+  let mut vfs = Vfs::default();
+  let a = vfs.set_disk(path("a.txt"), Some("disk".to_owned()));
+  assert_eq!(vfs.contents(a), Some("disk"));
+  vfs.set_overlay(path("a.txt"), "editor".to_owned());
+  assert!(vfs.is_open(a));
+  assert_eq!(vfs.contents(a), Some("editor"));
+  // changes on disk are hidden by the overlay.
+  vfs.set_disk(path("a.txt"), Some("new disk".to_owned()));
+  assert_eq!(vfs.contents(a), Some("editor"));
+  vfs.remove_overlay(a);
+  assert!(!vfs.is_open(a));
+  assert_eq!(vfs.contents(a), Some("new disk"));
+}
+
+#[test]
+fn changes() {
+  let mut vfs = Vfs::default();
+  let a = vfs.set_disk(path("a.txt"), Some("a".to_owned()));
+  // same contents as on disk, so no change.
+  vfs.set_overlay(path("a.txt"), "a".to_owned());
+  vfs.set_overlay(path("a.txt"), "aa".to_owned());
+  let b = vfs.set_overlay(path("b.txt"), "b".to_owned());
+  vfs.remove_overlay(b);
+  assert_eq!(
+    vfs.take_changes(),
+    [
+      Change {
+        file_id: a,
+        kind: ChangeKind::Create
+      },
+      Change {
+        file_id: a,
+        kind: ChangeKind::Modify
+      },
+      Change {
+        file_id: b,
+        kind: ChangeKind::Create
+      },
+      Change {
+        file_id: b,
+        kind: ChangeKind::Delete
+      },
+    ]
+  );
+  assert!(vfs.take_changes().is_empty());
+}
@@ -0,0 +1,150 @@
+//! Diffing syntax trees.
+
+use crate::TextEdit;
+use rowan::{
+  Language, NodeOrToken, SyntaxElement, SyntaxNode, TextRange, TextSize,
+};
+use std::convert::TryFrom as _;
+
+/// Returns the edits which change the text of `old` into the text of `new`, in
+/// order. The ranges of the edits are in `old`.
+///
+/// Subtrees which did not change (which have the same green node, or the same
+/// kind and text) are skipped, and the edits are trimmed to the text which
+/// changed, so the edits are small if the trees are mostly the same, as when
+/// `new` was made by mutating a part of `old`.
+pub fn diff<L>(old: &SyntaxNode<L>, new: &SyntaxNode<L>) -> Vec<TextEdit>
+where
+  L: Language,
+{
+  let mut ret = Vec::new();
+  go(
+    &mut ret,
+    &NodeOrToken::Node(old.clone()),
+    &NodeOrToken::Node(new.clone()),
+  );
+  ret
+}
+
+fn go<L>(ac: &mut Vec<TextEdit>, old: &SyntaxElement<L>, new: &SyntaxElement<L>)
+where
+  L: Language,
+{
+  if same(old, new) {
+    return;
+  }
+  let (old_node, new_node) = match (old, new) {
+    (NodeOrToken::Node(o), NodeOrToken::Node(n))
+      if raw_kind(old) == raw_kind(new) =>
+    {
+      (o, n)
+    }
+    _ => {
+      push(ac, old.text_range(), &text(old), text(new));
+      return;
+    }
+  };
+  let old_cs: Vec<_> = old_node.children_with_tokens().collect();
+  let new_cs: Vec<_> = new_node.children_with_tokens().collect();
+  let prefix = old_cs
+    .iter()
+    .zip(new_cs.iter())
+    .take_while(|(o, n)| same(o, n))
+    .count();
+  let suffix = old_cs[prefix..]
+    .iter()
+    .rev()
+    .zip(new_cs[prefix..].iter().rev())
+    .take_while(|(o, n)| same(o, n))
+    .count();
+  let old_mid = &old_cs[prefix..old_cs.len() - suffix];
+  let new_mid = &new_cs[prefix..new_cs.len() - suffix];
+  let pairwise = old_mid.len() == new_mid.len()
+    && old_mid
+      .iter()
+      .zip(new_mid)
+      .all(|(o, n)| raw_kind(o) == raw_kind(n));
+  if pairwise {
+    for (o, n) in old_mid.iter().zip(new_mid) {
+      go(ac, o, n);
+    }
+    return;
+  }
+  let start = match (old_mid.first(), prefix.checked_sub(1)) {
+    (Some(x), _) => x.text_range().start(),
+    (None, Some(idx)) => old_cs[idx].text_range().end(),
+    (None, None) => old_node.text_range().start(),
+  };
+  let end = old_mid.last().map_or(start, |x| x.text_range().end());
+  let old_text: String = old_mid.iter().map(text).collect();
+  let new_text: String = new_mid.iter().map(text).collect();
+  push(ac, TextRange::new(start, end), &old_text, new_text);
+}
+
+/// Returns whether the elements certainly have the same text, without looking
+/// at their children.
+fn same<L>(old: &SyntaxElement<L>, new: &SyntaxElement<L>) -> bool
+where
+  L: Language,
+{
+  match (old, new) {
+    (NodeOrToken::Node(o), NodeOrToken::Node(n)) => {
+      std::ptr::eq(o.green(), n.green())
+        || (raw_kind(old) == raw_kind(new) && o.text() == n.text())
+    }
+    (NodeOrToken::Token(o), NodeOrToken::Token(n)) => {
+      raw_kind(old) == raw_kind(new) && o.text() == n.text()
+    }
+    _ => false,
+  }
+}
+
+/// Returns the kind of the element, without needing `L::Kind: Eq`.
+fn raw_kind<L>(elem: &SyntaxElement<L>) -> rowan::SyntaxKind
+where
+  L: Language,
+{
+  match elem {
+    NodeOrToken::Node(x) => x.green().kind(),
+    NodeOrToken::Token(x) => x.green().kind(),
+  }
+}
+
+fn text<L>(elem: &SyntaxElement<L>) -> String
+where
+  L: Language,
+{
+  match elem {
+    NodeOrToken::Node(x) => x.text().to_string(),
+    NodeOrToken::Token(x) => x.text().to_owned(),
+  }
+}
+
+/// Pushes an edit replacing `range`, which has `old_text`, with `new_text`,
+/// after trimming the text they have in common at the start and end.
+fn push(ac: &mut Vec<TextEdit>, range: TextRange, old_text: &str, new: String) {
+  let prefix: usize = old_text
+    .chars()
+    .zip(new.chars())
+    .take_while(|(a, b)| a == b)
+    .map(|(a, _)| a.len_utf8())
+    .sum();
+  let suffix: usize = old_text[prefix..]
+    .chars()
+    .rev()
+    .zip(new[prefix..].chars().rev())
+    .take_while(|(a, b)| a == b)
+    .map(|(a, _)| a.len_utf8())
+    .sum();
+  if prefix + suffix == old_text.len() && prefix + suffix == new.len() {
+    return;
+  }
+  let start = range.start() + to_size(prefix);
+  let end = range.end() - to_size(suffix);
+  let new_text = new[prefix..new.len() - suffix].to_owned();
+  ac.push(TextEdit::replace(TextRange::new(start, end), new_text));
+}
+
+fn to_size(n: usize) -> TextSize {
+  TextSize::try_from(n).expect("text too long")
+}
@@ -0,0 +1,245 @@
+//! Building edits for code actions: diffing an old and new syntax tree into
+//! minimal text edits, writing snippets with placeholders, and merging the
+//! edits for each file.
+//!
+//! Each supported version of `lsp-types` is behind a feature of the same name,
+//! like `lsp-types-0-94`, which converts the edits into its `TextEdit`s and
+//! `WorkspaceEdit`s.
+
+#![deny(missing_debug_implementations)]
+#![deny(missing_docs)]
+#![deny(rust_2018_idioms)]
+
+mod diff;
+mod snippet;
+
+#[cfg(test)]
+mod tests;
+
+pub use diff::diff;
+pub use snippet::Snippet;
+
+use rowan::{TextRange, TextSize};
+use std::collections::BTreeMap;
+use text_pos::PositionDb;
+
+/// An edit to the text of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+  /// The range of the old text to replace. Empty for an insertion.
+  pub range: TextRange,
+  /// The new text. Empty for a deletion.
+  pub new_text: String,
+  /// Whether `new_text` is a snippet, which may have placeholders. See
+  /// [`Snippet`].
+  pub is_snippet: bool,
+}
+
+impl TextEdit {
+  /// Returns an edit replacing the `range` with the plain `new_text`.
+  pub fn replace(range: TextRange, new_text: String) -> Self {
+    Self {
+      range,
+      new_text,
+      is_snippet: false,
+    }
+  }
+
+  /// Returns an edit inserting the plain `new_text` at the `offset`.
+  pub fn insert(offset: TextSize, new_text: String) -> Self {
+    Self::replace(TextRange::empty(offset), new_text)
+  }
+
+  /// Returns an edit deleting the `range`.
+  pub fn delete(range: TextRange) -> Self {
+    Self::replace(range, String::new())
+  }
+
+  /// Returns an edit replacing the `range` with the `snippet`.
+  pub fn snippet(range: TextRange, snippet: Snippet) -> Self {
+    Self {
+      range,
+      new_text: snippet.finish(),
+      is_snippet: true,
+    }
+  }
+}
+
+/// Conversion into an LSP type `T`.
+pub trait ToLsp<T> {
+  /// Converts this into a `T`, with `db` for the file's old text.
+  fn to_lsp(&self, db: &PositionDb) -> T;
+}
+
+/// LSP workspace edit types, like `lsp_types::WorkspaceEdit`. See
+/// [`WorkspaceEdit::finish_lsp`].
+pub trait LspWorkspaceEdit: Sized {
+  /// The type of the URL of a file.
+  type Url;
+  /// The type of an edit to a file.
+  type TextEdit;
+  /// Returns the workspace edit with the edits for each file.
+  fn from_edits(edits: Vec<(Self::Url, Vec<Self::TextEdit>)>) -> Self;
+}
+
+/// Edits to many files, where `F` identifies a file.
+#[derive(Debug, Clone)]
+pub struct WorkspaceEdit<F> {
+  files: BTreeMap<F, Vec<TextEdit>>,
+}
+
+impl<F> Default for WorkspaceEdit<F>
+where
+  F: Ord,
+{
+  fn default() -> Self {
+    Self {
+      files: BTreeMap::new(),
+    }
+  }
+}
+
+impl<F> WorkspaceEdit<F>
+where
+  F: Ord,
+{
+  /// Returns a new, empty set of edits.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds an edit to the `file`.
+  pub fn insert(&mut self, file: F, edit: TextEdit) {
+    self.files.entry(file).or_default().push(edit);
+  }
+
+  /// Adds many edits to the `file`.
+  pub fn extend<I>(&mut self, file: F, edits: I)
+  where
+    I: IntoIterator<Item = TextEdit>,
+  {
+    self.files.entry(file).or_default().extend(edits);
+  }
+
+  /// Returns whether there are no edits.
+  pub fn is_empty(&self) -> bool {
+    self.files.values().all(Vec::is_empty)
+  }
+
+  /// Returns the edits for each file, ordered by file. The edits for a file are
+  /// ordered by range, with insertions at the same offset kept in the order
+  /// they were added.
+  ///
+  /// Returns `Err` with the file and the two edits if any two edits to the same
+  /// file overlap. Insertions at the start or end of a replaced range do not
+  /// overlap it.
+  pub fn finish(self) -> Result<Vec<(F, Vec<TextEdit>)>, Overlap<F>> {
+    let mut ret = Vec::with_capacity(self.files.len());
+    for (file, mut edits) in self.files {
+      if edits.is_empty() {
+        continue;
+      }
+      edits.sort_by_key(|e| (e.range.start(), !e.range.is_empty()));
+      let overlap = edits
+        .windows(2)
+        .position(|w| w[1].range.start() < w[0].range.end());
+      if let Some(idx) = overlap {
+        let second = edits.swap_remove(idx + 1);
+        let first = edits.swap_remove(idx);
+        return Err(Overlap {
+          file,
+          first,
+          second,
+        });
+      }
+      ret.push((file, edits));
+    }
+    Ok(ret)
+  }
+
+  /// Finishes like [`Self::finish`], and converts the edits into a `T`, like
+  /// `lsp_types::WorkspaceEdit`. `file` returns the URL of a file, and the
+  /// `PositionDb` for its old text.
+  ///
+  /// Snippet edits are sent as plain edits, which only some clients support.
+  pub fn finish_lsp<'a, T, G>(self, mut file: G) -> Result<T, Overlap<F>>
+  where
+    T: LspWorkspaceEdit,
+    TextEdit: ToLsp<T::TextEdit>,
+    G: FnMut(&F) -> (T::Url, &'a PositionDb),
+  {
+    let edits = self
+      .finish()?
+      .into_iter()
+      .map(|(f, edits)| {
+        let (url, db) = file(&f);
+        let edits = edits.iter().map(|e| e.to_lsp(db)).collect();
+        (url, edits)
+      })
+      .collect();
+    Ok(T::from_edits(edits))
+  }
+}
+
+/// An error from [`WorkspaceEdit::finish`]: two edits to the same file overlap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overlap<F> {
+  /// The file.
+  pub file: F,
+  /// The edit which starts first.
+  pub first: TextEdit,
+  /// The edit which overlaps `first`.
+  pub second: TextEdit,
+}
+
+#[allow(unused_macros)]
+macro_rules! impl_lsp {
+  ($lsp:ident) => {
+    impl ToLsp<$lsp::TextEdit> for TextEdit {
+      fn to_lsp(&self, db: &PositionDb) -> $lsp::TextEdit {
+        let range = db.range(self.range);
+        let pos = |p: text_pos::Position| $lsp::Position {
+          line: p.line,
+          character: p.character,
+        };
+        $lsp::TextEdit {
+          range: $lsp::Range {
+            start: pos(range.start),
+            end: pos(range.end),
+          },
+          new_text: self.new_text.clone(),
+        }
+      }
+    }
+
+    /// With the format of the new text, as for a completion item.
+    impl ToLsp<($lsp::TextEdit, $lsp::InsertTextFormat)> for TextEdit {
+      fn to_lsp(
+        &self,
+        db: &PositionDb,
+      ) -> ($lsp::TextEdit, $lsp::InsertTextFormat) {
+        let format = if self.is_snippet {
+          $lsp::InsertTextFormat::SNIPPET
+        } else {
+          $lsp::InsertTextFormat::PLAIN_TEXT
+        };
+        (self.to_lsp(db), format)
+      }
+    }
+
+    impl LspWorkspaceEdit for $lsp::WorkspaceEdit {
+      type Url = $lsp::Url;
+      type TextEdit = $lsp::TextEdit;
+
+      fn from_edits(edits: Vec<($lsp::Url, Vec<$lsp::TextEdit>)>) -> Self {
+        $lsp::WorkspaceEdit::new(edits.into_iter().collect())
+      }
+    }
+  };
+}
+
+#[cfg(feature = "lsp-types-0-93")]
+impl_lsp!(lsp_types_0_93);
+
+#[cfg(feature = "lsp-types-0-94")]
+impl_lsp!(lsp_types_0_94);
@@ -0,0 +1,60 @@
+//! Snippets, as the language server protocol defines them.
+
+/// A builder for snippet text, with tab stops and placeholders.
+///
+/// Text is escaped as it is pushed, so it is always shown literally.
+#[derive(Debug, Default, Clone)]
+pub struct Snippet {
+  text: String,
+  next: u32,
+  has_final: bool,
+}
+
+impl Snippet {
+  /// Returns a new, empty snippet.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Pushes plain text.
+  pub fn push_text(&mut self, s: &str) {
+    escape(&mut self.text, s);
+  }
+
+  /// Pushes a tab stop, where the cursor goes after the previous tab stop or
+  /// placeholder.
+  pub fn push_tab_stop(&mut self) {
+    self.next += 1;
+    self.text.push_str(&format!("${{{}}}", self.next));
+  }
+
+  /// Pushes a placeholder, a tab stop with `s` selected.
+  pub fn push_placeholder(&mut self, s: &str) {
+    self.next += 1;
+    self.text.push_str(&format!("${{{}:", self.next));
+    escape(&mut self.text, s);
+    self.text.push('}');
+  }
+
+  /// Pushes the final cursor position, after all the other tab stops and
+  /// placeholders. Panics if this was already pushed.
+  pub fn push_final(&mut self) {
+    assert!(!self.has_final, "already pushed the final cursor position");
+    self.has_final = true;
+    self.text.push_str("${0}");
+  }
+
+  /// Returns the text of the snippet.
+  pub fn finish(self) -> String {
+    self.text
+  }
+}
+
+fn escape(buf: &mut String, s: &str) {
+  for c in s.chars() {
+    if matches!(c, '$' | '}' | '\\') {
+      buf.push('\\');
+    }
+    buf.push(c);
+  }
+}
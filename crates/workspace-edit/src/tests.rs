@@ -0,0 +1,234 @@
+use crate::{diff, Overlap, Snippet, TextEdit, WorkspaceEdit};
+use rowan::{GreenNodeBuilder, Language, SyntaxNode, TextRange, TextSize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum SK {
+  Whitespace,
+  Word,
+  Line,
+  Root,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Lang {}
+
+impl Language for Lang {
+  type Kind = SK;
+
+  fn kind_from_raw(raw: rowan::SyntaxKind) -> Self::Kind {
+    [SK::Whitespace, SK::Word, SK::Line, SK::Root][raw.0 as usize]
+  }
+
+  fn kind_to_raw(kind: Self::Kind) -> rowan::SyntaxKind {
+    rowan::SyntaxKind(kind as u16)
+  }
+}
+
+/// Each line is a node containing words separated by spaces.
+fn tree(s: &str) -> SyntaxNode<Lang> {
+  let mut b = GreenNodeBuilder::new();
+  b.start_node(Lang::kind_to_raw(SK::Root));
+  for (idx, line) in s.split('\n').enumerate() {
+    if idx != 0 {
+      b.token(Lang::kind_to_raw(SK::Whitespace), "\n");
+    }
+    b.start_node(Lang::kind_to_raw(SK::Line));
+    for (idx, word) in line.split(' ').enumerate() {
+      if idx != 0 {
+        b.token(Lang::kind_to_raw(SK::Whitespace), " ");
+      }
+      b.token(Lang::kind_to_raw(SK::Word), word);
+    }
+    b.finish_node();
+  }
+  b.finish_node();
+  SyntaxNode::new_root(b.finish())
+}
+
+fn range(start: u32, end: u32) -> TextRange {
+  TextRange::new(TextSize::from(start), TextSize::from(end))
+}
+
+/// Applies edits, which must be ordered and not overlap.
+fn apply(s: &str, edits: &[TextEdit]) -> String {
+  let mut ret = String::new();
+  let mut prev = 0;
+  for e in edits {
+    let start = usize::from(e.range.start());
+    ret.push_str(&s[prev..start]);
+    ret.push_str(&e.new_text);
+    prev = usize::from(e.range.end());
+  }
+  ret.push_str(&s[prev..]);
+  ret
+}
+
+fn check_diff(old: &str, new: &str, want: &[(u32, u32, &str)]) {
+  let edits = diff(&tree(old), &tree(new));
+  let got: Vec<_> = edits
+    .iter()
+    .map(|e| {
+      let r = e.range;
+      (
+        u32::from(r.start()),
+        u32::from(r.end()),
+        e.new_text.as_str(),
+      )
+    })
+    .collect();
+  assert_eq!(got, want);
+  assert_eq!(apply(old, &edits), new);
+}
+
+#[test]
+fn diff_same() {
+  check_diff("a bc\nd", "a bc\nd", &[]);
+}
+
+#[test]
+fn diff_token() {
+  check_diff("a bc d\nx", "a bd d\nx", &[(3, 4, "d")]);
+}
+
+#[test]
+fn diff_many() {
+  check_diff(
+    "a b\nc d\ne f",
+    "a x\nc d\ne y",
+    &[(2, 3, "x"), (10, 11, "y")],
+  );
+}
+
+#[test]
+fn diff_insert_line() {
+  check_diff("a\nc", "a\nb\nc", &[(2, 2, "b\n")]);
+}
+
+#[test]
+fn diff_delete_words() {
+  check_diff("a b c d", "a d", &[(2, 6, "")]);
+}
+
+#[test]
+fn diff_non_ascii() {
+  check_diff("é ü", "é ö", &[(3, 5, "ö")]);
+}
+
+#[test]
+fn snippet() {
+  let mut s = Snippet::new();
+  s.push_text("fn ");
+  s.push_placeholder("name");
+  s.push_text("() { ");
+  s.push_tab_stop();
+  s.push_text(" } $x");
+  s.push_final();
+  assert_eq!(s.finish(), r"fn ${1:name}() { ${2} \} \$x${0}");
+}
+
+#[test]
+fn snippet_digit_after_tab_stop() {
+  let mut s = Snippet::new();
+  s.push_tab_stop();
+  s.push_text("2");
+  s.push_final();
+  s.push_text("3");
+  assert_eq!(s.finish(), "${1}2${0}3");
+}
+
+#[test]
+#[should_panic(expected = "already pushed the final cursor position")]
+fn snippet_two_final() {
+  let mut s = Snippet::new();
+  s.push_final();
+  s.push_final();
+}
+
+#[test]
+fn workspace_edit() {
+  let mut w = WorkspaceEdit::new();
+  assert!(w.is_empty());
+  w.insert("b", TextEdit::delete(range(3, 5)));
+  w.insert("a", TextEdit::replace(range(1, 2), "x".to_owned()));
+  w.insert("b", TextEdit::insert(TextSize::from(5), "y".to_owned()));
+  w.extend(
+    "b",
+    vec![
+      TextEdit::insert(TextSize::from(3), "z".to_owned()),
+      TextEdit::replace(range(0, 1), "w".to_owned()),
+    ],
+  );
+  assert!(!w.is_empty());
+  let got = w.finish().unwrap();
+  let want = vec![
+    ("a", vec![TextEdit::replace(range(1, 2), "x".to_owned())]),
+    (
+      "b",
+      vec![
+        TextEdit::replace(range(0, 1), "w".to_owned()),
+        TextEdit::insert(TextSize::from(3), "z".to_owned()),
+        TextEdit::delete(range(3, 5)),
+        TextEdit::insert(TextSize::from(5), "y".to_owned()),
+      ],
+    ),
+  ];
+  assert_eq!(got, want);
+}
+
+#[test]
+fn workspace_edit_overlap() {
+  let mut w = WorkspaceEdit::new();
+  w.insert(1, TextEdit::delete(range(2, 5)));
+  w.insert(1, TextEdit::insert(TextSize::from(3), "x".to_owned()));
+  let want = Overlap {
+    file: 1,
+    first: TextEdit::delete(range(2, 5)),
+    second: TextEdit::insert(TextSize::from(3), "x".to_owned()),
+  };
+  assert_eq!(w.finish().unwrap_err(), want);
+}
+
+// the same tests for each version of `lsp-types`.
+#[allow(unused_macros)]
+macro_rules! lsp_tests {
+  ($name:ident, $lsp:ident) => {
+    #[test]
+    fn $name() {
+      use crate::ToLsp as _;
+      let db = text_pos::PositionDb::new("ab\ncd");
+      let mut s = Snippet::new();
+      s.push_placeholder("x");
+      let edit = TextEdit::snippet(range(1, 4), s);
+      let (got, format): ($lsp::TextEdit, _) = edit.to_lsp(&db);
+      let pos = |line, character| $lsp::Position { line, character };
+      let want = $lsp::Range {
+        start: pos(0, 1),
+        end: pos(1, 1),
+      };
+      assert_eq!(got.range, want);
+      assert_eq!(got.new_text, "${1:x}");
+      assert_eq!(format, $lsp::InsertTextFormat::SNIPPET);
+      let url = |s: &str| $lsp::Url::parse(s).unwrap();
+      let mut w = WorkspaceEdit::new();
+      w.insert(2, TextEdit::delete(range(0, 1)));
+      w.insert(1, edit);
+      let got: $lsp::WorkspaceEdit = w
+        .finish_lsp(|&f| (url(&format!("file:///{}", f)), &db))
+        .unwrap();
+      let changes = got.changes.unwrap();
+      assert_eq!(changes.len(), 2);
+      assert_eq!(changes[&url("file:///1")][0].new_text, "${1:x}");
+      let want = $lsp::Range {
+        start: pos(0, 0),
+        end: pos(0, 1),
+      };
+      assert_eq!(changes[&url("file:///2")][0].range, want);
+    }
+  };
+}
+
+#[cfg(feature = "lsp-types-0-93")]
+lsp_tests!(lsp_types_0_93, lsp_types_0_93);
+
+#[cfg(feature = "lsp-types-0-94")]
+lsp_tests!(lsp_types_0_94, lsp_types_0_94);